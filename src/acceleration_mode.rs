@@ -0,0 +1,64 @@
+// fraction of the acceleration applied from a standstill when easing in
+const EASE_IN_START: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccelerationMode {
+    Linear,
+    // accelerates slowly at first and reaches the full rate at max speed
+    EaseIn,
+}
+
+impl Default for AccelerationMode {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl AccelerationMode {
+    pub const NAMES: [&'static str; 2] = ["Linear", "EaseIn"];
+
+    pub fn from_index(index: i64) -> Self {
+        match index {
+            1 => Self::EaseIn,
+            _ => Self::Linear,
+        }
+    }
+
+    pub fn index(self) -> i64 {
+        match self {
+            Self::Linear => 0,
+            Self::EaseIn => 1,
+        }
+    }
+
+    pub fn acceleration(self, acceleration: f32, speed: f32, max_speed: f32) -> f32 {
+        match self {
+            Self::Linear => acceleration,
+            Self::EaseIn => {
+                let progress = if max_speed > 0.0 {
+                    (speed / max_speed).min(1.0)
+                } else {
+                    1.0
+                };
+
+                acceleration * (EASE_IN_START + (1.0 - EASE_IN_START) * progress)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_linear_acceleration_is_constant() {
+    let mode = AccelerationMode::Linear;
+
+    assert_eq!(mode.acceleration(500.0, 0.0, 80.0), 500.0);
+    assert_eq!(mode.acceleration(500.0, 40.0, 80.0), 500.0);
+}
+
+#[test]
+fn test_ease_in_acceleration_ramps_up() {
+    let mode = AccelerationMode::EaseIn;
+
+    assert_eq!(mode.acceleration(500.0, 0.0, 80.0), 125.0);
+    assert_eq!(mode.acceleration(500.0, 80.0, 80.0), 500.0);
+}
@@ -0,0 +1,71 @@
+use gdnative::prelude::{Vector2, Vector2Godot};
+
+pub struct AggroTarget {
+    pub position: Vector2,
+    // how many pixels closer the target counts as being
+    pub taunt: f32,
+}
+
+pub fn aggro_score(from: Vector2, target: &AggroTarget) -> f32 {
+    target.taunt - from.distance_to(target.position)
+}
+
+/// Returns the index of the target with the highest score that is within the
+/// detection range.
+pub fn pick_target(from: Vector2, targets: &[AggroTarget], detection_range: f32) -> Option<usize> {
+    targets
+        .iter()
+        .enumerate()
+        .filter(|(_, target)| from.distance_to(target.position) <= detection_range)
+        .map(|(index, target)| (index, aggro_score(from, target)))
+        .fold(
+            None,
+            |best: Option<(usize, f32)>, (index, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((index, score)),
+            },
+        )
+        .map(|(index, _)| index)
+}
+
+#[test]
+fn test_pick_closest_target_without_taunt() {
+    let targets = [
+        AggroTarget {
+            position: Vector2::new(40.0, 0.0),
+            taunt: 0.0,
+        },
+        AggroTarget {
+            position: Vector2::new(20.0, 0.0),
+            taunt: 0.0,
+        },
+    ];
+
+    assert_eq!(pick_target(Vector2::zero(), &targets, 64.0), Some(1));
+}
+
+#[test]
+fn test_taunt_beats_distance() {
+    let targets = [
+        AggroTarget {
+            position: Vector2::new(40.0, 0.0),
+            taunt: 32.0,
+        },
+        AggroTarget {
+            position: Vector2::new(20.0, 0.0),
+            taunt: 0.0,
+        },
+    ];
+
+    assert_eq!(pick_target(Vector2::zero(), &targets, 64.0), Some(0));
+}
+
+#[test]
+fn test_ignore_targets_out_of_range() {
+    let targets = [AggroTarget {
+        position: Vector2::new(100.0, 0.0),
+        taunt: 200.0,
+    }];
+
+    assert_eq!(pick_target(Vector2::zero(), &targets, 64.0), None);
+}
@@ -0,0 +1,137 @@
+use crate::extensions::NodeExt;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node};
+use std::collections::HashMap;
+
+/// Tokens enemies in the same attack group have to hold to attack, so only
+/// so many of them go for the player at once while the rest wait their turn.
+/// Reached through the `AttackTokens` autoload.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct AttackTokens {
+    // enemies of a group that may attack at once
+    max_attackers: i32,
+    // the instance ids of the enemies holding a token, by group
+    holders: HashMap<String, Vec<i64>>,
+}
+
+fn register_properties(builder: &ClassBuilder<AttackTokens>) {
+    builder
+        .add_property::<i32>("max_attackers")
+        .with_default(2)
+        .with_getter(|tokens: &AttackTokens, _| tokens.max_attackers)
+        .with_setter(|tokens: &mut AttackTokens, _, max| tokens.max_attackers = max)
+        .done();
+}
+
+impl Default for AttackTokens {
+    fn default() -> Self {
+        Self {
+            max_attackers: 2,
+            holders: HashMap::new(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl AttackTokens {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    /// Hands the enemy one of its group's tokens, returning whether it may
+    /// attack. An enemy already holding one keeps it.
+    pub fn request_attack(&mut self, group: &str, id: i64) -> bool {
+        let holders = self.holders.entry(group.to_string()).or_default();
+
+        if holders.contains(&id) {
+            return true;
+        }
+
+        if holders.len() as i32 >= self.max_attackers {
+            return false;
+        }
+
+        holders.push(id);
+
+        true
+    }
+
+    pub fn release_attack(&mut self, group: &str, id: i64) {
+        if let Some(holders) = self.holders.get_mut(group) {
+            holders.retain(|holder| *holder != id);
+        }
+    }
+}
+
+fn with_tokens<T>(owner: &Node, f: impl FnOnce(&mut AttackTokens) -> T) -> Option<T> {
+    let tokens_node = unsafe { owner.get_typed_node::<Node, _>("/root/AttackTokens") };
+
+    tokens_node
+        .cast_instance::<AttackTokens>()?
+        .map_mut(|tokens, _| f(tokens))
+        .ok()
+}
+
+/// Requests an attack token of the group for the enemy through the
+/// `AttackTokens` autoload. Without the autoload, every enemy may attack.
+pub fn request_attack(owner: &Node, group: &str) -> bool {
+    let id = owner.get_instance_id();
+
+    with_tokens(owner, |tokens| tokens.request_attack(group, id)).unwrap_or(true)
+}
+
+pub fn release_attack(owner: &Node, group: &str) {
+    let id = owner.get_instance_id();
+
+    // the tokens may already be gone when the game quits
+    if owner.has_node("/root/AttackTokens") {
+        with_tokens(owner, |tokens| tokens.release_attack(group, id));
+    }
+}
+
+#[test]
+fn test_tokens_cap_concurrent_attackers() {
+    let mut tokens = AttackTokens::default();
+
+    assert!(tokens.request_attack("pack", 1));
+    assert!(tokens.request_attack("pack", 2));
+    assert!(!tokens.request_attack("pack", 3));
+    assert_eq!(tokens.holders["pack"].len(), 2);
+}
+
+#[test]
+fn test_released_token_goes_to_a_waiting_enemy() {
+    let mut tokens = AttackTokens::default();
+
+    tokens.request_attack("pack", 1);
+    tokens.request_attack("pack", 2);
+    tokens.release_attack("pack", 1);
+
+    assert!(tokens.request_attack("pack", 3));
+    assert!(!tokens.request_attack("pack", 1));
+}
+
+#[test]
+fn test_holder_requesting_again_keeps_its_token() {
+    let mut tokens = AttackTokens {
+        max_attackers: 1,
+        ..AttackTokens::default()
+    };
+
+    assert!(tokens.request_attack("pack", 1));
+    assert!(tokens.request_attack("pack", 1));
+    assert_eq!(tokens.holders["pack"].len(), 1);
+}
+
+#[test]
+fn test_groups_have_their_own_tokens() {
+    let mut tokens = AttackTokens {
+        max_attackers: 1,
+        ..AttackTokens::default()
+    };
+
+    assert!(tokens.request_attack("pack", 1));
+    assert!(tokens.request_attack("swarm", 2));
+    assert!(!tokens.request_attack("pack", 3));
+}
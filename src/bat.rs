@@ -1,18 +1,723 @@
+use crate::aggro::{self, AggroTarget};
+use crate::attack_tokens;
+use crate::boss_bar;
+use crate::coin_pickup::CoinPickup;
+use crate::combat::Hit;
+use crate::cooldown::Cooldown;
+use crate::damage_bus;
+use crate::damage_number::{DamageNumber, Popup};
+use crate::debug_draw;
+use crate::duelist::Duelist;
+use crate::enemy_profiler;
+use crate::entity_registry::{self, Kind};
+use crate::explosion;
 use crate::extensions::NodeExt;
+use crate::feign_death::FeignDeath;
+use crate::hover::Hover;
 use crate::load_scene;
+use crate::parry::{Parry, ParryStagger};
+use crate::phasing::Phasing;
+use crate::player::Player;
+use crate::player_stats::PlayerStats;
+use crate::projectile::{Projectile, Team};
+use crate::revenant::Revenant;
+use crate::rng::Rng;
+use crate::runner::{self, Runner};
+use crate::screen_flash;
+use crate::settings;
+use crate::shell::Shell;
+use crate::slow_mo;
 use crate::stats;
 use crate::stats::Stats;
-use crate::sword_hitbox::SwordHitbox;
-use gdnative::api::Area2D;
+use crate::thief::Thief;
+use crate::tracker::Tracker;
+use crate::wander_controller::WanderController;
+use gdnative::api::{AnimatedSprite, Area2D};
 use gdnative::prelude::{
-    KinematicBody2D, NativeClass, Node, Node2D, Ref, TRef, Vector2, Vector2Godot,
+    ClassBuilder, Color, GodotObject, KinematicBody2D, Label, NativeClass, Node, Node2D, Ref,
+    RefInstance, Shared, Signal, TRef, Variant, VariantArray, Vector2, Vector2Godot,
 };
+use std::time::Instant;
+
+const DAMAGE_NUMBER_OFFSET: Vector2 = Vector2::new(-8.0, -32.0);
+const FLASH_DURATION: f32 = 0.1;
+// pixels of debug arrow per pixel per second of knockback
+const KNOCKBACK_ARROW_SCALE: f32 = 0.25;
+const CRIT_FLASH_DURATION: f32 = 0.2;
+const EXECUTE_EFFECT_SCALE: f32 = 2.0;
+// how long an aura buff lasts once the ally leaves the aura
+const AURA_BUFF_DURATION: f32 = 0.5;
+// cosine of the widest angle between the bat's facing and a hit's direction
+// that still counts as a hit from behind, roughly 60 degrees
+const BACKSTAB_FACING: f32 = 0.5;
+const COUNTER_LUNGE_SPEED: f32 = 150.0;
+// a bat knocked back at least this fast knocks into the enemies it touches
+const CHAIN_MIN_SPEED: f32 = 60.0;
+const CHAIN_BUMP_RADIUS: f32 = 12.0;
+// share of the knockback passed on to a bumped enemy
+const CHAIN_KNOCKBACK_TRANSFER: f32 = 0.8;
+const ACCELERATION: f32 = 300.0;
+const MAX_SPEED: f32 = 50.0;
+const FRICTION: f32 = 200.0;
+const WANDER_TARGET_RANGE: f32 = 4.0;
+const MIN_WANDER_TIME: f32 = 1.0;
+const MAX_WANDER_TIME: f32 = 3.0;
+
+pub(crate) enum State {
+    // materializing, can't be hurt and doesn't move until the timer ends
+    Spawning(f32),
+    // poise broken, doesn't move or attack until the timer ends
+    Staggered(f32),
+    // lying still at one health, revives when a player comes close or the
+    // timer ends, but can still be finished off
+    FeigningDeath(f32),
+    // running away from the player after stealing from them
+    Fleeing(f32),
+    // running back to the nest after being hurt, healing once there
+    FleeingToNest,
+    // winding up a counter to an attack the player started nearby
+    CounterWindup(f32),
+    // dead, sliding along the killing blow's knockback while fading out
+    DeathSlide { time_left: f32, effect_scale: f32 },
+    Idle,
+    Wander,
+    Chase,
+    // lost sight of its target, flying to where it was last seen until the
+    // timer ends
+    Search(f32),
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
 
 #[derive(NativeClass)]
 #[inherit(KinematicBody2D)]
-#[derive(Default)]
+#[register_with(register_properties)]
 pub struct Bat {
     knockback: Vector2,
+    // knockback speed lost per second
+    knockback_friction: f32,
+    // the fastest any knockback can launch the bat, so it isn't flung off
+    // the map
+    max_knockback_speed: f32,
+    velocity: Vector2,
+    state: State,
+    flash: Cooldown,
+    pub(crate) damage: i32,
+    drops_key: bool,
+    drops_weapon: bool,
+    drops_magnet: bool,
+    // whether the last frame drew debug shapes, so they get cleared
+    debug_drawn: bool,
+    detection_range: f32,
+    // seconds spent searching for a target after losing sight of it
+    search_duration: f32,
+    last_seen: Vector2,
+    // the target picked the last time the bat thought
+    chase_target: Option<Vector2>,
+    // instance id of the last damage number, grown by aggregated hits
+    damage_number: Option<i64>,
+    deflects: bool,
+    deflect_cooldown: Cooldown,
+    spawn_duration: f32,
+    enrage: Enrage,
+    hazard_on_attack: bool,
+    // a warlord's aura buffs the damage of allies around it, a zero radius
+    // means the bat has no aura
+    aura_radius: f32,
+    aura_damage_multiplier: f32,
+    damage_buff: DamageBuff,
+    poise: Poise,
+    feign_death: FeignDeath,
+    facing: Vector2,
+    // radians per second, a player strafing faster than this stays behind
+    // the bat
+    turn_speed: f32,
+    backstab_multiplier: f32,
+    // zero frees the bat as soon as it dies
+    death_slide_duration: f32,
+    thief: Thief,
+    duelist: Duelist,
+    knockback_chain: KnockbackChain,
+    // smallest chain that gets a popup, zero hides them
+    chain_popup_threshold: i32,
+    phasing: Phasing,
+    runner: Runner,
+    parry_stagger: ParryStagger,
+    shell: Shell,
+    parry: Parry,
+    // shown on the boss bar, empty for bats that aren't bosses
+    boss_name: String,
+    boss_phases: i32,
+    // where the bat was placed, for practice mode to put it back
+    spawn_position: Vector2,
+    // stands still and can't be killed, for the combo trial
+    training_dummy: bool,
+    rng: Rng,
+    tracker: Tracker,
+    // bobs the sprite while idle or wandering
+    hover: Hover,
+    // the sprite's offset from the scene, what the bob is added to
+    sprite_offset: Vector2,
+    // enemies sharing an attack group take turns countering, empty for a
+    // bat that counters whenever it likes
+    attack_group: String,
+    holds_attack_token: bool,
+    revenant: Revenant,
+}
+
+// enemies knocked into each other, one after the other, by a single
+// knockback
+#[derive(Default)]
+struct KnockbackChain {
+    count: i32,
+    // instance ids of the enemies already part of the chain
+    bumped: Vec<i64>,
+}
+
+impl KnockbackChain {
+    /// Returns the grown chain count, or `None` when the enemy was already
+    /// knocked by this chain.
+    fn bump(&mut self, id: i64) -> Option<i32> {
+        if self.bumped.contains(&id) {
+            return None;
+        }
+
+        self.bumped.push(id);
+        // the knocked back bat is the first link
+        self.count = self.count.max(1) + 1;
+
+        Some(self.count)
+    }
+
+    /// Continues a chain that bumped into this bat.
+    fn join(&mut self, count: i32, from: i64) {
+        self.count = count;
+        self.bumped = vec![from];
+    }
+
+    fn settle(&mut self) {
+        self.count = 0;
+        self.bumped.clear();
+    }
+}
+
+struct DamageBuff {
+    multiplier: f32,
+    expiry: Cooldown,
+}
+
+impl Default for DamageBuff {
+    fn default() -> Self {
+        Self {
+            multiplier: 0.0,
+            expiry: Cooldown::new(AURA_BUFF_DURATION),
+        }
+    }
+}
+
+impl DamageBuff {
+    // refreshes the buff, keeping the strongest one if several auras overlap
+    fn apply(&mut self, multiplier: f32) {
+        if self.expiry.is_ready() || multiplier > self.multiplier {
+            self.multiplier = multiplier;
+        }
+
+        self.expiry.start();
+    }
+
+    fn tick(&mut self, delta: f32) {
+        self.expiry.tick(delta);
+    }
+
+    fn multiplier(&self) -> f32 {
+        if !self.expiry.is_ready() {
+            self.multiplier
+        } else {
+            1.0
+        }
+    }
+}
+
+struct Poise {
+    // damage taken in quick succession that breaks the bat's attack, zero
+    // means it never staggers
+    max: i32,
+    // runs for as long without a hit as the taken damage is remembered
+    reset: Cooldown,
+    stagger_duration: f32,
+    accumulated: i32,
+}
+
+impl Default for Poise {
+    fn default() -> Self {
+        Self {
+            max: 0,
+            reset: Cooldown::new(1.0),
+            stagger_duration: 0.6,
+            accumulated: 0,
+        }
+    }
+}
+
+impl Poise {
+    /// Returns whether the damage broke the poise.
+    fn take_damage(&mut self, damage: i32) -> bool {
+        if self.max <= 0 {
+            return false;
+        }
+
+        self.accumulated += damage;
+        self.reset.start();
+
+        if self.accumulated < self.max {
+            return false;
+        }
+
+        self.accumulated = 0;
+
+        true
+    }
+
+    fn tick(&mut self, delta: f32) {
+        self.reset.tick(delta);
+
+        if self.reset.is_ready() {
+            self.accumulated = 0;
+        }
+    }
+}
+
+struct Enrage {
+    // fraction of the max health below which the bat enrages, zero disables it
+    threshold: f32,
+    speed_multiplier: f32,
+    damage_multiplier: f32,
+    is_enraged: bool,
+    // seconds after spawning at which the bat enrages for good, so boss
+    // fights can't be stalled, zero disables it
+    time_limit: f32,
+    elapsed: f32,
+    timed_out: bool,
+}
+
+impl Default for Enrage {
+    fn default() -> Self {
+        Self {
+            threshold: 0.25,
+            speed_multiplier: 1.5,
+            damage_multiplier: 2.0,
+            is_enraged: false,
+            time_limit: 0.0,
+            elapsed: 0.0,
+            timed_out: false,
+        }
+    }
+}
+
+impl Enrage {
+    fn tick_time_limit(&mut self, delta: f32) {
+        if self.time_limit <= 0.0 || self.timed_out {
+            return;
+        }
+
+        self.elapsed += delta;
+        self.timed_out = self.elapsed >= self.time_limit;
+    }
+
+    fn time_left(&self) -> Option<f32> {
+        if self.time_limit <= 0.0 {
+            return None;
+        }
+
+        Some((self.time_limit - self.elapsed).max(0.0))
+    }
+}
+
+fn register_properties(builder: &ClassBuilder<Bat>) {
+    builder
+        .add_property::<f32>("hover_amplitude")
+        .with_default(2.0)
+        .with_getter(|bat: &Bat, _| bat.hover.amplitude)
+        .with_setter(|bat: &mut Bat, _, amplitude| bat.hover.amplitude = amplitude)
+        .done();
+    builder
+        .add_property::<f32>("hover_frequency")
+        .with_default(0.8)
+        .with_getter(|bat: &Bat, _| bat.hover.frequency)
+        .with_setter(|bat: &mut Bat, _, frequency| bat.hover.frequency = frequency)
+        .done();
+    builder
+        .add_property::<f32>("knockback_friction")
+        .with_default(200.0)
+        .with_getter(|bat: &Bat, _| bat.knockback_friction)
+        .with_setter(|bat: &mut Bat, _, friction| bat.knockback_friction = friction)
+        .done();
+    builder
+        .add_property::<f32>("max_knockback_speed")
+        .with_default(600.0)
+        .with_getter(|bat: &Bat, _| bat.max_knockback_speed)
+        .with_setter(|bat: &mut Bat, _, speed| bat.max_knockback_speed = speed)
+        .done();
+    builder
+        .add_property::<i32>("damage")
+        .with_default(1)
+        .with_getter(|bat: &Bat, _| bat.damage)
+        .with_setter(|bat: &mut Bat, _, damage| bat.damage = damage)
+        .done();
+    builder
+        .add_property::<bool>("drops_key")
+        .with_getter(|bat: &Bat, _| bat.drops_key)
+        .with_setter(|bat: &mut Bat, _, drops_key| bat.drops_key = drops_key)
+        .done();
+    builder
+        .add_property::<bool>("drops_weapon")
+        .with_getter(|bat: &Bat, _| bat.drops_weapon)
+        .with_setter(|bat: &mut Bat, _, drops_weapon| bat.drops_weapon = drops_weapon)
+        .done();
+    builder
+        .add_property::<bool>("drops_magnet")
+        .with_getter(|bat: &Bat, _| bat.drops_magnet)
+        .with_setter(|bat: &mut Bat, _, drops_magnet| bat.drops_magnet = drops_magnet)
+        .done();
+    builder
+        .add_property::<f32>("detection_range")
+        .with_default(64.0)
+        .with_getter(|bat: &Bat, _| bat.detection_range)
+        .with_setter(|bat: &mut Bat, _, detection_range| bat.detection_range = detection_range)
+        .done();
+    builder
+        .add_property::<f32>("search_duration")
+        .with_default(2.0)
+        .with_getter(|bat: &Bat, _| bat.search_duration)
+        .with_setter(|bat: &mut Bat, _, duration| bat.search_duration = duration)
+        .done();
+    builder
+        .add_property::<bool>("deflects")
+        .with_getter(|bat: &Bat, _| bat.deflects)
+        .with_setter(|bat: &mut Bat, _, deflects| bat.deflects = deflects)
+        .done();
+    builder
+        .add_property::<f32>("deflect_cooldown")
+        .with_default(1.0)
+        .with_getter(|bat: &Bat, _| bat.deflect_cooldown.duration)
+        .with_setter(|bat: &mut Bat, _, cooldown| bat.deflect_cooldown.duration = cooldown)
+        .done();
+    builder
+        .add_property::<f32>("spawn_duration")
+        .with_default(0.5)
+        .with_getter(|bat: &Bat, _| bat.spawn_duration)
+        .with_setter(|bat: &mut Bat, _, duration| bat.spawn_duration = duration)
+        .done();
+    builder
+        .add_property::<f32>("enrage_threshold")
+        .with_default(0.25)
+        .with_getter(|bat: &Bat, _| bat.enrage.threshold)
+        .with_setter(|bat: &mut Bat, _, threshold| bat.enrage.threshold = threshold)
+        .done();
+    builder
+        .add_property::<f32>("enrage_speed_multiplier")
+        .with_default(1.5)
+        .with_getter(|bat: &Bat, _| bat.enrage.speed_multiplier)
+        .with_setter(|bat: &mut Bat, _, multiplier| bat.enrage.speed_multiplier = multiplier)
+        .done();
+    builder
+        .add_property::<f32>("enrage_damage_multiplier")
+        .with_default(2.0)
+        .with_getter(|bat: &Bat, _| bat.enrage.damage_multiplier)
+        .with_setter(|bat: &mut Bat, _, multiplier| bat.enrage.damage_multiplier = multiplier)
+        .done();
+    builder
+        .add_property::<f32>("enrage_time_limit")
+        .with_getter(|bat: &Bat, _| bat.enrage.time_limit)
+        .with_setter(|bat: &mut Bat, _, time_limit| bat.enrage.time_limit = time_limit)
+        .done();
+    builder
+        .add_property::<bool>("hazard_on_attack")
+        .with_getter(|bat: &Bat, _| bat.hazard_on_attack)
+        .with_setter(|bat: &mut Bat, _, hazard_on_attack| bat.hazard_on_attack = hazard_on_attack)
+        .done();
+    builder
+        .add_property::<f32>("aura_radius")
+        .with_getter(|bat: &Bat, _| bat.aura_radius)
+        .with_setter(|bat: &mut Bat, _, radius| bat.aura_radius = radius)
+        .done();
+    builder
+        .add_property::<f32>("aura_damage_multiplier")
+        .with_default(1.5)
+        .with_getter(|bat: &Bat, _| bat.aura_damage_multiplier)
+        .with_setter(|bat: &mut Bat, _, multiplier| bat.aura_damage_multiplier = multiplier)
+        .done();
+    builder
+        .add_property::<i32>("poise")
+        .with_getter(|bat: &Bat, _| bat.poise.max)
+        .with_setter(|bat: &mut Bat, _, poise| bat.poise.max = poise)
+        .done();
+    builder
+        .add_property::<f32>("poise_reset_time")
+        .with_default(1.0)
+        .with_getter(|bat: &Bat, _| bat.poise.reset.duration)
+        .with_setter(|bat: &mut Bat, _, reset_time| bat.poise.reset.duration = reset_time)
+        .done();
+    builder
+        .add_property::<f32>("stagger_duration")
+        .with_default(0.6)
+        .with_getter(|bat: &Bat, _| bat.poise.stagger_duration)
+        .with_setter(|bat: &mut Bat, _, duration| bat.poise.stagger_duration = duration)
+        .done();
+    builder
+        .add_property::<bool>("feigns_death")
+        .with_getter(|bat: &Bat, _| bat.feign_death.enabled)
+        .with_setter(|bat: &mut Bat, _, enabled| bat.feign_death.enabled = enabled)
+        .done();
+    builder
+        .add_property::<f32>("feign_revive_range")
+        .with_default(32.0)
+        .with_getter(|bat: &Bat, _| bat.feign_death.revive_range)
+        .with_setter(|bat: &mut Bat, _, range| bat.feign_death.revive_range = range)
+        .done();
+    builder
+        .add_property::<f32>("feign_revive_time")
+        .with_default(4.0)
+        .with_getter(|bat: &Bat, _| bat.feign_death.revive_time)
+        .with_setter(|bat: &mut Bat, _, time| bat.feign_death.revive_time = time)
+        .done();
+    builder
+        .add_property::<f32>("turn_speed")
+        .with_default(4.0)
+        .with_getter(|bat: &Bat, _| bat.turn_speed)
+        .with_setter(|bat: &mut Bat, _, turn_speed| bat.turn_speed = turn_speed)
+        .done();
+    builder
+        .add_property::<f32>("backstab_multiplier")
+        .with_default(1.5)
+        .with_getter(|bat: &Bat, _| bat.backstab_multiplier)
+        .with_setter(|bat: &mut Bat, _, multiplier| bat.backstab_multiplier = multiplier)
+        .done();
+    builder
+        .add_property::<f32>("vulnerable_arc")
+        .with_default(360.0)
+        .with_getter(|bat: &Bat, _| bat.shell.vulnerable_arc)
+        .with_setter(|bat: &mut Bat, _, arc| bat.shell.vulnerable_arc = arc)
+        .done();
+    builder
+        .add_property::<f32>("death_slide_duration")
+        .with_getter(|bat: &Bat, _| bat.death_slide_duration)
+        .with_setter(|bat: &mut Bat, _, duration| bat.death_slide_duration = duration)
+        .done();
+    builder
+        .add_property::<i32>("steal_amount")
+        .with_getter(|bat: &Bat, _| bat.thief.steal_amount)
+        .with_setter(|bat: &mut Bat, _, amount| bat.thief.steal_amount = amount)
+        .done();
+    builder
+        .add_property::<f32>("flee_time")
+        .with_default(2.0)
+        .with_getter(|bat: &Bat, _| bat.thief.flee_time)
+        .with_setter(|bat: &mut Bat, _, flee_time| bat.thief.flee_time = flee_time)
+        .done();
+    builder
+        .add_property::<bool>("duelist")
+        .with_getter(|bat: &Bat, _| bat.duelist.enabled)
+        .with_setter(|bat: &mut Bat, _, enabled| bat.duelist.enabled = enabled)
+        .done();
+    builder
+        .add_property::<f32>("counter_range")
+        .with_default(40.0)
+        .with_getter(|bat: &Bat, _| bat.duelist.range)
+        .with_setter(|bat: &mut Bat, _, range| bat.duelist.range = range)
+        .done();
+    builder
+        .add_property::<f32>("counter_windup")
+        .with_default(0.3)
+        .with_getter(|bat: &Bat, _| bat.duelist.windup)
+        .with_setter(|bat: &mut Bat, _, windup| bat.duelist.windup = windup)
+        .done();
+    builder
+        .add_property::<i32>("chain_popup_threshold")
+        .with_default(2)
+        .with_getter(|bat: &Bat, _| bat.chain_popup_threshold)
+        .with_setter(|bat: &mut Bat, _, threshold| bat.chain_popup_threshold = threshold)
+        .done();
+    builder
+        .add_property::<bool>("phases_through_walls")
+        .with_getter(|bat: &Bat, _| bat.phasing.enabled)
+        .with_setter(|bat: &mut Bat, _, phases| bat.phasing.enabled = phases)
+        .done();
+    builder
+        .add_property::<f32>("parry_stagger_duration")
+        .with_default(1.0)
+        .with_getter(|bat: &Bat, _| bat.parry_stagger.duration)
+        .with_setter(|bat: &mut Bat, _, duration| bat.parry_stagger.duration = duration)
+        .done();
+    builder
+        .add_property::<f32>("parry_damage_multiplier")
+        .with_default(2.0)
+        .with_getter(|bat: &Bat, _| bat.parry_stagger.damage_multiplier)
+        .with_setter(|bat: &mut Bat, _, multiplier| {
+            bat.parry_stagger.damage_multiplier = multiplier
+        })
+        .done();
+    builder
+        .add_property::<f32>("parry_chance")
+        .with_getter(|bat: &Bat, _| bat.parry.chance)
+        .with_setter(|bat: &mut Bat, _, chance| bat.parry.chance = chance)
+        .done();
+    builder
+        .add_property::<f32>("parry_cooldown")
+        .with_default(2.0)
+        .with_getter(|bat: &Bat, _| bat.parry.cooldown.duration)
+        .with_setter(|bat: &mut Bat, _, cooldown| bat.parry.cooldown.duration = cooldown)
+        .done();
+    builder
+        .add_property::<String>("boss_name")
+        .with_ref_getter(|bat: &Bat, _| &bat.boss_name)
+        .with_setter(|bat: &mut Bat, _, name| bat.boss_name = name)
+        .done();
+    builder
+        .add_property::<i32>("boss_phases")
+        .with_default(3)
+        .with_getter(|bat: &Bat, _| bat.boss_phases)
+        .with_setter(|bat: &mut Bat, _, phases| bat.boss_phases = phases)
+        .done();
+    builder
+        .add_property::<bool>("training_dummy")
+        .with_getter(|bat: &Bat, _| bat.training_dummy)
+        .with_setter(|bat: &mut Bat, _, training_dummy| bat.training_dummy = training_dummy)
+        .done();
+    builder
+        .add_property::<bool>("revenant")
+        .with_getter(|bat: &Bat, _| bat.revenant.enabled)
+        .with_setter(|bat: &mut Bat, _, enabled| bat.revenant.enabled = enabled)
+        .done();
+    builder
+        .add_property::<f32>("revenant_damage_per_death")
+        .with_default(0.25)
+        .with_getter(|bat: &Bat, _| bat.revenant.damage_per_death)
+        .with_setter(|bat: &mut Bat, _, damage| bat.revenant.damage_per_death = damage)
+        .done();
+    builder
+        .add_property::<f32>("revenant_speed_per_death")
+        .with_default(0.1)
+        .with_getter(|bat: &Bat, _| bat.revenant.speed_per_death)
+        .with_setter(|bat: &mut Bat, _, speed| bat.revenant.speed_per_death = speed)
+        .done();
+    builder
+        .add_property::<i32>("revenant_max_stacks")
+        .with_default(8)
+        .with_getter(|bat: &Bat, _| bat.revenant.max_stacks)
+        .with_setter(|bat: &mut Bat, _, stacks| bat.revenant.max_stacks = stacks)
+        .done();
+    builder
+        .add_property::<bool>("runner")
+        .with_getter(|bat: &Bat, _| bat.runner.enabled)
+        .with_setter(|bat: &mut Bat, _, enabled| bat.runner.enabled = enabled)
+        .done();
+    builder
+        .add_property::<String>("nest")
+        .with_ref_getter(|bat: &Bat, _| &bat.runner.nest)
+        .with_setter(|bat: &mut Bat, _, nest| bat.runner.nest = nest)
+        .done();
+    builder
+        .add_property::<i32>("nest_heal")
+        .with_default(2)
+        .with_getter(|bat: &Bat, _| bat.runner.heal)
+        .with_setter(|bat: &mut Bat, _, heal| bat.runner.heal = heal)
+        .done();
+    builder
+        .add_property::<f32>("trail_interval")
+        .with_default(0.25)
+        .with_getter(|bat: &Bat, _| bat.runner.trail.duration)
+        .with_setter(|bat: &mut Bat, _, interval| bat.runner.trail.duration = interval)
+        .done();
+    builder
+        .add_property::<bool>("tracker")
+        .with_getter(|bat: &Bat, _| bat.tracker.enabled)
+        .with_setter(|bat: &mut Bat, _, tracker| bat.tracker.enabled = tracker)
+        .done();
+    builder
+        .add_property::<f32>("tracker_spacing")
+        .with_default(16.0)
+        .with_getter(|bat: &Bat, _| bat.tracker.trail.spacing)
+        .with_setter(|bat: &mut Bat, _, spacing| bat.tracker.trail.spacing = spacing)
+        .done();
+    // waypoints a tracker remembers, it skips ahead when left further behind
+    builder
+        .add_property::<i32>("tracker_length")
+        .with_default(24)
+        .with_getter(|bat: &Bat, _| bat.tracker.trail.capacity as i32)
+        .with_setter(|bat: &mut Bat, _, length| bat.tracker.trail.capacity = length.max(1) as usize)
+        .done();
+    builder
+        .add_property::<String>("attack_group")
+        .with_ref_getter(|bat: &Bat, _| &bat.attack_group)
+        .with_setter(|bat: &mut Bat, _, group| bat.attack_group = group)
+        .done();
+
+    builder.add_signal(Signal {
+        name: "enraged",
+        args: &[],
+    });
+}
+
+impl Default for Bat {
+    fn default() -> Self {
+        Self {
+            knockback: Vector2::zero(),
+            knockback_friction: 200.0,
+            max_knockback_speed: 600.0,
+            velocity: Vector2::zero(),
+            state: State::default(),
+            flash: Cooldown::default(),
+            damage: 1,
+            drops_key: false,
+            drops_weapon: false,
+            drops_magnet: false,
+            debug_drawn: false,
+            detection_range: 64.0,
+            search_duration: 2.0,
+            last_seen: Vector2::zero(),
+            chase_target: None,
+            damage_number: None,
+            deflects: false,
+            deflect_cooldown: Cooldown::new(1.0),
+            spawn_duration: 0.5,
+            enrage: Enrage::default(),
+            hazard_on_attack: false,
+            aura_radius: 0.0,
+            aura_damage_multiplier: 1.5,
+            damage_buff: DamageBuff::default(),
+            poise: Poise::default(),
+            feign_death: FeignDeath::default(),
+            facing: Vector2::new(1.0, 0.0),
+            turn_speed: 4.0,
+            backstab_multiplier: 1.5,
+            death_slide_duration: 0.0,
+            thief: Thief::default(),
+            duelist: Duelist::default(),
+            knockback_chain: KnockbackChain::default(),
+            chain_popup_threshold: 2,
+            phasing: Phasing::default(),
+            runner: Runner::default(),
+            parry_stagger: ParryStagger::default(),
+            shell: Shell::default(),
+            parry: Parry::default(),
+            boss_name: String::new(),
+            boss_phases: 3,
+            spawn_position: Vector2::zero(),
+            training_dummy: false,
+            rng: Rng::default(),
+            tracker: Tracker::default(),
+            hover: Hover::new(2.0, 0.8),
+            sprite_offset: Vector2::zero(),
+            attack_group: String::new(),
+            holds_attack_token: false,
+            revenant: Revenant::default(),
+        }
+    }
 }
 
 #[gdnative::methods]
@@ -22,50 +727,1826 @@ impl Bat {
     }
 
     #[export]
-    fn _process(&mut self, _owner: &KinematicBody2D, delta: f32) {
-        self.knockback = self.knockback.move_towards(Vector2::zero(), 200.0 * delta);
+    fn _ready(&mut self, owner: TRef<KinematicBody2D>) {
+        owner.add_to_group("enemies", false);
+        entity_registry::register(&owner, Kind::Enemy);
+
+        self.spawn_position = owner.global_position();
+        self.sprite_offset =
+            unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") }.offset();
+
+        self.scale_for_new_game_plus(&owner);
+
+        if self.spawn_duration > 0.0 {
+            self.state = State::Spawning(self.spawn_duration);
+        }
+
+        if self.is_boss() {
+            let stats = unsafe { owner.get_typed_node::<Node, _>("Stats") };
+
+            boss_bar::show(&owner, &self.boss_name, self.boss_phases, stats);
+        }
+
+        if self.revenant.enabled {
+            let bus = unsafe { owner.get_typed_node::<Node, _>("/root/DamageBus") };
+
+            let _ = bus.connect(
+                "entity_died",
+                owner,
+                "_on_entity_died",
+                VariantArray::new_shared(),
+                0,
+            );
+        }
     }
 
     #[export]
-    fn _physics_process(&mut self, owner: &KinematicBody2D, _delta: f32) {
-        self.knockback =
-            owner.move_and_slide(self.knockback, Vector2::zero(), false, 4, 0.785398, true);
+    fn _on_entity_died(&mut self, owner: &KinematicBody2D, entity: Variant) {
+        // an entity that's already gone can't be told apart from a player
+        let entity = match entity
+            .try_to_object::<Node>()
+            .and_then(|entity| unsafe { entity.assume_safe_if_sane() })
+        {
+            Some(entity) => entity,
+            None => return,
+        };
+
+        if entity.get_instance_id() != owner.get_instance_id() && !entity.is_in_group("player") {
+            self.revenant.record_ally_death();
+        }
     }
 
     #[export]
-    #[allow(non_snake_case)]
-    fn _on_Hurtbox_area_entered(&mut self, owner: &KinematicBody2D, _x: Ref<Area2D>) {
-        let sword_hitbox_node =
-            unsafe { owner.get_typed_node::<Area2D, _>("../Player/HitboxPivot/SwordHitbox") };
-        let stats_node = unsafe { owner.get_typed_node::<Node, _>("Stats") };
+    fn _exit_tree(&mut self, owner: &KinematicBody2D) {
+        entity_registry::unregister(owner);
+        self.release_attack_token(owner);
+    }
 
-        let sword_hitbox_instance = sword_hitbox_node.cast_instance::<SwordHitbox>().unwrap();
+    // the stats are ready before the bat, so their health is set already
+    fn scale_for_new_game_plus(&mut self, owner: &KinematicBody2D) {
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+        let stats_node = unsafe { owner.get_typed_node::<Node, _>("Stats") };
         let stats_instance = stats_node.cast_instance::<Stats>().unwrap();
 
         let _ = stats_instance.map_mut(|stats, _| {
-            if let stats::State::Dead = stats.receive_damage(1) {
-                owner.queue_free();
+            let _ = player_stats_instance.map(|player_stats, _| {
+                stats.max_health = player_stats.scale_enemy_health(stats.max_health);
+                stats.health = stats.max_health;
+                self.damage = player_stats.scale_enemy_damage(self.damage);
+            });
+        });
+    }
 
-                self.run_death_animation(owner);
-            } else {
-                let _ = sword_hitbox_instance.map(|sword_hitbox, _| {
-                    self.knockback = sword_hitbox.knockback_vector * 120.0;
-                });
+    pub fn is_boss(&self) -> bool {
+        !self.boss_name.is_empty()
+    }
+
+    /// Puts the bat back the way it spawned, for practice mode, returning
+    /// where it goes.
+    pub fn reset(&mut self) -> Vector2 {
+        self.state = State::Idle;
+        self.velocity = Vector2::zero();
+        self.knockback = Vector2::zero();
+        // calmed down by the next enrage check, which updates the color
+        self.enrage.elapsed = 0.0;
+        self.enrage.timed_out = false;
+
+        self.spawn_position
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &KinematicBody2D, delta: f32) {
+        let started = Instant::now();
+
+        self.update(owner, delta);
+
+        enemy_profiler::record_update(owner, started.elapsed());
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &KinematicBody2D, delta: f32) {
+        let started = Instant::now();
+
+        self.physics_update(owner, delta);
+
+        enemy_profiler::record_update(owner, started.elapsed());
+    }
+
+    fn update(&mut self, owner: &KinematicBody2D, delta: f32) {
+        let delta = settings::game_delta(owner, delta);
+
+        debug_draw::refresh(owner, &mut self.debug_drawn);
+
+        self.decay_knockback(delta);
+        self.deflect_cooldown.tick(delta);
+        self.damage_buff.tick(delta);
+        self.parry.tick(delta);
+        self.poise.tick(delta);
+
+        if self.tick_stagger(delta) {
+            self.set_attacking(owner, true);
+        }
+
+        self.tick_flee(delta);
+
+        // the token is given back however the windup ended
+        if !matches!(self.state, State::CounterWindup(_)) {
+            self.release_attack_token(owner);
+        }
+
+        if let State::FeigningDeath(_) = self.state {
+            let closest_player = self.closest_player_distance(owner);
+
+            if self.tick_feign_death(delta, closest_player) {
+                self.revive(owner);
+            }
+        }
+
+        self.bob(owner, delta);
+
+        if let State::Spawning(_) = self.state {
+            let progress = self.tick_spawn(delta);
+
+            let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+            sprite.set_modulate(Color::rgba(1.0, 1.0, 1.0, progress));
+        }
+
+        if self.can_act() {
+            self.enrage.tick_time_limit(delta);
+        }
+
+        if self.is_boss() {
+            boss_bar::set_time_left(owner, self.enrage.time_left());
+        }
+
+        // checked every frame so healing from anywhere calms the bat down
+        let (health, max_health) = self.health_and_max(owner);
+
+        if let Some(is_enraged) = self.update_enrage(health, max_health) {
+            if is_enraged {
+                owner.emit_signal("enraged", &[]);
+            }
+
+            let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+            sprite.set_modulate(self.base_color());
+        }
+
+        if !self.flash.is_ready() {
+            self.flash.tick(delta);
+
+            if self.flash.is_ready() {
+                let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+                sprite.set_modulate(self.base_color());
+            }
+        }
+
+        if let State::DeathSlide { effect_scale, .. } = self.state {
+            match self.tick_death_slide(delta) {
+                Some(alpha) => {
+                    let sprite =
+                        unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+                    sprite.set_modulate(Color::rgba(1.0, 1.0, 1.0, alpha));
+                }
+                None => {
+                    owner.queue_free();
+
+                    self.run_death_animation(owner, effect_scale);
+                }
+            }
+        }
+    }
+
+    #[export]
+    fn _draw(&self, owner: &KinematicBody2D) {
+        if !settings::debug_draw(owner) {
+            return;
+        }
+
+        if let Some(arrow) = debug_draw::knockback_arrow(self.knockback, KNOCKBACK_ARROW_SCALE) {
+            debug_draw::draw_arrow(owner, &arrow, Color::rgb(1.0, 0.3, 0.3));
+        }
+    }
+
+    fn physics_update(&mut self, owner: &KinematicBody2D, delta: f32) {
+        if let State::DeathSlide { .. } = self.state {
+            self.knockback =
+                owner.move_and_slide(self.knockback, Vector2::zero(), false, 4, 0.785398, true);
+        }
+
+        if !self.can_act() || self.training_dummy {
+            return;
+        }
+
+        let delta = settings::game_delta(owner, delta);
+
+        if self.aura_radius > 0.0 {
+            self.buff_allies(owner);
+        }
+
+        self.knockback =
+            owner.move_and_slide(self.knockback, Vector2::zero(), false, 4, 0.785398, true);
+
+        if self.knockback == Vector2::zero() {
+            self.knockback_chain.settle();
+        } else if self.knockback.length() >= CHAIN_MIN_SPEED {
+            self.knock_into_allies(owner);
+        }
+
+        // a bat waiting its turn to think acts on its last decision
+        let chase_target = if enemy_profiler::may_think(owner) {
+            self.chase_target = self.find_aggro_target(owner);
+            self.chase_target
+        } else {
+            self.chase_target
+        };
+
+        // a parry opens a counter even for bats that don't duel
+        if self.duelist.enabled || matches!(self.state, State::CounterWindup(_)) {
+            self.duel(owner, delta);
+        }
+
+        if let Some(target_position) = chase_target {
+            self.last_seen = target_position;
+        }
+
+        if let Some(state) =
+            sight_transition(&self.state, chase_target.is_some(), self.search_duration)
+        {
+            self.state = state;
+        }
+
+        // a new chase starts a new trail
+        if !matches!(self.state, State::Chase) {
+            self.tracker.lose_track();
+        }
+
+        if self.phasing.enabled {
+            let chasing = matches!(self.state, State::Chase);
+            let mask = self.phasing.collision_mask(owner.collision_mask(), chasing);
+
+            owner.set_collision_mask(mask);
+        }
+
+        let wander_controller_node =
+            unsafe { owner.get_typed_node::<Node2D, _>("WanderController") };
+        let wander_controller_instance = wander_controller_node
+            .cast_instance::<WanderController>()
+            .unwrap();
+
+        let _ = wander_controller_instance.map_mut(|wander_controller, wander_owner| {
+            match self.state {
+                State::FleeingToNest => {
+                    let nest = self
+                        .runner
+                        .nest_position(owner, wander_controller.start_position());
+
+                    match runner::nest_direction(owner.global_position(), nest) {
+                        Some(direction) => {
+                            self.velocity = self
+                                .velocity
+                                .move_towards(direction * self.max_speed(), ACCELERATION * delta);
+
+                            if self.runner.tick_trail(delta) {
+                                runner::drop_breadcrumb(owner);
+                            }
+                        }
+                        None => self.arrive_at_nest(owner),
+                    }
+                }
+                State::Spawning(_) | State::FeigningDeath(_) | State::DeathSlide { .. } => {}
+                State::Staggered(_) | State::CounterWindup(_) => {
+                    self.velocity = self
+                        .velocity
+                        .move_towards(Vector2::zero(), FRICTION * delta);
+                }
+                State::Idle => {
+                    self.velocity = self
+                        .velocity
+                        .move_towards(Vector2::zero(), FRICTION * delta);
+
+                    if wander_controller.time_left() == 0.0 {
+                        self.pick_next_state(&wander_owner, wander_controller);
+                    }
+                }
+                State::Wander => {
+                    let direction = (wander_controller.target_position - owner.global_position())
+                        .try_normalize()
+                        .unwrap_or(Vector2::zero());
+
+                    self.velocity = self
+                        .velocity
+                        .move_towards(direction * self.max_speed(), ACCELERATION * delta);
+
+                    let arrived = owner
+                        .global_position()
+                        .distance_to(wander_controller.target_position)
+                        <= WANDER_TARGET_RANGE;
+
+                    if arrived || wander_controller.time_left() == 0.0 {
+                        self.pick_next_state(&wander_owner, wander_controller);
+                    }
+                }
+                State::Fleeing(_) => {
+                    if let Some(target_position) = chase_target {
+                        let direction = (owner.global_position() - target_position)
+                            .try_normalize()
+                            .unwrap_or(Vector2::zero());
+
+                        self.velocity = self
+                            .velocity
+                            .move_towards(direction * self.max_speed(), ACCELERATION * delta);
+                    }
+                }
+                State::Chase => {
+                    if let Some(target_position) = chase_target {
+                        let target_position = self.tracker.follow(
+                            owner.global_position(),
+                            target_position,
+                            WANDER_TARGET_RANGE,
+                        );
+                        let direction = (target_position - owner.global_position())
+                            .try_normalize()
+                            .unwrap_or(Vector2::zero());
+
+                        self.velocity = self
+                            .velocity
+                            .move_towards(direction * self.max_speed(), ACCELERATION * delta);
+                    }
+                }
+                State::Search(time_left) => {
+                    let arrived =
+                        owner.global_position().distance_to(self.last_seen) <= WANDER_TARGET_RANGE;
+                    let direction = (self.last_seen - owner.global_position())
+                        .try_normalize()
+                        .unwrap_or(Vector2::zero());
+
+                    // hovers where the target was once it gets there
+                    let target_velocity = if arrived {
+                        Vector2::zero()
+                    } else {
+                        direction * self.max_speed()
+                    };
+
+                    self.velocity = self
+                        .velocity
+                        .move_towards(target_velocity, ACCELERATION * delta);
+
+                    match tick_search(time_left, delta) {
+                        Some(time_left) => self.state = State::Search(time_left),
+                        None => self.start_wander(&wander_owner, wander_controller),
+                    }
+                }
             }
         });
+
+        // the bat faces what it chases, otherwise where it flies
+        let look_direction = chase_target
+            .map(|target_position| target_position - owner.global_position())
+            .unwrap_or(self.velocity)
+            .try_normalize();
+
+        if let Some(look_direction) = look_direction {
+            self.facing = turn_towards(self.facing, look_direction, self.turn_speed * delta);
+        }
+
+        let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+        sprite.set_flip_h(self.facing.x < 0.0);
+
+        self.velocity =
+            owner.move_and_slide(self.velocity, Vector2::zero(), false, 4, 0.785398, true);
     }
 
-    fn run_death_animation(&self, owner: &KinematicBody2D) {
-        let enemy_death_effect_scene = load_scene("res://scenes/EnemyDeathEffect.tscn").unwrap();
+    // targets are any node in the aggro_targets group, a companion only
+    // needs to join it and expose a taunt property
+    fn find_aggro_target(&self, owner: &KinematicBody2D) -> Option<Vector2> {
+        let tree = unsafe { owner.get_tree()?.assume_safe() };
 
-        let enemy_death_effect_node =
-            unsafe { enemy_death_effect_scene.instance(0).unwrap().assume_safe() };
-        let enemy_death_effect: TRef<Node2D> = enemy_death_effect_node.cast().unwrap();
+        let targets: Vec<AggroTarget> = tree
+            .get_nodes_in_group("aggro_targets")
+            .iter()
+            .filter_map(|target| target.try_to_object::<Node2D>())
+            .map(|target| {
+                let target = unsafe { target.assume_safe() };
 
-        enemy_death_effect.set_global_position(owner.global_position());
+                AggroTarget {
+                    position: target.global_position(),
+                    taunt: target.get("taunt").to_f64() as f32,
+                }
+            })
+            .collect();
 
-        let bat_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+        aggro::pick_target(owner.global_position(), &targets, self.detection_range)
+            .map(|index| targets[index].position)
+    }
 
-        bat_parent.add_child(enemy_death_effect, false);
+    fn pick_next_state(&mut self, owner: &Node2D, wander_controller: &mut WanderController) {
+        if wander_controller.roll() < 0.5 {
+            self.state = State::Idle;
+
+            let duration =
+                MIN_WANDER_TIME + (MAX_WANDER_TIME - MIN_WANDER_TIME) * wander_controller.roll();
+            wander_controller.start_wander_timer(duration);
+        } else {
+            self.start_wander(owner, wander_controller);
+        }
+    }
+
+    fn start_wander(&mut self, owner: &Node2D, wander_controller: &mut WanderController) {
+        wander_controller.update_target_position(owner);
+        self.state = State::Wander;
+
+        let duration =
+            MIN_WANDER_TIME + (MAX_WANDER_TIME - MIN_WANDER_TIME) * wander_controller.roll();
+        wander_controller.start_wander_timer(duration);
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_Hurtbox_area_entered(&mut self, owner: &KinematicBody2D, area: Ref<Area2D>) {
+        // sword hits are dispatched by the SwordHitbox itself, so only
+        // projectiles are handled here
+        let projectile = unsafe { area.assume_safe_if_sane() }
+            .and_then(|area| area.cast_instance::<Projectile>());
+
+        if let Some(projectile) = projectile {
+            self.on_projectile_hit(owner, projectile);
+        }
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_Hitbox_area_entered(&self, owner: &KinematicBody2D, area: Ref<Area2D>) {
+        if !self.hazard_on_attack {
+            return;
+        }
+
+        if let Some(area) = unsafe { area.assume_safe_if_sane() } {
+            self.spawn_hazard(owner, area.global_position());
+        }
+    }
+
+    /// Returns whether the hit hurt the bat.
+    pub fn receive_sword_hit(
+        &mut self,
+        owner: &KinematicBody2D,
+        mut hit: Hit,
+        knockback_vector: Vector2,
+        mut popup: Popup,
+        source: Option<i64>,
+    ) -> bool {
+        if !self.can_be_damaged() {
+            return false;
+        }
+
+        if !self.hurt_from(knockback_vector) {
+            self.clang(owner);
+            return false;
+        }
+
+        if is_backstab(self.facing, knockback_vector) {
+            let damage = (hit.damage as f32 * self.backstab_multiplier).round() as i32;
+
+            popup.total += damage - hit.damage;
+            hit.damage = damage;
+        }
+
+        let damage = self.parry_stagger.apply(hit.damage);
+
+        popup.total += damage - hit.damage;
+        hit.damage = damage;
+
+        self.show_damage_popup(owner, popup);
+        self.flash(owner, hit.is_crit);
+
+        if hit.is_crit {
+            screen_flash::flash(owner, Color::rgb(1.0, 1.0, 1.0), 0.1, 0.3);
+        }
+
+        self.apply_lifesteal(owner, hit.damage);
+
+        // a killing blow still knocks back a bat that slides on death
+        let state = self.take_hit(owner, hit, source);
+        self.apply_knockback(knockback_vector * 120.0);
+
+        if let stats::State::Alive = state {
+            self.break_poise(owner, hit.damage);
+        }
+
+        true
     }
+
+    fn on_projectile_hit(
+        &mut self,
+        owner: &KinematicBody2D,
+        projectile: RefInstance<Projectile, Shared>,
+    ) {
+        let (team, damage, velocity) = projectile
+            .map(|projectile, _| (projectile.team, projectile.damage, projectile.velocity))
+            .unwrap();
+
+        // enemy projectiles fly through other enemies, and everything flies
+        // through an enemy that is still spawning
+        if team == Team::Enemy || !self.can_be_damaged() {
+            return;
+        }
+
+        if self.try_deflect() {
+            let _ = projectile.map_mut(|projectile, _| projectile.deflect());
+            return;
+        }
+
+        if !self.hurt_from(velocity) {
+            projectile.base().queue_free();
+            self.clang(owner);
+            return;
+        }
+
+        let owner_id = owner.get_instance_id();
+
+        match projectile.map_mut(|projectile, _| projectile.hit_target(owner_id)) {
+            Ok(Some(true)) => projectile.base().queue_free(),
+            Ok(Some(false)) => {}
+            _ => return,
+        }
+
+        self.spawn_damage_number(owner, damage, false);
+        self.flash(owner, false);
+
+        let state = self.take_damage(owner, damage);
+        self.apply_knockback(velocity.try_normalize().unwrap_or(Vector2::zero()) * 120.0);
+
+        if let stats::State::Alive = state {
+            self.break_poise(owner, damage);
+        }
+    }
+
+    // returns how far along the spawn animation is, from 0 to 1
+    fn tick_spawn(&mut self, delta: f32) -> f32 {
+        if let State::Spawning(time_left) = &mut self.state {
+            *time_left -= delta;
+
+            if *time_left > 0.0 {
+                return 1.0 - *time_left / self.spawn_duration;
+            }
+
+            self.state = State::Idle;
+        }
+
+        1.0
+    }
+
+    // staggers the bat when the damage breaks its poise
+    fn break_poise(&mut self, owner: &KinematicBody2D, damage: i32) {
+        if let State::FeigningDeath(_) = self.state {
+            return;
+        }
+
+        if self.poise.take_damage(damage) {
+            self.state = State::Staggered(self.poise.stagger_duration);
+            self.set_attacking(owner, false);
+        }
+    }
+
+    /// Returns whether the stagger ended this frame.
+    fn tick_stagger(&mut self, delta: f32) -> bool {
+        if let State::Staggered(time_left) = &mut self.state {
+            *time_left -= delta;
+
+            if *time_left <= 0.0 {
+                self.state = State::Idle;
+                self.parry_stagger.end();
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Staggers the bat after the player parried its attack.
+    pub fn receive_parry(&mut self, owner: &KinematicBody2D) {
+        if self.start_parry_stagger() {
+            self.set_attacking(owner, false);
+        }
+    }
+
+    fn start_parry_stagger(&mut self) -> bool {
+        if !self.can_act() {
+            return false;
+        }
+
+        self.state = State::Staggered(self.parry_stagger.start());
+
+        true
+    }
+
+    // the hitbox is toggled deferred since this can run during a physics
+    // callback
+    fn set_attacking(&self, owner: &KinematicBody2D, attacking: bool) {
+        let hitbox = unsafe { owner.get_typed_node::<Area2D, _>("Hitbox") };
+
+        hitbox.set_deferred("monitoring", attacking);
+        hitbox.set_deferred("monitorable", attacking);
+    }
+
+    // reads the closest player's attacks, winding up a counter when one
+    // starts and lunging at them once it's ready
+    fn duel(&mut self, owner: &KinematicBody2D, delta: f32) {
+        let closest_player = entity_registry::all_players(owner)
+            .into_iter()
+            .filter_map(|player| {
+                let is_attacking = player
+                    .cast_instance::<Player>()?
+                    .map(|player, _| player.is_attacking())
+                    .ok()?;
+
+                Some((player.global_position(), is_attacking))
+            })
+            .min_by(|(a, _), (b, _)| {
+                let position = owner.global_position();
+
+                position
+                    .distance_to(*a)
+                    .partial_cmp(&position.distance_to(*b))
+                    .unwrap()
+            });
+
+        let (player_position, player_attacking) = match closest_player {
+            Some(closest_player) => closest_player,
+            None => return,
+        };
+
+        let distance = owner.global_position().distance_to(player_position);
+
+        // react is checked every frame to keep track of the player's attacks
+        let countering = self.duelist.react(player_attacking, distance);
+
+        if countering
+            && matches!(
+                self.state,
+                State::Idle | State::Wander | State::Chase | State::Search(_)
+            )
+            && self.request_attack_token(owner)
+        {
+            self.state = State::CounterWindup(self.duelist.windup);
+        }
+
+        if self.tick_counter_windup(delta) {
+            let direction = (player_position - owner.global_position())
+                .try_normalize()
+                .unwrap_or(Vector2::zero());
+
+            self.velocity = direction * COUNTER_LUNGE_SPEED;
+        }
+    }
+
+    /// Returns whether the counter is ready to be unleashed this frame.
+    fn tick_counter_windup(&mut self, delta: f32) -> bool {
+        if let State::CounterWindup(time_left) = &mut self.state {
+            *time_left -= delta;
+
+            if *time_left <= 0.0 {
+                self.state = State::Chase;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns whether the bat may start a counter, holding one of its attack
+    /// group's tokens until it lunges or the windup is cut short.
+    fn request_attack_token(&mut self, owner: &KinematicBody2D) -> bool {
+        if self.attack_group.is_empty() || self.holds_attack_token {
+            return true;
+        }
+
+        self.holds_attack_token = attack_tokens::request_attack(owner, &self.attack_group);
+
+        self.holds_attack_token
+    }
+
+    fn release_attack_token(&mut self, owner: &KinematicBody2D) {
+        if self.holds_attack_token {
+            attack_tokens::release_attack(owner, &self.attack_group);
+            self.holds_attack_token = false;
+        }
+    }
+
+    /// Steals from the player after a hit landed on them, then flees.
+    pub fn steal_coins(&mut self, player_stats: &mut PlayerStats) {
+        if self.thief.steal_from(player_stats) {
+            self.state = State::Fleeing(self.thief.flee_time);
+        }
+    }
+
+    fn tick_flee(&mut self, delta: f32) {
+        if let State::Fleeing(time_left) = &mut self.state {
+            *time_left -= delta;
+
+            if *time_left <= 0.0 {
+                self.state = State::Idle;
+            }
+        }
+    }
+
+    fn flee_to_nest(&mut self) {
+        if self.runner.enabled
+            && matches!(
+                self.state,
+                State::Idle | State::Wander | State::Chase | State::Search(_)
+            )
+        {
+            self.runner.start();
+            self.state = State::FleeingToNest;
+        }
+    }
+
+    fn arrive_at_nest(&mut self, owner: &KinematicBody2D) {
+        let stats_node = unsafe { owner.get_typed_node::<Node, _>("Stats") };
+        let stats_instance = stats_node.cast_instance::<Stats>().unwrap();
+
+        let runner = &self.runner;
+        let _ = stats_instance.map_mut(|stats, _| {
+            stats.health = runner.arrival_health(stats.health, stats.max_health)
+        });
+
+        self.state = State::Idle;
+    }
+
+    fn can_be_damaged(&self) -> bool {
+        !matches!(self.state, State::Spawning(_) | State::DeathSlide { .. })
+    }
+
+    fn can_act(&self) -> bool {
+        !matches!(
+            self.state,
+            State::Spawning(_) | State::FeigningDeath(_) | State::DeathSlide { .. }
+        )
+    }
+
+    fn bob(&mut self, owner: &KinematicBody2D, delta: f32) {
+        let offset = if matches!(self.state, State::Idle | State::Wander) {
+            self.hover.advance(delta)
+        } else {
+            self.hover.stop();
+            0.0
+        };
+
+        let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+        sprite.set_offset(self.sprite_offset + Vector2::new(0.0, offset));
+    }
+
+    fn apply_knockback(&mut self, knockback: Vector2) {
+        self.knockback = knockback.with_max_length(self.max_knockback_speed.max(0.0));
+    }
+
+    fn decay_knockback(&mut self, delta: f32) {
+        self.knockback = self
+            .knockback
+            .move_towards(Vector2::zero(), self.knockback_friction * delta);
+    }
+
+    /// Returns the sliding bat's opacity, or `None` once it has faded out.
+    fn tick_death_slide(&mut self, delta: f32) -> Option<f32> {
+        if let State::DeathSlide { time_left, .. } = &mut self.state {
+            *time_left -= delta;
+
+            if *time_left <= 0.0 {
+                return None;
+            }
+
+            return Some(*time_left / self.death_slide_duration);
+        }
+
+        Some(1.0)
+    }
+
+    fn closest_player_distance(&self, owner: &KinematicBody2D) -> Option<f32> {
+        entity_registry::all_players(owner)
+            .iter()
+            .map(|player| {
+                owner
+                    .global_position()
+                    .distance_to(player.global_position())
+            })
+            .min_by(|a, b| a.partial_cmp(b).unwrap())
+    }
+
+    /// Returns whether the bat revives this frame.
+    fn tick_feign_death(&mut self, delta: f32, closest_player: Option<f32>) -> bool {
+        if let State::FeigningDeath(time_left) = &mut self.state {
+            *time_left -= delta;
+
+            if self.feign_death.revives(*time_left, closest_player) {
+                self.state = State::Chase;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // returns whether the killing blow was shrugged off by feigning death
+    fn try_feign_death(&mut self) -> bool {
+        match self.feign_death.try_feign() {
+            Some(revive_time) => {
+                self.state = State::FeigningDeath(revive_time);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn play_feign_death(&self, owner: &KinematicBody2D) {
+        let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+        sprite.stop();
+        sprite.set_modulate(self.base_color());
+
+        self.set_attacking(owner, false);
+    }
+
+    fn revive(&self, owner: &KinematicBody2D) {
+        let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+        sprite.play("Fly", false);
+        sprite.set_modulate(self.base_color());
+
+        self.set_attacking(owner, true);
+    }
+
+    /// Returns whether the bat parried the sword swing, winding up a
+    /// counter.
+    pub fn try_parry(&mut self, owner: &KinematicBody2D) -> bool {
+        if !self.request_attack_token(owner) {
+            return false;
+        }
+
+        if !self.start_parry() {
+            self.release_attack_token(owner);
+            return false;
+        }
+
+        self.spawn_popup_text(owner, "parry".to_string());
+
+        true
+    }
+
+    fn start_parry(&mut self) -> bool {
+        if !matches!(
+            self.state,
+            State::Idle | State::Wander | State::Chase | State::Search(_)
+        ) {
+            return false;
+        }
+
+        let roll = self.rng.next_f32();
+
+        if !self.parry.try_parry(roll) {
+            return false;
+        }
+
+        self.state = State::CounterWindup(self.duelist.windup);
+
+        true
+    }
+
+    fn try_deflect(&mut self) -> bool {
+        if !self.deflects || !self.deflect_cooldown.is_ready() {
+            return false;
+        }
+
+        self.deflect_cooldown.start();
+
+        true
+    }
+
+    pub fn health(&self, owner: &KinematicBody2D) -> i32 {
+        self.health_and_max(owner).0
+    }
+
+    fn health_and_max(&self, owner: &KinematicBody2D) -> (i32, i32) {
+        let stats_node = unsafe { owner.get_typed_node::<Node, _>("Stats") };
+        let stats_instance = stats_node.cast_instance::<Stats>().unwrap();
+
+        stats_instance
+            .map(|stats, _| (stats.health, stats.max_health))
+            .unwrap()
+    }
+
+    /// Returns the new rage state when it changes.
+    fn update_enrage(&mut self, health: i32, max_health: i32) -> Option<bool> {
+        let is_low_health = max_health > 0
+            && health > 0
+            && (health as f32) < self.enrage.threshold * max_health as f32;
+        let is_enraged = self.enrage.timed_out || is_low_health;
+
+        if is_enraged == self.enrage.is_enraged {
+            return None;
+        }
+
+        self.enrage.is_enraged = is_enraged;
+
+        Some(is_enraged)
+    }
+
+    fn max_speed(&self) -> f32 {
+        let speed = MAX_SPEED * self.revenant.speed_multiplier();
+
+        if self.enrage.is_enraged {
+            speed * self.enrage.speed_multiplier
+        } else {
+            speed
+        }
+    }
+
+    pub fn is_chasing(&self) -> bool {
+        matches!(self.state, State::Chase)
+    }
+
+    /// The damage dealt to the player on contact.
+    pub fn contact_damage(&self) -> i32 {
+        let enrage_multiplier = if self.enrage.is_enraged {
+            self.enrage.damage_multiplier
+        } else {
+            1.0
+        };
+
+        (self.damage as f32
+            * enrage_multiplier
+            * self.damage_buff.multiplier()
+            * self.revenant.damage_multiplier())
+        .round() as i32
+    }
+
+    fn buff_allies(&self, owner: &KinematicBody2D) {
+        let owner_id = owner.get_instance_id();
+
+        // the warlord itself is already borrowed, and doesn't buff itself
+        let allies: Vec<TRef<KinematicBody2D>> = entity_registry::all_enemies(owner)
+            .into_iter()
+            .filter(|enemy| enemy.get_instance_id() != owner_id)
+            .collect();
+        let positions: Vec<Vector2> = allies.iter().map(|ally| ally.global_position()).collect();
+
+        for index in
+            explosion::targets_in_radius(owner.global_position(), self.aura_radius, &positions)
+        {
+            if let Some(ally) = allies[index].cast_instance::<Bat>() {
+                let _ = ally.map_mut(|ally, _| ally.damage_buff.apply(self.aura_damage_multiplier));
+            }
+        }
+    }
+
+    fn knock_into_allies(&mut self, owner: &KinematicBody2D) {
+        let owner_id = owner.get_instance_id();
+
+        let allies: Vec<TRef<KinematicBody2D>> = entity_registry::all_enemies(owner)
+            .into_iter()
+            .filter(|enemy| enemy.get_instance_id() != owner_id)
+            .collect();
+        let positions: Vec<Vector2> = allies.iter().map(|ally| ally.global_position()).collect();
+
+        for index in
+            explosion::targets_in_radius(owner.global_position(), CHAIN_BUMP_RADIUS, &positions)
+        {
+            let count = match self.knockback_chain.bump(allies[index].get_instance_id()) {
+                Some(count) => count,
+                None => continue,
+            };
+
+            if let Some(ally) = allies[index].cast_instance::<Bat>() {
+                let knockback = self.knockback * CHAIN_KNOCKBACK_TRANSFER;
+
+                let _ = ally.map_mut(|ally, _| {
+                    ally.apply_knockback(knockback);
+                    ally.knockback_chain.join(count, owner_id);
+                });
+            }
+
+            if self.chain_popup_threshold > 0 && count >= self.chain_popup_threshold {
+                self.spawn_popup_text(owner, chain_text(count));
+            }
+        }
+    }
+
+    fn base_color(&self) -> Color {
+        if let State::FeigningDeath(_) = self.state {
+            Color::rgb(0.5, 0.5, 0.5)
+        } else if self.enrage.is_enraged {
+            Color::rgb(1.0, 0.6, 0.6)
+        } else {
+            Color::rgb(1.0, 1.0, 1.0)
+        }
+    }
+
+    pub fn take_damage(&mut self, owner: &KinematicBody2D, damage: i32) -> stats::State {
+        let hit = Hit {
+            damage,
+            is_crit: false,
+        };
+
+        self.take_hit(owner, hit, None)
+    }
+
+    /// Takes the hit's damage, sending it through the `DamageBus` with the
+    /// instance id of whoever dealt it, if anyone.
+    pub fn take_hit(
+        &mut self,
+        owner: &KinematicBody2D,
+        hit: Hit,
+        source: Option<i64>,
+    ) -> stats::State {
+        if !self.can_be_damaged() {
+            return stats::State::Alive;
+        }
+
+        let damage = hit.damage;
+
+        let stats_node = unsafe { owner.get_typed_node::<Node, _>("Stats") };
+        let stats_instance = stats_node.cast_instance::<Stats>().unwrap();
+
+        let training_dummy = self.training_dummy;
+        let mut state = stats_instance
+            .map_mut(|stats, _| {
+                let state = stats.receive_damage(damage);
+
+                if training_dummy {
+                    stats.health = stats.max_health;
+
+                    return stats::State::Alive;
+                }
+
+                state
+            })
+            .unwrap();
+
+        // executes can't be faked out of
+        if matches!(state, stats::State::Dead) && self.try_feign_death() {
+            let _ = stats_instance.map_mut(|stats, _| stats.health = 1);
+            self.play_feign_death(owner);
+
+            state = stats::State::Alive;
+        }
+
+        if !matches!(state, stats::State::Alive) {
+            if self.drops_key {
+                self.drop_pickup(owner, "res://scenes/KeyPickup.tscn");
+            }
+
+            if self.drops_weapon {
+                self.drop_pickup(owner, "res://scenes/WeaponPickup.tscn");
+            }
+
+            if self.drops_magnet {
+                self.drop_pickup(owner, "res://scenes/MagnetPickup.tscn");
+            }
+
+            let stolen = self.thief.take_stolen();
+
+            if stolen > 0 {
+                self.drop_coins(owner, stolen);
+            }
+        }
+
+        let died = !matches!(state, stats::State::Alive);
+        damage_bus::emit(
+            owner,
+            damage_bus::hit_events(source, owner.get_instance_id(), hit, died),
+        );
+
+        match state {
+            stats::State::Alive => self.flee_to_nest(),
+            stats::State::Dead => self.die(owner, 1.0),
+            stats::State::Executed => self.die(owner, EXECUTE_EFFECT_SCALE),
+        }
+
+        state
+    }
+
+    fn die(&mut self, owner: &KinematicBody2D, effect_scale: f32) {
+        if self.death_slide_duration > 0.0 {
+            // a sliding bat is already dead to everything looking for enemies
+            owner.remove_from_group("enemies");
+            entity_registry::unregister(owner);
+            self.set_attacking(owner, false);
+
+            self.state = State::DeathSlide {
+                time_left: self.death_slide_duration,
+                effect_scale,
+            };
+        } else {
+            owner.queue_free();
+
+            self.run_death_animation(owner, effect_scale);
+        }
+
+        if self.is_boss() {
+            boss_bar::hide(owner);
+        }
+
+        slow_mo::on_enemy_killed(owner, owner.global_position());
+    }
+
+    fn apply_lifesteal(&self, owner: &KinematicBody2D, damage: i32) {
+        let player_node = unsafe { owner.get_typed_node::<KinematicBody2D, _>("../Player") };
+        let player_instance = player_node.cast_instance::<Player>().unwrap();
+
+        let heal = player_instance
+            .map_mut(|player, _| player.lifesteal_heal(damage))
+            .unwrap_or(0);
+
+        if heal == 0 {
+            return;
+        }
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let _ = player_stats_instance.map_mut(|player_stats, _| {
+            player_stats.heal(heal);
+        });
+    }
+
+    fn flash(&mut self, owner: &KinematicBody2D, is_crit: bool) {
+        let sprite = unsafe { owner.get_typed_node::<AnimatedSprite, _>("AnimatedSprite") };
+
+        if is_crit {
+            sprite.set_modulate(Color::rgb(1.0, 0.85, 0.2));
+            self.flash = Cooldown::started(CRIT_FLASH_DURATION);
+        } else {
+            sprite.set_modulate(Color::rgb(1.0, 0.4, 0.4));
+            self.flash = Cooldown::started(FLASH_DURATION);
+        }
+    }
+
+    fn show_damage_popup(&mut self, owner: &KinematicBody2D, popup: Popup) {
+        if !popup.is_new {
+            // the last number may already be gone, then a new one is spawned
+            let merged = self
+                .damage_number
+                .and_then(|id| unsafe { Label::try_from_instance_id(id) })
+                .and_then(|label| label.cast_instance::<DamageNumber>())
+                .and_then(|damage_number| {
+                    damage_number
+                        .map_mut(|damage_number, label| {
+                            damage_number.merge(label, popup.total, popup.is_crit)
+                        })
+                        .ok()
+                })
+                .is_some();
+
+            if merged {
+                return;
+            }
+        }
+
+        self.spawn_damage_number(owner, popup.total, popup.is_crit);
+    }
+
+    fn spawn_damage_number(&mut self, owner: &KinematicBody2D, amount: i32, is_crit: bool) {
+        let damage_number_scene = load_scene("res://scenes/DamageNumber.tscn").unwrap();
+
+        let damage_number_node = unsafe { damage_number_scene.instance(0).unwrap().assume_safe() };
+        let damage_number: TRef<Label> = damage_number_node.cast().unwrap();
+
+        let _ = damage_number
+            .cast_instance::<DamageNumber>()
+            .unwrap()
+            .map_mut(|damage_number, _| damage_number.setup(amount, is_crit));
+
+        damage_number.set_global_position(owner.global_position() + DAMAGE_NUMBER_OFFSET, false);
+
+        self.damage_number = Some(damage_number.get_instance_id());
+
+        let bat_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        bat_parent.add_child(damage_number, false);
+    }
+
+    fn hurt_from(&self, hit_direction: Vector2) -> bool {
+        self.shell.is_hurt_by(self.facing, hit_direction)
+    }
+
+    // a hit blocked by the armor around the bat's front
+    fn clang(&self, owner: &KinematicBody2D) {
+        self.spawn_popup_text(owner, "clang".to_string());
+    }
+
+    fn spawn_popup_text(&self, owner: &KinematicBody2D, text: String) {
+        let damage_number_scene = load_scene("res://scenes/DamageNumber.tscn").unwrap();
+
+        let damage_number_node = unsafe { damage_number_scene.instance(0).unwrap().assume_safe() };
+        let damage_number: TRef<Label> = damage_number_node.cast().unwrap();
+
+        let _ = damage_number
+            .cast_instance::<DamageNumber>()
+            .unwrap()
+            .map_mut(|damage_number, _| damage_number.setup_text(text));
+
+        damage_number.set_global_position(owner.global_position() + DAMAGE_NUMBER_OFFSET, false);
+
+        let bat_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        bat_parent.add_child(damage_number, false);
+    }
+
+    fn spawn_hazard(&self, owner: &KinematicBody2D, position: Vector2) {
+        let hazard_scene = load_scene("res://scenes/HazardArea.tscn").unwrap();
+
+        let hazard_node = unsafe { hazard_scene.instance(0).unwrap().assume_safe() };
+        let hazard: TRef<Area2D> = hazard_node.cast().unwrap();
+
+        hazard.set_global_position(position);
+
+        let bat_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        bat_parent.add_child(hazard, false);
+    }
+
+    fn drop_pickup(&self, owner: &KinematicBody2D, path: &str) {
+        let pickup_scene = load_scene(path).unwrap();
+
+        let pickup_node = unsafe { pickup_scene.instance(0).unwrap().assume_safe() };
+        let pickup: TRef<Area2D> = pickup_node.cast().unwrap();
+
+        pickup.set_global_position(owner.global_position());
+
+        let bat_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        bat_parent.add_child(pickup, false);
+    }
+
+    fn drop_coins(&self, owner: &KinematicBody2D, amount: i32) {
+        let coin_pickup_scene = load_scene("res://scenes/CoinPickup.tscn").unwrap();
+
+        let coin_pickup_node = unsafe { coin_pickup_scene.instance(0).unwrap().assume_safe() };
+        let coin_pickup: TRef<Area2D> = coin_pickup_node.cast().unwrap();
+
+        let _ = coin_pickup
+            .cast_instance::<CoinPickup>()
+            .unwrap()
+            .map_mut(|coin_pickup, _| coin_pickup.amount = amount);
+
+        coin_pickup.set_global_position(owner.global_position());
+
+        let bat_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        bat_parent.add_child(coin_pickup, false);
+    }
+
+    fn run_death_animation(&self, owner: &KinematicBody2D, scale: f32) {
+        let enemy_death_effect_scene = load_scene("res://scenes/EnemyDeathEffect.tscn").unwrap();
+
+        let enemy_death_effect_node =
+            unsafe { enemy_death_effect_scene.instance(0).unwrap().assume_safe() };
+        let enemy_death_effect: TRef<Node2D> = enemy_death_effect_node.cast().unwrap();
+
+        enemy_death_effect.set_global_position(owner.global_position());
+        enemy_death_effect.set_scale(Vector2::new(scale, scale));
+
+        let bat_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        bat_parent.add_child(enemy_death_effect, false);
+    }
+}
+
+/// The state a bat goes to on seeing a target or losing sight of it, or
+/// `None` to stay in its state.
+pub(crate) fn sight_transition(
+    state: &State,
+    target_in_sight: bool,
+    search_duration: f32,
+) -> Option<State> {
+    match (state, target_in_sight) {
+        (State::Staggered(_), _)
+        | (State::Fleeing(_), _)
+        | (State::FleeingToNest, _)
+        | (State::CounterWindup(_), _) => None,
+        (_, true) => Some(State::Chase),
+        (State::Chase, false) if search_duration > 0.0 => Some(State::Search(search_duration)),
+        (State::Chase, false) => Some(State::Idle),
+        _ => None,
+    }
+}
+
+/// Counts a search down, returning the time left or `None` once it ends.
+pub(crate) fn tick_search(time_left: f32, delta: f32) -> Option<f32> {
+    let time_left = time_left - delta;
+
+    if time_left > 0.0 {
+        Some(time_left)
+    } else {
+        None
+    }
+}
+
+fn chain_text(count: i32) -> String {
+    format!("x{} chain!", count)
+}
+
+// rotates the facing towards the direction, by at most max_angle radians
+fn turn_towards(facing: Vector2, direction: Vector2, max_angle: f32) -> Vector2 {
+    let angle = facing.cross(direction).atan2(facing.dot(direction));
+    let angle = angle.max(-max_angle).min(max_angle);
+    let (sin, cos) = angle.sin_cos();
+
+    Vector2::new(
+        facing.x * cos - facing.y * sin,
+        facing.x * sin + facing.y * cos,
+    )
+}
+
+// a hit travelling the same way the bat faces came from behind it
+fn is_backstab(facing: Vector2, hit_direction: Vector2) -> bool {
+    hit_direction
+        .try_normalize()
+        .map_or(false, |direction| facing.dot(direction) >= BACKSTAB_FACING)
+}
+
+#[test]
+fn test_deflect_needs_deflector() {
+    let mut bat = Bat::default();
+
+    assert!(!bat.try_deflect());
+}
+
+#[test]
+fn test_deflect_cooldown() {
+    let mut bat = Bat {
+        deflects: true,
+        ..Bat::default()
+    };
+
+    assert!(bat.try_deflect());
+    assert!(!bat.try_deflect());
+
+    bat.deflect_cooldown.tick(bat.deflect_cooldown.duration);
+
+    assert!(bat.try_deflect());
+}
+
+#[test]
+fn test_spawning_bat_ignores_damage_and_movement() {
+    let bat = Bat {
+        state: State::Spawning(0.5),
+        ..Bat::default()
+    };
+
+    assert!(!bat.can_be_damaged());
+    assert!(!bat.can_act());
+}
+
+#[test]
+fn test_spawning_ends_after_duration() {
+    let mut bat = Bat {
+        state: State::Spawning(0.5),
+        ..Bat::default()
+    };
+
+    assert_eq!(bat.tick_spawn(0.25), 0.5);
+    assert!(!bat.can_act());
+
+    assert_eq!(bat.tick_spawn(0.25), 1.0);
+    assert!(bat.can_be_damaged());
+    assert!(bat.can_act());
+}
+
+#[test]
+fn test_enrage_below_threshold() {
+    let mut bat = Bat::default();
+
+    assert_eq!(bat.update_enrage(3, 8), None);
+    assert_eq!(bat.update_enrage(1, 8), Some(true));
+    assert_eq!(bat.max_speed(), MAX_SPEED * 1.5);
+    assert_eq!(bat.contact_damage(), 2);
+}
+
+#[test]
+fn test_enrage_removed_when_healed() {
+    let mut bat = Bat::default();
+
+    bat.update_enrage(1, 8);
+
+    assert_eq!(bat.update_enrage(4, 8), Some(false));
+    assert_eq!(bat.max_speed(), MAX_SPEED);
+    assert_eq!(bat.contact_damage(), 1);
+}
+
+#[test]
+fn test_enrage_when_time_limit_runs_out() {
+    let mut bat = Bat::default();
+    bat.enrage.time_limit = 60.0;
+
+    bat.enrage.tick_time_limit(59.0);
+
+    assert_eq!(bat.enrage.time_left(), Some(1.0));
+    assert_eq!(bat.update_enrage(8, 8), None);
+
+    bat.enrage.tick_time_limit(1.0);
+
+    assert_eq!(bat.enrage.time_left(), Some(0.0));
+    assert_eq!(bat.update_enrage(8, 8), Some(true));
+    assert_eq!(bat.max_speed(), MAX_SPEED * 1.5);
+    assert_eq!(bat.contact_damage(), 2);
+}
+
+#[test]
+fn test_timed_out_enrage_is_permanent() {
+    let mut bat = Bat::default();
+    bat.enrage.time_limit = 60.0;
+
+    bat.enrage.tick_time_limit(60.0);
+    bat.update_enrage(1, 8);
+
+    assert_eq!(bat.update_enrage(8, 8), None);
+    assert_eq!(bat.contact_damage(), 2);
+}
+
+#[test]
+fn test_no_time_limit_never_enrages() {
+    let mut bat = Bat::default();
+
+    bat.enrage.tick_time_limit(1000.0);
+
+    assert_eq!(bat.enrage.time_left(), None);
+    assert_eq!(bat.update_enrage(8, 8), None);
+}
+
+#[test]
+fn test_aura_membership() {
+    let positions = [
+        Vector2::new(10.0, 0.0),
+        Vector2::new(0.0, 40.0),
+        Vector2::new(-24.0, 0.0),
+    ];
+
+    assert_eq!(
+        explosion::targets_in_radius(Vector2::zero(), 24.0, &positions),
+        vec![0, 2]
+    );
+}
+
+#[test]
+fn test_aura_buff_applies_and_decays() {
+    let mut bat = Bat {
+        damage: 2,
+        ..Bat::default()
+    };
+
+    bat.damage_buff.apply(1.5);
+
+    assert_eq!(bat.contact_damage(), 3);
+
+    bat.damage_buff.tick(AURA_BUFF_DURATION);
+
+    assert_eq!(bat.contact_damage(), 2);
+}
+
+#[test]
+fn test_weak_hits_do_not_break_poise() {
+    let mut bat = Bat::default();
+    bat.poise.max = 4;
+
+    assert!(!bat.poise.take_damage(2));
+
+    // the damage is forgotten before the next hit lands
+    bat.poise.tick(1.0);
+
+    assert!(!bat.poise.take_damage(2));
+}
+
+#[test]
+fn test_poise_breaks_past_threshold() {
+    let mut bat = Bat::default();
+    bat.poise.max = 4;
+
+    assert!(!bat.poise.take_damage(2));
+    bat.poise.tick(0.5);
+    assert!(bat.poise.take_damage(2));
+
+    bat.state = State::Staggered(bat.poise.stagger_duration);
+
+    assert!(!bat.tick_stagger(0.3));
+    assert!(bat.tick_stagger(0.4));
+    assert!(matches!(bat.state, State::Idle));
+}
+
+#[test]
+fn test_poise_disabled_never_breaks() {
+    let mut bat = Bat::default();
+
+    assert!(!bat.poise.take_damage(100));
+}
+
+#[test]
+fn test_feigns_death_once() {
+    let mut bat = Bat::default();
+    bat.feign_death.enabled = true;
+
+    assert!(bat.try_feign_death());
+    assert!(!bat.can_act());
+    assert!(bat.can_be_damaged());
+
+    assert!(!bat.try_feign_death());
+}
+
+#[test]
+fn test_feigner_revives_when_player_approaches() {
+    let mut bat = Bat {
+        state: State::FeigningDeath(4.0),
+        ..Bat::default()
+    };
+
+    assert!(!bat.tick_feign_death(0.1, Some(40.0)));
+    assert!(!bat.tick_feign_death(0.1, None));
+
+    assert!(bat.tick_feign_death(0.1, Some(30.0)));
+    assert!(matches!(bat.state, State::Chase));
+    assert!(bat.can_act());
+}
+
+#[test]
+fn test_feigner_revives_after_time() {
+    let mut bat = Bat {
+        state: State::FeigningDeath(1.0),
+        ..Bat::default()
+    };
+
+    assert!(bat.tick_feign_death(1.0, None));
+}
+
+#[test]
+fn test_turn_rate_is_clamped() {
+    let facing = turn_towards(Vector2::new(1.0, 0.0), Vector2::new(-1.0, 0.0), 0.5);
+
+    assert!((facing.x - 0.5f32.cos()).abs() < 0.001);
+    assert!((facing.y.abs() - 0.5f32.sin()).abs() < 0.001);
+}
+
+#[test]
+fn test_turn_reaches_close_direction() {
+    let facing = turn_towards(Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0), 2.0);
+
+    assert!(facing.x.abs() < 0.001);
+    assert!((facing.y - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn test_backstab_angle() {
+    let facing = Vector2::new(1.0, 0.0);
+
+    // the player swings right at a bat facing right, from behind it
+    assert!(is_backstab(facing, Vector2::new(1.0, 0.0)));
+    assert!(is_backstab(facing, Vector2::new(1.0, 1.0)));
+    assert!(!is_backstab(facing, Vector2::new(0.0, 1.0)));
+    assert!(!is_backstab(facing, Vector2::new(-1.0, 0.0)));
+    assert!(!is_backstab(facing, Vector2::zero()));
+}
+
+/// Decays the knockback the way every frame does, for as many frames of the
+/// delta, returning what's left of it.
+#[cfg(test)]
+fn simulate_knockback(friction: f32, initial: Vector2, steps: usize, dt: f32) -> Vector2 {
+    let mut bat = Bat {
+        knockback: initial,
+        knockback_friction: friction,
+        ..Bat::default()
+    };
+
+    for _ in 0..steps {
+        bat.decay_knockback(dt);
+    }
+
+    bat.knockback
+}
+
+#[test]
+fn test_death_slide_knockback_decays() {
+    let mut bat = Bat {
+        knockback: Vector2::new(120.0, 0.0),
+        ..Bat::default()
+    };
+
+    bat.decay_knockback(0.25);
+    assert_eq!(bat.knockback, Vector2::new(70.0, 0.0));
+
+    bat.decay_knockback(1.0);
+    assert_eq!(bat.knockback, Vector2::zero());
+}
+
+#[test]
+fn test_death_slide_fades_then_frees() {
+    let mut bat = Bat {
+        death_slide_duration: 0.4,
+        state: State::DeathSlide {
+            time_left: 0.4,
+            effect_scale: 1.0,
+        },
+        ..Bat::default()
+    };
+
+    assert!(!bat.can_act());
+    assert!(!bat.can_be_damaged());
+
+    assert_eq!(bat.tick_death_slide(0.2), Some(0.5));
+    assert_eq!(bat.tick_death_slide(0.2), None);
+}
+
+#[test]
+fn test_thief_holds_stolen_coins_and_flees() {
+    let mut bat = Bat::default();
+    bat.thief.steal_amount = 3;
+    let mut player_stats = PlayerStats {
+        coins: 5,
+        ..PlayerStats::default()
+    };
+
+    bat.steal_coins(&mut player_stats);
+    bat.steal_coins(&mut player_stats);
+
+    assert_eq!(player_stats.coins, 0);
+    assert!(matches!(bat.state, State::Fleeing(_)));
+
+    // everything stolen is returned on death
+    assert_eq!(bat.thief.take_stolen(), 5);
+}
+
+#[test]
+fn test_counter_windup_ends_in_a_chase() {
+    let mut bat = Bat {
+        state: State::CounterWindup(0.3),
+        ..Bat::default()
+    };
+
+    assert!(!bat.tick_counter_windup(0.2));
+    assert!(bat.tick_counter_windup(0.2));
+    assert!(matches!(bat.state, State::Chase));
+}
+
+#[test]
+fn test_knockback_chain_grows_per_bumped_enemy() {
+    let mut chain = KnockbackChain::default();
+
+    assert_eq!(chain.bump(1), Some(2));
+    assert_eq!(chain.bump(2), Some(3));
+
+    // still touching the first one
+    assert_eq!(chain.bump(1), None);
+    assert_eq!(chain_text(chain.count), "x3 chain!");
+}
+
+#[test]
+fn test_knockback_chain_carries_over_and_resets() {
+    let mut chain = KnockbackChain::default();
+
+    chain.join(3, 1);
+
+    assert_eq!(chain.bump(1), None);
+    assert_eq!(chain.bump(2), Some(4));
+
+    chain.settle();
+
+    assert_eq!(chain.count, 0);
+    assert_eq!(chain.bump(2), Some(2));
+}
+
+#[test]
+fn test_runner_flees_when_hurt() {
+    let mut bat = Bat::default();
+    bat.runner.enabled = true;
+
+    bat.flee_to_nest();
+
+    assert!(matches!(bat.state, State::FleeingToNest));
+}
+
+#[test]
+fn test_parry_staggers_attacker() {
+    let mut bat = Bat::default();
+
+    assert!(bat.start_parry_stagger());
+    assert!(matches!(bat.state, State::Staggered(_)));
+
+    bat.tick_stagger(bat.parry_stagger.duration);
+
+    assert!(matches!(bat.state, State::Idle));
+    // the bonus hit ends with the stagger
+    assert_eq!(bat.parry_stagger.apply(2), 2);
+}
+
+#[test]
+fn test_parry_opens_counter() {
+    let mut bat = Bat::default();
+    bat.parry.chance = 1.0;
+
+    assert!(bat.start_parry());
+    assert!(matches!(bat.state, State::CounterWindup(_)));
+}
+
+#[test]
+fn test_reset_returns_to_spawn() {
+    let mut bat = Bat {
+        state: State::Chase,
+        velocity: Vector2::new(20.0, 0.0),
+        spawn_position: Vector2::new(64.0, 32.0),
+        ..Bat::default()
+    };
+    bat.enrage.time_limit = 10.0;
+    bat.enrage.tick_time_limit(10.0);
+    bat.update_enrage(8, 8);
+
+    assert_eq!(bat.reset(), Vector2::new(64.0, 32.0));
+    assert!(matches!(bat.state, State::Idle));
+    assert_eq!(bat.velocity, Vector2::zero());
+    assert_eq!(bat.update_enrage(8, 8), Some(false));
+}
+
+#[test]
+fn test_knockback_decays_by_the_friction_every_frame() {
+    let initial = Vector2::new(120.0, 0.0);
+
+    assert_eq!(simulate_knockback(200.0, initial, 0, 0.125), initial);
+    assert_eq!(
+        simulate_knockback(200.0, initial, 1, 0.125),
+        Vector2::new(95.0, 0.0)
+    );
+    assert_eq!(
+        simulate_knockback(200.0, initial, 3, 0.125),
+        Vector2::new(45.0, 0.0)
+    );
+}
+
+#[test]
+fn test_knockback_decays_fully_and_stays_at_zero() {
+    let initial = Vector2::new(0.0, -120.0);
+
+    // 120 at 25 per frame is gone by the fifth
+    assert_ne!(
+        simulate_knockback(200.0, initial, 4, 0.125),
+        Vector2::zero()
+    );
+    assert_eq!(
+        simulate_knockback(200.0, initial, 5, 0.125),
+        Vector2::zero()
+    );
+    assert_eq!(
+        simulate_knockback(200.0, initial, 50, 0.125),
+        Vector2::zero()
+    );
+}
+
+#[test]
+fn test_higher_knockback_friction_stops_sooner() {
+    let initial = Vector2::new(120.0, 0.0);
+
+    assert_eq!(
+        simulate_knockback(100.0, initial, 4, 0.125),
+        Vector2::new(70.0, 0.0)
+    );
+    assert_eq!(
+        simulate_knockback(400.0, initial, 3, 0.125),
+        Vector2::zero()
+    );
+}
+
+#[test]
+fn test_oversized_knockback_is_capped() {
+    let mut bat = Bat {
+        max_knockback_speed: 200.0,
+        ..Bat::default()
+    };
+
+    bat.apply_knockback(Vector2::new(600.0, -800.0));
+
+    assert!((bat.knockback.length() - 200.0).abs() < 0.001);
+    assert!((bat.knockback - Vector2::new(120.0, -160.0)).length() < 0.001);
+}
+
+#[test]
+fn test_knockback_under_the_cap_is_kept() {
+    let mut bat = Bat::default();
+
+    bat.apply_knockback(Vector2::new(120.0, 0.0));
+
+    assert_eq!(bat.knockback, Vector2::new(120.0, 0.0));
+}
+
+#[test]
+fn test_revenant_grows_with_each_ally_death() {
+    let mut bat = Bat {
+        damage: 4,
+        ..Bat::default()
+    };
+    bat.revenant.enabled = true;
+
+    bat.revenant.record_ally_death();
+
+    assert_eq!(bat.contact_damage(), 5);
+    assert_eq!(bat.max_speed(), MAX_SPEED * 1.1);
+
+    bat.revenant.record_ally_death();
+    bat.revenant.record_ally_death();
+
+    assert_eq!(bat.contact_damage(), 7);
+    assert!((bat.max_speed() - MAX_SPEED * 1.3).abs() < 0.001);
 }
@@ -0,0 +1,223 @@
+use crate::extensions::NodeExt;
+use gdnative::api::Control;
+use gdnative::prelude::{
+    ClassBuilder, Color, Label, NativeClass, Node, Rect2, TRef, VariantArray, Vector2,
+};
+
+const BAR_HEIGHT: f32 = 6.0;
+const PIP_RADIUS: f32 = 2.0;
+const PIP_SPACING: f32 = 8.0;
+
+/// The big health bar along the top of the screen during a boss fight, with
+/// the boss name and a pip for each phase left. Bosses show it when they
+/// spawn and hide it when they die. Reached through the `BossBar` autoload.
+#[derive(NativeClass)]
+#[inherit(Control)]
+#[register_with(register_properties)]
+pub struct BossBar {
+    fill_color: Color,
+    fill: f32,
+    phase_count: i32,
+}
+
+fn register_properties(builder: &ClassBuilder<BossBar>) {
+    builder
+        .add_property::<Color>("fill_color")
+        .with_default(Color::rgb(0.8, 0.1, 0.15))
+        .with_getter(|bar: &BossBar, _| bar.fill_color)
+        .with_setter(|bar: &mut BossBar, _, color| bar.fill_color = color)
+        .done();
+}
+
+impl Default for BossBar {
+    fn default() -> Self {
+        Self {
+            fill_color: Color::rgb(0.8, 0.1, 0.15),
+            fill: 1.0,
+            phase_count: 1,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl BossBar {
+    fn new(_owner: &Control) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: &Control) {
+        owner.set_visible(false);
+    }
+
+    #[export]
+    fn _draw(&self, owner: &Control) {
+        let size = Vector2::new(owner.rect_size().x, BAR_HEIGHT);
+
+        owner.draw_rect(
+            Rect2::new(Vector2::zero().to_point(), size.to_size()),
+            Color::rgba(0.0, 0.0, 0.0, 0.6),
+            true,
+            1.0,
+            false,
+        );
+        owner.draw_rect(
+            Rect2::new(
+                Vector2::zero().to_point(),
+                Vector2::new(size.x * self.fill, size.y).to_size(),
+            ),
+            self.fill_color,
+            true,
+            1.0,
+            false,
+        );
+
+        for (index, lit) in phase_pips(self.fill, self.phase_count)
+            .into_iter()
+            .enumerate()
+        {
+            let color = if lit {
+                Color::rgb(1.0, 0.85, 0.2)
+            } else {
+                Color::rgba(1.0, 1.0, 1.0, 0.3)
+            };
+            let position = Vector2::new(
+                PIP_RADIUS + index as f32 * PIP_SPACING,
+                BAR_HEIGHT + PIP_SPACING / 2.0,
+            );
+
+            owner.draw_circle(position, PIP_RADIUS as f64, color);
+        }
+    }
+
+    #[export]
+    fn _on_boss_health_changed(&mut self, owner: &Control, health: i64, max_health: i64) {
+        self.fill = fill_fraction(health as i32, max_health as i32);
+
+        owner.update();
+    }
+
+    pub fn show(&mut self, owner: &Control, name: &str, phase_count: i32) {
+        self.fill = 1.0;
+        self.phase_count = phase_count;
+
+        let name_label = unsafe { owner.get_typed_node::<Label, _>("Name") };
+        name_label.set_text(name);
+
+        owner.set_visible(true);
+        owner.update();
+    }
+
+    /// Shows the time left until the boss enrages, `None` hides it.
+    pub fn set_time_left(&mut self, owner: &Control, time_left: Option<f32>) {
+        let timer_label = unsafe { owner.get_typed_node::<Label, _>("Timer") };
+
+        match time_left {
+            Some(time_left) => {
+                timer_label.set_text(countdown_text(time_left));
+                timer_label.set_visible(true);
+            }
+            None => timer_label.set_visible(false),
+        }
+    }
+
+    pub fn hide(&mut self, owner: &Control) {
+        owner.set_visible(false);
+    }
+}
+
+fn with_bar<T>(owner: &Node, f: impl FnOnce(&mut BossBar, TRef<Control>) -> T) -> Option<T> {
+    let bar_node = unsafe { owner.get_typed_node::<Control, _>("/root/BossBar/Bar") };
+
+    bar_node
+        .cast_instance::<BossBar>()?
+        .map_mut(|bar, bar_owner| f(bar, bar_owner))
+        .ok()
+}
+
+/// Shows the bar of the `BossBar` autoload for a boss, following the
+/// `health_changed` signal of its stats.
+pub fn show(owner: &Node, name: &str, phase_count: i32, stats: TRef<Node>) {
+    with_bar(owner, |bar, bar_owner| {
+        bar.show(&bar_owner, name, phase_count);
+
+        let _ = stats.connect(
+            "health_changed",
+            bar_owner,
+            "_on_boss_health_changed",
+            VariantArray::new_shared(),
+            0,
+        );
+    });
+}
+
+pub fn set_time_left(owner: &Node, time_left: Option<f32>) {
+    with_bar(owner, |bar, bar_owner| {
+        bar.set_time_left(&bar_owner, time_left)
+    });
+}
+
+pub fn hide(owner: &Node) {
+    with_bar(owner, |bar, bar_owner| bar.hide(&bar_owner));
+}
+
+fn fill_fraction(health: i32, max_health: i32) -> f32 {
+    if max_health <= 0 {
+        return 0.0;
+    }
+
+    (health as f32 / max_health as f32).clamp(0.0, 1.0)
+}
+
+// minutes and seconds, rounded up so it reads zero only once it ran out
+fn countdown_text(time_left: f32) -> String {
+    if time_left <= 0.0 {
+        return "Enraged".to_string();
+    }
+
+    let seconds = time_left.ceil() as i32;
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Returns whether each phase pip is still lit, the phases splitting the
+/// health evenly and running out from the last pip.
+fn phase_pips(fill: f32, phase_count: i32) -> Vec<bool> {
+    let phases_left = (fill * phase_count as f32).ceil() as i32;
+
+    (0..phase_count).map(|index| index < phases_left).collect()
+}
+
+#[test]
+fn test_fill_fraction() {
+    assert_eq!(fill_fraction(15, 20), 0.75);
+    assert_eq!(fill_fraction(0, 20), 0.0);
+}
+
+#[test]
+fn test_fill_fraction_is_clamped() {
+    assert_eq!(fill_fraction(-3, 20), 0.0);
+    assert_eq!(fill_fraction(25, 20), 1.0);
+    assert_eq!(fill_fraction(5, 0), 0.0);
+}
+
+#[test]
+fn test_phase_pips_go_out_as_phases_end() {
+    assert_eq!(phase_pips(1.0, 3), vec![true, true, true]);
+    assert_eq!(phase_pips(0.5, 3), vec![true, true, false]);
+    assert_eq!(phase_pips(0.2, 3), vec![true, false, false]);
+    assert_eq!(phase_pips(0.0, 3), vec![false, false, false]);
+}
+
+#[test]
+fn test_phase_pip_stays_lit_until_its_phase_is_cleared() {
+    assert_eq!(phase_pips(0.7, 3), vec![true, true, true]);
+    assert_eq!(phase_pips(0.6, 3), vec![true, true, false]);
+}
+
+#[test]
+fn test_countdown_text() {
+    assert_eq!(countdown_text(65.0), "1:05");
+    assert_eq!(countdown_text(0.2), "0:01");
+    assert_eq!(countdown_text(0.0), "Enraged");
+}
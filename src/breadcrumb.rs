@@ -0,0 +1,60 @@
+use gdnative::api::Sprite;
+use gdnative::prelude::{ClassBuilder, Color, NativeClass};
+
+#[derive(NativeClass)]
+#[inherit(Sprite)]
+#[register_with(register_properties)]
+pub struct Breadcrumb {
+    lifetime: f32,
+    elapsed: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<Breadcrumb>) {
+    builder
+        .add_property::<f32>("lifetime")
+        .with_default(4.0)
+        .with_getter(|breadcrumb: &Breadcrumb, _| breadcrumb.lifetime)
+        .with_setter(|breadcrumb: &mut Breadcrumb, _, lifetime| breadcrumb.lifetime = lifetime)
+        .done();
+}
+
+impl Default for Breadcrumb {
+    fn default() -> Self {
+        Self {
+            lifetime: 4.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Breadcrumb {
+    fn new(_owner: &Sprite) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Sprite, delta: f32) {
+        self.elapsed += delta;
+
+        match fade_alpha(self.elapsed, self.lifetime) {
+            Some(alpha) => owner.set_modulate(Color::rgba(1.0, 1.0, 1.0, alpha)),
+            None => owner.queue_free(),
+        }
+    }
+}
+
+/// Returns the breadcrumb's opacity, or `None` once it has faded out.
+fn fade_alpha(elapsed: f32, lifetime: f32) -> Option<f32> {
+    if elapsed >= lifetime {
+        return None;
+    }
+
+    Some(1.0 - elapsed / lifetime)
+}
+
+#[test]
+fn test_breadcrumb_fades_out() {
+    assert_eq!(fade_alpha(1.0, 4.0), Some(0.75));
+    assert_eq!(fade_alpha(4.0, 4.0), None);
+}
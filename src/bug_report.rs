@@ -0,0 +1,355 @@
+use crate::extensions::NodeExt;
+use crate::player::action_name;
+use gdnative::api::{Area2D, File};
+use gdnative::prelude::{godot_error, godot_print, ClassBuilder, Input, NativeClass, Node};
+use std::collections::VecDeque;
+
+/// Actions a bug report records, held or not each frame.
+pub const RECORDED_ACTIONS: [&str; 12] = [
+    "ui_left", "ui_right", "ui_up", "ui_down", "attack", "roll", "grapple", "parry", "special",
+    "jump", "block", "taunt",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFrame {
+    // seconds since the recording started
+    pub time: f32,
+    // without the player prefix, so the report replays on any player
+    pub held: Vec<String>,
+}
+
+/// The latest inputs of a run with what's needed to play them back the same
+/// way, saved as text so it can be attached to a bug report.
+#[derive(Debug, PartialEq)]
+pub struct BugReport {
+    pub version: String,
+    pub seed: i64,
+    pub inputs: Vec<InputFrame>,
+}
+
+impl BugReport {
+    /// Bundles the inputs recorded within the window before the last one.
+    pub fn new(seed: i64, inputs: &VecDeque<InputFrame>, window: f32) -> Self {
+        let end = inputs.back().map_or(0.0, |frame| frame.time);
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            seed,
+            inputs: inputs
+                .iter()
+                .filter(|frame| frame.time >= end - window)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // a line each for the version and the seed, then a line per frame of
+    // `input time held...`
+    fn to_text(&self) -> String {
+        let mut text = format!("version {}\nseed {}\n", self.version, self.seed);
+
+        for frame in &self.inputs {
+            text.push_str(&format!("input {}", frame.time));
+
+            for action in &frame.held {
+                text.push(' ');
+                text.push_str(action);
+            }
+
+            text.push('\n');
+        }
+
+        text
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        let mut report = Self {
+            version: String::new(),
+            seed: 0,
+            inputs: Vec::new(),
+        };
+
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                Some("version") => report.version = words.next()?.to_string(),
+                Some("seed") => report.seed = words.next()?.parse().ok()?,
+                Some("input") => report.inputs.push(InputFrame {
+                    time: words.next()?.parse().ok()?,
+                    held: words.map(str::to_string).collect(),
+                }),
+                _ => {}
+            }
+        }
+
+        Some(report)
+    }
+
+    pub fn save(&self, path: &str) {
+        let file = File::new();
+
+        if file.open(path, File::WRITE).is_err() {
+            godot_error!("couldn't save the bug report to {}", path);
+            return;
+        }
+
+        file.store_string(self.to_text());
+        file.close();
+
+        godot_print!("bug report saved to {}", path);
+    }
+
+    fn load(path: &str) -> Option<Self> {
+        let file = File::new();
+
+        file.open(path, File::READ).ok()?;
+
+        let text = file.get_as_text().to_string();
+        file.close();
+
+        Self::from_text(&text)
+    }
+}
+
+/// Keeps the latest inputs of the player it's a child of, exporting them as a
+/// bug report when `export_bug_report` is pressed.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_recorder_properties)]
+pub struct BugRecorder {
+    report_path: String,
+    // seconds of input a bug report covers
+    window: f32,
+    inputs: VecDeque<InputFrame>,
+    time: f32,
+}
+
+fn register_recorder_properties(builder: &ClassBuilder<BugRecorder>) {
+    builder
+        .add_property::<String>("report_path")
+        .with_default("user://bug_report.txt".to_string())
+        .with_ref_getter(|recorder: &BugRecorder, _| &recorder.report_path)
+        .with_setter(|recorder: &mut BugRecorder, _, path| recorder.report_path = path)
+        .done();
+    builder
+        .add_property::<f32>("window")
+        .with_default(30.0)
+        .with_getter(|recorder: &BugRecorder, _| recorder.window)
+        .with_setter(|recorder: &mut BugRecorder, _, window| recorder.window = window)
+        .done();
+}
+
+impl Default for BugRecorder {
+    fn default() -> Self {
+        Self {
+            report_path: "user://bug_report.txt".to_string(),
+            window: 30.0,
+            inputs: VecDeque::new(),
+            time: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl BugRecorder {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f32) {
+        self.time += delta;
+
+        self.record_inputs(owner);
+
+        if Input::godot_singleton().is_action_just_pressed("export_bug_report") {
+            self.export_bug_report(owner, self.report_path.clone());
+        }
+    }
+
+    fn record_inputs(&mut self, owner: &Node) {
+        let input = Input::godot_singleton();
+        let player_index = unsafe { owner.get_typed_node::<Node, _>("..") }
+            .get("player_index")
+            .to_i64();
+
+        let held = RECORDED_ACTIONS
+            .iter()
+            .filter(|action| input.is_action_pressed(action_name(player_index, action)))
+            .map(|action| action.to_string())
+            .collect();
+
+        self.inputs.push_back(InputFrame {
+            time: self.time,
+            held,
+        });
+
+        while let Some(frame) = self.inputs.front() {
+            if frame.time >= self.time - self.window {
+                break;
+            }
+
+            self.inputs.pop_front();
+        }
+    }
+
+    /// Saves the latest inputs with the seed of the player's rolls, for
+    /// replaying with a `BugReplay`.
+    #[export]
+    fn export_bug_report(&self, owner: &Node, path: String) {
+        let sword_hitbox =
+            unsafe { owner.get_typed_node::<Area2D, _>("../HitboxPivot/SwordHitbox") };
+        let seed = sword_hitbox.get("seed").to_i64();
+
+        BugReport::new(seed, &self.inputs, self.window).save(&path);
+    }
+}
+
+/// Plays a bug report back on the player it's a child of, pressing the
+/// recorded actions frame by frame, for reproducing a report.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct BugReplay {
+    report_path: String,
+    report: Option<BugReport>,
+    time: f32,
+    next_input: usize,
+}
+
+fn register_properties(builder: &ClassBuilder<BugReplay>) {
+    builder
+        .add_property::<String>("report_path")
+        .with_default("user://bug_report.txt".to_string())
+        .with_ref_getter(|replay: &BugReplay, _| &replay.report_path)
+        .with_setter(|replay: &mut BugReplay, _, path| replay.report_path = path)
+        .done();
+}
+
+impl Default for BugReplay {
+    fn default() -> Self {
+        Self {
+            report_path: "user://bug_report.txt".to_string(),
+            report: None,
+            time: 0.0,
+            next_input: 0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl BugReplay {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Node) {
+        self.report = BugReport::load(&self.report_path);
+
+        let report = match &self.report {
+            Some(report) => report,
+            None => {
+                godot_error!("couldn't load the bug report at {}", self.report_path);
+                return;
+            }
+        };
+
+        if report.version != env!("CARGO_PKG_VERSION") {
+            godot_print!(
+                "bug report from version {}, it may not replay the same",
+                report.version
+            );
+        }
+
+        let sword_hitbox =
+            unsafe { owner.get_typed_node::<Area2D, _>("../HitboxPivot/SwordHitbox") };
+        sword_hitbox.set("seed", report.seed);
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f32) {
+        let report = match &self.report {
+            Some(report) => report,
+            None => return,
+        };
+
+        self.time += delta;
+
+        // the latest frame that's due, skipping any a slower frame missed
+        let mut due = None;
+        while let Some(frame) = report.inputs.get(self.next_input) {
+            if frame.time - report.inputs[0].time > self.time {
+                break;
+            }
+
+            due = Some(frame);
+            self.next_input += 1;
+        }
+
+        let frame = match due {
+            Some(frame) => frame,
+            None => return,
+        };
+
+        let input = Input::godot_singleton();
+        let player_index = unsafe { owner.get_typed_node::<Node, _>("..") }
+            .get("player_index")
+            .to_i64();
+
+        for action in RECORDED_ACTIONS.iter() {
+            let name = action_name(player_index, action);
+
+            if frame.held.iter().any(|held| held == action) {
+                input.action_press(name, 1.0);
+            } else {
+                input.action_release(name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn frames(frames: &[(f32, &[&str])]) -> VecDeque<InputFrame> {
+    frames
+        .iter()
+        .map(|(time, held)| InputFrame {
+            time: *time,
+            held: held.iter().map(|action| action.to_string()).collect(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_bug_report_round_trip() {
+    let report = BugReport::new(
+        42,
+        &frames(&[
+            (0.5, &["ui_right"]),
+            (0.75, &["ui_right", "attack"]),
+            (1.0, &[]),
+        ]),
+        10.0,
+    );
+
+    assert_eq!(BugReport::from_text(&report.to_text()), Some(report));
+}
+
+#[test]
+fn test_bug_report_keeps_only_the_window() {
+    let report = BugReport::new(
+        7,
+        &frames(&[(0.0, &["roll"]), (5.0, &["attack"]), (8.0, &[])]),
+        4.0,
+    );
+
+    assert_eq!(report.inputs.len(), 2);
+    assert_eq!(report.inputs[0].time, 5.0);
+    assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_damaged_bug_report_fails_to_load() {
+    assert_eq!(BugReport::from_text("version 0.1.0\nseed lots\n"), None);
+}
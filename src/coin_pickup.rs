@@ -0,0 +1,120 @@
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use crate::player_stats::PlayerStats;
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node, Ref, Vector2, Vector2Godot};
+
+const MAGNET_SPEED: f32 = 120.0;
+
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct CoinPickup {
+    pub(crate) amount: i32,
+}
+
+fn register_properties(builder: &ClassBuilder<CoinPickup>) {
+    builder
+        .add_property::<i32>("amount")
+        .with_default(1)
+        .with_getter(|coin_pickup: &CoinPickup, _| coin_pickup.amount)
+        .with_setter(|coin_pickup: &mut CoinPickup, _, amount| coin_pickup.amount = amount)
+        .done();
+}
+
+impl Default for CoinPickup {
+    fn default() -> Self {
+        Self { amount: 1 }
+    }
+}
+
+#[gdnative::methods]
+impl CoinPickup {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    // flies to the closest player with the coin in their magnet radius
+    #[export]
+    fn _physics_process(&self, owner: &Area2D, delta: f32) {
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let radius = player_stats_node
+            .cast_instance::<PlayerStats>()
+            .and_then(|player_stats| {
+                player_stats
+                    .map(|player_stats, _| player_stats.effective_magnet_radius())
+                    .ok()
+            })
+            .unwrap_or(0.0);
+
+        let positions: Vec<Vector2> = entity_registry::all_players(owner)
+            .iter()
+            .map(|player| player.global_position())
+            .collect();
+
+        if let Some(target) = magnet_target(owner.global_position(), &positions, radius) {
+            let position = owner.global_position();
+
+            owner.set_global_position(position.move_towards(target, MAGNET_SPEED * delta));
+        }
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_CoinPickup_body_entered(&self, owner: &Area2D, body: Ref<Node>) {
+        let is_player =
+            unsafe { body.assume_safe_if_sane() }.map_or(false, |body| body.is_in_group("player"));
+
+        if !is_player {
+            return;
+        }
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let _ = player_stats_instance.map_mut(|player_stats, _| self.pick_up(player_stats));
+
+        owner.queue_free();
+    }
+
+    pub fn pick_up(&self, player_stats: &mut PlayerStats) {
+        player_stats.coins += self.amount;
+    }
+}
+
+fn magnet_target(position: Vector2, players: &[Vector2], radius: f32) -> Option<Vector2> {
+    players
+        .iter()
+        .copied()
+        .filter(|player| position.distance_to(*player) <= radius)
+        .min_by(|a, b| {
+            position
+                .distance_to(*a)
+                .partial_cmp(&position.distance_to(*b))
+                .unwrap()
+        })
+}
+
+#[test]
+fn test_pick_up_adds_coins() {
+    let coin_pickup = CoinPickup { amount: 3 };
+    let mut player_stats = PlayerStats {
+        coins: 2,
+        ..PlayerStats::default()
+    };
+
+    coin_pickup.pick_up(&mut player_stats);
+
+    assert_eq!(player_stats.coins, 5);
+}
+
+#[test]
+fn test_magnet_pulls_towards_closest_player_in_radius() {
+    let players = [Vector2::new(20.0, 0.0), Vector2::new(-10.0, 0.0)];
+
+    assert_eq!(
+        magnet_target(Vector2::zero(), &players, 24.0),
+        Some(Vector2::new(-10.0, 0.0))
+    );
+    assert_eq!(magnet_target(Vector2::zero(), &players, 8.0), None);
+}
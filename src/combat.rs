@@ -0,0 +1,318 @@
+use crate::rng::Rng;
+use gdnative::prelude::Vector2;
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub damage: i32,
+    pub is_crit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HitMode {
+    // a target is hit once each time it starts overlapping
+    OnEnter,
+    // a target is hit every interval while it keeps overlapping
+    Continuous,
+}
+
+impl Default for HitMode {
+    fn default() -> Self {
+        Self::OnEnter
+    }
+}
+
+impl HitMode {
+    pub const NAMES: [&'static str; 2] = ["OnEnter", "Continuous"];
+
+    pub fn from_index(index: i64) -> Self {
+        match index {
+            1 => Self::Continuous,
+            _ => Self::OnEnter,
+        }
+    }
+
+    pub fn index(self) -> i64 {
+        match self {
+            Self::OnEnter => 0,
+            Self::Continuous => 1,
+        }
+    }
+}
+
+/// Keeps track of the targets a hitbox is overlapping, keyed by instance id.
+pub struct HitTracker {
+    pub mode: HitMode,
+    pub interval: f32,
+    // `None` hits every overlapping target
+    pub max_targets: Option<usize>,
+    // time left until the next hit, only used in `HitMode::Continuous`
+    tracked: HashMap<i64, f32>,
+    // every target hit since the swing started, what `max_targets` caps
+    swing_targets: HashSet<i64>,
+}
+
+impl Default for HitTracker {
+    fn default() -> Self {
+        Self {
+            mode: HitMode::default(),
+            interval: 0.25,
+            max_targets: None,
+            tracked: HashMap::new(),
+            swing_targets: HashSet::new(),
+        }
+    }
+}
+
+impl HitTracker {
+    /// Starts counting the targets hit over again.
+    pub fn start_swing(&mut self) {
+        self.swing_targets.clear();
+    }
+
+    /// Returns the targets that should be hit this frame.
+    pub fn update(&mut self, overlapping: &[i64], delta: f32) -> Vec<i64> {
+        self.tracked.retain(|id, _| overlapping.contains(id));
+
+        let mut hits = Vec::new();
+
+        for &id in overlapping {
+            let is_full = self
+                .max_targets
+                .map_or(false, |max_targets| self.swing_targets.len() >= max_targets);
+
+            match self.tracked.entry(id) {
+                Entry::Vacant(entry) => {
+                    // targets over the cap are ignored until the next swing,
+                    // the ones already hit can be hit again
+                    if !is_full || self.swing_targets.contains(&id) {
+                        entry.insert(self.interval);
+                        self.swing_targets.insert(id);
+                        hits.push(id);
+                    }
+                }
+                Entry::Occupied(mut entry) => {
+                    if let HitMode::Continuous = self.mode {
+                        let time_left = entry.get_mut();
+                        *time_left -= delta;
+
+                        if *time_left <= 0.0 {
+                            *time_left += self.interval;
+                            hits.push(id);
+                        }
+                    }
+                }
+            }
+        }
+
+        hits
+    }
+}
+
+/// What came of a hitbox overlapping a hurtbox for a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HitOutcome {
+    Applied,
+    // the hit mode already spent the overlap, or the target was over the cap
+    ConsumedByHitMode,
+    // the target couldn't be hurt, like while spawning or on its armored side
+    ConsumedByIframes,
+    Parried,
+}
+
+/// A hitbox overlapping a hurtbox, logged for diagnosing missed hits.
+pub struct OverlapEntry {
+    pub hitbox_position: Vector2,
+    pub hurtbox_position: Vector2,
+    // `None` when no hit was rolled
+    pub damage: Option<i32>,
+    pub outcome: HitOutcome,
+}
+
+impl OverlapEntry {
+    pub fn to_log_line(&self) -> String {
+        let outcome = match self.outcome {
+            HitOutcome::Applied => "applied",
+            HitOutcome::ConsumedByHitMode => "consumed (hit mode)",
+            HitOutcome::ConsumedByIframes => "consumed (i-frames)",
+            HitOutcome::Parried => "parried",
+        };
+        let damage = self
+            .damage
+            .map_or("-".to_string(), |damage| damage.to_string());
+
+        format!(
+            "hitbox ({:.1}, {:.1}) hurtbox ({:.1}, {:.1}) damage {} {}",
+            self.hitbox_position.x,
+            self.hitbox_position.y,
+            self.hurtbox_position.x,
+            self.hurtbox_position.y,
+            damage,
+            outcome
+        )
+    }
+}
+
+pub fn compute_hit(base_damage: i32, crit_chance: f32, crit_multiplier: f32, roll: f32) -> Hit {
+    let is_crit = roll < crit_chance;
+
+    let damage = if is_crit {
+        (base_damage as f32 * crit_multiplier).round() as i32
+    } else {
+        base_damage
+    };
+
+    Hit { damage, is_crit }
+}
+
+pub fn roll_hit(base_damage: i32, crit_chance: f32, crit_multiplier: f32, rng: &mut Rng) -> Hit {
+    compute_hit(base_damage, crit_chance, crit_multiplier, rng.next_f32())
+}
+
+#[test]
+fn test_roll_below_chance_is_crit() {
+    let hit = compute_hit(2, 0.25, 2.0, 0.1);
+
+    assert_eq!(
+        hit,
+        Hit {
+            damage: 4,
+            is_crit: true
+        }
+    );
+}
+
+#[test]
+fn test_roll_above_chance_is_not_crit() {
+    let hit = compute_hit(2, 0.25, 2.0, 0.25);
+
+    assert_eq!(
+        hit,
+        Hit {
+            damage: 2,
+            is_crit: false
+        }
+    );
+}
+
+#[test]
+fn test_zero_crit_chance_never_crits() {
+    let mut rng = Rng::new(1);
+
+    for _ in 0..100 {
+        assert!(!roll_hit(1, 0.0, 2.0, &mut rng).is_crit);
+    }
+}
+
+#[test]
+fn test_crit_multiplier_rounds_damage() {
+    let hit = compute_hit(3, 1.0, 1.5, 0.0);
+
+    assert_eq!(hit.damage, 5);
+}
+
+#[test]
+fn test_on_enter_hits_once_per_overlap() {
+    let mut tracker = HitTracker::default();
+
+    let hits: usize = (0..8).map(|_| tracker.update(&[1], 0.125).len()).sum();
+
+    assert_eq!(hits, 1);
+}
+
+#[test]
+fn test_on_enter_hits_again_after_leaving() {
+    let mut tracker = HitTracker::default();
+
+    tracker.update(&[1], 0.125);
+    tracker.update(&[], 0.125);
+
+    assert_eq!(tracker.update(&[1], 0.125), vec![1]);
+}
+
+#[test]
+fn test_continuous_hits_every_interval() {
+    let mut tracker = HitTracker {
+        mode: HitMode::Continuous,
+        ..Default::default()
+    };
+
+    let hits: usize = (0..8).map(|_| tracker.update(&[1], 0.125).len()).sum();
+
+    assert_eq!(hits, 4);
+}
+
+#[test]
+fn test_hits_every_overlapping_target() {
+    let mut tracker = HitTracker::default();
+
+    assert_eq!(tracker.update(&[1, 2, 3], 0.125), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_max_targets_caps_targets_hit() {
+    let mut tracker = HitTracker {
+        max_targets: Some(2),
+        ..Default::default()
+    };
+
+    assert_eq!(tracker.update(&[1, 2, 3], 0.125), vec![1, 2]);
+    // the third target stays ignored while the first two still overlap
+    assert_eq!(tracker.update(&[1, 2, 3], 0.125), vec![]);
+}
+
+#[test]
+fn test_max_targets_counts_every_target_in_the_swing() {
+    let mut tracker = HitTracker {
+        max_targets: Some(1),
+        ..Default::default()
+    };
+
+    assert_eq!(tracker.update(&[1], 0.125), vec![1]);
+    // the first target leaving doesn't free its slot for another
+    assert_eq!(tracker.update(&[2], 0.125), vec![]);
+
+    tracker.start_swing();
+
+    assert_eq!(tracker.update(&[2], 0.125), vec![2]);
+}
+
+#[test]
+fn test_overlap_log_shows_applied_hit() {
+    let entry = OverlapEntry {
+        hitbox_position: Vector2::new(10.0, 4.0),
+        hurtbox_position: Vector2::new(12.5, 4.0),
+        damage: Some(3),
+        outcome: HitOutcome::Applied,
+    };
+
+    assert_eq!(
+        entry.to_log_line(),
+        "hitbox (10.0, 4.0) hurtbox (12.5, 4.0) damage 3 applied"
+    );
+}
+
+#[test]
+fn test_overlap_log_shows_consumed_hit() {
+    let entry = OverlapEntry {
+        hitbox_position: Vector2::zero(),
+        hurtbox_position: Vector2::zero(),
+        damage: None,
+        outcome: HitOutcome::ConsumedByHitMode,
+    };
+
+    assert!(entry
+        .to_log_line()
+        .ends_with("damage - consumed (hit mode)"));
+
+    let entry = OverlapEntry {
+        damage: Some(2),
+        outcome: HitOutcome::ConsumedByIframes,
+        ..entry
+    };
+
+    assert!(entry
+        .to_log_line()
+        .ends_with("damage 2 consumed (i-frames)"));
+}
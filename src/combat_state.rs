@@ -0,0 +1,159 @@
+use crate::bat::Bat;
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use gdnative::prelude::{
+    ClassBuilder, ExportInfo, NativeClass, Node, PropertyUsage, Signal, SignalArgument, TRef,
+    ToVariant, Variant, VariantArray, VariantType,
+};
+
+/// Whether the players are in combat, which they are while an enemy is
+/// chasing one of them and for a while after any damage is dealt or taken.
+/// Sends `in_combat_changed` when that changes, for out of combat things
+/// like regen. Damage is followed through the `DamageBus`. Reached through
+/// the `CombatState` autoload.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct CombatState {
+    // seconds after the last damage that combat lasts
+    timeout: f32,
+    since_damage: f32,
+    in_combat: bool,
+}
+
+fn register_properties(builder: &ClassBuilder<CombatState>) {
+    builder
+        .add_property::<f32>("timeout")
+        .with_default(5.0)
+        .with_getter(|state: &CombatState, _| state.timeout)
+        .with_setter(|state: &mut CombatState, _, timeout| state.timeout = timeout)
+        .done();
+
+    builder.add_signal(Signal {
+        name: "in_combat_changed",
+        args: &[SignalArgument {
+            name: "in_combat",
+            default: Variant::new(),
+            export_info: ExportInfo::new(VariantType::Bool),
+            usage: PropertyUsage::DEFAULT,
+        }],
+    });
+}
+
+impl Default for CombatState {
+    fn default() -> Self {
+        Self {
+            timeout: 5.0,
+            // out of combat until the first damage
+            since_damage: f32::INFINITY,
+            in_combat: false,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl CombatState {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: TRef<Node>) {
+        let bus = unsafe { owner.get_typed_node::<Node, _>("/root/DamageBus") };
+
+        let _ = bus.connect(
+            "damage_dealt",
+            owner,
+            "_on_damage_dealt",
+            VariantArray::new_shared(),
+            0,
+        );
+    }
+
+    #[export]
+    fn _on_damage_dealt(
+        &mut self,
+        _owner: &Node,
+        _source: Variant,
+        _target: Variant,
+        _amount: i32,
+        _is_crit: bool,
+    ) {
+        self.record_damage();
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f32) {
+        let enemies_chasing = enemies_chasing(owner);
+
+        if let Some(in_combat) = self.update(delta, enemies_chasing) {
+            owner.emit_signal("in_combat_changed", &[in_combat.to_variant()]);
+        }
+    }
+
+    pub fn record_damage(&mut self) {
+        self.since_damage = 0.0;
+    }
+
+    /// Moves the time since the last damage along, returning whether the
+    /// players are in combat if that changed.
+    fn update(&mut self, delta: f32, enemies_chasing: bool) -> Option<bool> {
+        self.since_damage += delta;
+
+        let in_combat = enemies_chasing || self.since_damage < self.timeout;
+
+        if in_combat == self.in_combat {
+            return None;
+        }
+
+        self.in_combat = in_combat;
+
+        Some(in_combat)
+    }
+}
+
+fn enemies_chasing(owner: &Node) -> bool {
+    entity_registry::all_enemies(owner)
+        .into_iter()
+        .filter_map(|enemy| enemy.cast_instance::<Bat>())
+        .any(|bat| bat.map(|bat, _| bat.is_chasing()).unwrap_or(false))
+}
+
+#[test]
+fn test_out_of_combat_at_first() {
+    let mut state = CombatState::default();
+
+    assert_eq!(state.update(0.1, false), None);
+    assert!(!state.in_combat);
+}
+
+#[test]
+fn test_damage_starts_combat() {
+    let mut state = CombatState::default();
+
+    state.record_damage();
+
+    assert_eq!(state.update(0.1, false), Some(true));
+    assert!(state.in_combat);
+}
+
+#[test]
+fn test_chasing_enemy_keeps_combat_going() {
+    let mut state = CombatState::default();
+
+    assert_eq!(state.update(0.1, true), Some(true));
+    assert_eq!(state.update(10.0, true), None);
+    assert_eq!(state.update(0.1, false), Some(false));
+}
+
+#[test]
+fn test_combat_times_out_after_damage() {
+    let mut state = CombatState::default();
+
+    state.record_damage();
+    state.update(0.1, false);
+
+    assert_eq!(state.update(4.0, false), None);
+    assert_eq!(state.update(1.0, false), Some(false));
+    assert!(!state.in_combat);
+}
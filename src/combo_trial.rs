@@ -0,0 +1,243 @@
+use crate::extensions::NodeExt;
+use gdnative::api::CanvasLayer;
+use gdnative::prelude::{ClassBuilder, Label, NativeClass, Node};
+use std::collections::VecDeque;
+
+/// A combo trial for practicing on the training dummy. The player's action
+/// presses are recorded and checked against the target combo, each press
+/// having to come its delay after the one before, give or take the
+/// tolerance. Reached through the `ComboTrial` autoload, and does nothing
+/// unless enabled.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct ComboTrial {
+    enabled: bool,
+    // like "attack, attack@0.4, roll@0.3", each action after the first with
+    // the seconds it comes after the one before
+    pattern: String,
+    tolerance: f32,
+    steps: Vec<ComboStep>,
+    // the latest presses with the time they came at, only as many as the
+    // combo is long
+    inputs: VecDeque<(f32, String)>,
+    time: f32,
+    completed: i32,
+}
+
+fn register_properties(builder: &ClassBuilder<ComboTrial>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_getter(|trial: &ComboTrial, _| trial.enabled)
+        .with_setter(|trial: &mut ComboTrial, _, enabled| trial.enabled = enabled)
+        .done();
+    builder
+        .add_property::<String>("pattern")
+        .with_default("attack, attack@0.4, roll@0.3, attack@0.2".to_string())
+        .with_ref_getter(|trial: &ComboTrial, _| &trial.pattern)
+        .with_setter(|trial: &mut ComboTrial, _, pattern| trial.set_pattern(pattern))
+        .done();
+    builder
+        .add_property::<f32>("tolerance")
+        .with_default(0.15)
+        .with_getter(|trial: &ComboTrial, _| trial.tolerance)
+        .with_setter(|trial: &mut ComboTrial, _, tolerance| trial.tolerance = tolerance)
+        .done();
+}
+
+impl Default for ComboTrial {
+    fn default() -> Self {
+        let pattern = "attack, attack@0.4, roll@0.3, attack@0.2".to_string();
+
+        Self {
+            enabled: false,
+            steps: parse_pattern(&pattern),
+            pattern,
+            tolerance: 0.15,
+            inputs: VecDeque::new(),
+            time: 0.0,
+            completed: 0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl ComboTrial {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &CanvasLayer, delta: f32) {
+        let readout = unsafe { owner.get_typed_node::<Label, _>("Readout") };
+
+        readout.set_visible(self.enabled);
+
+        if !self.enabled {
+            return;
+        }
+
+        self.time += delta;
+
+        readout.set_text(format!(
+            "trial: {}\ncompleted: {}",
+            self.pattern, self.completed
+        ));
+    }
+
+    fn set_pattern(&mut self, pattern: String) {
+        self.steps = parse_pattern(&pattern);
+        self.pattern = pattern;
+        self.inputs.clear();
+    }
+
+    pub fn record_input(&mut self, action: &str) {
+        if self.enabled {
+            self.record(self.time, action);
+        }
+    }
+
+    /// Records a press, returning whether it completed the combo.
+    fn record(&mut self, time: f32, action: &str) -> bool {
+        self.inputs.push_back((time, action.to_string()));
+
+        while self.inputs.len() > self.steps.len() {
+            self.inputs.pop_front();
+        }
+
+        if !matches_pattern(self.inputs.make_contiguous(), &self.steps, self.tolerance) {
+            return false;
+        }
+
+        // a finished combo doesn't count towards the next one
+        self.inputs.clear();
+        self.completed += 1;
+
+        true
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ComboStep {
+    action: String,
+    // seconds after the step before, unused for the first
+    delay: f32,
+}
+
+fn parse_pattern(pattern: &str) -> Vec<ComboStep> {
+    pattern
+        .split(',')
+        .map(str::trim)
+        .filter(|step| !step.is_empty())
+        .map(|step| match step.split_once('@') {
+            Some((action, delay)) => ComboStep {
+                action: action.trim().to_string(),
+                delay: delay.trim().parse().unwrap_or(0.0),
+            },
+            None => ComboStep {
+                action: step.to_string(),
+                delay: 0.0,
+            },
+        })
+        .collect()
+}
+
+/// Returns whether the presses are the combo's actions in order, each
+/// coming within the tolerance of its delay after the press before.
+fn matches_pattern(inputs: &[(f32, String)], steps: &[ComboStep], tolerance: f32) -> bool {
+    if steps.is_empty() || inputs.len() != steps.len() {
+        return false;
+    }
+
+    let actions_match = inputs
+        .iter()
+        .zip(steps)
+        .all(|((_, action), step)| *action == step.action);
+
+    let timings_match = inputs
+        .windows(2)
+        .zip(steps.iter().skip(1))
+        .all(|(pair, step)| ((pair[1].0 - pair[0].0) - step.delay).abs() <= tolerance);
+
+    actions_match && timings_match
+}
+
+fn with_trial(owner: &Node, f: impl FnOnce(&mut ComboTrial)) {
+    let trial_node = unsafe { owner.get_typed_node::<CanvasLayer, _>("/root/ComboTrial") };
+
+    if let Some(trial) = trial_node.cast_instance::<ComboTrial>() {
+        let _ = trial.map_mut(|trial, _| f(trial));
+    }
+}
+
+/// Logs an action press through the `ComboTrial` autoload.
+pub fn record_input(owner: &Node, action: &str) {
+    with_trial(owner, |trial| trial.record_input(action));
+}
+
+#[cfg(test)]
+fn presses(presses: &[(f32, &str)]) -> Vec<(f32, String)> {
+    presses
+        .iter()
+        .map(|(time, action)| (*time, action.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_parse_pattern() {
+    assert_eq!(
+        parse_pattern("attack, roll@0.3"),
+        vec![
+            ComboStep {
+                action: "attack".to_string(),
+                delay: 0.0,
+            },
+            ComboStep {
+                action: "roll".to_string(),
+                delay: 0.3,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_combo_within_tolerance_matches() {
+    let steps = parse_pattern("attack, attack@0.4, roll@0.3");
+
+    let inputs = presses(&[(1.0, "attack"), (1.5, "attack"), (1.75, "roll")]);
+
+    assert!(matches_pattern(&inputs, &steps, 0.15));
+}
+
+#[test]
+fn test_combo_outside_tolerance_fails() {
+    let steps = parse_pattern("attack, attack@0.4, roll@0.3");
+
+    let too_slow = presses(&[(1.0, "attack"), (1.5, "attack"), (2.0, "roll")]);
+    let too_fast = presses(&[(1.0, "attack"), (1.125, "attack"), (1.5, "roll")]);
+
+    assert!(!matches_pattern(&too_slow, &steps, 0.15));
+    assert!(!matches_pattern(&too_fast, &steps, 0.15));
+}
+
+#[test]
+fn test_combo_with_wrong_action_fails() {
+    let steps = parse_pattern("attack, roll@0.3");
+
+    let inputs = presses(&[(1.0, "attack"), (1.25, "grapple")]);
+
+    assert!(!matches_pattern(&inputs, &steps, 0.15));
+}
+
+#[test]
+fn test_trial_completes_on_latest_presses() {
+    let mut trial = ComboTrial::default();
+    trial.set_pattern("attack, roll@0.3".to_string());
+
+    // a stray press before the combo is dropped
+    assert!(!trial.record(0.0, "roll"));
+    assert!(!trial.record(1.0, "attack"));
+    assert!(trial.record(1.25, "roll"));
+    assert_eq!(trial.completed, 1);
+    assert!(trial.inputs.is_empty());
+}
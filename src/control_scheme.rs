@@ -0,0 +1,71 @@
+use gdnative::prelude::Vector2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlScheme {
+    // attacks go where the player is facing
+    Classic,
+    // movement and aim are read from separate sticks
+    TwinStick,
+}
+
+impl Default for ControlScheme {
+    fn default() -> Self {
+        Self::Classic
+    }
+}
+
+impl ControlScheme {
+    pub const NAMES: [&'static str; 2] = ["Classic", "TwinStick"];
+
+    pub fn from_index(index: i64) -> Self {
+        match index {
+            1 => Self::TwinStick,
+            _ => Self::Classic,
+        }
+    }
+
+    pub fn index(self) -> i64 {
+        match self {
+            Self::Classic => 0,
+            Self::TwinStick => 1,
+        }
+    }
+
+    pub fn aim_direction(self, facing: Vector2, aim_input: Vector2) -> Vector2 {
+        match self {
+            Self::Classic => facing,
+            // keep the last facing while the aim stick is at rest
+            Self::TwinStick if aim_input == Vector2::zero() => facing,
+            Self::TwinStick => aim_input,
+        }
+    }
+}
+
+#[test]
+fn test_classic_aims_with_facing() {
+    let aim = ControlScheme::Classic.aim_direction(Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0));
+
+    assert_eq!(aim, Vector2::new(1.0, 0.0));
+}
+
+#[test]
+fn test_twin_stick_aims_with_aim_input() {
+    let aim =
+        ControlScheme::TwinStick.aim_direction(Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0));
+
+    assert_eq!(aim, Vector2::new(0.0, 1.0));
+}
+
+#[test]
+fn test_twin_stick_without_aim_input_keeps_facing() {
+    let aim = ControlScheme::TwinStick.aim_direction(Vector2::new(1.0, 0.0), Vector2::zero());
+
+    assert_eq!(aim, Vector2::new(1.0, 0.0));
+}
+
+#[test]
+fn test_index_round_trip() {
+    for scheme in [ControlScheme::Classic, ControlScheme::TwinStick].iter() {
+        assert_eq!(ControlScheme::from_index(scheme.index()), *scheme);
+    }
+}
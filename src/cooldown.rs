@@ -0,0 +1,104 @@
+/// Time that has to pass after something is used before it can be used
+/// again, like a parry or a spawner. Ready until first started.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Cooldown {
+    pub duration: f32,
+    time_left: f32,
+}
+
+impl Cooldown {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            time_left: 0.0,
+        }
+    }
+
+    /// A cooldown that's already running, for one-off waits like a flash.
+    pub fn started(duration: f32) -> Self {
+        Self {
+            duration,
+            time_left: duration,
+        }
+    }
+
+    /// Starts the cooldown over, even if it's still running.
+    pub fn start(&mut self) {
+        self.time_left = self.duration;
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        self.time_left = (self.time_left - delta).max(0.0);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.time_left <= 0.0
+    }
+
+    pub fn time_left(&self) -> f32 {
+        self.time_left
+    }
+}
+
+#[test]
+fn test_cooldown_is_ready_until_started() {
+    let mut cooldown = Cooldown::new(2.0);
+
+    assert!(cooldown.is_ready());
+
+    cooldown.start();
+
+    assert!(!cooldown.is_ready());
+    assert_eq!(cooldown.time_left(), 2.0);
+}
+
+#[test]
+fn test_cooldown_is_ready_once_ticked_through() {
+    let mut cooldown = Cooldown::new(2.0);
+
+    cooldown.start();
+    cooldown.tick(1.5);
+
+    assert!(!cooldown.is_ready());
+
+    cooldown.tick(1.0);
+
+    assert!(cooldown.is_ready());
+    assert_eq!(cooldown.time_left(), 0.0);
+}
+
+#[test]
+fn test_started_cooldown_runs_right_away() {
+    let cooldown = Cooldown::started(2.0);
+
+    assert!(!cooldown.is_ready());
+    assert_eq!(cooldown.time_left(), 2.0);
+}
+
+#[test]
+fn test_starting_again_restarts_the_cooldown() {
+    let mut cooldown = Cooldown::new(2.0);
+
+    cooldown.start();
+    cooldown.tick(1.5);
+    cooldown.start();
+    cooldown.tick(1.5);
+
+    assert!(!cooldown.is_ready());
+    assert_eq!(cooldown.time_left(), 0.5);
+}
+
+#[test]
+fn test_paused_tick_does_not_advance_the_cooldown() {
+    let settings = crate::settings::Settings::default();
+    let mut cooldown = Cooldown::new(1.0);
+
+    cooldown.start();
+    cooldown.tick(settings.game_delta(0.0625, true));
+
+    assert_eq!(cooldown.time_left(), 1.0);
+
+    cooldown.tick(settings.game_delta(0.0625, false));
+
+    assert_eq!(cooldown.time_left(), 0.9375);
+}
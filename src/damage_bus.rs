@@ -0,0 +1,153 @@
+use crate::combat::Hit;
+use crate::extensions::NodeExt;
+use gdnative::prelude::{
+    ClassBuilder, ExportInfo, NativeClass, Node, PropertyUsage, Signal, SignalArgument, ToVariant,
+    Variant, VariantType,
+};
+
+/// Sends every hit landed on an enemy or a player as `damage_dealt`, and
+/// every death as `entity_died`, so meters, UI and audio can follow all the
+/// fighting from one place. The source of a hit is null when there isn't
+/// one, like for hazards. Reached through the `DamageBus` autoload.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_signals)]
+#[derive(Default)]
+pub struct DamageBus;
+
+fn register_signals(builder: &ClassBuilder<DamageBus>) {
+    let argument = |name, variant_type| SignalArgument {
+        name,
+        default: Variant::new(),
+        export_info: ExportInfo::new(variant_type),
+        usage: PropertyUsage::DEFAULT,
+    };
+
+    builder.add_signal(Signal {
+        name: "damage_dealt",
+        args: &[
+            argument("source", VariantType::Object),
+            argument("target", VariantType::Object),
+            argument("amount", VariantType::I64),
+            argument("is_crit", VariantType::Bool),
+        ],
+    });
+    builder.add_signal(Signal {
+        name: "entity_died",
+        args: &[argument("entity", VariantType::Object)],
+    });
+}
+
+#[gdnative::methods]
+impl DamageBus {
+    fn new(_owner: &Node) -> Self {
+        Self
+    }
+}
+
+/// What happened in a hit, with the instance ids of who was involved.
+#[derive(Debug, PartialEq)]
+pub enum DamageEvent {
+    Dealt {
+        source: Option<i64>,
+        target: i64,
+        amount: i32,
+        is_crit: bool,
+    },
+    Died {
+        entity: i64,
+    },
+}
+
+/// Returns the events for a hit on the target, which it may have died of.
+pub fn hit_events(source: Option<i64>, target: i64, hit: Hit, died: bool) -> Vec<DamageEvent> {
+    let mut events = vec![DamageEvent::Dealt {
+        source,
+        target,
+        amount: hit.damage,
+        is_crit: hit.is_crit,
+    }];
+
+    if died {
+        events.push(DamageEvent::Died { entity: target });
+    }
+
+    events
+}
+
+// an entity that's already gone is sent as null
+fn node_variant(id: i64) -> Variant {
+    unsafe { Node::try_from_instance_id(id) }
+        .map(|node| node.to_variant())
+        .unwrap_or_else(Variant::new)
+}
+
+/// Sends the events through the `DamageBus` autoload.
+pub fn emit(owner: &Node, events: Vec<DamageEvent>) {
+    let bus = unsafe { owner.get_typed_node::<Node, _>("/root/DamageBus") };
+
+    for event in events {
+        match event {
+            DamageEvent::Dealt {
+                source,
+                target,
+                amount,
+                is_crit,
+            } => {
+                let source = source.map(node_variant).unwrap_or_else(Variant::new);
+
+                bus.emit_signal(
+                    "damage_dealt",
+                    &[
+                        source,
+                        node_variant(target),
+                        amount.to_variant(),
+                        is_crit.to_variant(),
+                    ],
+                );
+            }
+            DamageEvent::Died { entity } => {
+                bus.emit_signal("entity_died", &[node_variant(entity)]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hit_sends_its_damage() {
+    let hit = Hit {
+        damage: 3,
+        is_crit: true,
+    };
+
+    assert_eq!(
+        hit_events(Some(1), 2, hit, false),
+        vec![DamageEvent::Dealt {
+            source: Some(1),
+            target: 2,
+            amount: 3,
+            is_crit: true,
+        }]
+    );
+}
+
+#[test]
+fn test_killing_hit_sends_the_death_after_the_damage() {
+    let hit = Hit {
+        damage: 1,
+        is_crit: false,
+    };
+
+    assert_eq!(
+        hit_events(None, 2, hit, true),
+        vec![
+            DamageEvent::Dealt {
+                source: None,
+                target: 2,
+                amount: 1,
+                is_crit: false,
+            },
+            DamageEvent::Died { entity: 2 },
+        ]
+    );
+}
@@ -0,0 +1,187 @@
+use crate::extensions::NodeExt;
+use gdnative::api::CanvasLayer;
+use gdnative::prelude::{ClassBuilder, Label, NativeClass, Node, Ref, TRef, Variant, VariantArray};
+use std::collections::VecDeque;
+
+/// Debug readout of the damage dealt to enemies and taken by the player,
+/// as totals and as damage per second over the last few seconds, for
+/// balancing, following the `DamageBus`. Reached through the `DamageMeter`
+/// autoload, and does nothing unless enabled.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct DamageMeter {
+    enabled: bool,
+    // seconds the damage per second is averaged over
+    window: f32,
+    // seconds since the meter started, what damage is timestamped with
+    time: f32,
+    dealt: RollingDamage,
+    taken: RollingDamage,
+}
+
+fn register_properties(builder: &ClassBuilder<DamageMeter>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_getter(|meter: &DamageMeter, _| meter.enabled)
+        .with_setter(|meter: &mut DamageMeter, _, enabled| meter.enabled = enabled)
+        .done();
+    builder
+        .add_property::<f32>("window")
+        .with_default(5.0)
+        .with_getter(|meter: &DamageMeter, _| meter.window)
+        .with_setter(|meter: &mut DamageMeter, _, window| meter.window = window)
+        .done();
+}
+
+impl Default for DamageMeter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: 5.0,
+            time: 0.0,
+            dealt: RollingDamage::default(),
+            taken: RollingDamage::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl DamageMeter {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: TRef<CanvasLayer>) {
+        let bus = unsafe { owner.get_typed_node::<Node, _>("/root/DamageBus") };
+
+        let _ = bus.connect(
+            "damage_dealt",
+            owner,
+            "_on_damage_dealt",
+            VariantArray::new_shared(),
+            0,
+        );
+    }
+
+    #[export]
+    fn _on_damage_dealt(
+        &mut self,
+        _owner: &CanvasLayer,
+        _source: Variant,
+        target: Ref<Node>,
+        amount: i32,
+        _is_crit: bool,
+    ) {
+        let target_is_player = unsafe { target.assume_safe_if_sane() }
+            .map_or(false, |target| target.is_in_group("player"));
+
+        if target_is_player {
+            self.record_taken(amount);
+        } else {
+            self.record_dealt(amount);
+        }
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &CanvasLayer, delta: f32) {
+        let readout = unsafe { owner.get_typed_node::<Label, _>("Readout") };
+
+        readout.set_visible(self.enabled);
+
+        if !self.enabled {
+            return;
+        }
+
+        self.time += delta;
+
+        readout.set_text(format!(
+            "dealt: {} ({:.1} dps)\ntaken: {} ({:.1} dps)",
+            self.dealt.total,
+            self.dealt.dps(self.time, self.window),
+            self.taken.total,
+            self.taken.dps(self.time, self.window),
+        ));
+    }
+
+    pub fn record_dealt(&mut self, damage: i32) {
+        if self.enabled {
+            self.dealt.record(self.time, damage);
+        }
+    }
+
+    pub fn record_taken(&mut self, damage: i32) {
+        if self.enabled {
+            self.taken.record(self.time, damage);
+        }
+    }
+}
+
+#[derive(Default)]
+struct RollingDamage {
+    // oldest first, each with the time it was dealt
+    events: VecDeque<(f32, i32)>,
+    total: i64,
+}
+
+impl RollingDamage {
+    fn record(&mut self, time: f32, damage: i32) {
+        self.events.push_back((time, damage));
+        self.total += damage as i64;
+    }
+
+    /// Returns the damage per second over the window ending now, dropping
+    /// the damage that fell out of it.
+    fn dps(&mut self, now: f32, window: f32) -> f32 {
+        if window <= 0.0 {
+            return 0.0;
+        }
+
+        while let Some((time, _)) = self.events.front() {
+            if *time > now - window {
+                break;
+            }
+
+            self.events.pop_front();
+        }
+
+        self.events.iter().map(|(_, damage)| *damage).sum::<i32>() as f32 / window
+    }
+}
+
+#[test]
+fn test_dps_over_window() {
+    let mut damage = RollingDamage::default();
+
+    damage.record(0.5, 4);
+    damage.record(1.0, 2);
+    damage.record(2.5, 4);
+
+    assert_eq!(damage.dps(3.0, 5.0), 2.0);
+}
+
+#[test]
+fn test_old_damage_leaves_window() {
+    let mut damage = RollingDamage::default();
+
+    damage.record(0.0, 10);
+    damage.record(4.0, 5);
+    damage.record(5.0, 5);
+
+    assert_eq!(damage.dps(6.0, 5.0), 2.0);
+    assert_eq!(damage.events.len(), 2);
+    // the total keeps everything
+    assert_eq!(damage.total, 20);
+}
+
+#[test]
+fn test_no_damage_is_zero_dps() {
+    let mut damage = RollingDamage::default();
+
+    assert_eq!(damage.dps(10.0, 5.0), 0.0);
+
+    damage.record(1.0, 3);
+
+    assert_eq!(damage.dps(10.0, 5.0), 0.0);
+}
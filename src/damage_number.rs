@@ -0,0 +1,390 @@
+use crate::rng::Rng;
+use gdnative::api::DynamicFont;
+use gdnative::prelude::{ClassBuilder, Color, Label, NativeClass, TRef, Vector2};
+use std::collections::HashMap;
+
+const FLOAT_SPEED: f32 = 30.0;
+const LIFETIME: f32 = 0.6;
+
+#[derive(NativeClass)]
+#[inherit(Label)]
+#[register_with(register_properties)]
+pub struct DamageNumber {
+    pub(crate) amount: i32,
+    pub(crate) is_crit: bool,
+    // shown instead of the amount, for popups that aren't damage
+    text: Option<String>,
+    normal_color: Color,
+    crit_color: Color,
+    outline_size: i64,
+    crit_outline_size: i64,
+    crit_scale: f32,
+    // pixels a crit shakes by at first, settling over the shake duration
+    crit_shake: f32,
+    crit_shake_duration: f32,
+    // the shake the label was last moved by, taken back the next frame
+    shake_offset: Vector2,
+    rng: Rng,
+    elapsed: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<DamageNumber>) {
+    builder
+        .add_property::<Color>("normal_color")
+        .with_default(Color::rgb(1.0, 1.0, 1.0))
+        .with_getter(|number: &DamageNumber, _| number.normal_color)
+        .with_setter(|number: &mut DamageNumber, _, color| number.normal_color = color)
+        .done();
+    builder
+        .add_property::<Color>("crit_color")
+        .with_default(Color::rgb(1.0, 0.85, 0.2))
+        .with_getter(|number: &DamageNumber, _| number.crit_color)
+        .with_setter(|number: &mut DamageNumber, _, color| number.crit_color = color)
+        .done();
+    builder
+        .add_property::<i64>("outline_size")
+        .with_default(1)
+        .with_getter(|number: &DamageNumber, _| number.outline_size)
+        .with_setter(|number: &mut DamageNumber, _, size| number.outline_size = size)
+        .done();
+    builder
+        .add_property::<i64>("crit_outline_size")
+        .with_default(2)
+        .with_getter(|number: &DamageNumber, _| number.crit_outline_size)
+        .with_setter(|number: &mut DamageNumber, _, size| number.crit_outline_size = size)
+        .done();
+    builder
+        .add_property::<f32>("crit_scale")
+        .with_default(1.5)
+        .with_getter(|number: &DamageNumber, _| number.crit_scale)
+        .with_setter(|number: &mut DamageNumber, _, scale| number.crit_scale = scale)
+        .done();
+    builder
+        .add_property::<f32>("crit_shake")
+        .with_default(3.0)
+        .with_getter(|number: &DamageNumber, _| number.crit_shake)
+        .with_setter(|number: &mut DamageNumber, _, shake| number.crit_shake = shake)
+        .done();
+    builder
+        .add_property::<f32>("crit_shake_duration")
+        .with_default(0.2)
+        .with_getter(|number: &DamageNumber, _| number.crit_shake_duration)
+        .with_setter(|number: &mut DamageNumber, _, duration| number.crit_shake_duration = duration)
+        .done();
+}
+
+impl Default for DamageNumber {
+    fn default() -> Self {
+        Self {
+            amount: 0,
+            is_crit: false,
+            text: None,
+            normal_color: Color::rgb(1.0, 1.0, 1.0),
+            crit_color: Color::rgb(1.0, 0.85, 0.2),
+            outline_size: 1,
+            crit_outline_size: 2,
+            crit_scale: 1.5,
+            crit_shake: 3.0,
+            crit_shake_duration: 0.2,
+            shake_offset: Vector2::zero(),
+            rng: Rng::default(),
+            elapsed: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl DamageNumber {
+    fn new(_owner: &Label) -> Self {
+        Self::default()
+    }
+
+    pub fn setup(&mut self, amount: i32, is_crit: bool) {
+        self.amount = amount;
+        self.is_crit = is_crit;
+    }
+
+    pub fn setup_text(&mut self, text: String) {
+        self.text = Some(text);
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: TRef<Label>) {
+        // numbers popping up together shouldn't shake alike
+        self.rng = Rng::new(owner.get_instance_id() as u64);
+
+        self.refresh(owner);
+    }
+
+    /// Replaces the shown damage with the aggregated total and restarts
+    /// the float.
+    pub fn merge(&mut self, owner: TRef<Label>, total: i32, is_crit: bool) {
+        self.setup(total, is_crit);
+        self.elapsed = 0.0;
+
+        self.refresh(owner);
+    }
+
+    fn refresh(&self, owner: TRef<Label>) {
+        match self.text.as_ref() {
+            Some(text) => owner.set_text(text.as_str()),
+            None => owner.set_text(self.amount.to_string()),
+        }
+        owner.add_color_override("font_color", self.color());
+
+        let scale = self.text_scale();
+        owner.set_scale(Vector2::new(scale, scale));
+
+        self.apply_outline(owner);
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Label, delta: f32) {
+        self.elapsed += delta;
+
+        let shake_offset = self.shake();
+
+        owner.set_position(
+            owner.position() - self.shake_offset
+                + Vector2::new(0.0, -FLOAT_SPEED * delta)
+                + shake_offset,
+            false,
+        );
+        self.shake_offset = shake_offset;
+
+        if self.elapsed >= LIFETIME {
+            owner.queue_free();
+        }
+    }
+
+    // the outline is only visible when the label uses a DynamicFont, so any
+    // other font is left untouched
+    fn apply_outline(&self, owner: TRef<Label>) {
+        let font = match owner.get_font("font", "") {
+            Some(font) => font,
+            None => return,
+        };
+        let font = unsafe { font.assume_safe() };
+
+        // the font is a shared resource, so it is duplicated before changing
+        // the outline, otherwise every label using it would change as well
+        let font = match font
+            .duplicate(false)
+            .and_then(|font| font.cast::<DynamicFont>())
+        {
+            Some(font) => font,
+            None => return,
+        };
+        let font = unsafe { font.assume_safe() };
+
+        font.set_outline_size(self.outline_thickness());
+        font.set_outline_color(Color::rgb(0.0, 0.0, 0.0));
+
+        owner.add_font_override("font", font);
+    }
+
+    /// Returns a random offset for a crit, weaker the longer it has been
+    /// shown and settled once the shake duration is over.
+    fn shake(&mut self) -> Vector2 {
+        if !self.is_crit || self.elapsed >= self.crit_shake_duration {
+            return Vector2::zero();
+        }
+
+        let strength = self.crit_shake * (1.0 - self.elapsed / self.crit_shake_duration);
+
+        Vector2::new(self.rng.range_f32(-1.0, 1.0), self.rng.range_f32(-1.0, 1.0)) * strength
+    }
+
+    pub fn color(&self) -> Color {
+        if self.is_crit {
+            self.crit_color
+        } else {
+            self.normal_color
+        }
+    }
+
+    pub fn outline_thickness(&self) -> i64 {
+        if self.is_crit {
+            self.crit_outline_size
+        } else {
+            self.outline_size
+        }
+    }
+
+    pub fn text_scale(&self) -> f32 {
+        if self.is_crit {
+            self.crit_scale
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Popup {
+    pub total: i32,
+    pub is_crit: bool,
+    // whether a new number has to be spawned instead of growing the last one
+    pub is_new: bool,
+}
+
+struct Aggregate {
+    total: i32,
+    is_crit: bool,
+    time_left: f32,
+}
+
+/// Sums the damage each target takes within a window so it shows up as one
+/// growing number.
+#[derive(Default)]
+pub struct DamageAggregator {
+    // zero spawns a number for every hit
+    pub window: f32,
+    aggregates: HashMap<i64, Aggregate>,
+}
+
+impl DamageAggregator {
+    pub fn update(&mut self, delta: f32) {
+        for aggregate in self.aggregates.values_mut() {
+            aggregate.time_left -= delta;
+        }
+
+        self.aggregates
+            .retain(|_, aggregate| aggregate.time_left > 0.0);
+    }
+
+    pub fn add(&mut self, target: i64, amount: i32, is_crit: bool) -> Popup {
+        let window = self.window;
+
+        if let Some(aggregate) = self.aggregates.get_mut(&target) {
+            aggregate.total += amount;
+            aggregate.is_crit |= is_crit;
+            aggregate.time_left = window;
+
+            return Popup {
+                total: aggregate.total,
+                is_crit: aggregate.is_crit,
+                is_new: false,
+            };
+        }
+
+        if window > 0.0 {
+            self.aggregates.insert(
+                target,
+                Aggregate {
+                    total: amount,
+                    is_crit,
+                    time_left: window,
+                },
+            );
+        }
+
+        Popup {
+            total: amount,
+            is_crit,
+            is_new: true,
+        }
+    }
+}
+
+#[test]
+fn test_text_scale_normal() {
+    let number = DamageNumber::default();
+
+    assert_eq!(number.text_scale(), 1.0);
+    assert_eq!(number.outline_thickness(), 1);
+}
+
+#[test]
+fn test_text_scale_crit() {
+    let mut number = DamageNumber::default();
+
+    number.setup(3, true);
+
+    assert_eq!(number.text_scale(), 1.5);
+    assert_eq!(number.outline_thickness(), 2);
+}
+
+#[test]
+fn test_aggregates_hits_within_window() {
+    let mut aggregator = DamageAggregator {
+        window: 0.3,
+        ..Default::default()
+    };
+
+    assert!(aggregator.add(1, 2, false).is_new);
+
+    aggregator.update(0.1);
+
+    assert_eq!(
+        aggregator.add(1, 3, true),
+        Popup {
+            total: 5,
+            is_crit: true,
+            is_new: false,
+        }
+    );
+    // other targets get their own number
+    assert!(aggregator.add(2, 1, false).is_new);
+}
+
+#[test]
+fn test_new_popup_after_window() {
+    let mut aggregator = DamageAggregator {
+        window: 0.3,
+        ..Default::default()
+    };
+
+    aggregator.add(1, 2, false);
+    aggregator.update(0.4);
+
+    assert_eq!(
+        aggregator.add(1, 3, false),
+        Popup {
+            total: 3,
+            is_crit: false,
+            is_new: true,
+        }
+    );
+}
+
+#[test]
+fn test_normal_numbers_dont_shake() {
+    let mut number = DamageNumber::default();
+
+    number.setup(3, false);
+
+    assert_eq!(number.shake(), Vector2::zero());
+}
+
+#[test]
+fn test_crit_shake_weakens_over_its_duration() {
+    let mut number = DamageNumber::default();
+
+    number.setup(3, true);
+
+    let mut last_strength = number.crit_shake;
+
+    for step in 0..4 {
+        number.elapsed = step as f32 * 0.05;
+
+        let strength = number.crit_shake * (1.0 - number.elapsed / number.crit_shake_duration);
+        let offset = number.shake();
+
+        assert!(offset.x.abs() <= strength && offset.y.abs() <= strength);
+        assert!(strength <= last_strength);
+
+        last_strength = strength;
+    }
+}
+
+#[test]
+fn test_crit_shake_settles_before_the_float_out() {
+    let mut number = DamageNumber::default();
+
+    number.setup(3, true);
+    number.elapsed = number.crit_shake_duration;
+
+    assert!(number.crit_shake_duration < LIFETIME);
+    assert_eq!(number.shake(), Vector2::zero());
+}
@@ -0,0 +1,70 @@
+use crate::settings;
+use gdnative::api::CanvasItem;
+use gdnative::prelude::{Angle, Color, Vector2, Vector2Godot};
+
+// the arrowhead's sides, angled back from the tip
+const HEAD_LENGTH: f32 = 4.0;
+const HEAD_ANGLE: f32 = 2.6;
+
+/// An arrow from a body's origin, in its local space.
+#[derive(Debug, PartialEq)]
+pub struct Arrow {
+    pub end: Vector2,
+    pub head_left: Vector2,
+    pub head_right: Vector2,
+}
+
+/// Returns the arrow showing a knockback, as long as the knockback times the
+/// scale, or `None` without one.
+pub fn knockback_arrow(knockback: Vector2, scale: f32) -> Option<Arrow> {
+    let direction = knockback.try_normalize()?;
+    let end = knockback * scale;
+    // short arrows keep a head in proportion
+    let head_length = HEAD_LENGTH.min(end.length() / 2.0);
+
+    Some(Arrow {
+        end,
+        head_left: end + direction.rotated(Angle::radians(HEAD_ANGLE)) * head_length,
+        head_right: end + direction.rotated(Angle::radians(-HEAD_ANGLE)) * head_length,
+    })
+}
+
+pub fn draw_arrow(owner: &CanvasItem, arrow: &Arrow, color: Color) {
+    owner.draw_line(Vector2::zero(), arrow.end, color, 1.0, false);
+    owner.draw_line(arrow.end, arrow.head_left, color, 1.0, false);
+    owner.draw_line(arrow.end, arrow.head_right, color, 1.0, false);
+}
+
+/// Redraws a body while debug draw is on, and once more when it's turned
+/// off to clear what it drew.
+pub fn refresh(owner: &CanvasItem, drawn: &mut bool) {
+    let enabled = settings::debug_draw(owner);
+
+    if enabled || *drawn {
+        owner.update();
+    }
+
+    *drawn = enabled;
+}
+
+#[test]
+fn test_arrow_ends_at_scaled_knockback() {
+    let arrow = knockback_arrow(Vector2::new(120.0, -40.0), 0.25).unwrap();
+
+    assert_eq!(arrow.end, Vector2::new(30.0, -10.0));
+}
+
+#[test]
+fn test_arrowhead_points_back_along_the_arrow() {
+    let arrow = knockback_arrow(Vector2::new(0.0, 80.0), 0.5).unwrap();
+
+    assert!(arrow.head_left.y < arrow.end.y);
+    assert!(arrow.head_right.y < arrow.end.y);
+    assert!((arrow.head_left.x + arrow.head_right.x).abs() < 1e-4);
+    assert!((arrow.end.distance_to(arrow.head_left) - HEAD_LENGTH).abs() < 1e-4);
+}
+
+#[test]
+fn test_no_knockback_no_arrow() {
+    assert_eq!(knockback_arrow(Vector2::zero(), 0.25), None);
+}
@@ -0,0 +1,99 @@
+use crate::extensions::NodeExt;
+use crate::flags::Flags;
+use gdnative::api::StaticBody2D;
+use gdnative::prelude::{ClassBuilder, Label, NativeClass, Node, Ref};
+
+const LOCKED_PROMPT: &str = "locked";
+
+#[derive(NativeClass)]
+#[inherit(StaticBody2D)]
+#[derive(Default)]
+#[register_with(register_properties)]
+pub struct Door {
+    // an empty flag leaves the door unlocked
+    requires_flag: String,
+}
+
+fn register_properties(builder: &ClassBuilder<Door>) {
+    builder
+        .add_property::<String>("requires_flag")
+        .with_ref_getter(|door: &Door, _| &door.requires_flag)
+        .with_setter(|door: &mut Door, _, requires_flag| door.requires_flag = requires_flag)
+        .done();
+}
+
+#[gdnative::methods]
+impl Door {
+    fn new(_owner: &StaticBody2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: &StaticBody2D) {
+        let prompt = unsafe { owner.get_typed_node::<Label, _>("Prompt") };
+        prompt.set_text(LOCKED_PROMPT);
+        prompt.hide();
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_InteractArea_body_entered(&self, owner: &StaticBody2D, body: Ref<Node>) {
+        if !Self::is_player(body) {
+            return;
+        }
+
+        let flags_node = unsafe { owner.get_typed_node::<Node, _>("/root/Flags") };
+        let flags_instance = flags_node.cast_instance::<Flags>().unwrap();
+
+        let is_unlocked = flags_instance
+            .map(|flags, _| self.is_unlocked(flags))
+            .unwrap_or(false);
+
+        if is_unlocked {
+            owner.queue_free();
+        } else {
+            let prompt = unsafe { owner.get_typed_node::<Label, _>("Prompt") };
+            prompt.show();
+        }
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_InteractArea_body_exited(&self, owner: &StaticBody2D, body: Ref<Node>) {
+        if !Self::is_player(body) {
+            return;
+        }
+
+        let prompt = unsafe { owner.get_typed_node::<Label, _>("Prompt") };
+        prompt.hide();
+    }
+
+    fn is_player(body: Ref<Node>) -> bool {
+        unsafe { body.assume_safe_if_sane() }.map_or(false, |body| body.is_in_group("player"))
+    }
+
+    pub fn is_unlocked(&self, flags: &Flags) -> bool {
+        self.requires_flag.is_empty() || flags.has(&self.requires_flag)
+    }
+}
+
+#[test]
+fn test_door_without_flag_is_unlocked() {
+    let door = Door::default();
+
+    assert!(door.is_unlocked(&Flags::default()));
+}
+
+#[test]
+fn test_door_locked_until_flag_is_set() {
+    let door = Door {
+        requires_flag: "has_key".to_string(),
+    };
+    let mut flags = Flags::default();
+
+    assert!(!door.is_unlocked(&flags));
+
+    flags.set("has_key");
+
+    assert!(door.is_unlocked(&flags));
+}
@@ -0,0 +1,55 @@
+/// Reads the player's attacks, countering the ones they start nearby.
+pub struct Duelist {
+    pub enabled: bool,
+    pub range: f32,
+    pub windup: f32,
+    // only an attack the player starts is countered, not one that's ongoing
+    player_was_attacking: bool,
+}
+
+impl Default for Duelist {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            range: 40.0,
+            windup: 0.3,
+            player_was_attacking: false,
+        }
+    }
+}
+
+impl Duelist {
+    /// Returns whether to start a counter, when the player just started an
+    /// attack in range.
+    pub fn react(&mut self, player_attacking: bool, distance: f32) -> bool {
+        let attack_started = player_attacking && !self.player_was_attacking;
+        self.player_was_attacking = player_attacking;
+
+        self.enabled && attack_started && distance <= self.range
+    }
+}
+
+#[test]
+fn test_duelist_counters_an_attack_starting_in_range() {
+    let mut duelist = Duelist {
+        enabled: true,
+        ..Duelist::default()
+    };
+
+    assert!(!duelist.react(false, 20.0));
+    assert!(duelist.react(true, 20.0));
+
+    // the same attack isn't countered twice
+    assert!(!duelist.react(true, 20.0));
+}
+
+#[test]
+fn test_duelist_ignores_attacks_out_of_range() {
+    let mut duelist = Duelist {
+        enabled: true,
+        ..Duelist::default()
+    };
+
+    assert!(!duelist.react(true, 60.0));
+    assert!(!duelist.react(false, 20.0));
+}
@@ -0,0 +1,203 @@
+use crate::entity_registry::{self, Kind};
+use crate::extensions::NodeExt;
+use crate::think_scheduler::ThinkScheduler;
+use gdnative::api::CanvasLayer;
+use gdnative::prelude::{ClassBuilder, Label, NativeClass, Node};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Debug readout of the time spent updating enemies each frame, and how
+/// many there are, for performance tuning. Reached through the
+/// `EnemyProfiler` autoload, and does nothing unless enabled, apart from
+/// the think budget, which holds whether or not it's shown.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct EnemyProfiler {
+    enabled: bool,
+    // frames the average covers
+    sample_count: i64,
+    // time spent in enemy updates since the last frame
+    current: Duration,
+    frame_times: FrameTimes,
+    think_scheduler: ThinkScheduler,
+}
+
+fn register_properties(builder: &ClassBuilder<EnemyProfiler>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_getter(|profiler: &EnemyProfiler, _| profiler.enabled)
+        .with_setter(|profiler: &mut EnemyProfiler, _, enabled| profiler.enabled = enabled)
+        .done();
+    builder
+        .add_property::<i64>("sample_count")
+        .with_default(60)
+        .with_getter(|profiler: &EnemyProfiler, _| profiler.sample_count)
+        .with_setter(|profiler: &mut EnemyProfiler, _, count| profiler.sample_count = count)
+        .done();
+    // enemies that may pick targets and states each physics frame, taking
+    // turns, zero lets all of them
+    builder
+        .add_property::<i64>("think_budget")
+        .with_getter(|profiler: &EnemyProfiler, _| profiler.think_scheduler.budget as i64)
+        .with_setter(|profiler: &mut EnemyProfiler, _, budget| {
+            profiler.think_scheduler.budget = budget.max(0) as usize
+        })
+        .done();
+}
+
+impl Default for EnemyProfiler {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_count: 60,
+            current: Duration::default(),
+            frame_times: FrameTimes::default(),
+            think_scheduler: ThinkScheduler::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl EnemyProfiler {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &CanvasLayer, _delta: f32) {
+        let readout = unsafe { owner.get_typed_node::<Label, _>("Readout") };
+
+        readout.set_visible(self.enabled);
+
+        if !self.enabled {
+            return;
+        }
+
+        let frame_time = std::mem::take(&mut self.current);
+        self.frame_times
+            .record(frame_time, self.sample_count.max(1) as usize);
+
+        let enemy_count = entity_registry::count(owner, Kind::Enemy);
+
+        let (thought, asked) = self.think_scheduler.last_counts;
+        let budget = match self.think_scheduler.budget {
+            0 => "unlimited".to_string(),
+            budget => budget.to_string(),
+        };
+
+        readout.set_text(format!(
+            "enemies: {}\nai: {:.3} ms (avg {:.3} ms)\nthinking: {}/{} (budget {})",
+            enemy_count,
+            millis(frame_time),
+            millis(self.frame_times.average()),
+            thought,
+            asked,
+            budget,
+        ));
+    }
+
+    // as an autoload this runs before any enemy asks to think
+    #[export]
+    fn _physics_process(&mut self, _owner: &CanvasLayer, _delta: f32) {
+        self.think_scheduler.start_frame();
+    }
+
+    pub fn may_think(&mut self) -> bool {
+        self.think_scheduler.request()
+    }
+
+    pub fn record_update(&mut self, duration: Duration) {
+        if self.enabled {
+            self.current += duration;
+        }
+    }
+}
+
+#[derive(Default)]
+struct FrameTimes {
+    // oldest first
+    samples: VecDeque<Duration>,
+}
+
+impl FrameTimes {
+    fn record(&mut self, frame_time: Duration, sample_count: usize) {
+        self.samples.push_back(frame_time);
+
+        while self.samples.len() > sample_count {
+            self.samples.pop_front();
+        }
+    }
+
+    fn average(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::default();
+        }
+
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn with_profiler<T>(owner: &Node, f: impl FnOnce(&mut EnemyProfiler) -> T) -> Option<T> {
+    let profiler_node = unsafe { owner.get_typed_node::<CanvasLayer, _>("/root/EnemyProfiler") };
+
+    profiler_node
+        .cast_instance::<EnemyProfiler>()
+        .and_then(|profiler| profiler.map_mut(|profiler, _| f(profiler)).ok())
+}
+
+/// Logs the time an enemy took to update through the `EnemyProfiler`
+/// autoload.
+pub fn record_update(owner: &Node, duration: Duration) {
+    with_profiler(owner, |profiler| profiler.record_update(duration));
+}
+
+/// Asks the think budget on the `EnemyProfiler` autoload whether an enemy
+/// gets to think this physics frame.
+pub fn may_think(owner: &Node) -> bool {
+    with_profiler(owner, |profiler| profiler.may_think()).unwrap_or(true)
+}
+
+#[test]
+fn test_average_frame_time() {
+    let mut frame_times = FrameTimes::default();
+
+    frame_times.record(Duration::from_micros(100), 60);
+    frame_times.record(Duration::from_micros(300), 60);
+
+    assert_eq!(frame_times.average(), Duration::from_micros(200));
+}
+
+#[test]
+fn test_average_covers_latest_frames() {
+    let mut frame_times = FrameTimes::default();
+
+    frame_times.record(Duration::from_micros(900), 2);
+    frame_times.record(Duration::from_micros(100), 2);
+    frame_times.record(Duration::from_micros(300), 2);
+
+    assert_eq!(frame_times.samples.len(), 2);
+    assert_eq!(frame_times.average(), Duration::from_micros(200));
+}
+
+#[test]
+fn test_no_frames_average_zero() {
+    assert_eq!(FrameTimes::default().average(), Duration::default());
+}
+
+#[test]
+fn test_updates_add_up_within_a_frame() {
+    let mut profiler = EnemyProfiler {
+        enabled: true,
+        ..EnemyProfiler::default()
+    };
+
+    profiler.record_update(Duration::from_micros(40));
+    profiler.record_update(Duration::from_micros(60));
+
+    assert_eq!(profiler.current, Duration::from_micros(100));
+}
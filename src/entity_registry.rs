@@ -0,0 +1,165 @@
+use crate::explosion;
+use crate::extensions::NodeExt;
+use gdnative::prelude::{GodotObject, KinematicBody2D, NativeClass, Node, TRef, Vector2};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Enemy,
+    Player,
+}
+
+/// Every living enemy and player, registered when they enter the room and
+/// unregistered when they die or leave it, so finding them doesn't take a
+/// walk through the scene tree. Reached through the `EntityRegistry`
+/// autoload.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[derive(Default)]
+pub struct EntityRegistry {
+    registry: Registry,
+}
+
+#[gdnative::methods]
+impl EntityRegistry {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+}
+
+/// The instance ids of the registered entities, in the order they
+/// registered.
+#[derive(Default)]
+struct Registry {
+    entries: Vec<(i64, Kind)>,
+}
+
+impl Registry {
+    /// Registering an entity again keeps it as it was.
+    fn register(&mut self, id: i64, kind: Kind) {
+        if !self.entries.iter().any(|(entry, _)| *entry == id) {
+            self.entries.push((id, kind));
+        }
+    }
+
+    fn unregister(&mut self, id: i64) {
+        self.entries.retain(|(entry, _)| *entry != id);
+    }
+
+    fn ids(&self, kind: Kind) -> Vec<i64> {
+        self.entries
+            .iter()
+            .filter(|(_, entry_kind)| *entry_kind == kind)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    fn count(&self, kind: Kind) -> usize {
+        self.entries
+            .iter()
+            .filter(|(_, entry_kind)| *entry_kind == kind)
+            .count()
+    }
+}
+
+fn with_registry<T>(owner: &Node, f: impl FnOnce(&mut Registry) -> T) -> Option<T> {
+    let registry_node = unsafe { owner.get_typed_node::<Node, _>("/root/EntityRegistry") };
+
+    registry_node
+        .cast_instance::<EntityRegistry>()?
+        .map_mut(|registry, _| f(&mut registry.registry))
+        .ok()
+}
+
+pub fn register(owner: &Node, kind: Kind) {
+    let id = owner.get_instance_id();
+
+    with_registry(owner, |registry| registry.register(id, kind));
+}
+
+pub fn unregister(owner: &Node) {
+    let id = owner.get_instance_id();
+
+    // the registry may already be gone when the game quits
+    if owner.has_node("/root/EntityRegistry") {
+        with_registry(owner, |registry| registry.unregister(id));
+    }
+}
+
+pub fn count(owner: &Node, kind: Kind) -> usize {
+    with_registry(owner, |registry| registry.count(kind)).unwrap_or(0)
+}
+
+fn all_of<'a>(owner: &Node, kind: Kind) -> Vec<TRef<'a, KinematicBody2D>> {
+    with_registry(owner, |registry| registry.ids(kind))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|id| unsafe { KinematicBody2D::try_from_instance_id(id) })
+        .collect()
+}
+
+/// Returns every registered enemy that's still around.
+pub fn all_enemies<'a>(owner: &Node) -> Vec<TRef<'a, KinematicBody2D>> {
+    all_of(owner, Kind::Enemy)
+}
+
+/// Returns every registered player, in the order they joined.
+pub fn all_players<'a>(owner: &Node) -> Vec<TRef<'a, KinematicBody2D>> {
+    all_of(owner, Kind::Player)
+}
+
+/// Returns the registered enemies within `radius` of `center`.
+pub fn enemies_within<'a>(
+    owner: &Node,
+    center: Vector2,
+    radius: f32,
+) -> Vec<TRef<'a, KinematicBody2D>> {
+    let enemies = all_enemies(owner);
+    let positions: Vec<Vector2> = enemies
+        .iter()
+        .map(|enemy| enemy.global_position())
+        .collect();
+
+    explosion::targets_in_radius(center, radius, &positions)
+        .into_iter()
+        .map(|index| enemies[index])
+        .collect()
+}
+
+#[test]
+fn test_registering_counts_by_kind() {
+    let mut registry = Registry::default();
+
+    registry.register(1, Kind::Enemy);
+    registry.register(2, Kind::Enemy);
+    registry.register(3, Kind::Player);
+
+    assert_eq!(registry.count(Kind::Enemy), 2);
+    assert_eq!(registry.count(Kind::Player), 1);
+    assert_eq!(registry.ids(Kind::Enemy), vec![1, 2]);
+}
+
+#[test]
+fn test_registering_twice_counts_once() {
+    let mut registry = Registry::default();
+
+    registry.register(1, Kind::Enemy);
+    registry.register(1, Kind::Enemy);
+
+    assert_eq!(registry.count(Kind::Enemy), 1);
+}
+
+#[test]
+fn test_unregistering_keeps_the_count_accurate() {
+    let mut registry = Registry::default();
+
+    registry.register(1, Kind::Enemy);
+    registry.register(2, Kind::Enemy);
+    registry.unregister(1);
+    // unregistering what isn't registered, like a bat that died and then
+    // left the tree, changes nothing
+    registry.unregister(1);
+    registry.unregister(7);
+
+    assert_eq!(registry.ids(Kind::Enemy), vec![2]);
+    assert_eq!(registry.count(Kind::Enemy), 1);
+}
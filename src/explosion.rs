@@ -0,0 +1,69 @@
+use crate::bat::Bat;
+use crate::entity_registry;
+use gdnative::prelude::{Node, Vector2, Vector2Godot};
+
+/// Damages every enemy within `radius` of `center`.
+pub fn explode(owner: &Node, center: Vector2, radius: f32, damage: i32) {
+    for enemy in entity_registry::enemies_within(owner, center, radius) {
+        if let Some(enemy) = enemy.cast_instance::<Bat>() {
+            let _ = enemy.map_mut(|enemy, enemy_owner| {
+                enemy.take_damage(&enemy_owner, damage);
+            });
+        }
+    }
+}
+
+/// Returns the indices of the positions within `radius` of `center`.
+pub fn targets_in_radius(center: Vector2, radius: f32, positions: &[Vector2]) -> Vec<usize> {
+    positions
+        .iter()
+        .enumerate()
+        .filter(|(_, position)| center.distance_to(**position) <= radius)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Returns the indices of the positions on the ring of `radius` around
+/// `center`, within half of `band_width` of it.
+pub fn targets_in_band(
+    center: Vector2,
+    radius: f32,
+    band_width: f32,
+    positions: &[Vector2],
+) -> Vec<usize> {
+    positions
+        .iter()
+        .enumerate()
+        .filter(|(_, position)| (center.distance_to(**position) - radius).abs() <= band_width / 2.0)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[test]
+fn test_targets_in_radius() {
+    let positions = [Vector2::new(30.0, 0.0), Vector2::new(0.0, -12.0)];
+
+    assert_eq!(
+        targets_in_radius(Vector2::zero(), 16.0, &positions),
+        vec![1]
+    );
+}
+
+#[test]
+fn test_targets_in_band_on_the_ring() {
+    let positions = [
+        Vector2::new(31.0, 0.0),
+        Vector2::new(0.0, -34.0),
+        Vector2::new(10.0, 0.0),
+        Vector2::new(0.0, 40.0),
+    ];
+
+    assert_eq!(
+        targets_in_band(Vector2::zero(), 32.0, 6.0, &positions),
+        vec![0, 1]
+    );
+    assert_eq!(
+        targets_in_band(Vector2::zero(), 32.0, 3.0, &positions),
+        vec![0]
+    );
+}
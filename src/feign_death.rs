@@ -0,0 +1,74 @@
+/// Lets an enemy shrug off its killing blow once, lying still at one health
+/// until a player comes close or enough time passes, then reviving.
+pub struct FeignDeath {
+    pub enabled: bool,
+    pub revive_range: f32,
+    pub revive_time: f32,
+    // an enemy only feigns death once
+    used: bool,
+}
+
+impl Default for FeignDeath {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            revive_range: 32.0,
+            revive_time: 4.0,
+            used: false,
+        }
+    }
+}
+
+impl FeignDeath {
+    /// Returns how long to lie still for when the killing blow is shrugged
+    /// off, or `None` when the enemy really dies.
+    pub fn try_feign(&mut self) -> Option<f32> {
+        if !self.enabled || self.used {
+            return None;
+        }
+
+        self.used = true;
+
+        Some(self.revive_time)
+    }
+
+    /// Returns whether an enemy feigning death for the time left revives,
+    /// given how far the closest player is.
+    pub fn revives(&self, time_left: f32, closest_player: Option<f32>) -> bool {
+        let player_close = closest_player.map_or(false, |distance| distance <= self.revive_range);
+
+        player_close || time_left <= 0.0
+    }
+}
+
+#[test]
+fn test_feigns_death_once() {
+    let mut feign_death = FeignDeath {
+        enabled: true,
+        ..FeignDeath::default()
+    };
+
+    assert_eq!(feign_death.try_feign(), Some(4.0));
+    assert_eq!(feign_death.try_feign(), None);
+}
+
+#[test]
+fn test_only_feigners_feign_death() {
+    assert_eq!(FeignDeath::default().try_feign(), None);
+}
+
+#[test]
+fn test_revives_when_player_approaches() {
+    let feign_death = FeignDeath::default();
+
+    assert!(!feign_death.revives(3.9, Some(40.0)));
+    assert!(!feign_death.revives(3.8, None));
+    assert!(feign_death.revives(3.7, Some(30.0)));
+}
+
+#[test]
+fn test_revives_after_time() {
+    let feign_death = FeignDeath::default();
+
+    assert!(feign_death.revives(0.0, None));
+}
@@ -0,0 +1,34 @@
+use gdnative::prelude::{NativeClass, Node};
+use std::collections::HashSet;
+
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[derive(Default)]
+pub struct Flags {
+    flags: HashSet<String>,
+}
+
+#[gdnative::methods]
+impl Flags {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, flag: &str) {
+        self.flags.insert(flag.to_string());
+    }
+
+    pub fn has(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+#[test]
+fn test_set_flag() {
+    let mut flags = Flags::default();
+
+    flags.set("has_key");
+
+    assert!(flags.has("has_key"));
+    assert!(!flags.has("has_map"));
+}
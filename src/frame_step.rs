@@ -0,0 +1,140 @@
+use gdnative::prelude::{ClassBuilder, Input, NativeClass, Node};
+
+/// Debug pausing that runs the game one physics frame per `frame_step`
+/// press, toggled with `frame_step_toggle`. Reached through the `FrameStep`
+/// autoload, which keeps processing while the tree is paused.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+#[derive(Default)]
+pub struct FrameStep {
+    enabled: bool,
+    // physics frames the presses so far still owe
+    frames_left: u32,
+}
+
+fn register_properties(builder: &ClassBuilder<FrameStep>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_getter(|frame_step: &FrameStep, _| frame_step.enabled)
+        .with_setter(|frame_step: &mut FrameStep, _, enabled| frame_step.set_enabled(enabled))
+        .done();
+}
+
+#[gdnative::methods]
+impl FrameStep {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, _delta: f32) {
+        let input = Input::godot_singleton();
+
+        if input.is_action_just_pressed("frame_step_toggle") {
+            self.set_enabled(!self.enabled);
+
+            // leaving frame step lets the game carry on
+            if !self.enabled {
+                set_paused(owner, false);
+            }
+        }
+
+        if input.is_action_just_pressed("frame_step") {
+            self.press();
+        }
+    }
+
+    // as an autoload this runs before the rest of the tree, so pausing here
+    // holds back the whole frame
+    #[export]
+    fn _physics_process(&mut self, owner: &Node, _delta: f32) {
+        if self.enabled {
+            let runs = self.start_frame();
+
+            set_paused(owner, !runs);
+        }
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.frames_left = 0;
+    }
+
+    fn press(&mut self) {
+        if self.enabled {
+            self.frames_left += 1;
+        }
+    }
+
+    /// Returns whether the physics frame that's starting gets to run.
+    fn start_frame(&mut self) -> bool {
+        if !self.enabled {
+            return true;
+        }
+
+        if self.frames_left == 0 {
+            return false;
+        }
+
+        self.frames_left -= 1;
+
+        true
+    }
+}
+
+fn set_paused(owner: &Node, paused: bool) {
+    let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+
+    tree.set_pause(paused);
+}
+
+#[test]
+fn test_press_advances_exactly_one_frame() {
+    let mut frame_step = FrameStep::default();
+    frame_step.set_enabled(true);
+
+    assert!(!frame_step.start_frame());
+
+    frame_step.press();
+
+    assert!(frame_step.start_frame());
+    assert!(!frame_step.start_frame());
+    assert!(!frame_step.start_frame());
+}
+
+#[test]
+fn test_presses_between_frames_add_up() {
+    let mut frame_step = FrameStep::default();
+    frame_step.set_enabled(true);
+
+    frame_step.press();
+    frame_step.press();
+
+    assert!(frame_step.start_frame());
+    assert!(frame_step.start_frame());
+    assert!(!frame_step.start_frame());
+}
+
+#[test]
+fn test_disabled_runs_every_frame() {
+    let mut frame_step = FrameStep::default();
+
+    frame_step.press();
+
+    assert!(frame_step.start_frame());
+    assert!(frame_step.start_frame());
+    assert_eq!(frame_step.frames_left, 0);
+}
+
+#[test]
+fn test_toggling_drops_owed_frames() {
+    let mut frame_step = FrameStep::default();
+    frame_step.set_enabled(true);
+    frame_step.press();
+
+    frame_step.set_enabled(false);
+    frame_step.set_enabled(true);
+
+    assert!(!frame_step.start_frame());
+}
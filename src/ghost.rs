@@ -0,0 +1,277 @@
+use crate::extensions::NodeExt;
+use gdnative::api::{ConfigFile, Sprite};
+use gdnative::prelude::{
+    godot_error, ClassBuilder, Color, Float32Array, Int32Array, NativeClass, Node, Variant,
+    Vector2, Vector2Array,
+};
+
+const SAVE_SECTION: &str = "ghost";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frame {
+    // seconds since the run started
+    time: f32,
+    position: Vector2,
+    // the player's sprite frame, which is all its animations show
+    sprite_frame: i64,
+}
+
+/// A recorded run, oldest frame first.
+#[derive(Default)]
+struct Track {
+    frames: Vec<Frame>,
+}
+
+impl Track {
+    /// Returns where the run was at the time and the sprite frame it showed,
+    /// moving in a straight line between recorded frames. After the end of a
+    /// run shorter than the current one, the ghost waits where it finished.
+    fn sample(&self, time: f32) -> Option<(Vector2, i64)> {
+        let first = self.frames.first()?;
+        let last = self.frames.last()?;
+
+        if time <= first.time {
+            return Some((first.position, first.sprite_frame));
+        }
+
+        if time >= last.time {
+            return Some((last.position, last.sprite_frame));
+        }
+
+        // the first frame past the time, which can't be the first frame
+        let next = self.frames.iter().position(|frame| frame.time > time)?;
+        let (from, to) = (self.frames[next - 1], self.frames[next]);
+        let weight = (time - from.time) / (to.time - from.time);
+
+        Some((from.position.lerp(to.position, weight), from.sprite_frame))
+    }
+
+    fn load(path: &str) -> Self {
+        let config = ConfigFile::new();
+
+        // no recording yet means no ghost
+        if config.load(path).is_err() {
+            return Self::default();
+        }
+
+        let value = |key: &str| config.get_value(SAVE_SECTION, key, Variant::new());
+        let times = value("times").to_float32_array();
+        let positions = value("positions").to_vector2_array();
+        let sprite_frames = value("sprite_frames").to_int32_array();
+
+        let (times, positions, sprite_frames) =
+            (times.read(), positions.read(), sprite_frames.read());
+
+        let frames = times
+            .iter()
+            .zip(positions.iter())
+            .zip(sprite_frames.iter())
+            .map(|((time, position), sprite_frame)| Frame {
+                time: *time,
+                position: *position,
+                sprite_frame: *sprite_frame as i64,
+            })
+            .collect();
+
+        Self { frames }
+    }
+
+    fn save(&self, path: &str) {
+        let config = ConfigFile::new();
+
+        let times: Vec<f32> = self.frames.iter().map(|frame| frame.time).collect();
+        let positions: Vec<Vector2> = self.frames.iter().map(|frame| frame.position).collect();
+        let sprite_frames: Vec<i32> = self
+            .frames
+            .iter()
+            .map(|frame| frame.sprite_frame as i32)
+            .collect();
+
+        config.set_value(SAVE_SECTION, "times", Float32Array::from_vec(times));
+        config.set_value(SAVE_SECTION, "positions", Vector2Array::from_vec(positions));
+        config.set_value(
+            SAVE_SECTION,
+            "sprite_frames",
+            Int32Array::from_vec(sprite_frames),
+        );
+
+        if config.save(path).is_err() {
+            godot_error!("couldn't save the ghost recording to {}", path);
+        }
+    }
+}
+
+/// A see-through player that replays the previous run, for time trials.
+#[derive(NativeClass)]
+#[inherit(Sprite)]
+#[register_with(register_ghost_properties)]
+pub struct Ghost {
+    recording_path: String,
+    alpha: f32,
+    track: Track,
+    time: f32,
+}
+
+fn register_ghost_properties(builder: &ClassBuilder<Ghost>) {
+    builder
+        .add_property::<String>("recording_path")
+        .with_default("user://ghost.cfg".to_string())
+        .with_ref_getter(|ghost: &Ghost, _| &ghost.recording_path)
+        .with_setter(|ghost: &mut Ghost, _, path| ghost.recording_path = path)
+        .done();
+    builder
+        .add_property::<f32>("alpha")
+        .with_default(0.4)
+        .with_getter(|ghost: &Ghost, _| ghost.alpha)
+        .with_setter(|ghost: &mut Ghost, _, alpha| ghost.alpha = alpha)
+        .done();
+}
+
+impl Default for Ghost {
+    fn default() -> Self {
+        Self {
+            recording_path: "user://ghost.cfg".to_string(),
+            alpha: 0.4,
+            track: Track::default(),
+            time: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Ghost {
+    fn new(_owner: &Sprite) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Sprite) {
+        self.track = Track::load(&self.recording_path);
+
+        owner.set_modulate(Color::rgba(1.0, 1.0, 1.0, self.alpha));
+        owner.set_visible(!self.track.frames.is_empty());
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Sprite, delta: f32) {
+        self.time += delta;
+
+        if let Some((position, sprite_frame)) = self.track.sample(self.time) {
+            owner.set_global_position(position);
+            owner.set_frame(sprite_frame);
+        }
+    }
+}
+
+/// Records the run of the player it's a child of, saving it when the room
+/// is left for the next run's ghost.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_recorder_properties)]
+pub struct GhostRecorder {
+    recording_path: String,
+    // seconds between recorded frames
+    interval: f32,
+    track: Track,
+    time: f32,
+    next_frame: f32,
+}
+
+fn register_recorder_properties(builder: &ClassBuilder<GhostRecorder>) {
+    builder
+        .add_property::<String>("recording_path")
+        .with_default("user://ghost.cfg".to_string())
+        .with_ref_getter(|recorder: &GhostRecorder, _| &recorder.recording_path)
+        .with_setter(|recorder: &mut GhostRecorder, _, path| recorder.recording_path = path)
+        .done();
+    builder
+        .add_property::<f32>("interval")
+        .with_default(0.05)
+        .with_getter(|recorder: &GhostRecorder, _| recorder.interval)
+        .with_setter(|recorder: &mut GhostRecorder, _, interval| recorder.interval = interval)
+        .done();
+}
+
+impl Default for GhostRecorder {
+    fn default() -> Self {
+        Self {
+            recording_path: "user://ghost.cfg".to_string(),
+            interval: 0.05,
+            track: Track::default(),
+            time: 0.0,
+            next_frame: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl GhostRecorder {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f32) {
+        self.time += delta;
+
+        if self.time < self.next_frame {
+            return;
+        }
+
+        self.next_frame = self.time + self.interval;
+
+        let sprite = unsafe { owner.get_typed_node::<Sprite, _>("../Sprite") };
+
+        self.track.frames.push(Frame {
+            time: self.time,
+            position: sprite.global_position(),
+            sprite_frame: sprite.frame(),
+        });
+    }
+
+    #[export]
+    fn _exit_tree(&self, _owner: &Node) {
+        self.track.save(&self.recording_path);
+    }
+}
+
+#[cfg(test)]
+fn track(frames: &[(f32, f32, i64)]) -> Track {
+    Track {
+        frames: frames
+            .iter()
+            .map(|(time, x, sprite_frame)| Frame {
+                time: *time,
+                position: Vector2::new(*x, 0.0),
+                sprite_frame: *sprite_frame,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_sample_between_frames() {
+    let track = track(&[(0.0, 0.0, 1), (1.0, 10.0, 2), (2.0, 30.0, 3)]);
+
+    assert_eq!(track.sample(0.5), Some((Vector2::new(5.0, 0.0), 1)));
+    assert_eq!(track.sample(1.5), Some((Vector2::new(20.0, 0.0), 2)));
+}
+
+#[test]
+fn test_sample_on_a_frame() {
+    let track = track(&[(0.0, 0.0, 1), (1.0, 10.0, 2), (2.0, 30.0, 3)]);
+
+    assert_eq!(track.sample(1.0), Some((Vector2::new(10.0, 0.0), 2)));
+}
+
+#[test]
+fn test_sample_past_the_end_waits_at_the_finish() {
+    let track = track(&[(0.0, 0.0, 1), (1.0, 10.0, 2)]);
+
+    assert_eq!(track.sample(5.0), Some((Vector2::new(10.0, 0.0), 2)));
+}
+
+#[test]
+fn test_sample_without_recording() {
+    assert_eq!(Track::default().sample(1.0), None);
+}
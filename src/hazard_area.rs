@@ -0,0 +1,118 @@
+use crate::combat::{HitMode, HitTracker};
+use crate::player::Player;
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, KinematicBody2D, NativeClass};
+
+/// A damaging area, like a puddle left by an enemy attack, that hurts
+/// players standing in it and frees itself when its lifetime runs out.
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct HazardArea {
+    damage: i32,
+    lifetime: f32,
+    elapsed: f32,
+    hit_tracker: HitTracker,
+}
+
+fn register_properties(builder: &ClassBuilder<HazardArea>) {
+    builder
+        .add_property::<i32>("damage")
+        .with_default(1)
+        .with_getter(|hazard: &HazardArea, _| hazard.damage)
+        .with_setter(|hazard: &mut HazardArea, _, damage| hazard.damage = damage)
+        .done();
+    builder
+        .add_property::<f32>("lifetime")
+        .with_default(3.0)
+        .with_getter(|hazard: &HazardArea, _| hazard.lifetime)
+        .with_setter(|hazard: &mut HazardArea, _, lifetime| hazard.lifetime = lifetime)
+        .done();
+    builder
+        .add_property::<f32>("hit_interval")
+        .with_default(0.5)
+        .with_getter(|hazard: &HazardArea, _| hazard.hit_tracker.interval)
+        .with_setter(|hazard: &mut HazardArea, _, interval| hazard.hit_tracker.interval = interval)
+        .done();
+}
+
+impl Default for HazardArea {
+    fn default() -> Self {
+        Self {
+            damage: 1,
+            lifetime: 3.0,
+            elapsed: 0.0,
+            hit_tracker: HitTracker {
+                mode: HitMode::Continuous,
+                interval: 0.5,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[gdnative::methods]
+impl HazardArea {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Area2D, delta: f32) {
+        if self.tick(delta) {
+            owner.queue_free();
+            return;
+        }
+
+        // hurtboxes are direct children of the player they protect
+        let players: Vec<(i64, _)> = owner
+            .get_overlapping_areas()
+            .iter()
+            .filter_map(|area| area.try_to_object::<Area2D>())
+            .map(|area| unsafe { area.assume_safe() })
+            .filter_map(|area| {
+                let player = area
+                    .get_parent()
+                    .map(|parent| unsafe { parent.assume_safe() })
+                    .and_then(|parent| parent.cast::<KinematicBody2D>())
+                    .and_then(|parent| parent.cast_instance::<Player>())?;
+
+                Some((area.get_instance_id(), player))
+            })
+            .collect();
+        let ids: Vec<i64> = players.iter().map(|(id, _)| *id).collect();
+
+        let hit_ids = self.hit_tracker.update(&ids, delta);
+        let damage = self.damage;
+
+        for (_, player) in players.iter().filter(|(id, _)| hit_ids.contains(id)) {
+            let _ = player.map_mut(|player, player_owner| {
+                player.receive_hazard_damage(&player_owner, damage)
+            });
+        }
+    }
+
+    /// Advances the lifetime, returning whether the hazard has expired.
+    pub fn tick(&mut self, delta: f32) -> bool {
+        self.elapsed += delta;
+
+        self.elapsed >= self.lifetime
+    }
+}
+
+#[test]
+fn test_hazard_lasts_its_lifetime() {
+    let mut hazard = HazardArea::default();
+
+    assert!(!hazard.tick(1.0));
+    assert!(!hazard.tick(1.0));
+}
+
+#[test]
+fn test_hazard_expires_after_lifetime() {
+    let mut hazard = HazardArea::default();
+
+    hazard.tick(2.0);
+
+    assert!(hazard.tick(1.0));
+}
@@ -0,0 +1,70 @@
+const TAU: f32 = std::f32::consts::PI * 2.0;
+
+/// Returns how far a hovering body is lifted the time into its bob, rising
+/// first and swinging between the amplitude above and below its rest.
+pub fn bob_offset(time: f32, amplitude: f32, frequency: f32) -> f32 {
+    -amplitude * (TAU * frequency * time).sin()
+}
+
+/// A gentle up and down bob for an idle flier, as a vertical offset for its
+/// sprite so its collision stays put.
+pub struct Hover {
+    pub amplitude: f32,
+    // bobs per second
+    pub frequency: f32,
+    time: f32,
+}
+
+impl Hover {
+    pub fn new(amplitude: f32, frequency: f32) -> Self {
+        Self {
+            amplitude,
+            frequency,
+            time: 0.0,
+        }
+    }
+
+    /// Moves the bob along, returning the offset it's at.
+    pub fn advance(&mut self, delta: f32) -> f32 {
+        self.time += delta;
+
+        bob_offset(self.time, self.amplitude, self.frequency)
+    }
+
+    /// Settles the bob, starting it over from rest the next time.
+    pub fn stop(&mut self) {
+        self.time = 0.0;
+    }
+}
+
+#[test]
+fn test_bob_starts_at_rest() {
+    assert_eq!(bob_offset(0.0, 2.0, 1.0), 0.0);
+}
+
+#[test]
+fn test_bob_peaks_at_the_amplitude() {
+    assert!((bob_offset(0.25, 2.0, 1.0) + 2.0).abs() < 0.001);
+    assert!((bob_offset(0.75, 2.0, 1.0) - 2.0).abs() < 0.001);
+}
+
+#[test]
+fn test_bob_stays_within_the_amplitude() {
+    let mut hover = Hover::new(3.0, 1.5);
+
+    for _ in 0..200 {
+        let offset = hover.advance(0.016);
+
+        assert!(offset.abs() <= 3.0 + 0.001);
+    }
+}
+
+#[test]
+fn test_stopped_bob_starts_over() {
+    let mut hover = Hover::new(2.0, 1.0);
+
+    hover.advance(0.3);
+    hover.stop();
+
+    assert!((hover.advance(0.25) + 2.0).abs() < 0.001);
+}
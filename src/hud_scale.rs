@@ -0,0 +1,154 @@
+use gdnative::api::CanvasLayer;
+use gdnative::prelude::{ClassBuilder, NativeClass, TRef, VariantArray, Vector2};
+
+/// Returns how much a HUD laid out for the reference resolution has to be
+/// scaled to fit the viewport, keeping its proportions. With integer
+/// scaling, a viewport at least as big as the reference gets a whole
+/// factor, so pixel art stays crisp.
+fn scale_factor(viewport: Vector2, reference: Vector2, integer_scaling: bool) -> f32 {
+    if reference.x <= 0.0 || reference.y <= 0.0 {
+        return 1.0;
+    }
+
+    let scale = (viewport.x / reference.x).min(viewport.y / reference.y);
+
+    if integer_scaling && scale >= 1.0 {
+        scale.floor()
+    } else {
+        scale
+    }
+}
+
+/// Scales the HUD canvas layer it's on from the viewport size against a
+/// reference resolution, centering it along the side with room to spare.
+/// It's rescaled whenever the viewport is resized.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct HudScale {
+    // the resolution the HUD is laid out for
+    reference_resolution: Vector2,
+    integer_scaling: bool,
+}
+
+fn register_properties(builder: &ClassBuilder<HudScale>) {
+    builder
+        .add_property::<Vector2>("reference_resolution")
+        .with_default(Vector2::new(320.0, 180.0))
+        .with_getter(|hud: &HudScale, _| hud.reference_resolution)
+        .with_setter(|hud: &mut HudScale, _, resolution| hud.reference_resolution = resolution)
+        .done();
+    builder
+        .add_property::<bool>("integer_scaling")
+        .with_default(true)
+        .with_getter(|hud: &HudScale, _| hud.integer_scaling)
+        .with_setter(|hud: &mut HudScale, _, integer_scaling| hud.integer_scaling = integer_scaling)
+        .done();
+}
+
+impl Default for HudScale {
+    fn default() -> Self {
+        Self {
+            reference_resolution: Vector2::new(320.0, 180.0),
+            integer_scaling: true,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl HudScale {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: TRef<CanvasLayer>) {
+        if let Some(viewport) = owner.get_viewport() {
+            let _ = unsafe { viewport.assume_safe() }.connect(
+                "size_changed",
+                owner,
+                "_on_viewport_size_changed",
+                VariantArray::new_shared(),
+                0,
+            );
+        }
+
+        self.rescale(&owner);
+    }
+
+    #[export]
+    fn _on_viewport_size_changed(&self, owner: &CanvasLayer) {
+        self.rescale(owner);
+    }
+
+    fn rescale(&self, owner: &CanvasLayer) {
+        let viewport = match owner.get_viewport() {
+            Some(viewport) => unsafe { viewport.assume_safe() },
+            None => return,
+        };
+
+        // the visible rect is what's left after the project's own stretching,
+        // so a HUD that's already stretched isn't scaled twice
+        let size = viewport.get_visible_rect().size.to_vector();
+        let scale = scale_factor(size, self.reference_resolution, self.integer_scaling);
+
+        owner.set_scale(Vector2::new(scale, scale));
+        owner.set_offset((size - self.reference_resolution * scale) / 2.0);
+    }
+}
+
+#[test]
+fn test_reference_resolution_is_unscaled() {
+    let reference = Vector2::new(320.0, 180.0);
+
+    assert_eq!(scale_factor(reference, reference, true), 1.0);
+}
+
+#[test]
+fn test_scale_keeps_proportions() {
+    let reference = Vector2::new(320.0, 180.0);
+
+    assert_eq!(
+        scale_factor(Vector2::new(1280.0, 720.0), reference, false),
+        4.0
+    );
+    // an ultrawide viewport is fit by its height
+    assert_eq!(
+        scale_factor(Vector2::new(2560.0, 720.0), reference, false),
+        4.0
+    );
+    // and a square one by its width
+    assert_eq!(
+        scale_factor(Vector2::new(640.0, 640.0), reference, false),
+        2.0
+    );
+}
+
+#[test]
+fn test_integer_scaling_rounds_down() {
+    let reference = Vector2::new(320.0, 180.0);
+
+    assert_eq!(
+        scale_factor(Vector2::new(1920.0, 1080.0), reference, true),
+        6.0
+    );
+    assert_eq!(
+        scale_factor(Vector2::new(1366.0, 768.0), reference, true),
+        4.0
+    );
+    // fit by its height, the tighter side
+    assert_eq!(
+        scale_factor(Vector2::new(1366.0, 768.0), reference, false),
+        768.0 / 180.0
+    );
+}
+
+#[test]
+fn test_viewport_smaller_than_reference_scales_down() {
+    let reference = Vector2::new(320.0, 180.0);
+
+    assert_eq!(
+        scale_factor(Vector2::new(160.0, 90.0), reference, true),
+        0.5
+    );
+}
@@ -0,0 +1,177 @@
+use crate::extensions::NodeExt;
+use gdnative::api::{CanvasLayer, Engine};
+use gdnative::prelude::{godot_print, ClassBuilder, Label, NativeClass, Node};
+use std::collections::HashMap;
+
+// how many of the latest measurements the average covers
+const SAMPLE_COUNT: usize = 30;
+
+/// Debug readout of the frames between pressing an action and the player
+/// changing into the state it triggers, like attack into the attack state.
+/// Reached through the `InputLatency` autoload, and does nothing unless
+/// enabled.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct InputLatency {
+    enabled: bool,
+    tracker: LatencyTracker,
+}
+
+fn register_properties(builder: &ClassBuilder<InputLatency>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_getter(|latency: &InputLatency, _| latency.enabled)
+        .with_setter(|latency: &mut InputLatency, _, enabled| latency.enabled = enabled)
+        .done();
+}
+
+impl Default for InputLatency {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tracker: LatencyTracker::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl InputLatency {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &CanvasLayer, _delta: f32) {
+        let readout = unsafe { owner.get_typed_node::<Label, _>("Readout") };
+
+        readout.set_visible(self.enabled);
+
+        if !self.enabled {
+            return;
+        }
+
+        let text = match (self.tracker.last, self.tracker.average()) {
+            (Some(last), Some(average)) => {
+                format!("input latency: {} frames (avg {:.1})", last, average)
+            }
+            _ => "input latency: -".to_string(),
+        };
+
+        readout.set_text(text);
+    }
+
+    pub fn record_input(&mut self, action: &str, frame: i64) {
+        if !self.enabled {
+            return;
+        }
+
+        godot_print!("[{}] {} pressed", frame, action);
+
+        self.tracker.record_input(action, frame);
+    }
+
+    pub fn record_transition(&mut self, action: &str, state: &str, frame: i64) {
+        if !self.enabled {
+            return;
+        }
+
+        if let Some(latency) = self.tracker.record_transition(action, frame) {
+            godot_print!("[{}] entered {} after {} frames", frame, state, latency);
+        }
+    }
+}
+
+#[derive(Default)]
+struct LatencyTracker {
+    // frame each action was pressed on, until the state it triggers starts
+    pending: HashMap<String, i64>,
+    last: Option<i64>,
+    samples: Vec<i64>,
+}
+
+impl LatencyTracker {
+    fn record_input(&mut self, action: &str, frame: i64) {
+        // holding the earliest press measures the full wait
+        self.pending.entry(action.to_string()).or_insert(frame);
+    }
+
+    /// Returns the frames since the action was pressed, or `None` when the
+    /// transition wasn't triggered by a press.
+    fn record_transition(&mut self, action: &str, frame: i64) -> Option<i64> {
+        let pressed = self.pending.remove(action)?;
+        let latency = frame - pressed;
+
+        self.last = Some(latency);
+        self.samples.push(latency);
+
+        if self.samples.len() > SAMPLE_COUNT {
+            self.samples.remove(0);
+        }
+
+        Some(latency)
+    }
+
+    fn average(&self) -> Option<f32> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        Some(self.samples.iter().sum::<i64>() as f32 / self.samples.len() as f32)
+    }
+}
+
+fn with_latency(owner: &Node, f: impl FnOnce(&mut InputLatency, i64)) {
+    let latency_node = unsafe { owner.get_typed_node::<CanvasLayer, _>("/root/InputLatency") };
+    let frame = Engine::godot_singleton().get_idle_frames();
+
+    if let Some(latency) = latency_node.cast_instance::<InputLatency>() {
+        let _ = latency.map_mut(|latency, _| f(latency, frame));
+    }
+}
+
+/// Logs an action press through the `InputLatency` autoload.
+pub fn record_input(owner: &Node, action: &str) {
+    with_latency(owner, |latency, frame| latency.record_input(action, frame));
+}
+
+/// Logs the player entering the state an action triggers through the
+/// `InputLatency` autoload.
+pub fn record_transition(owner: &Node, action: &str, state: &str) {
+    with_latency(owner, |latency, frame| {
+        latency.record_transition(action, state, frame)
+    });
+}
+
+#[test]
+fn test_latency_is_frames_between_press_and_transition() {
+    let mut tracker = LatencyTracker::default();
+
+    tracker.record_input("attack", 100);
+
+    assert_eq!(tracker.record_transition("attack", 103), Some(3));
+    assert_eq!(tracker.last, Some(3));
+}
+
+#[test]
+fn test_transition_without_press_is_not_measured() {
+    let mut tracker = LatencyTracker::default();
+
+    tracker.record_input("roll", 10);
+
+    assert_eq!(tracker.record_transition("attack", 12), None);
+    assert_eq!(tracker.last, None);
+}
+
+#[test]
+fn test_latency_average_over_samples() {
+    let mut tracker = LatencyTracker::default();
+
+    tracker.record_input("attack", 0);
+    tracker.record_transition("attack", 1);
+    tracker.record_input("attack", 10);
+    tracker.record_input("attack", 11);
+    tracker.record_transition("attack", 14);
+
+    assert_eq!(tracker.average(), Some(2.5));
+}
@@ -0,0 +1,121 @@
+use gdnative::prelude::{Node, Node2D, Vector2};
+
+/// Returns how high a jump is the fraction of the way through it, rising
+/// to the apex halfway and back to the ground along a parabola.
+pub fn arc_height(progress: f32, apex: f32) -> f32 {
+    let progress = progress.max(0.0).min(1.0);
+
+    4.0 * apex * progress * (1.0 - progress)
+}
+
+/// A fake vertical hop for a top-down body. Only its sprite is lifted by
+/// the height, so its collision and hurtbox stay where it stands and its
+/// shadow stays on the ground.
+pub struct Jump {
+    pub duration: f32,
+    pub apex: f32,
+    // seconds into the jump, `None` while on the ground
+    time: Option<f32>,
+    // the height the sprite was last lifted by
+    lifted: f32,
+}
+
+impl Jump {
+    pub fn new(duration: f32, apex: f32) -> Self {
+        Self {
+            duration,
+            apex,
+            time: None,
+            lifted: 0.0,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.time = Some(0.0);
+    }
+
+    pub fn is_airborne(&self) -> bool {
+        self.time.is_some()
+    }
+
+    /// Moves the jump along, returning whether it landed this frame.
+    pub fn advance(&mut self, delta: f32) -> bool {
+        let time = match self.time.as_mut() {
+            Some(time) => time,
+            None => return false,
+        };
+
+        *time += delta;
+
+        if *time < self.duration {
+            return false;
+        }
+
+        self.time = None;
+
+        true
+    }
+
+    pub fn height(&self) -> f32 {
+        match self.time {
+            Some(time) if self.duration > 0.0 => arc_height(time / self.duration, self.apex),
+            _ => 0.0,
+        }
+    }
+
+    /// Lifts the body's sprite by the height, from wherever it's placed in
+    /// the body, and tells its shadow so it shrinks under it.
+    pub fn lift(&mut self, body: &Node) {
+        let height = self.height();
+
+        for sprite_path in &["Sprite", "AnimatedSprite"] {
+            if let Some(sprite) = body
+                .get_node(*sprite_path)
+                .map(|sprite| unsafe { sprite.assume_safe() })
+                .and_then(|sprite| sprite.cast::<Node2D>())
+            {
+                sprite.set_position(sprite.position() + Vector2::new(0.0, self.lifted - height));
+            }
+        }
+
+        if let Some(shadow) = body.get_node("Shadow") {
+            unsafe { shadow.assume_safe() }.set("jump_height", height);
+        }
+
+        self.lifted = height;
+    }
+}
+
+#[test]
+fn test_jump_starts_on_the_ground() {
+    assert_eq!(arc_height(0.0, 16.0), 0.0);
+}
+
+#[test]
+fn test_jump_peaks_halfway() {
+    assert_eq!(arc_height(0.5, 16.0), 16.0);
+    assert_eq!(arc_height(0.25, 16.0), 12.0);
+    assert_eq!(arc_height(0.75, 16.0), 12.0);
+}
+
+#[test]
+fn test_jump_lands_at_the_end() {
+    assert_eq!(arc_height(1.0, 16.0), 0.0);
+    assert_eq!(arc_height(1.5, 16.0), 0.0);
+}
+
+#[test]
+fn test_jump_advances_to_landing() {
+    let mut jump = Jump::new(0.5, 16.0);
+
+    assert!(!jump.advance(0.25));
+    assert_eq!(jump.height(), 0.0);
+
+    jump.start();
+
+    assert!(!jump.advance(0.25));
+    assert_eq!(jump.height(), 16.0);
+    assert!(jump.advance(0.25));
+    assert!(!jump.is_airborne());
+    assert_eq!(jump.height(), 0.0);
+}
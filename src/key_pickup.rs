@@ -0,0 +1,67 @@
+use crate::extensions::NodeExt;
+use crate::flags::Flags;
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node, Ref};
+
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct KeyPickup {
+    flag: String,
+}
+
+fn register_properties(builder: &ClassBuilder<KeyPickup>) {
+    builder
+        .add_property::<String>("flag")
+        .with_default("has_key".to_string())
+        .with_ref_getter(|key_pickup: &KeyPickup, _| &key_pickup.flag)
+        .with_setter(|key_pickup: &mut KeyPickup, _, flag| key_pickup.flag = flag)
+        .done();
+}
+
+impl Default for KeyPickup {
+    fn default() -> Self {
+        Self {
+            flag: "has_key".to_string(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl KeyPickup {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_KeyPickup_body_entered(&self, owner: &Area2D, body: Ref<Node>) {
+        let is_player =
+            unsafe { body.assume_safe_if_sane() }.map_or(false, |body| body.is_in_group("player"));
+
+        if !is_player {
+            return;
+        }
+
+        let flags_node = unsafe { owner.get_typed_node::<Node, _>("/root/Flags") };
+        let flags_instance = flags_node.cast_instance::<Flags>().unwrap();
+
+        let _ = flags_instance.map_mut(|flags, _| self.pick_up(flags));
+
+        owner.queue_free();
+    }
+
+    pub fn pick_up(&self, flags: &mut Flags) {
+        flags.set(&self.flag);
+    }
+}
+
+#[test]
+fn test_pick_up_sets_flag() {
+    let key_pickup = KeyPickup::default();
+    let mut flags = Flags::default();
+
+    key_pickup.pick_up(&mut flags);
+
+    assert!(flags.has("has_key"));
+}
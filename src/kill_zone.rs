@@ -0,0 +1,143 @@
+use crate::bat::Bat;
+use crate::extensions::NodeExt;
+use crate::player::Player;
+use gdnative::api::{Area2D, CollisionShape2D, RectangleShape2D};
+use gdnative::prelude::{
+    KinematicBody2D, NativeClass, Node, Rect2, Ref, TRef, VariantArray, Vector2,
+};
+
+#[derive(Debug, PartialEq)]
+enum Victim {
+    Player,
+    Enemy,
+    Other,
+}
+
+impl Victim {
+    fn of(body: TRef<Node>) -> Self {
+        Self::from_groups(body.is_in_group("player"), body.is_in_group("enemies"))
+    }
+
+    fn from_groups(is_player: bool, is_enemy: bool) -> Self {
+        if is_player {
+            Self::Player
+        } else if is_enemy {
+            Self::Enemy
+        } else {
+            Self::Other
+        }
+    }
+}
+
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+pub struct KillZone;
+
+#[gdnative::methods]
+impl KillZone {
+    fn new(_owner: &Area2D) -> Self {
+        Self
+    }
+
+    #[export]
+    fn _ready(&self, owner: TRef<Area2D>) {
+        owner.add_to_group("kill_zones", false);
+
+        owner
+            .connect(
+                "body_entered",
+                owner,
+                "_on_body_entered",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+    }
+
+    pub fn bounds(&self, owner: &Area2D) -> Rect2 {
+        let shape = unsafe { owner.get_typed_node::<CollisionShape2D, _>("CollisionShape2D") };
+        let extents = shape
+            .shape()
+            .and_then(|shape| shape.cast::<RectangleShape2D>())
+            .map(|shape| unsafe { shape.assume_safe() }.extents())
+            .unwrap_or(Vector2::zero());
+
+        Rect2::new(
+            (shape.global_position() - extents).to_point(),
+            (extents * 2.0).to_size(),
+        )
+    }
+
+    #[export]
+    fn _on_body_entered(&self, _owner: &Area2D, body: Ref<Node>) {
+        let body = match unsafe { body.assume_safe_if_sane() } {
+            Some(body) => body,
+            None => return,
+        };
+
+        let victim = Victim::of(body);
+
+        let body = match body.cast::<KinematicBody2D>() {
+            Some(body) => body,
+            None => return,
+        };
+
+        match victim {
+            Victim::Player => {
+                if let Some(player) = body.cast_instance::<Player>() {
+                    let _ = player.map_mut(|player, owner| player.respawn(&owner));
+                }
+            }
+            Victim::Enemy => {
+                if let Some(bat) = body.cast_instance::<Bat>() {
+                    let _ = bat.map_mut(|bat, owner| {
+                        let damage = lethal_damage(bat.health(&owner));
+                        bat.take_damage(&owner, damage);
+                    });
+                }
+            }
+            Victim::Other => {}
+        }
+    }
+}
+
+fn lethal_damage(health: i32) -> i32 {
+    health.max(1)
+}
+
+#[test]
+fn test_lethal_damage_kills_on_entry() {
+    let mut stats = crate::stats::Stats {
+        max_health: 4,
+        health: 3,
+        execute_threshold: 0,
+        ..crate::stats::Stats::default()
+    };
+
+    let damage = lethal_damage(stats.health);
+
+    assert!(matches!(
+        stats.receive_damage(damage),
+        crate::stats::State::Dead
+    ));
+}
+
+#[test]
+fn test_lethal_damage_is_never_zero() {
+    assert_eq!(lethal_damage(0), 1);
+}
+
+#[test]
+fn test_victim_player_respawns() {
+    assert_eq!(Victim::from_groups(true, false), Victim::Player);
+}
+
+#[test]
+fn test_victim_enemy_is_killed() {
+    assert_eq!(Victim::from_groups(false, true), Victim::Enemy);
+}
+
+#[test]
+fn test_victim_other_bodies_are_ignored() {
+    assert_eq!(Victim::from_groups(false, false), Victim::Other);
+}
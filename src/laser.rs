@@ -0,0 +1,245 @@
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use crate::player::Player;
+use gdnative::api::{Area2D, Line2D};
+use gdnative::prelude::{ClassBuilder, Color, NativeClass, Node2D, Vector2, Vector2Godot};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    // waiting for a player to come in range once the timer ends
+    Cooldown(f32),
+    // the beam is telegraphed along the locked in direction
+    Charging(f32),
+    Firing(f32),
+}
+
+/// A stationary enemy that charges a telegraphed beam towards the closest
+/// player, then fires it, hurting everything on the line.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct Laser {
+    damage: i32,
+    range: f32,
+    width: f32,
+    charge_time: f32,
+    fire_time: f32,
+    cooldown: f32,
+    phase: Phase,
+    direction: Vector2,
+}
+
+fn register_properties(builder: &ClassBuilder<Laser>) {
+    builder
+        .add_property::<i32>("damage")
+        .with_default(2)
+        .with_getter(|laser: &Laser, _| laser.damage)
+        .with_setter(|laser: &mut Laser, _, damage| laser.damage = damage)
+        .done();
+    builder
+        .add_property::<f32>("range")
+        .with_default(160.0)
+        .with_getter(|laser: &Laser, _| laser.range)
+        .with_setter(|laser: &mut Laser, _, range| laser.range = range)
+        .done();
+    builder
+        .add_property::<f32>("width")
+        .with_default(8.0)
+        .with_getter(|laser: &Laser, _| laser.width)
+        .with_setter(|laser: &mut Laser, _, width| laser.width = width)
+        .done();
+    builder
+        .add_property::<f32>("charge_time")
+        .with_default(1.0)
+        .with_getter(|laser: &Laser, _| laser.charge_time)
+        .with_setter(|laser: &mut Laser, _, time| laser.charge_time = time)
+        .done();
+    builder
+        .add_property::<f32>("fire_time")
+        .with_default(0.2)
+        .with_getter(|laser: &Laser, _| laser.fire_time)
+        .with_setter(|laser: &mut Laser, _, time| laser.fire_time = time)
+        .done();
+    builder
+        .add_property::<f32>("cooldown")
+        .with_default(2.0)
+        .with_getter(|laser: &Laser, _| laser.cooldown)
+        .with_setter(|laser: &mut Laser, _, cooldown| laser.cooldown = cooldown)
+        .done();
+}
+
+impl Default for Laser {
+    fn default() -> Self {
+        Self {
+            damage: 2,
+            range: 160.0,
+            width: 8.0,
+            charge_time: 1.0,
+            fire_time: 0.2,
+            cooldown: 2.0,
+            phase: Phase::Cooldown(0.0),
+            direction: Vector2::new(1.0, 0.0),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Laser {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Node2D, delta: f32) {
+        let players = entity_registry::all_players(owner);
+
+        let position = owner.global_position();
+        let target = players
+            .iter()
+            .map(|player| player.global_position() - position)
+            .filter(|offset| offset.length() <= self.range)
+            .min_by(|a, b| a.length().partial_cmp(&b.length()).unwrap());
+
+        if self.update(delta, target) {
+            let end = position + self.direction * self.range;
+
+            // the beam hits hurtboxes, which are direct children of the
+            // player they protect
+            for player in players.iter() {
+                let hurtbox = unsafe { player.get_typed_node::<Area2D, _>("Hurtbox") };
+
+                if !crossed_by_ray(position, end, self.width, hurtbox.global_position()) {
+                    continue;
+                }
+
+                if let Some(player) = player.cast_instance::<Player>() {
+                    let damage = self.damage;
+                    let _ = player.map_mut(|player, player_owner| {
+                        player.receive_hazard_damage(&player_owner, damage)
+                    });
+                }
+            }
+        }
+
+        self.update_beam(owner);
+    }
+
+    /// Advances the phases, aiming at the target offset when a charge
+    /// starts. Returns whether the beam fired this frame.
+    fn update(&mut self, delta: f32, target: Option<Vector2>) -> bool {
+        match &mut self.phase {
+            Phase::Cooldown(time_left) => {
+                *time_left -= delta;
+
+                if *time_left <= 0.0 {
+                    if let Some(direction) = target.and_then(|target| target.try_normalize()) {
+                        self.direction = direction;
+                        self.phase = Phase::Charging(self.charge_time);
+                    }
+                }
+
+                false
+            }
+            Phase::Charging(time_left) => {
+                *time_left -= delta;
+
+                if *time_left <= 0.0 {
+                    self.phase = Phase::Firing(self.fire_time);
+                    return true;
+                }
+
+                false
+            }
+            Phase::Firing(time_left) => {
+                *time_left -= delta;
+
+                if *time_left <= 0.0 {
+                    self.phase = Phase::Cooldown(self.cooldown);
+                }
+
+                false
+            }
+        }
+    }
+
+    fn update_beam(&self, owner: &Node2D) {
+        let beam = unsafe { owner.get_typed_node::<Line2D, _>("Beam") };
+
+        beam.set_point_position(1, self.direction * self.range);
+
+        match self.phase {
+            Phase::Cooldown(_) => beam.set_visible(false),
+            Phase::Charging(time_left) => {
+                // the telegraph grows more opaque as the shot gets closer
+                let progress = 1.0 - time_left / self.charge_time;
+
+                beam.set_visible(true);
+                beam.set_width(1.0);
+                beam.set_default_color(Color::rgba(1.0, 0.3, 0.3, progress));
+            }
+            Phase::Firing(_) => {
+                beam.set_visible(true);
+                beam.set_width(self.width);
+                beam.set_default_color(Color::rgb(1.0, 0.9, 0.9));
+            }
+        }
+    }
+}
+
+/// Returns whether the point is within half the width of the ray from start
+/// to end.
+pub fn crossed_by_ray(start: Vector2, end: Vector2, width: f32, point: Vector2) -> bool {
+    let ray = end - start;
+    let length_squared = ray.square_length();
+
+    let t = if length_squared > 0.0 {
+        ((point - start).dot(ray) / length_squared)
+            .max(0.0)
+            .min(1.0)
+    } else {
+        0.0
+    };
+
+    point.distance_to(start + ray * t) <= width / 2.0
+}
+
+#[test]
+fn test_ray_hits_points_on_the_line() {
+    let start = Vector2::zero();
+    let end = Vector2::new(100.0, 0.0);
+
+    assert!(crossed_by_ray(start, end, 8.0, Vector2::new(50.0, 0.0)));
+    assert!(crossed_by_ray(start, end, 8.0, Vector2::new(80.0, -4.0)));
+}
+
+#[test]
+fn test_ray_misses_points_off_the_line() {
+    let start = Vector2::zero();
+    let end = Vector2::new(100.0, 0.0);
+
+    assert!(!crossed_by_ray(start, end, 8.0, Vector2::new(50.0, 6.0)));
+    // behind the laser and past the end of the beam
+    assert!(!crossed_by_ray(start, end, 8.0, Vector2::new(-10.0, 0.0)));
+    assert!(!crossed_by_ray(start, end, 8.0, Vector2::new(110.0, 0.0)));
+}
+
+#[test]
+fn test_fires_after_charging() {
+    let mut laser = Laser::default();
+
+    assert!(!laser.update(0.1, Some(Vector2::new(0.0, 40.0))));
+    assert_eq!(laser.direction, Vector2::new(0.0, 1.0));
+
+    assert!(!laser.update(0.5, None));
+    assert!(laser.update(0.5, None));
+    assert!(!laser.update(0.2, None));
+    assert_eq!(laser.phase, Phase::Cooldown(2.0));
+}
+
+#[test]
+fn test_waits_for_a_target() {
+    let mut laser = Laser::default();
+
+    assert!(!laser.update(0.1, None));
+    assert!(matches!(laser.phase, Phase::Cooldown(_)));
+}
@@ -1,19 +1,148 @@
 use gdnative::prelude::{godot_init, InitHandle, PackedScene, Ref, ResourceLoader, ThreadLocal};
 
+mod acceleration_mode;
+mod aggro;
+mod attack_tokens;
 mod bat;
+mod boss_bar;
+mod breadcrumb;
+mod bug_report;
+mod coin_pickup;
+mod combat;
+mod combat_state;
+mod combo_trial;
+mod control_scheme;
+mod cooldown;
+mod damage_bus;
+mod damage_meter;
+mod damage_number;
+mod debug_draw;
+mod door;
+mod duelist;
 mod effect;
+mod enemy_profiler;
+mod entity_registry;
+mod explosion;
 mod extensions;
+mod feign_death;
+mod flags;
+mod frame_step;
+mod ghost;
 mod grass;
+mod hazard_area;
+mod hover;
+mod hud_scale;
+mod input_latency;
+mod jump;
+mod key_pickup;
+mod kill_zone;
+mod laser;
+mod loot;
+mod magnet_pickup;
+mod menu_input;
+mod mimic;
+mod movement_mode;
+mod music_manager;
+mod occluder;
+mod parry;
+mod phasing;
 mod player;
+mod player_camera;
+mod player_stats;
+mod practice;
+mod projectile;
+mod revenant;
+mod rng;
+mod runner;
+mod screen_flash;
+mod screen_transition;
+mod settings;
+mod shadow;
+mod shell;
+mod shockwave;
+mod shop;
+#[cfg(test)]
+mod sim;
+mod slammer;
+mod slow_mo;
+mod stat_inspector;
 mod stats;
+mod stress_test;
+mod summoner;
 mod sword_hitbox;
+mod thief;
+mod think_scheduler;
+mod time_attack;
+mod timer;
+mod tracker;
+mod trail;
+mod transition_style;
+mod tween;
+mod upgrades;
+mod wall_builder;
+mod wander_controller;
+mod wave_banner;
+mod weapon;
+mod weapon_pickup;
+mod weapon_wheel;
+mod ysort;
 
+use attack_tokens::AttackTokens;
 use bat::Bat;
+use boss_bar::BossBar;
+use breadcrumb::Breadcrumb;
+use bug_report::{BugRecorder, BugReplay};
+use coin_pickup::CoinPickup;
+use combat_state::CombatState;
+use combo_trial::ComboTrial;
+use damage_bus::DamageBus;
+use damage_meter::DamageMeter;
+use damage_number::DamageNumber;
+use door::Door;
 use effect::Effect;
+use enemy_profiler::EnemyProfiler;
+use entity_registry::EntityRegistry;
+use flags::Flags;
+use frame_step::FrameStep;
+use ghost::{Ghost, GhostRecorder};
 use grass::Grass;
+use hazard_area::HazardArea;
+use hud_scale::HudScale;
+use input_latency::InputLatency;
+use key_pickup::KeyPickup;
+use kill_zone::KillZone;
+use laser::Laser;
+use loot::Chest;
+use magnet_pickup::MagnetPickup;
+use mimic::Mimic;
+use music_manager::MusicManager;
+use occluder::Occluder;
 use player::Player;
+use player_camera::PlayerCamera;
+use player_stats::PlayerStats;
+use practice::Practice;
+use projectile::Projectile;
+use screen_flash::ScreenFlash;
+use screen_transition::ScreenTransition;
+use settings::Settings;
+use shadow::Shadow;
+use shockwave::Shockwave;
+use shop::Shop;
+use slammer::Slammer;
+use slow_mo::SlowMo;
+use stat_inspector::StatInspector;
 use stats::Stats;
+use stress_test::StressTest;
+use summoner::Summoner;
 use sword_hitbox::SwordHitbox;
+use time_attack::{TimeAttack, TimeAttackGoal};
+use upgrades::Upgrades;
+use wall_builder::{TemporaryWall, WallBuilder};
+use wander_controller::WanderController;
+use wave_banner::WaveBanner;
+use weapon_pickup::WeaponPickup;
+use weapon_wheel::WeaponWheel;
+use ysort::YSortZ;
 
 fn init(handle: InitHandle) {
     handle.add_class::<Player>();
@@ -22,6 +151,60 @@ fn init(handle: InitHandle) {
     handle.add_class::<SwordHitbox>();
     handle.add_class::<Grass>();
     handle.add_class::<Effect>();
+    handle.add_class::<DamageNumber>();
+    handle.add_class::<PlayerStats>();
+    handle.add_class::<PlayerCamera>();
+    handle.add_class::<Settings>();
+    handle.add_class::<KillZone>();
+    handle.add_class::<Projectile>();
+    handle.add_class::<WanderController>();
+    handle.add_class::<Flags>();
+    handle.add_class::<Door>();
+    handle.add_class::<KeyPickup>();
+    handle.add_class::<Mimic>();
+    handle.add_class::<HazardArea>();
+    handle.add_class::<WaveBanner>();
+    handle.add_class::<ScreenFlash>();
+    handle.add_class::<Summoner>();
+    handle.add_class::<Laser>();
+    handle.add_class::<SlowMo>();
+    handle.add_class::<WeaponPickup>();
+    handle.add_class::<CoinPickup>();
+    handle.add_class::<Breadcrumb>();
+    handle.add_class::<MusicManager>();
+    handle.add_class::<WallBuilder>();
+    handle.add_class::<TemporaryWall>();
+    handle.add_class::<Occluder>();
+    handle.add_class::<Shockwave>();
+    handle.add_class::<Slammer>();
+    handle.add_class::<WeaponWheel>();
+    handle.add_class::<InputLatency>();
+    handle.add_class::<Shop>();
+    handle.add_class::<Upgrades>();
+    handle.add_class::<BossBar>();
+    handle.add_class::<DamageMeter>();
+    handle.add_class::<Practice>();
+    handle.add_class::<ComboTrial>();
+    handle.add_class::<Ghost>();
+    handle.add_class::<GhostRecorder>();
+    handle.add_class::<TimeAttack>();
+    handle.add_class::<TimeAttackGoal>();
+    handle.add_class::<MagnetPickup>();
+    handle.add_class::<ScreenTransition>();
+    handle.add_class::<BugRecorder>();
+    handle.add_class::<BugReplay>();
+    handle.add_class::<EnemyProfiler>();
+    handle.add_class::<StressTest>();
+    handle.add_class::<StatInspector>();
+    handle.add_class::<FrameStep>();
+    handle.add_class::<YSortZ>();
+    handle.add_class::<Shadow>();
+    handle.add_class::<CombatState>();
+    handle.add_class::<EntityRegistry>();
+    handle.add_class::<DamageBus>();
+    handle.add_class::<HudScale>();
+    handle.add_class::<AttackTokens>();
+    handle.add_class::<Chest>();
 }
 
 pub fn load_scene(path: &str) -> Option<Ref<PackedScene, ThreadLocal>> {
@@ -0,0 +1,179 @@
+use crate::extensions::NodeExt;
+use crate::load_scene;
+use crate::player_stats::PlayerStats;
+use crate::rng::Rng;
+use gdnative::api::Area2D;
+use gdnative::prelude::{
+    ClassBuilder, GodotString, NativeClass, Node, Ref, StringArray, TRef, Vector2,
+};
+
+/// Returns the seed of a chest's roll, from where it's placed and the run's
+/// seed, so the same chest drops the same thing however often the run is
+/// loaded, while chests next to each other still roll apart.
+fn chest_seed(position: Vector2, run_seed: i64) -> u64 {
+    // whole pixels, so a chest nudged by float error still rolls the same
+    let x = position.x.round() as i32 as u32 as u64;
+    let y = position.y.round() as i32 as u32 as u64;
+
+    mix(((x << 32) | y) ^ mix(run_seed as u64))
+}
+
+// splitmix64's finalizer, spreading every input bit over the whole seed
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Picks one of the drops, all equally likely, or `None` without any.
+fn roll(loot: &[String], seed: u64) -> Option<&String> {
+    if loot.is_empty() {
+        return None;
+    }
+
+    let index = (Rng::new(seed).next_f32() * loot.len() as f32) as usize;
+
+    loot.get(index.min(loot.len() - 1))
+}
+
+/// A chest placed in a room, dropping one of its loot scenes when a player
+/// opens it by walking into it. What it drops is rolled from its position
+/// and the run seed kept in the save, so reloading can't reroll it.
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct Chest {
+    // the scenes it may drop, like pickups
+    loot: Vec<String>,
+}
+
+fn register_properties(builder: &ClassBuilder<Chest>) {
+    builder
+        .add_property::<StringArray>("loot")
+        .with_default(default_loot_array())
+        .with_getter(|chest: &Chest, _| {
+            StringArray::from_vec(chest.loot.iter().map(GodotString::from).collect())
+        })
+        .with_setter(|chest: &mut Chest, _, loot: StringArray| {
+            chest.loot = loot.read().iter().map(|path| path.to_string()).collect()
+        })
+        .done();
+}
+
+fn default_loot() -> Vec<String> {
+    vec![
+        "res://scenes/CoinPickup.tscn".to_string(),
+        "res://scenes/MagnetPickup.tscn".to_string(),
+        "res://scenes/WeaponPickup.tscn".to_string(),
+    ]
+}
+
+fn default_loot_array() -> StringArray {
+    StringArray::from_vec(default_loot().iter().map(GodotString::from).collect())
+}
+
+impl Default for Chest {
+    fn default() -> Self {
+        Self {
+            loot: default_loot(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Chest {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_Chest_body_entered(&self, owner: &Area2D, body: Ref<Node>) {
+        let is_player =
+            unsafe { body.assume_safe_if_sane() }.map_or(false, |body| body.is_in_group("player"));
+
+        if !is_player {
+            return;
+        }
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let run_seed = player_stats_node
+            .cast_instance::<PlayerStats>()
+            .and_then(|player_stats| {
+                player_stats
+                    .map(|player_stats, _| player_stats.run_seed)
+                    .ok()
+            })
+            .unwrap_or(0);
+
+        let seed = chest_seed(owner.global_position(), run_seed);
+
+        if let Some(path) = roll(&self.loot, seed) {
+            self.drop_loot(owner, path);
+        }
+
+        owner.queue_free();
+    }
+
+    fn drop_loot(&self, owner: &Area2D, path: &str) {
+        let loot_scene = match load_scene(path) {
+            Some(loot_scene) => loot_scene,
+            None => return,
+        };
+
+        let loot_node = unsafe { loot_scene.instance(0).unwrap().assume_safe() };
+        let loot: TRef<Area2D> = loot_node.cast().unwrap();
+
+        loot.set_global_position(owner.global_position());
+
+        let chest_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        chest_parent.add_child(loot, false);
+    }
+}
+
+#[test]
+fn test_same_chest_and_seed_drop_the_same_across_runs() {
+    let loot = default_loot();
+    let position = Vector2::new(128.0, 64.0);
+
+    let first = roll(&loot, chest_seed(position, 42)).cloned();
+    let reloaded = roll(&loot, chest_seed(position, 42)).cloned();
+
+    assert!(first.is_some());
+    assert_eq!(first, reloaded);
+}
+
+#[test]
+fn test_chest_seed_ignores_float_error() {
+    assert_eq!(
+        chest_seed(Vector2::new(128.0, 64.0), 42),
+        chest_seed(Vector2::new(128.0001, 63.9999), 42)
+    );
+}
+
+#[test]
+fn test_chest_seed_depends_on_position_and_run() {
+    let seed = chest_seed(Vector2::new(128.0, 64.0), 42);
+
+    assert_ne!(seed, chest_seed(Vector2::new(144.0, 64.0), 42));
+    assert_ne!(seed, chest_seed(Vector2::new(128.0, 80.0), 42));
+    assert_ne!(seed, chest_seed(Vector2::new(128.0, 64.0), 43));
+}
+
+#[test]
+fn test_chests_spread_over_their_loot() {
+    let loot = default_loot();
+
+    let drops: std::collections::HashSet<&String> = (0..32)
+        .filter_map(|x| roll(&loot, chest_seed(Vector2::new(x as f32 * 16.0, 0.0), 7)))
+        .collect();
+
+    assert_eq!(drops.len(), loot.len());
+}
+
+#[test]
+fn test_chest_without_loot_drops_nothing() {
+    assert_eq!(roll(&[], chest_seed(Vector2::zero(), 42)), None);
+}
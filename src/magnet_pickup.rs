@@ -0,0 +1,68 @@
+use crate::extensions::NodeExt;
+use crate::player_stats::PlayerStats;
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node, Ref};
+
+/// Widens the coin magnet for a while, dropped by some enemies.
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct MagnetPickup {
+    bonus_radius: f32,
+    duration: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<MagnetPickup>) {
+    builder
+        .add_property::<f32>("bonus_radius")
+        .with_default(96.0)
+        .with_getter(|magnet_pickup: &MagnetPickup, _| magnet_pickup.bonus_radius)
+        .with_setter(|magnet_pickup: &mut MagnetPickup, _, bonus_radius| {
+            magnet_pickup.bonus_radius = bonus_radius
+        })
+        .done();
+    builder
+        .add_property::<f32>("duration")
+        .with_default(8.0)
+        .with_getter(|magnet_pickup: &MagnetPickup, _| magnet_pickup.duration)
+        .with_setter(|magnet_pickup: &mut MagnetPickup, _, duration| {
+            magnet_pickup.duration = duration
+        })
+        .done();
+}
+
+impl Default for MagnetPickup {
+    fn default() -> Self {
+        Self {
+            bonus_radius: 96.0,
+            duration: 8.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl MagnetPickup {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_MagnetPickup_body_entered(&self, owner: &Area2D, body: Ref<Node>) {
+        let is_player =
+            unsafe { body.assume_safe_if_sane() }.map_or(false, |body| body.is_in_group("player"));
+
+        if !is_player {
+            return;
+        }
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let _ = player_stats_instance.map_mut(|player_stats, _| {
+            player_stats.apply_magnet_buff(self.bonus_radius, self.duration)
+        });
+
+        owner.queue_free();
+    }
+}
@@ -0,0 +1,84 @@
+use gdnative::prelude::Vector2;
+
+/// Turns a held menu direction into discrete presses, firing once on press
+/// and then repeatedly after an initial delay.
+pub struct MenuInput {
+    pub initial_delay: f32,
+    pub repeat_rate: f32,
+    held: Vector2,
+    time_left: f32,
+}
+
+impl Default for MenuInput {
+    fn default() -> Self {
+        Self::new(0.4, 0.1)
+    }
+}
+
+impl MenuInput {
+    pub fn new(initial_delay: f32, repeat_rate: f32) -> Self {
+        Self {
+            initial_delay,
+            repeat_rate,
+            held: Vector2::zero(),
+            time_left: 0.0,
+        }
+    }
+
+    /// Returns whether the direction counts as a press this frame. A zero
+    /// direction releases the held one.
+    pub fn poll(&mut self, direction: Vector2, delta: f32) -> bool {
+        if direction == Vector2::zero() {
+            self.held = Vector2::zero();
+            return false;
+        }
+
+        // pressing a new direction starts over, even without a release
+        if direction != self.held {
+            self.held = direction;
+            self.time_left = self.initial_delay;
+            return true;
+        }
+
+        self.time_left -= delta;
+
+        if self.time_left <= 0.0 {
+            self.time_left += self.repeat_rate;
+            return true;
+        }
+
+        false
+    }
+}
+
+#[test]
+fn test_fires_on_press() {
+    let mut menu_input = MenuInput::default();
+
+    assert!(menu_input.poll(Vector2::new(0.0, 1.0), 0.1));
+    assert!(!menu_input.poll(Vector2::new(0.0, 1.0), 0.1));
+}
+
+#[test]
+fn test_repeats_after_initial_delay() {
+    let mut menu_input = MenuInput::new(0.5, 0.25);
+    let down = Vector2::new(0.0, 1.0);
+
+    let presses: Vec<bool> = (0..6).map(|_| menu_input.poll(down, 0.125)).collect();
+    let late_presses: Vec<bool> = (0..4).map(|_| menu_input.poll(down, 0.125)).collect();
+
+    // the press, then nothing until the delay runs out on the fifth frame
+    assert_eq!(presses, vec![true, false, false, false, true, false]);
+    assert_eq!(late_presses, vec![true, false, true, false]);
+}
+
+#[test]
+fn test_release_resets_repeat() {
+    let mut menu_input = MenuInput::new(0.5, 0.25);
+    let down = Vector2::new(0.0, 1.0);
+
+    menu_input.poll(down, 0.125);
+    menu_input.poll(Vector2::zero(), 0.125);
+
+    assert!(menu_input.poll(down, 0.125));
+}
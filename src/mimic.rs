@@ -0,0 +1,127 @@
+use crate::entity_registry;
+use gdnative::prelude::{
+    ClassBuilder, KinematicBody2D, NativeClass, Node2D, Signal, Sprite, TRef, Vector2, Vector2Godot,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    // looks like a pickup and doesn't move or attack
+    Disguised,
+    Revealed,
+}
+
+/// Sits under an enemy and keeps it hidden behind a pickup disguise until a
+/// player comes close, then hands control back to the enemy.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct Mimic {
+    state: State,
+    reveal_range: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<Mimic>) {
+    builder
+        .add_property::<f32>("reveal_range")
+        .with_default(24.0)
+        .with_getter(|mimic: &Mimic, _| mimic.reveal_range)
+        .with_setter(|mimic: &mut Mimic, _, range| mimic.reveal_range = range)
+        .done();
+
+    builder.add_signal(Signal {
+        name: "revealed",
+        args: &[],
+    });
+}
+
+impl Default for Mimic {
+    fn default() -> Self {
+        Self {
+            state: State::Disguised,
+            reveal_range: 24.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Mimic {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: &Node2D) {
+        self.set_disguised(owner, true);
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Node2D, _delta: f32) {
+        if self.state == State::Revealed {
+            return;
+        }
+
+        let player_positions: Vec<Vector2> = entity_registry::all_players(owner)
+            .iter()
+            .map(|player| player.global_position())
+            .collect();
+
+        if self.update(owner.global_position(), &player_positions) {
+            self.set_disguised(owner, false);
+            owner.emit_signal("revealed", &[]);
+        }
+    }
+
+    /// Returns whether the mimic revealed itself this frame.
+    pub fn update(&mut self, position: Vector2, player_positions: &[Vector2]) -> bool {
+        if self.state == State::Revealed {
+            return false;
+        }
+
+        let player_close = player_positions
+            .iter()
+            .any(|player| position.distance_to(*player) <= self.reveal_range);
+
+        if player_close {
+            self.state = State::Revealed;
+        }
+
+        player_close
+    }
+
+    fn set_disguised(&self, owner: &Node2D, disguised: bool) {
+        let disguise = unsafe { owner.get_node("Disguise").unwrap().assume_safe() };
+        let disguise: TRef<Sprite> = disguise.cast().unwrap();
+        disguise.set_visible(disguised);
+
+        let enemy = unsafe { owner.get_parent().unwrap().assume_safe() };
+        let enemy: TRef<KinematicBody2D> = enemy.cast().unwrap();
+
+        enemy.set_physics_process(!disguised);
+
+        if let Some(sprite) = enemy.get_node("AnimatedSprite") {
+            let sprite = unsafe { sprite.assume_safe() };
+            let sprite: TRef<Node2D> = sprite.cast().unwrap();
+
+            sprite.set_visible(!disguised);
+        }
+    }
+}
+
+#[test]
+fn test_stays_disguised_while_players_are_far() {
+    let mut mimic = Mimic::default();
+
+    assert!(!mimic.update(Vector2::zero(), &[Vector2::new(40.0, 0.0)]));
+    assert_eq!(mimic.state, State::Disguised);
+}
+
+#[test]
+fn test_reveals_when_player_is_close() {
+    let mut mimic = Mimic::default();
+
+    assert!(mimic.update(Vector2::zero(), &[Vector2::new(20.0, 0.0)]));
+    assert_eq!(mimic.state, State::Revealed);
+
+    // the reveal only happens once
+    assert!(!mimic.update(Vector2::zero(), &[Vector2::new(20.0, 0.0)]));
+}
@@ -0,0 +1,45 @@
+use gdnative::prelude::Vector2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovementMode {
+    TopDown,
+    // sideways movement with gravity, for platforming sections
+    Platformer,
+}
+
+impl Default for MovementMode {
+    fn default() -> Self {
+        Self::TopDown
+    }
+}
+
+impl MovementMode {
+    pub const NAMES: [&'static str; 2] = ["TopDown", "Platformer"];
+
+    pub fn from_index(index: i64) -> Self {
+        match index {
+            1 => Self::Platformer,
+            _ => Self::TopDown,
+        }
+    }
+
+    pub fn index(self) -> i64 {
+        match self {
+            Self::TopDown => 0,
+            Self::Platformer => 1,
+        }
+    }
+
+    pub fn has_gravity(self) -> bool {
+        self == Self::Platformer
+    }
+
+    /// The `up_direction` for `move_and_slide`, top-down movement has no
+    /// floor so every collision counts as a wall.
+    pub fn up_direction(self) -> Vector2 {
+        match self {
+            Self::TopDown => Vector2::zero(),
+            Self::Platformer => Vector2::new(0.0, -1.0),
+        }
+    }
+}
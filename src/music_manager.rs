@@ -0,0 +1,134 @@
+use crate::bat::Bat;
+use crate::entity_registry;
+use gdnative::api::AudioStreamPlayer;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node};
+
+const SILENT_DB: f32 = -80.0;
+
+/// Layers the music by how many enemies are chasing a player. Every
+/// `AudioStreamPlayer` child is a layer, the first one always plays at full
+/// volume and each next one fades in as more enemies join the fight.
+/// Reached through the `MusicManager` autoload.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct MusicManager {
+    // chasing enemies it takes to bring in each layer after the first
+    enemies_per_layer: f32,
+    // volume change per second, as a fraction of the full volume
+    fade_speed: f32,
+    volumes: Vec<f32>,
+}
+
+fn register_properties(builder: &ClassBuilder<MusicManager>) {
+    builder
+        .add_property::<f32>("enemies_per_layer")
+        .with_default(2.0)
+        .with_getter(|music: &MusicManager, _| music.enemies_per_layer)
+        .with_setter(|music: &mut MusicManager, _, enemies| music.enemies_per_layer = enemies)
+        .done();
+    builder
+        .add_property::<f32>("fade_speed")
+        .with_default(0.5)
+        .with_getter(|music: &MusicManager, _| music.fade_speed)
+        .with_setter(|music: &mut MusicManager, _, speed| music.fade_speed = speed)
+        .done();
+}
+
+impl Default for MusicManager {
+    fn default() -> Self {
+        Self {
+            enemies_per_layer: 2.0,
+            fade_speed: 0.5,
+            volumes: Vec::new(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl MusicManager {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f32) {
+        let layers: Vec<_> = owner
+            .get_children()
+            .iter()
+            .filter_map(|child| child.try_to_object::<AudioStreamPlayer>())
+            .map(|layer| unsafe { layer.assume_safe() })
+            .collect();
+
+        let targets = layer_volumes(
+            self.aggro_count(owner),
+            layers.len(),
+            self.enemies_per_layer,
+        );
+
+        self.volumes.resize(layers.len(), 0.0);
+
+        for ((layer, volume), target) in layers.iter().zip(&mut self.volumes).zip(targets) {
+            *volume = fade_towards(*volume, target, self.fade_speed * delta);
+
+            layer.set_volume_db(linear_to_db(*volume) as f64);
+        }
+    }
+
+    fn aggro_count(&self, owner: &Node) -> usize {
+        entity_registry::all_enemies(owner)
+            .into_iter()
+            .filter_map(|enemy| enemy.cast_instance::<Bat>())
+            .filter(|bat| bat.map(|bat, _| bat.is_chasing()).unwrap_or(false))
+            .count()
+    }
+}
+
+/// Returns the linear volume of each layer for this many chasing enemies.
+fn layer_volumes(aggro_count: usize, layer_count: usize, enemies_per_layer: f32) -> Vec<f32> {
+    (0..layer_count)
+        .map(|index| {
+            if index == 0 || enemies_per_layer <= 0.0 {
+                return 1.0;
+            }
+
+            let needed = (index - 1) as f32 * enemies_per_layer;
+
+            ((aggro_count as f32 - needed) / enemies_per_layer).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+fn fade_towards(volume: f32, target: f32, step: f32) -> f32 {
+    if volume < target {
+        (volume + step).min(target)
+    } else {
+        (volume - step).max(target)
+    }
+}
+
+fn linear_to_db(volume: f32) -> f32 {
+    if volume <= 0.0 {
+        return SILENT_DB;
+    }
+
+    (20.0 * volume.log10()).max(SILENT_DB)
+}
+
+#[test]
+fn test_base_layer_plays_without_aggro() {
+    assert_eq!(layer_volumes(0, 3, 2.0), vec![1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn test_layers_fade_in_with_aggro() {
+    assert_eq!(layer_volumes(1, 3, 2.0), vec![1.0, 0.5, 0.0]);
+    assert_eq!(layer_volumes(3, 3, 2.0), vec![1.0, 1.0, 0.5]);
+    assert_eq!(layer_volumes(10, 3, 2.0), vec![1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn test_silent_layer_is_muted() {
+    assert_eq!(linear_to_db(0.0), SILENT_DB);
+    assert_eq!(linear_to_db(1.0), 0.0);
+}
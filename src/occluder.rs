@@ -0,0 +1,101 @@
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node2D};
+
+/// Sits under a foreground object, like a tree, and fades it out while a
+/// player walks behind it so they stay visible.
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct Occluder {
+    faded_alpha: f32,
+    // alpha change per second
+    fade_speed: f32,
+    alpha: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<Occluder>) {
+    builder
+        .add_property::<f32>("faded_alpha")
+        .with_default(0.4)
+        .with_getter(|occluder: &Occluder, _| occluder.faded_alpha)
+        .with_setter(|occluder: &mut Occluder, _, alpha| occluder.faded_alpha = alpha)
+        .done();
+    builder
+        .add_property::<f32>("fade_speed")
+        .with_default(4.0)
+        .with_getter(|occluder: &Occluder, _| occluder.fade_speed)
+        .with_setter(|occluder: &mut Occluder, _, speed| occluder.fade_speed = speed)
+        .done();
+}
+
+impl Default for Occluder {
+    fn default() -> Self {
+        Self {
+            faded_alpha: 0.4,
+            fade_speed: 4.0,
+            alpha: 1.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Occluder {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Area2D, delta: f32) {
+        let occluding = owner
+            .get_overlapping_bodies()
+            .iter()
+            .filter_map(|body| body.try_to_object::<Node2D>())
+            .any(|body| unsafe { body.assume_safe() }.is_in_group("player"));
+
+        let alpha = self.update(occluding, delta);
+
+        let object = owner
+            .get_parent()
+            .map(|parent| unsafe { parent.assume_safe() })
+            .and_then(|parent| parent.cast::<Node2D>());
+
+        if let Some(object) = object {
+            let mut modulate = object.modulate();
+            modulate.a = alpha;
+
+            object.set_modulate(modulate);
+        }
+    }
+
+    /// Moves the alpha towards faded while a player is behind the object and
+    /// back to opaque otherwise, returning the new alpha.
+    pub fn update(&mut self, occluding: bool, delta: f32) -> f32 {
+        let target = if occluding { self.faded_alpha } else { 1.0 };
+        let step = self.fade_speed * delta;
+
+        self.alpha = if self.alpha > target {
+            (self.alpha - step).max(target)
+        } else {
+            (self.alpha + step).min(target)
+        };
+
+        self.alpha
+    }
+}
+
+#[test]
+fn test_fades_out_while_occluding() {
+    let mut occluder = Occluder::default();
+
+    assert!((occluder.update(true, 0.1) - 0.6).abs() < 1e-5);
+    assert!((occluder.update(true, 1.0) - 0.4).abs() < 1e-5);
+}
+
+#[test]
+fn test_fades_back_in_once_clear() {
+    let mut occluder = Occluder::default();
+    occluder.update(true, 1.0);
+
+    assert!((occluder.update(false, 0.1) - 0.8).abs() < 1e-5);
+    assert_eq!(occluder.update(false, 1.0), 1.0);
+}
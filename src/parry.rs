@@ -0,0 +1,113 @@
+use crate::cooldown::Cooldown;
+
+/// An elite's chance to parry a sword swing, staggering the player and
+/// opening a counter.
+pub struct Parry {
+    pub chance: f32,
+    pub cooldown: Cooldown,
+}
+
+impl Default for Parry {
+    fn default() -> Self {
+        Self {
+            chance: 0.0,
+            cooldown: Cooldown::new(2.0),
+        }
+    }
+}
+
+impl Parry {
+    /// Returns whether the swing is parried, given a roll in `[0, 1)`.
+    pub fn try_parry(&mut self, roll: f32) -> bool {
+        if !self.cooldown.is_ready() || roll >= self.chance {
+            return false;
+        }
+
+        self.cooldown.start();
+
+        true
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        self.cooldown.tick(delta);
+    }
+}
+
+/// The stagger of an enemy whose attack the player parried, where the next
+/// sword hit deals bonus damage.
+pub struct ParryStagger {
+    pub duration: f32,
+    pub damage_multiplier: f32,
+    vulnerable: bool,
+}
+
+impl Default for ParryStagger {
+    fn default() -> Self {
+        Self {
+            duration: 1.0,
+            damage_multiplier: 2.0,
+            vulnerable: false,
+        }
+    }
+}
+
+impl ParryStagger {
+    /// Opens the enemy to the bonus hit, returning how long it's staggered.
+    pub fn start(&mut self) -> f32 {
+        self.vulnerable = true;
+
+        self.duration
+    }
+
+    pub fn end(&mut self) {
+        self.vulnerable = false;
+    }
+
+    /// Returns the damage of a hit, spending the bonus if it applies.
+    pub fn apply(&mut self, damage: i32) -> i32 {
+        if !self.vulnerable {
+            return damage;
+        }
+
+        self.vulnerable = false;
+
+        (damage as f32 * self.damage_multiplier).round() as i32
+    }
+}
+
+#[test]
+fn test_parry_roll_against_chance() {
+    let mut parry = Parry {
+        chance: 0.25,
+        ..Parry::default()
+    };
+
+    assert!(!parry.try_parry(0.5));
+    assert!(parry.try_parry(0.1));
+    // on cooldown right after a parry
+    assert!(!parry.try_parry(0.1));
+
+    parry.tick(parry.cooldown.duration);
+
+    assert!(parry.try_parry(0.1));
+}
+
+#[test]
+fn test_parry_stagger_bonus_damage_once() {
+    let mut stagger = ParryStagger::default();
+
+    stagger.start();
+
+    assert_eq!(stagger.apply(2), 4);
+    assert_eq!(stagger.apply(2), 2);
+}
+
+#[test]
+fn test_parry_stagger_bonus_ends_with_it() {
+    let mut stagger = ParryStagger::default();
+
+    stagger.start();
+    stagger.end();
+
+    assert_eq!(stagger.apply(2), 2);
+}
@@ -0,0 +1,36 @@
+use crate::projectile::WORLD_LAYER;
+
+/// Lets a ghost fly through walls while it chases. Knockback still carries
+/// it into kill zones, since those detect bodies by their own mask.
+#[derive(Default)]
+pub struct Phasing {
+    pub enabled: bool,
+}
+
+impl Phasing {
+    /// Returns the body's collision mask, without the walls while it chases.
+    pub fn collision_mask(&self, mask: i64, chasing: bool) -> i64 {
+        if chasing {
+            mask & !WORLD_LAYER
+        } else {
+            mask | WORLD_LAYER
+        }
+    }
+}
+
+#[test]
+fn test_ghost_ignores_walls_while_chasing() {
+    let ghost = Phasing { enabled: true };
+    // the mask of Bat.tscn
+    let mask = 2147483649;
+
+    assert_eq!(ghost.collision_mask(mask, true), 2147483648);
+}
+
+#[test]
+fn test_ghost_collides_with_walls_after_chase() {
+    let ghost = Phasing { enabled: true };
+    let chasing_mask = ghost.collision_mask(2147483649, true);
+
+    assert_eq!(ghost.collision_mask(chasing_mask, false), 2147483649);
+}
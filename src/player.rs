@@ -1,26 +1,862 @@
+use crate::acceleration_mode::AccelerationMode;
+use crate::bat::Bat;
+use crate::combat::{Hit, HitMode};
+use crate::combo_trial;
+use crate::control_scheme::ControlScheme;
+use crate::cooldown::Cooldown;
+use crate::damage_bus;
+use crate::damage_number::DamageNumber;
+use crate::debug_draw;
+use crate::entity_registry::{self, Kind};
+use crate::explosion;
 use crate::extensions::{NodeExt, Vector2Ext};
+use crate::hazard_area::HazardArea;
+use crate::input_latency;
+use crate::load_scene;
+use crate::movement_mode::MovementMode;
+use crate::player_camera;
+use crate::player_stats::PlayerStats;
+use crate::projectile::{Projectile, Team};
+use crate::screen_flash;
+use crate::settings::{self, Settings};
 use crate::sword_hitbox::SwordHitbox;
-use gdnative::api::{AnimationNodeStateMachinePlayback, AnimationTree, Area2D};
-use gdnative::prelude::{Input, KinematicBody2D, NativeClass, TRef, Vector2, Vector2Godot};
+use crate::time_attack;
+use crate::timer::GameTimer;
+use crate::upgrades;
+use crate::weapon_wheel;
+use gdnative::api::{
+    AnimationNodeStateMachinePlayback, AnimationTree, Area2D, Camera2D, CollisionShape2D,
+    Position2D,
+};
+use gdnative::nativescript::init::property::{EnumHint, IntHint};
+use gdnative::prelude::{
+    godot_warn, ClassBuilder, Color, Input, KinematicBody2D, Label, NativeClass, Node, Node2D, Ref,
+    Signal, TRef, Vector2, Vector2Godot,
+};
+use std::collections::VecDeque;
 
 const ACCELERATION: f32 = 500.0;
 const MAX_SPEED: f32 = 80.0;
 const ROLL_SPEED: f32 = 120.0;
 const FRICTION: f32 = 500.0;
+const AUTO_ATTACK_RANGE: f32 = 24.0;
+const AUTO_ATTACK_COOLDOWN: f32 = 0.5;
+// cosine of the widest angle between the facing and an enemy that still
+// counts as facing it, roughly 45 degrees
+const AUTO_ATTACK_FACING: f32 = 0.7;
+const PARRY_WINDOW: f32 = 0.2;
+const IMPACT_DRAG_DURATION: f32 = 0.15;
+// how soon after the roll starts a hit has to arrive to count as a perfect
+// dodge
+const PERFECT_DODGE_WINDOW: f32 = 0.15;
+// how long the roll animations last
+const ROLL_DURATION: f32 = 0.5;
+// cosine of the widest angle between the aim and a grapple point that can
+// still be grappled, roughly 25 degrees
+const GRAPPLE_AIM_FACING: f32 = 0.9;
+const TAUNT_EMOTE_OFFSET: Vector2 = Vector2::new(-2.0, -32.0);
+const TAU: f32 = std::f32::consts::PI * 2.0;
+// pixels of debug arrow per unit of the sword's knockback direction
+const KNOCKBACK_ARROW_SCALE: f32 = 16.0;
+// even full knockback resistance lets a hit nudge the player
+const MAX_KNOCKBACK_REDUCTION: f32 = 0.9;
+const DEFAULT_INPUT_GUARD_FRAMES: i32 = 1;
+// faced before the player first moves, towards the camera like the idle
+// animation
+const FIRST_DIRECTION: Vector2 = Vector2::new(0.0, 1.0);
 
 #[derive(NativeClass)]
 #[inherit(KinematicBody2D)]
 #[derive(Default)]
+#[register_with(register_properties)]
 pub struct Player {
     velocity: Vector2,
+    // the speed the slide out of a roll or a knockback starts at, which is
+    // let past the max speed while it slows down to it
+    slide_speed: Option<f32>,
     state: State,
-    roll_vector: Vector2,
+    // where the player last moved, which every state faces when there's no
+    // aim input, so attacking or rolling from standing still keeps it,
+    // `None` until the player first moves
+    last_direction: Option<Vector2>,
+    // the twin-stick aim, zero while the stick is at rest
+    aim_input: Vector2,
+    // seconds the movement input takes to catch up with the keys or stick,
+    // zero moves with them right away
+    input_smoothing: f32,
+    smoothed_input: Vector2,
+    control_scheme: ControlScheme,
+    pub(crate) lifesteal: f32,
+    // the fraction of a health point lifesteal has built up, so small hits
+    // still heal once enough of them land
+    lifesteal_carry: f32,
+    pub(crate) thorns: f32,
+    // sword hits taken off the combo each time the player is hurt
+    combo_loss_on_hurt: i32,
+    // speed a contact hit knocks the player away from the attacker at, on
+    // top of how they were moving, zero leaves them be
+    hurt_knockback: f32,
+    // from zero to one, how much of the hurt knockback is shrugged off
+    knockback_resistance: f32,
+    taunt: f32,
+    taunt_emote: TauntEmote,
+    input_guard: InputGuard,
+    auto_attack: bool,
+    // whether a roll can cut an attack short, once its swing is over
+    attack_roll_cancel: bool,
+    // set by the attack animations once the sword's active frames end
+    attack_recovering: bool,
+    attack_cooldown: Cooldown,
+    checkpoint: Vector2,
+    // whether the last frame drew debug shapes, so they get cleared
+    debug_drawn: bool,
+    speed_multipliers: SpeedMultipliers,
+    parry_window: GameTimer,
+    acceleration_mode: AccelerationMode,
+    grapple: Grapple,
+    platforming: Platforming,
+    player_index: i64,
+    off_screen_invulnerable: bool,
+    impact_drag: ImpactDrag,
+    roll_elapsed: f32,
+    finisher: Finisher,
+    block: Block,
+    momentum: Momentum,
+    dash_attack: DashAttack,
+    stamina: Stamina,
+    // stamina each roll costs
+    roll_stamina_cost: f32,
+    // health a roll costs instead when out of stamina, with the setting on
+    emergency_dodge_cost: i32,
+    // how long an enemy parrying the player's attack leaves them reeling
+    parried_stagger_duration: f32,
+    // share of the max speed the player leaves an attack with when holding a
+    // direction, before accelerating as usual, zero starts from standing
+    // still
+    attack_exit_speed: f32,
+    spin: Spin,
+    adrenaline: Adrenaline,
+    follow_up: FollowUp,
+    // action that triggered the state the player was in last frame
+    latency_action: Option<&'static str>,
+    state_log: StateLog,
 }
 
+fn register_properties(builder: &ClassBuilder<Player>) {
+    // players after the first read their own prefixed input actions
+    builder
+        .add_property::<i64>("player_index")
+        .with_getter(|player: &Player, _| player.player_index)
+        .with_setter(|player: &mut Player, _, index| player.player_index = index)
+        .done();
+    // keeps enemies from killing a co-op player that the shared camera left
+    // behind
+    builder
+        .add_property::<bool>("off_screen_invulnerable")
+        .with_getter(|player: &Player, _| player.off_screen_invulnerable)
+        .with_setter(|player: &mut Player, _, invulnerable| {
+            player.off_screen_invulnerable = invulnerable
+        })
+        .done();
+    builder
+        .add_property::<f32>("lifesteal")
+        .with_getter(|player: &Player, _| player.lifesteal)
+        .with_setter(|player: &mut Player, _, lifesteal| player.lifesteal = lifesteal)
+        .done();
+    builder
+        .add_property::<f32>("thorns")
+        .with_getter(|player: &Player, _| player.thorns)
+        .with_setter(|player: &mut Player, _, thorns| player.thorns = thorns)
+        .done();
+    builder
+        .add_property::<i32>("combo_loss_on_hurt")
+        .with_getter(|player: &Player, _| player.combo_loss_on_hurt)
+        .with_setter(|player: &mut Player, _, loss| player.combo_loss_on_hurt = loss)
+        .done();
+    builder
+        .add_property::<f32>("hurt_knockback")
+        .with_default(0.0)
+        .with_getter(|player: &Player, _| player.hurt_knockback)
+        .with_setter(|player: &mut Player, _, knockback| player.hurt_knockback = knockback)
+        .done();
+    builder
+        .add_property::<f32>("knockback_resistance")
+        .with_getter(|player: &Player, _| player.knockback_resistance)
+        .with_setter(|player: &mut Player, _, resistance| player.knockback_resistance = resistance)
+        .done();
+    // how many of the latest state changes are kept for debugging, zero
+    // keeps none
+    builder
+        .add_property::<i32>("state_log_size")
+        .with_getter(|player: &Player, _| player.state_log.capacity as i32)
+        .with_setter(|player: &mut Player, _, size| {
+            player.state_log.capacity = size.max(0) as usize
+        })
+        .done();
+    builder
+        .add_property::<f32>("taunt")
+        .with_getter(|player: &Player, _| player.taunt_weight())
+        .with_setter(|player: &mut Player, _, taunt| player.taunt = taunt)
+        .done();
+    // extra taunt right after the taunt emote, fading out over its duration
+    builder
+        .add_property::<f32>("taunt_emote_boost")
+        .with_default(64.0)
+        .with_getter(|player: &Player, _| player.taunt_emote.boost)
+        .with_setter(|player: &mut Player, _, boost| player.taunt_emote.boost = boost)
+        .done();
+    builder
+        .add_property::<f32>("taunt_emote_duration")
+        .with_default(3.0)
+        .with_getter(|player: &Player, _| player.taunt_emote.fade.duration)
+        .with_setter(|player: &mut Player, _, duration| player.taunt_emote.fade.duration = duration)
+        .done();
+    // frames after entering a room on which presses are ignored
+    builder
+        .add_property::<i32>("input_guard_frames")
+        .with_default(DEFAULT_INPUT_GUARD_FRAMES)
+        .with_getter(|player: &Player, _| player.input_guard.frames)
+        .with_setter(|player: &mut Player, _, frames| player.input_guard.frames = frames)
+        .done();
+    builder
+        .add_property::<bool>("auto_attack")
+        .with_getter(|player: &Player, _| player.auto_attack)
+        .with_setter(|player: &mut Player, _, auto_attack| player.auto_attack = auto_attack)
+        .done();
+    builder
+        .add_property::<f32>("input_smoothing")
+        .with_getter(|player: &Player, _| player.input_smoothing)
+        .with_setter(|player: &mut Player, _, smoothing| player.input_smoothing = smoothing)
+        .done();
+    builder
+        .add_property::<bool>("attack_roll_cancel")
+        .with_getter(|player: &Player, _| player.attack_roll_cancel)
+        .with_setter(|player: &mut Player, _, cancel| player.attack_roll_cancel = cancel)
+        .done();
+    builder
+        .add_property::<f32>("move_speed_multiplier")
+        .with_default(1.0)
+        .with_getter(|player: &Player, _| player.speed_multipliers.moving)
+        .with_setter(|player: &mut Player, _, multiplier| {
+            player.speed_multipliers.moving = multiplier
+        })
+        .done();
+    builder
+        .add_property::<f32>("attack_speed_multiplier")
+        .with_default(1.0)
+        .with_getter(|player: &Player, _| player.speed_multipliers.attacking)
+        .with_setter(|player: &mut Player, _, multiplier| {
+            player.speed_multipliers.attacking = multiplier
+        })
+        .done();
+    builder
+        .add_property::<f32>("roll_speed_multiplier")
+        .with_default(1.0)
+        .with_getter(|player: &Player, _| player.speed_multipliers.rolling)
+        .with_setter(|player: &mut Player, _, multiplier| {
+            player.speed_multipliers.rolling = multiplier
+        })
+        .done();
+    builder
+        .add_property::<i64>("acceleration_mode")
+        .with_hint(IntHint::Enum(EnumHint::new(
+            AccelerationMode::NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )))
+        .with_getter(|player: &Player, _| player.acceleration_mode.index())
+        .with_setter(|player: &mut Player, _, index| {
+            player.acceleration_mode = AccelerationMode::from_index(index)
+        })
+        .done();
+    builder
+        .add_property::<f32>("grapple_speed")
+        .with_default(200.0)
+        .with_getter(|player: &Player, _| player.grapple.speed)
+        .with_setter(|player: &mut Player, _, speed| player.grapple.speed = speed)
+        .done();
+    builder
+        .add_property::<f32>("grapple_range")
+        .with_default(96.0)
+        .with_getter(|player: &Player, _| player.grapple.range)
+        .with_setter(|player: &mut Player, _, range| player.grapple.range = range)
+        .done();
+    builder
+        .add_property::<i64>("movement_mode")
+        .with_hint(IntHint::Enum(EnumHint::new(
+            MovementMode::NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )))
+        .with_getter(|player: &Player, _| player.platforming.mode.index())
+        .with_setter(|player: &mut Player, _, index| {
+            player.platforming.mode = MovementMode::from_index(index)
+        })
+        .done();
+    builder
+        .add_property::<f32>("gravity")
+        .with_default(600.0)
+        .with_getter(|player: &Player, _| player.platforming.gravity)
+        .with_setter(|player: &mut Player, _, gravity| player.platforming.gravity = gravity)
+        .done();
+    builder
+        .add_property::<f32>("jump_speed")
+        .with_default(220.0)
+        .with_getter(|player: &Player, _| player.platforming.jump_speed)
+        .with_setter(|player: &mut Player, _, speed| player.platforming.jump_speed = speed)
+        .done();
+    builder
+        .add_property::<f32>("wall_slide_speed")
+        .with_default(40.0)
+        .with_getter(|player: &Player, _| player.platforming.wall_slide_speed)
+        .with_setter(|player: &mut Player, _, speed| player.platforming.wall_slide_speed = speed)
+        .done();
+    builder
+        .add_property::<f32>("wall_jump_speed")
+        .with_default(200.0)
+        .with_getter(|player: &Player, _| player.platforming.wall_jump_speed)
+        .with_setter(|player: &mut Player, _, speed| player.platforming.wall_jump_speed = speed)
+        .done();
+    builder
+        .add_property::<f32>("wall_jump_push")
+        .with_default(120.0)
+        .with_getter(|player: &Player, _| player.platforming.wall_jump_push)
+        .with_setter(|player: &mut Player, _, push| player.platforming.wall_jump_push = push)
+        .done();
+    builder
+        .add_property::<i32>("finisher_threshold")
+        .with_default(10)
+        .with_getter(|player: &Player, _| player.finisher.threshold)
+        .with_setter(|player: &mut Player, _, threshold| player.finisher.threshold = threshold)
+        .done();
+    builder
+        .add_property::<i32>("finisher_damage")
+        .with_default(4)
+        .with_getter(|player: &Player, _| player.finisher.damage)
+        .with_setter(|player: &mut Player, _, damage| player.finisher.damage = damage)
+        .done();
+    builder
+        .add_property::<f32>("finisher_radius")
+        .with_default(48.0)
+        .with_getter(|player: &Player, _| player.finisher.radius)
+        .with_setter(|player: &mut Player, _, radius| player.finisher.radius = radius)
+        .done();
+    // fraction of a melee hit's damage stopped while blocking
+    builder
+        .add_property::<f32>("block_damage_reduction")
+        .with_default(1.0)
+        .with_getter(|player: &Player, _| player.block.damage_reduction)
+        .with_setter(|player: &mut Player, _, reduction| player.block.damage_reduction = reduction)
+        .done();
+    // fraction of the blocked damage bashed back into the attacker
+    builder
+        .add_property::<f32>("block_reflect")
+        .with_getter(|player: &Player, _| player.block.reflect)
+        .with_setter(|player: &mut Player, _, reflect| player.block.reflect = reflect)
+        .done();
+    // speed bonus of the second roll in a row in the same direction, each
+    // roll after that adds half as much as the previous one
+    builder
+        .add_property::<f32>("momentum_bonus")
+        .with_default(0.1)
+        .with_getter(|player: &Player, _| player.momentum.bonus)
+        .with_setter(|player: &mut Player, _, bonus| player.momentum.bonus = bonus)
+        .done();
+    builder
+        .add_property::<f32>("momentum_max_bonus")
+        .with_default(0.25)
+        .with_getter(|player: &Player, _| player.momentum.max_bonus)
+        .with_setter(|player: &mut Player, _, max_bonus| player.momentum.max_bonus = max_bonus)
+        .done();
+    // fraction of the roll, from its start, during which attacking cancels
+    // the roll into an attack, later attacks wait for the roll to end
+    builder
+        .add_property::<f32>("dash_attack_window")
+        .with_default(0.5)
+        .with_getter(|player: &Player, _| player.dash_attack.window)
+        .with_setter(|player: &mut Player, _, window| player.dash_attack.window = window)
+        .done();
+    builder
+        .add_property::<f32>("max_stamina")
+        .with_default(100.0)
+        .with_getter(|player: &Player, _| player.stamina.max)
+        .with_setter(|player: &mut Player, _, max_stamina| {
+            player.stamina.max = max_stamina;
+            player.stamina.current = max_stamina;
+        })
+        .done();
+    builder
+        .add_property::<f32>("stamina_regen")
+        .with_default(25.0)
+        .with_getter(|player: &Player, _| player.stamina.regen)
+        .with_setter(|player: &mut Player, _, regen| player.stamina.regen = regen)
+        .done();
+    builder
+        .add_property::<f32>("parried_stagger_duration")
+        .with_default(0.6)
+        .with_getter(|player: &Player, _| player.parried_stagger_duration)
+        .with_setter(|player: &mut Player, _, duration| player.parried_stagger_duration = duration)
+        .done();
+    builder
+        .add_property::<f32>("attack_exit_speed")
+        .with_default(0.0)
+        .with_getter(|player: &Player, _| player.attack_exit_speed)
+        .with_setter(|player: &mut Player, _, speed| player.attack_exit_speed = speed)
+        .done();
+    builder
+        .add_property::<f32>("roll_stamina_cost")
+        .with_default(25.0)
+        .with_getter(|player: &Player, _| player.roll_stamina_cost)
+        .with_setter(|player: &mut Player, _, cost| player.roll_stamina_cost = cost)
+        .done();
+    builder
+        .add_property::<i32>("emergency_dodge_cost")
+        .with_default(1)
+        .with_getter(|player: &Player, _| player.emergency_dodge_cost)
+        .with_setter(|player: &mut Player, _, cost| player.emergency_dodge_cost = cost)
+        .done();
+    // holding attack past the swing keeps spinning the sword until it is
+    // released or the stamina runs out
+    builder
+        .add_property::<f32>("spin_stamina_drain")
+        .with_default(50.0)
+        .with_getter(|player: &Player, _| player.spin.drain)
+        .with_setter(|player: &mut Player, _, drain| player.spin.drain = drain)
+        .done();
+    // in radians per second
+    builder
+        .add_property::<f32>("spin_speed")
+        .with_default(12.0)
+        .with_getter(|player: &Player, _| player.spin.speed)
+        .with_setter(|player: &mut Player, _, speed| player.spin.speed = speed)
+        .done();
+
+    // dodging a hit speeds up the next attack started within the window
+    builder
+        .add_property::<f32>("adrenaline_window")
+        .with_default(1.0)
+        .with_getter(|player: &Player, _| player.adrenaline.window)
+        .with_setter(|player: &mut Player, _, window| player.adrenaline.window = window)
+        .done();
+    builder
+        .add_property::<f32>("adrenaline_attack_speed")
+        .with_default(1.5)
+        .with_getter(|player: &Player, _| player.adrenaline.attack_speed)
+        .with_setter(|player: &mut Player, _, speed| player.adrenaline.attack_speed = speed)
+        .done();
+
+    // a follow-up attack pressed during a swing only chains if that swing
+    // hit something
+    builder
+        .add_property::<bool>("require_hit_confirm")
+        .with_getter(|player: &Player, _| player.follow_up.require_hit_confirm)
+        .with_setter(|player: &mut Player, _, require| {
+            player.follow_up.require_hit_confirm = require
+        })
+        .done();
+
+    builder.add_signal(Signal {
+        name: "perfect_dodge",
+        args: &[],
+    });
+}
+
+struct Block {
+    damage_reduction: f32,
+    reflect: f32,
+    is_blocking: bool,
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Self {
+            damage_reduction: 1.0,
+            reflect: 0.0,
+            is_blocking: false,
+        }
+    }
+}
+
+impl Block {
+    /// Splits a melee hit into the damage taken and the damage reflected
+    /// back to the attacker.
+    fn block_hit(&self, damage: i32) -> (i32, i32) {
+        let blocked = (damage as f32 * self.damage_reduction).round() as i32;
+        let reflected = (blocked as f32 * self.reflect).round() as i32;
+
+        (damage - blocked, reflected)
+    }
+}
+
+// ignores presses on the first frames after entering a room, which could be
+// a button still held from the menu or the room before
+struct InputGuard {
+    frames: i32,
+    frames_left: i32,
+    // whether presses are ignored this frame
+    guarding: bool,
+}
+
+impl Default for InputGuard {
+    fn default() -> Self {
+        Self {
+            frames: DEFAULT_INPUT_GUARD_FRAMES,
+            frames_left: 0,
+            guarding: false,
+        }
+    }
+}
+
+impl InputGuard {
+    fn reset(&mut self) {
+        self.frames_left = self.frames;
+    }
+
+    /// Starts a frame, guarding it if it's one of the first since the reset.
+    fn tick(&mut self) {
+        self.guarding = self.frames_left > 0;
+        self.frames_left = (self.frames_left - 1).max(0);
+    }
+}
+
+struct TauntEmote {
+    boost: f32,
+    fade: Cooldown,
+}
+
+impl Default for TauntEmote {
+    fn default() -> Self {
+        Self {
+            boost: 64.0,
+            fade: Cooldown::new(3.0),
+        }
+    }
+}
+
+impl TauntEmote {
+    fn current_boost(&self) -> f32 {
+        if self.fade.duration <= 0.0 {
+            return 0.0;
+        }
+
+        self.boost * self.fade.time_left() / self.fade.duration
+    }
+}
+
+// an area attack unlocked by a long enough combo
+struct Finisher {
+    threshold: i32,
+    damage: i32,
+    radius: f32,
+}
+
+impl Default for Finisher {
+    fn default() -> Self {
+        Self {
+            threshold: 10,
+            damage: 4,
+            radius: 48.0,
+        }
+    }
+}
+
+impl Finisher {
+    fn is_available(&self, combo: i32) -> bool {
+        combo >= self.threshold
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Dodge {
+    // dodged right as the hit arrived, which readies a counter-attack
+    Perfect,
+    Normal,
+}
+
+#[derive(Debug, PartialEq)]
+enum RollAttack {
+    // cancels the roll straight into the attack
+    Dash,
+    // attacks once the roll ends
+    Buffered,
+}
+
+struct DashAttack {
+    window: f32,
+    buffered: bool,
+}
+
+impl Default for DashAttack {
+    fn default() -> Self {
+        Self {
+            window: 0.5,
+            buffered: false,
+        }
+    }
+}
+
+impl DashAttack {
+    fn classify(&self, roll_elapsed: f32) -> RollAttack {
+        if roll_elapsed / ROLL_DURATION <= self.window {
+            RollAttack::Dash
+        } else {
+            RollAttack::Buffered
+        }
+    }
+}
+
+// an attack pressed during a swing, chained right after it
+#[derive(Default)]
+struct FollowUp {
+    require_hit_confirm: bool,
+    buffered: bool,
+    // the current swing hit something
+    confirmed: bool,
+}
+
+impl FollowUp {
+    /// Returns whether to chain into the follow-up as the swing ends, and
+    /// gets ready for the next swing.
+    fn finish_swing(&mut self) -> bool {
+        let chain = self.buffered && (self.confirmed || !self.require_hit_confirm);
+
+        self.buffered = false;
+        self.confirmed = false;
+
+        chain
+    }
+}
+
+struct Adrenaline {
+    window: f32,
+    attack_speed: f32,
+    timer: GameTimer,
+}
+
+impl Default for Adrenaline {
+    fn default() -> Self {
+        Self {
+            window: 1.0,
+            attack_speed: 1.5,
+            timer: GameTimer::default(),
+        }
+    }
+}
+
+impl Adrenaline {
+    fn grant(&mut self) {
+        self.timer.start(self.window);
+    }
+
+    fn tick(&mut self, delta: f32) {
+        self.timer.tick(delta);
+    }
+
+    fn attack_speed(&self) -> f32 {
+        if self.timer.is_running() {
+            self.attack_speed
+        } else {
+            1.0
+        }
+    }
+}
+
+struct Stamina {
+    current: f32,
+    max: f32,
+    regen: f32,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self {
+            current: 100.0,
+            max: 100.0,
+            regen: 25.0,
+        }
+    }
+}
+
+impl Stamina {
+    /// Spends as much as is left, returning whether it ran out.
+    fn spend(&mut self, amount: f32) -> bool {
+        self.current = (self.current - amount).max(0.0);
+
+        self.current <= 0.0
+    }
+
+    fn regen(&mut self, delta: f32) {
+        self.current = (self.current + self.regen * delta).min(self.max);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum DodgeCost {
+    Stamina,
+    // out of stamina, paid for with health through the emergency dodge
+    Health,
+    Unaffordable,
+}
+
+fn dodge_cost(stamina: f32, cost: f32, emergency_dodge: bool) -> DodgeCost {
+    if stamina >= cost {
+        DodgeCost::Stamina
+    } else if emergency_dodge {
+        DodgeCost::Health
+    } else {
+        DodgeCost::Unaffordable
+    }
+}
+
+struct Spin {
+    // stamina per second
+    drain: f32,
+    speed: f32,
+    // hit mode the sword goes back to once the spin ends
+    previous_hit_mode: HitMode,
+}
+
+impl Default for Spin {
+    fn default() -> Self {
+        Self {
+            drain: 50.0,
+            speed: 12.0,
+            previous_hit_mode: HitMode::default(),
+        }
+    }
+}
+
+// cosine of the widest angle between two rolls that still keeps the momentum
+const MOMENTUM_FACING: f32 = 0.9;
+
+// a speed bonus built by rolling several times in a row in the same
+// direction
+struct Momentum {
+    bonus: f32,
+    max_bonus: f32,
+    chain: i32,
+    last_direction: Vector2,
+}
+
+impl Default for Momentum {
+    fn default() -> Self {
+        Self {
+            bonus: 0.1,
+            max_bonus: 0.25,
+            chain: 0,
+            last_direction: Vector2::zero(),
+        }
+    }
+}
+
+impl Momentum {
+    fn start_roll(&mut self, direction: Vector2) {
+        if self.chain > 0 && self.last_direction.dot(direction) >= MOMENTUM_FACING {
+            self.chain += 1;
+        } else {
+            self.chain = 1;
+        }
+
+        self.last_direction = direction;
+    }
+
+    fn multiplier(&self) -> f32 {
+        // the first roll of a chain gets no bonus, then it halves each roll
+        let rolls = (self.chain - 1).max(0);
+        let bonus = self.bonus * 2.0 * (1.0 - 0.5f32.powi(rolls));
+
+        1.0 + bonus.min(self.max_bonus)
+    }
+}
+
+// a short self-slow after a hit lands, so attacks feel weighty
+#[derive(Default)]
+struct ImpactDrag {
+    drag: f32,
+    expiry: Cooldown,
+}
+
+impl ImpactDrag {
+    fn multiplier(&self) -> f32 {
+        if !self.expiry.is_ready() {
+            1.0 - self.drag
+        } else {
+            1.0
+        }
+    }
+}
+
+struct SpeedMultipliers {
+    moving: f32,
+    attacking: f32,
+    rolling: f32,
+}
+
+impl Default for SpeedMultipliers {
+    fn default() -> Self {
+        Self {
+            moving: 1.0,
+            attacking: 1.0,
+            rolling: 1.0,
+        }
+    }
+}
+
+struct Grapple {
+    speed: f32,
+    range: f32,
+    target: Vector2,
+}
+
+impl Default for Grapple {
+    fn default() -> Self {
+        Self {
+            speed: 200.0,
+            range: 96.0,
+            target: Vector2::zero(),
+        }
+    }
+}
+
+// everything but the mode only applies to the platformer mode
+struct Platforming {
+    mode: MovementMode,
+    gravity: f32,
+    jump_speed: f32,
+    wall_slide_speed: f32,
+    wall_jump_speed: f32,
+    wall_jump_push: f32,
+    is_on_floor: bool,
+    // normal of the wall touched on the last physics frame
+    wall_normal: Option<Vector2>,
+}
+
+impl Default for Platforming {
+    fn default() -> Self {
+        Self {
+            mode: MovementMode::default(),
+            gravity: 600.0,
+            jump_speed: 220.0,
+            wall_slide_speed: 40.0,
+            wall_jump_speed: 200.0,
+            wall_jump_push: 120.0,
+            is_on_floor: false,
+            wall_normal: None,
+        }
+    }
+}
+
+#[derive(Debug)]
 enum State {
     Move,
     Attack,
     Roll,
+    Grapple,
+    Spin,
+    // reeling from an attack an enemy parried, until the timer ends
+    Staggered(f32),
 }
 
 impl Default for State {
@@ -29,17 +865,102 @@ impl Default for State {
     }
 }
 
+impl State {
+    // the input action that puts the player in this state, if any
+    fn trigger_action(&self) -> Option<&'static str> {
+        match self {
+            Self::Attack => Some("attack"),
+            Self::Roll => Some("roll"),
+            Self::Grapple => Some("grapple"),
+            Self::Move | Self::Spin | Self::Staggered(_) => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Move => "move",
+            Self::Attack => "attack",
+            Self::Roll => "roll",
+            Self::Grapple => "grapple",
+            Self::Spin => "spin",
+            Self::Staggered(_) => "staggered",
+        }
+    }
+}
+
+/// The latest state changes, each with the seconds since the player spawned
+/// it came at, oldest first.
+#[derive(Default)]
+struct StateLog {
+    capacity: usize,
+    time: f32,
+    transitions: VecDeque<(f32, &'static str)>,
+}
+
+impl StateLog {
+    fn record(&mut self, state: &State) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.transitions.push_back((self.time, state.name()));
+
+        while self.transitions.len() > self.capacity {
+            self.transitions.pop_front();
+        }
+    }
+}
+
 #[gdnative::methods]
 impl Player {
     fn new(_owner: &KinematicBody2D) -> Self {
         Player {
-            roll_vector: Vector2::down(),
+            roll_stamina_cost: 25.0,
+            attack_cooldown: Cooldown::new(AUTO_ATTACK_COOLDOWN),
+            emergency_dodge_cost: 1,
+            parried_stagger_duration: 0.6,
+            attack_exit_speed: 0.0,
             ..Default::default()
         }
     }
 
+    #[export]
+    fn _exit_tree(&mut self, owner: &KinematicBody2D) {
+        entity_registry::unregister(owner);
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &KinematicBody2D) {
+        owner.add_to_group("player", false);
+        owner.add_to_group("aggro_targets", false);
+        entity_registry::register(owner, Kind::Player);
+
+        self.checkpoint = owner.global_position();
+        self.input_guard.reset();
+
+        // entering a room starts a time attack run
+        time_attack::start(owner);
+
+        // every room is its own scene with its own player, and each gets a
+        // fresh last stand
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let _ = player_stats_instance.map_mut(|player_stats, _| {
+            player_stats.reset_last_stand();
+
+            self.stamina.max += player_stats.bonus_stamina;
+            self.stamina.current = self.stamina.max;
+            self.knockback_resistance += player_stats.bonus_knockback_resistance;
+        });
+    }
+
     #[export]
     fn _process(&mut self, owner: &KinematicBody2D, delta: f32) {
+        let delta = settings::game_delta(owner, delta);
+
+        debug_draw::refresh(owner, &mut self.debug_drawn);
+
         let animation_tree = unsafe { owner.get_typed_node::<AnimationTree, _>("AnimationTree") };
         let playback_prop = animation_tree
             .get("parameters/playback")
@@ -55,58 +976,290 @@ impl Player {
 
         let input_singleton = Input::godot_singleton();
 
+        self.input_guard.tick();
+        self.state_log.time += delta;
+        self.attack_cooldown.tick(delta);
+        self.parry_window.tick(delta);
+        self.impact_drag.expiry.tick(delta);
+        self.taunt_emote.fade.tick(delta);
+
+        if !matches!(self.state, State::Spin) {
+            self.stamina.regen(delta);
+        }
+
+        // the window only has to be met by the start of the attack, which
+        // then stays fast until it ends
+        if !matches!(self.state, State::Attack) {
+            self.adrenaline.tick(delta);
+        }
+
+        if let Ok(Some(drag)) = instance.map_mut(|sword_hitbox, _| sword_hitbox.take_impact()) {
+            self.apply_impact_drag(drag);
+        }
+
+        let landed_hit = instance
+            .map_mut(|sword_hitbox, _| sword_hitbox.take_landed_hit())
+            .unwrap_or(false);
+
+        if landed_hit && matches!(self.state, State::Attack) {
+            self.follow_up.confirmed = true;
+        }
+
+        // the block is held, and drops as soon as the player does anything
+        // else
+        self.block.is_blocking = matches!(self.state, State::Move)
+            && input_singleton.is_action_pressed(self.action("block"));
+
+        for action in &["attack", "roll", "grapple"] {
+            if self.just_pressed(input_singleton, action) {
+                input_latency::record_input(owner, action);
+            }
+        }
+
+        for action in &["attack", "roll", "grapple", "parry", "special", "jump"] {
+            if self.just_pressed(input_singleton, action) {
+                combo_trial::record_input(owner, action);
+            }
+        }
+
+        // the wheel works in any state, so it can't be left open
+        let _ = instance.map_mut(|sword_hitbox, _| {
+            self.handle_weapon_wheel_input(owner, input_singleton, sword_hitbox)
+        });
+
         match self.state {
             State::Move => {
                 let input_vector = self.get_movement_input(input_singleton);
+                let input_vector = self.smooth_input(input_vector, delta);
+
+                let aim_input = self.get_aim_input(input_singleton);
+                let control_scheme = self.get_control_scheme(owner);
 
                 self.animate_on_input(&animation_tree, &animation_state, input_vector);
 
                 let _ = instance.map_mut(|sword_hitbox, _| {
-                    self.move_on_input(input_vector, delta, sword_hitbox);
+                    self.move_on_input(input_vector, delta);
+
+                    self.control_scheme = control_scheme;
+                    self.aim_input = aim_input;
+                    self.aim(&animation_tree, sword_hitbox);
                 });
 
                 self.handle_attack_input(input_singleton);
-                self.handle_roll_input(input_singleton);
+                self.handle_auto_attack(owner);
+                self.handle_parry_input(input_singleton);
+                self.handle_grapple_input(owner, input_singleton);
+                self.handle_jump_input(input_singleton);
+                self.handle_special_input(owner, input_singleton);
+                self.handle_taunt_input(owner, input_singleton);
+                self.handle_roll_input(owner, input_singleton);
             }
             State::Attack => {
                 self.animate_attack(&animation_state);
+
+                if self.just_pressed(input_singleton, "attack") {
+                    self.follow_up.buffered = true;
+                }
+
+                if self.can_roll_cancel() {
+                    self.handle_roll_input(owner, input_singleton);
+
+                    if matches!(self.state, State::Roll) {
+                        self.cancel_attack();
+                    }
+                }
+
+                // the tree already played this frame at normal speed
+                let extra_speed = self.adrenaline.attack_speed() - 1.0;
+
+                if extra_speed > 0.0 {
+                    animation_tree.advance((extra_speed * delta) as f64);
+                }
             }
             State::Roll => {
+                self.roll_elapsed += delta;
                 self.roll();
+                self.handle_roll_attack_input(input_singleton);
                 self.animate_roll(&animation_state);
             }
+            // input is ignored until the player reaches the grapple point
+            State::Grapple => {
+                match grapple_pull(
+                    owner.global_position(),
+                    self.grapple.target,
+                    self.grapple.speed,
+                    delta,
+                ) {
+                    Some(velocity) => self.velocity = velocity,
+                    None => self.release_grapple(),
+                }
+            }
+            State::Staggered(_) => {
+                animation_state.travel("Idle");
+
+                self.tick_stagger(delta);
+            }
+            State::Spin => {
+                let pivot = unsafe { owner.get_typed_node::<Position2D, _>("HitboxPivot") };
+                let rotation = spin_rotation_step(pivot.rotation() as f32, self.spin.speed, delta);
+
+                pivot.set_rotation(rotation as f64);
+
+                let _ = instance.map_mut(|sword_hitbox, _| {
+                    sword_hitbox.knockback_vector = Vector2::new(rotation.cos(), rotation.sin());
+                });
+
+                let exhausted = self.stamina.spend(self.spin.drain * delta);
+
+                if exhausted || !input_singleton.is_action_pressed(self.action("attack")) {
+                    self.end_spin(owner);
+                }
+            }
         };
+
+        // states changed from animation callbacks are seen a frame late
+        let trigger_action = self.state.trigger_action();
+
+        if trigger_action != self.latency_action {
+            if let Some(action) = trigger_action {
+                input_latency::record_transition(owner, action, &format!("{:?}", self.state));
+            }
+
+            self.latency_action = trigger_action;
+        }
+    }
+
+    // the knockback the sword deals, which swings take their direction from
+    #[export]
+    fn _draw(&self, owner: &KinematicBody2D) {
+        if !settings::debug_draw(owner) {
+            return;
+        }
+
+        let sword_hitbox_node =
+            unsafe { owner.get_typed_node::<Area2D, _>("HitboxPivot/SwordHitbox") };
+        let knockback_vector = sword_hitbox_node
+            .cast_instance::<SwordHitbox>()
+            .and_then(|sword_hitbox| {
+                sword_hitbox
+                    .map(|sword_hitbox, _| sword_hitbox.knockback_vector)
+                    .ok()
+            })
+            .unwrap_or_else(Vector2::zero);
+
+        if let Some(arrow) = debug_draw::knockback_arrow(knockback_vector, KNOCKBACK_ARROW_SCALE) {
+            debug_draw::draw_arrow(owner, &arrow, Color::rgb(0.3, 0.6, 1.0));
+        }
     }
 
     #[export]
-    fn _physics_process(&mut self, owner: &KinematicBody2D, _delta: f32) {
+    fn _physics_process(&mut self, owner: &KinematicBody2D, delta: f32) {
+        // `move_and_slide` steps by the fixed physics rate itself, but gravity
+        // goes by this
+        let delta = settings::game_delta(owner, delta);
+
+        if let Some(speed) = self.clamp_to_max_speed() {
+            if cfg!(debug_assertions) {
+                godot_warn!(
+                    "player moving at {:.1} while in {}, clamped to its max",
+                    speed,
+                    self.state.name()
+                );
+            }
+        }
+
         match self.state {
+            State::Move if self.platforming.mode.has_gravity() => {
+                self.velocity.y = apply_gravity(self.velocity.y, self.platforming.gravity, delta);
+
+                if self.platforming.wall_normal.is_some() {
+                    self.velocity.y =
+                        wall_slide(self.velocity.y, self.platforming.wall_slide_speed);
+                }
+
+                self.velocity = owner.move_and_slide(
+                    self.velocity,
+                    self.platforming.mode.up_direction(),
+                    false,
+                    4,
+                    0.785398,
+                    true,
+                );
+
+                self.platforming.is_on_floor = owner.is_on_floor();
+                self.platforming.wall_normal = if owner.is_on_wall() {
+                    self.find_wall_normal(owner)
+                } else {
+                    None
+                };
+            }
             State::Move => {
                 self.velocity =
                     owner.move_and_slide(self.velocity, Vector2::zero(), false, 4, 0.785398, true);
             }
-            State::Attack => {
+            State::Attack | State::Spin | State::Staggered(_) => {
                 self.velocity = Vector2::zero();
             }
             State::Roll => {
                 self.velocity =
                     owner.move_and_slide(self.velocity, Vector2::zero(), false, 4, 0.785398, true);
             }
+            State::Grapple => {
+                self.velocity =
+                    owner.move_and_slide(self.velocity, Vector2::zero(), false, 4, 0.785398, true);
+
+                // a wall in the way would otherwise keep the player stuck
+                if owner.get_slide_count() > 0 {
+                    self.release_grapple();
+                }
+            }
         }
     }
 
-    fn get_movement_input(&self, input: &Input) -> Vector2 {
-        let right_strength = input.get_action_strength("ui_right");
-        let left_strength = input.get_action_strength("ui_left");
-        let down_strength = input.get_action_strength("ui_down");
-        let up_strength = input.get_action_strength("ui_up");
+    fn action(&self, action: &str) -> String {
+        action_name(self.player_index, action)
+    }
 
-        let mut input_vector = Vector2::zero();
+    fn just_pressed(&self, input: &Input, action: &str) -> bool {
+        !self.input_guard.guarding && input.is_action_just_pressed(self.action(action))
+    }
 
-        input_vector.x = (right_strength - left_strength) as f32;
-        input_vector.y = (down_strength - up_strength) as f32;
+    fn get_movement_input(&self, input: &Input) -> Vector2 {
+        let right_strength = input.get_action_strength(self.action("ui_right"));
+        let left_strength = input.get_action_strength(self.action("ui_left"));
+        let down_strength = input.get_action_strength(self.action("ui_down"));
+        let up_strength = input.get_action_strength(self.action("ui_up"));
+
+        input_vector_from_strengths(
+            right_strength as f32,
+            left_strength as f32,
+            down_strength as f32,
+            up_strength as f32,
+        )
+    }
+
+    fn get_aim_input(&self, input: &Input) -> Vector2 {
+        let right_strength = input.get_action_strength(self.action("aim_right"));
+        let left_strength = input.get_action_strength(self.action("aim_left"));
+        let down_strength = input.get_action_strength(self.action("aim_down"));
+        let up_strength = input.get_action_strength(self.action("aim_up"));
+
+        let aim_vector = Vector2::new(
+            (right_strength - left_strength) as f32,
+            (down_strength - up_strength) as f32,
+        );
+
+        aim_vector.try_normalize().unwrap_or(aim_vector)
+    }
+
+    // read every frame, so changing the setting takes effect immediately
+    fn get_control_scheme(&self, owner: &KinematicBody2D) -> ControlScheme {
+        let settings_node = unsafe { owner.get_typed_node::<Node, _>("/root/Settings") };
+        let settings_instance = settings_node.cast_instance::<Settings>().unwrap();
 
-        input_vector.try_normalize().unwrap_or(input_vector)
+        settings_instance
+            .map(|settings, _| settings.control_scheme)
+            .unwrap_or_default()
     }
 
     fn animate_on_input(
@@ -118,7 +1271,6 @@ impl Player {
         if input_vector != Vector2::zero() {
             animation_tree.set("parameters/Idle/blend_position", input_vector);
             animation_tree.set("parameters/Run/blend_position", input_vector);
-            animation_tree.set("parameters/Attack/blend_position", input_vector);
             animation_tree.set("parameters/Roll/blend_position", input_vector);
 
             animation_state.travel("Run");
@@ -127,35 +1279,440 @@ impl Player {
         }
     }
 
-    fn move_on_input(&mut self, input_vector: Vector2, delta: f32, sword_hitbox: &mut SwordHitbox) {
+    /// Eases the movement input towards the raw one, for direction changes
+    /// that aren't as snappy.
+    fn smooth_input(&mut self, raw_input: Vector2, delta: f32) -> Vector2 {
+        if self.input_smoothing <= 0.0 {
+            self.smoothed_input = raw_input;
+            return raw_input;
+        }
+
+        let weight = (delta / self.input_smoothing).min(1.0);
+        self.smoothed_input = self.smoothed_input.lerp(raw_input, weight);
+
+        // close enough is the input, so letting go comes to a stop
+        if (self.smoothed_input - raw_input).length() < 0.01 {
+            self.smoothed_input = raw_input;
+        }
+
+        self.smoothed_input
+    }
+
+    fn move_on_input(&mut self, input_vector: Vector2, delta: f32) {
+        let vertical_velocity = self.velocity.y;
+
         if input_vector != Vector2::zero() {
-            self.roll_vector = input_vector;
-            sword_hitbox.knockback_vector = input_vector;
+            self.last_direction = Some(input_vector);
+
+            let max_speed = MAX_SPEED * self.speed_multiplier_for(&self.state);
+            let acceleration = self.acceleration_mode.acceleration(
+                ACCELERATION,
+                self.velocity.length(),
+                max_speed,
+            );
 
             self.velocity = self
                 .velocity
-                .move_towards(input_vector * MAX_SPEED, ACCELERATION * delta);
+                .move_towards(input_vector * max_speed, acceleration * delta);
         } else {
             self.velocity = self
                 .velocity
                 .move_towards(Vector2::zero(), FRICTION * delta);
         }
+
+        // with gravity the vertical speed belongs to falling and jumping
+        if self.platforming.mode.has_gravity() {
+            self.velocity.y = vertical_velocity;
+        }
+    }
+
+    /// The direction the player last moved in, which idle rolls go in.
+    fn direction(&self) -> Vector2 {
+        self.last_direction.unwrap_or(FIRST_DIRECTION)
+    }
+
+    /// The direction attacks, auto attacks and grapples go in.
+    fn facing(&self) -> Vector2 {
+        self.control_scheme
+            .aim_direction(self.direction(), self.aim_input)
+    }
+
+    fn aim(&self, animation_tree: &AnimationTree, sword_hitbox: &mut SwordHitbox) {
+        let facing = self.facing();
+
+        animation_tree.set("parameters/Attack/blend_position", facing);
+        sword_hitbox.knockback_vector = facing;
+    }
+
+    #[cfg(test)]
+    fn r#move(
+        &mut self,
+        right_strength: f32,
+        left_strength: f32,
+        down_strength: f32,
+        up_strength: f32,
+        delta: f32,
+    ) {
+        let input_vector =
+            input_vector_from_strengths(right_strength, left_strength, down_strength, up_strength);
+
+        self.move_on_input(input_vector, delta);
+    }
+
+    fn speed_multiplier_for(&self, state: &State) -> f32 {
+        let multiplier = match state {
+            State::Move => self.speed_multipliers.moving,
+            State::Attack | State::Spin => self.speed_multipliers.attacking,
+            State::Roll => self.speed_multipliers.rolling,
+            State::Grapple | State::Staggered(_) => 1.0,
+        };
+
+        multiplier * self.impact_drag.multiplier()
+    }
+
+    fn apply_impact_drag(&mut self, drag: f32) {
+        self.impact_drag = ImpactDrag {
+            drag,
+            expiry: Cooldown::started(IMPACT_DRAG_DURATION),
+        };
+        self.velocity *= self.impact_drag.multiplier();
+    }
+
+    /// The fastest the player can move in the state, or `None` when
+    /// anything goes, like the platformer's jumps.
+    fn max_speed_for(&self, state: &State) -> Option<f32> {
+        match state {
+            State::Move if self.platforming.mode.has_gravity() => None,
+            State::Move => Some(MAX_SPEED * self.speed_multiplier_for(state)),
+            State::Roll => {
+                Some(ROLL_SPEED * self.speed_multiplier_for(state) * self.momentum.multiplier())
+            }
+            State::Grapple => Some(self.grapple.speed),
+            // they hold the player still
+            State::Attack | State::Spin | State::Staggered(_) => Some(0.0),
+        }
+    }
+
+    fn holds_still(&self, state: &State) -> bool {
+        self.max_speed_for(state) == Some(0.0)
+    }
+
+    /// Keeps the velocity within the max speed of the state, returning the
+    /// speed it had when it had to be clamped, which is a bug somewhere.
+    fn clamp_to_max_speed(&mut self) -> Option<f32> {
+        let max_speed = self.max_speed_for(&self.state)?;
+        let speed = self.velocity.length();
+
+        if speed <= max_speed {
+            self.slide_speed = None;
+        }
+
+        let max_speed = max_speed.max(self.slide_speed.unwrap_or(0.0));
+
+        // leeway for the rounding of moving towards the max
+        if speed <= max_speed + 0.001 {
+            return None;
+        }
+
+        self.velocity = self.velocity * (max_speed / speed);
+
+        Some(speed)
+    }
+
+    // attacking holds the player still even when hit
+    fn knock_back(&mut self, knockback: Vector2) {
+        if knockback == Vector2::zero() || self.holds_still(&self.state) {
+            return;
+        }
+
+        self.velocity += knockback;
+        self.slide_speed = Some(self.velocity.length().max(self.slide_speed.unwrap_or(0.0)));
     }
 
     fn roll(&mut self) {
-        self.velocity = self.roll_vector * ROLL_SPEED;
+        self.velocity = self.direction()
+            * ROLL_SPEED
+            * self.speed_multiplier_for(&State::Roll)
+            * self.momentum.multiplier();
     }
 
     fn handle_attack_input(&mut self, input: &Input) {
-        if input.is_action_just_pressed("attack") {
-            self.state = State::Attack;
+        if self.just_pressed(input, "attack") {
+            self.set_state(State::Attack);
+        }
+    }
+
+    fn handle_weapon_wheel_input(
+        &self,
+        owner: &KinematicBody2D,
+        input: &Input,
+        sword_hitbox: &mut SwordHitbox,
+    ) {
+        let action = self.action("weapon_wheel");
+
+        // keyboard players pick with the movement keys
+        let aim_input = self.get_aim_input(input);
+        let direction = if aim_input == Vector2::zero() {
+            self.get_movement_input(input)
+        } else {
+            aim_input
+        };
+
+        if self.just_pressed(input, "weapon_wheel") {
+            weapon_wheel::open(owner, sword_hitbox.owned_weapon_count());
+        } else if input.is_action_pressed(action.as_str()) {
+            weapon_wheel::aim(owner, direction);
+        } else if input.is_action_just_released(action.as_str()) {
+            if let Some(index) = weapon_wheel::close(owner) {
+                sword_hitbox.equip_owned(index);
+            }
+        }
+    }
+
+    fn handle_roll_attack_input(&mut self, input: &Input) {
+        if !self.just_pressed(input, "attack") {
+            return;
+        }
+
+        match self.dash_attack.classify(self.roll_elapsed) {
+            RollAttack::Dash => self.set_state(State::Attack),
+            RollAttack::Buffered => self.dash_attack.buffered = true,
+        }
+    }
+
+    fn handle_auto_attack(&mut self, owner: &KinematicBody2D) {
+        if !self.auto_attack {
+            return;
+        }
+
+        let enemy_positions: Vec<Vector2> = entity_registry::all_enemies(owner)
+            .iter()
+            .map(|enemy| enemy.global_position())
+            .collect();
+
+        if should_auto_attack(
+            owner.global_position(),
+            self.facing(),
+            &enemy_positions,
+            self.attack_cooldown.time_left(),
+        ) {
+            self.set_state(State::Attack);
+        }
+    }
+
+    fn handle_parry_input(&mut self, input: &Input) {
+        if self.just_pressed(input, "parry") {
+            self.parry_window.start(PARRY_WINDOW);
+        }
+    }
+
+    fn handle_grapple_input(&mut self, owner: &KinematicBody2D, input: &Input) {
+        if !self.just_pressed(input, "grapple") {
+            return;
+        }
+
+        let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+        let grapple_points: Vec<Vector2> = tree
+            .get_nodes_in_group("grapple_points")
+            .iter()
+            .filter_map(|point| point.try_to_object::<Node2D>())
+            .map(|point| unsafe { point.assume_safe() }.global_position())
+            .collect();
+
+        if let Some(target) = find_grapple_point(
+            owner.global_position(),
+            self.facing(),
+            &grapple_points,
+            self.grapple.range,
+        ) {
+            self.grapple.target = target;
+            self.set_state(State::Grapple);
+        }
+    }
+
+    fn handle_jump_input(&mut self, input: &Input) {
+        if !self.platforming.mode.has_gravity() || !self.just_pressed(input, "jump") {
+            return;
+        }
+
+        if self.platforming.is_on_floor {
+            self.velocity.y = jump_velocity(self.platforming.jump_speed);
+            self.platforming.is_on_floor = false;
+        } else if let Some(wall_normal) = self.platforming.wall_normal {
+            self.velocity = wall_jump_velocity(
+                wall_normal,
+                self.platforming.wall_jump_speed,
+                self.platforming.wall_jump_push,
+            );
+            self.platforming.wall_normal = None;
+        }
+    }
+
+    fn find_wall_normal(&self, owner: &KinematicBody2D) -> Option<Vector2> {
+        (0..owner.get_slide_count())
+            .filter_map(|index| owner.get_slide_collision(index))
+            .map(|collision| unsafe { collision.assume_safe() }.normal())
+            .find(|normal| normal.x.abs() > normal.y.abs())
+    }
+
+    fn handle_special_input(&mut self, owner: &KinematicBody2D, input: &Input) {
+        if !self.just_pressed(input, "special") {
+            return;
+        }
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let finished = player_stats_instance
+            .map_mut(|player_stats, _| self.try_finisher(player_stats))
+            .unwrap_or(false);
+
+        if finished {
+            explosion::explode(
+                owner,
+                owner.global_position(),
+                self.finisher.radius,
+                self.finisher.damage,
+            );
+            screen_flash::flash(owner, Color::rgb(1.0, 1.0, 1.0), 0.25, 0.6);
+        }
+    }
+
+    // spends the combo on the finisher, returning whether it was available
+    fn try_finisher(&self, player_stats: &mut PlayerStats) -> bool {
+        if !self.finisher.is_available(player_stats.combo) {
+            return false;
+        }
+
+        player_stats.combo = 0;
+
+        true
+    }
+
+    fn release_grapple(&mut self) {
+        self.velocity = Vector2::zero();
+        self.set_state(State::Move);
+    }
+
+    fn handle_taunt_input(&mut self, owner: &KinematicBody2D, input: &Input) {
+        if !self.just_pressed(input, "taunt") {
+            return;
+        }
+
+        self.taunt_emote.fade.start();
+
+        let emote_scene = load_scene("res://scenes/DamageNumber.tscn").unwrap();
+
+        let emote_node = unsafe { emote_scene.instance(0).unwrap().assume_safe() };
+        let emote: TRef<Label> = emote_node.cast().unwrap();
+
+        let _ = emote
+            .cast_instance::<DamageNumber>()
+            .unwrap()
+            .map_mut(|emote, _| emote.setup_text("!".to_string()));
+
+        emote.set_global_position(owner.global_position() + TAUNT_EMOTE_OFFSET, false);
+
+        let player_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        player_parent.add_child(emote, false);
+    }
+
+    /// The taunt enemies weigh the player's aggro score with.
+    pub fn taunt_weight(&self) -> f32 {
+        self.taunt + self.taunt_emote.current_boost()
+    }
+
+    pub fn is_attacking(&self) -> bool {
+        matches!(self.state, State::Attack | State::Spin)
+    }
+
+    /// Staggers the player after an enemy parried their attack.
+    pub fn receive_enemy_parry(&mut self, owner: &KinematicBody2D) {
+        if let State::Spin = self.state {
+            self.end_spin(owner);
+        }
+
+        // the attack animation that would disable the sword is cut short
+        self.set_sword_enabled(owner, false);
+        self.start_stagger();
+    }
+
+    fn start_stagger(&mut self) {
+        self.follow_up.buffered = false;
+        self.velocity = Vector2::zero();
+        self.set_state(State::Staggered(self.parried_stagger_duration));
+    }
+
+    fn tick_stagger(&mut self, delta: f32) {
+        if let State::Staggered(time_left) = &mut self.state {
+            *time_left -= delta;
+
+            if *time_left <= 0.0 {
+                self.set_state(State::Move);
+            }
         }
     }
 
-    fn handle_roll_input(&mut self, input: &Input) {
-        if input.is_action_just_pressed("roll") {
-            self.state = State::Roll;
+    pub fn is_parrying(&self) -> bool {
+        self.parry_window.is_running()
+    }
+
+    fn handle_roll_input(&mut self, owner: &KinematicBody2D, input: &Input) {
+        if !self.just_pressed(input, "roll") {
+            return;
+        }
+
+        let emergency_dodge = self.get_emergency_dodge(owner);
+
+        match dodge_cost(
+            self.stamina.current,
+            self.roll_stamina_cost,
+            emergency_dodge,
+        ) {
+            DodgeCost::Stamina => {
+                self.stamina.spend(self.roll_stamina_cost);
+            }
+            DodgeCost::Health => {
+                if !self.pay_emergency_dodge(owner) {
+                    return;
+                }
+            }
+            DodgeCost::Unaffordable => return,
         }
+
+        self.set_state(State::Roll);
+        self.roll_elapsed = 0.0;
+        self.momentum.start_roll(self.direction());
+    }
+
+    fn get_emergency_dodge(&self, owner: &KinematicBody2D) -> bool {
+        let settings_node = unsafe { owner.get_typed_node::<Node, _>("/root/Settings") };
+        let settings_instance = settings_node.cast_instance::<Settings>().unwrap();
+
+        settings_instance
+            .map(|settings, _| settings.emergency_dodge)
+            .unwrap_or(false)
+    }
+
+    // never spends the last of the player's health, returns whether it paid
+    fn pay_emergency_dodge(&self, owner: &KinematicBody2D) -> bool {
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let cost = self.emergency_dodge_cost;
+
+        player_stats_instance
+            .map_mut(|player_stats, _| {
+                if player_stats.health <= cost {
+                    return false;
+                }
+
+                player_stats.set_health(player_stats.health - cost);
+
+                true
+            })
+            .unwrap_or(false)
     }
 
     fn animate_attack(&mut self, animation_state: &AnimationNodeStateMachinePlayback) {
@@ -166,71 +1723,1684 @@ impl Player {
         animation_state.travel("Roll");
     }
 
-    #[export]
-    fn attack_animation_finished(&mut self, _owner: &KinematicBody2D) {
-        self.state = State::Move;
+    pub fn respawn(&mut self, owner: &KinematicBody2D) {
+        owner.set_global_position(self.checkpoint);
+
+        if let State::Spin = self.state {
+            self.end_spin(owner);
+        }
+
+        self.velocity = Vector2::zero();
+        self.set_state(State::Move);
+    }
+
+    /// Returns the current and max stamina.
+    pub fn stamina(&self) -> (f32, f32) {
+        (self.stamina.current, self.stamina.max)
+    }
+
+    /// Returns the whole health points the damage dealt heals, keeping the
+    /// rest for the next hits.
+    pub fn lifesteal_heal(&mut self, damage: i32) -> i32 {
+        self.lifesteal_carry += damage as f32 * self.lifesteal.max(0.0);
+
+        let heal = self.lifesteal_carry.floor();
+        self.lifesteal_carry -= heal;
+
+        heal as i32
+    }
+
+    pub fn thorns_damage(&self, damage: i32) -> i32 {
+        (damage as f32 * self.thorns).round() as i32
     }
 
     #[export]
-    fn roll_animation_finished(&mut self, _owner: &KinematicBody2D) {
-        self.velocity = self.velocity * 0.8;
-        self.state = State::Move;
+    #[allow(non_snake_case)]
+    fn _on_Hurtbox_area_entered(&mut self, owner: &KinematicBody2D, area: Ref<Area2D>) {
+        let area = match unsafe { area.assume_safe_if_sane() } {
+            Some(area) => area,
+            None => return,
+        };
+
+        // hazards deal their own damage over time
+        if !self.damage_allowed(self.on_screen(owner))
+            || area.cast_instance::<HazardArea>().is_some()
+        {
+            return;
+        }
+
+        // rolling dodges everything, projectiles included
+        if let State::Roll = self.state {
+            self.adrenaline.grant();
+
+            if classify_dodge(self.roll_elapsed) == Dodge::Perfect {
+                self.on_perfect_dodge(owner);
+            }
+
+            return;
+        }
+
+        if let Some(projectile) = area.cast_instance::<Projectile>() {
+            let (team, damage) = projectile
+                .map(|projectile, _| (projectile.team, projectile.damage))
+                .unwrap();
+
+            if team == Team::Player {
+                return;
+            }
+
+            if self.is_parrying() {
+                let _ = projectile.map_mut(|projectile, _| projectile.reflect());
+                return;
+            }
+
+            projectile.base().queue_free();
+            self.take_damage(owner, damage, None);
+            return;
+        }
+
+        // the hitbox belongs to the attacker, which may already have been freed
+        let attacker = area
+            .get_parent()
+            .and_then(|attacker| unsafe { attacker.assume_safe_if_sane() })
+            .filter(|attacker| !attacker.is_queued_for_deletion())
+            .and_then(|attacker| attacker.cast::<KinematicBody2D>())
+            .and_then(|attacker| attacker.cast_instance::<Bat>());
+
+        if self.is_parrying() {
+            if let Some(attacker) = attacker {
+                let _ = attacker.map_mut(|bat, bat_owner| bat.receive_parry(&bat_owner));
+            }
+
+            return;
+        }
+
+        let damage = attacker
+            .as_ref()
+            .and_then(|attacker| attacker.map(|bat, _| bat.contact_damage()).ok())
+            .unwrap_or(1);
+
+        let (damage, reflected) = if self.block.is_blocking {
+            self.block.block_hit(damage)
+        } else {
+            (damage, 0)
+        };
+
+        if damage > 0 {
+            let source = attacker
+                .as_ref()
+                .map(|attacker| attacker.base().get_instance_id());
+
+            self.take_damage(owner, damage, source);
+
+            if let Some(attacker) = attacker.as_ref() {
+                let away = (owner.global_position() - attacker.base().global_position())
+                    .try_normalize()
+                    .unwrap_or(Vector2::zero());
+
+                self.knock_back(resisted_knockback(
+                    away * self.hurt_knockback,
+                    self.knockback_resistance,
+                ));
+
+                let player_stats_node =
+                    unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+                let player_stats_instance =
+                    player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+                let _ = player_stats_instance.map_mut(|player_stats, _| {
+                    attacker.map_mut(|bat, _| bat.steal_coins(player_stats))
+                });
+            }
+        }
+
+        let retaliation = self.thorns_damage(damage) + reflected;
+
+        if retaliation > 0 {
+            if let Some(attacker) = attacker {
+                let hit = Hit {
+                    damage: retaliation,
+                    is_crit: false,
+                };
+                let source = Some(owner.get_instance_id());
+
+                let _ = attacker.map_mut(|bat, bat_owner| {
+                    bat.take_hit(&bat_owner, hit, source);
+                });
+            }
+        }
     }
-}
 
-#[test]
-fn test_move_nothing() {
-    let mut player = Player::default();
+    fn on_perfect_dodge(&self, owner: &KinematicBody2D) {
+        let sword_hitbox_node =
+            unsafe { owner.get_typed_node::<Area2D, _>("HitboxPivot/SwordHitbox") };
 
-    player.r#move(0.0, 0.0, 0.0, 0.0, 0.6);
+        let _ = sword_hitbox_node
+            .cast_instance::<SwordHitbox>()
+            .unwrap()
+            .map_mut(|sword_hitbox, _| sword_hitbox.grant_counter());
 
-    assert_eq!(player.velocity, Vector2::zero());
-}
+        owner.emit_signal("perfect_dodge", &[]);
+    }
 
-#[test]
-fn test_move_right() {
-    let mut player = Player::default();
+    pub fn receive_hazard_damage(&mut self, owner: &KinematicBody2D, damage: i32) {
+        if self.damage_allowed(self.on_screen(owner)) {
+            self.take_damage(owner, damage, None);
+        }
+    }
 
-    player.r#move(1.0, 0.0, 0.0, 0.0, 0.6);
+    fn on_screen(&self, owner: &KinematicBody2D) -> bool {
+        let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+        let camera = tree
+            .get_nodes_in_group("player_camera")
+            .iter()
+            .find_map(|camera| camera.try_to_object::<Camera2D>());
 
-    assert_eq!(player.velocity, Vector2::new(1.0 * MAX_SPEED, 0.0));
-}
+        // without a camera there is no screen to leave
+        camera.map_or(true, |camera| {
+            let camera = unsafe { camera.assume_safe() };
 
-#[test]
-fn test_move_left() {
-    let mut player = Player::default();
+            player_camera::view_bounds(&camera).contains(owner.global_position().to_point())
+        })
+    }
 
-    player.r#move(0.0, 1.0, 0.0, 0.0, 0.6);
+    fn damage_allowed(&self, on_screen: bool) -> bool {
+        on_screen || !self.off_screen_invulnerable
+    }
 
-    assert_eq!(player.velocity, Vector2::new(-1.0 * MAX_SPEED, 0.0));
-}
+    fn take_damage(&mut self, owner: &KinematicBody2D, damage: i32, source: Option<i64>) {
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
 
-#[test]
-fn test_move_down() {
-    let mut player = Player::default();
+        let combo_loss = self.combo_loss_on_hurt;
+        let health = player_stats_instance
+            .map_mut(|player_stats, _| {
+                player_stats.receive_damage(damage);
+                player_stats.lose_combo(combo_loss);
+                player_stats.health
+            })
+            .unwrap();
 
-    player.r#move(0.0, 0.0, 1.0, 0.0, 0.6);
+        let hit = Hit {
+            damage,
+            is_crit: false,
+        };
+        damage_bus::emit(
+            owner,
+            damage_bus::hit_events(source, owner.get_instance_id(), hit, health <= 0),
+        );
 
-    assert_eq!(player.velocity, Vector2::new(0.0, 1.0 * MAX_SPEED));
-}
+        if health <= 0 {
+            // the run is over with its last player, who is only
+            // unregistered once freed
+            if entity_registry::count(owner, Kind::Player) <= 1 {
+                upgrades::end_run(owner);
+            }
 
-#[test]
-fn test_move_up() {
-    let mut player = Player::default();
+            owner.queue_free();
+        }
+    }
 
-    player.r#move(0.0, 0.0, 0.0, 1.0, 0.6);
+    #[export]
+    fn attack_animation_finished(&mut self, owner: &KinematicBody2D) {
+        self.attack_cooldown.start();
 
-    assert_eq!(player.velocity, Vector2::new(0.0, -1.0 * MAX_SPEED));
-}
+        if self.follow_up.finish_swing() {
+            let animation_tree =
+                unsafe { owner.get_typed_node::<AnimationTree, _>("AnimationTree") };
+            let playback_prop = animation_tree
+                .get("parameters/playback")
+                .try_to_object::<AnimationNodeStateMachinePlayback>()
+                .unwrap();
 
-#[ignore]
-#[test]
-fn test_move_diagonals() {
-    let mut player = Player::default();
+            // traveling to the node that's already playing wouldn't replay it
+            unsafe { playback_prop.assume_safe() }.start("Attack");
+            self.attack_recovering = false;
 
-    player.r#move(0.0, 1.0, 1.0, 0.0, 0.6);
-    player.r#move(0.0, 1.0, 1.0, 0.0, 0.6);
-    player.r#move(1.0, 0.0, 0.0, 1.0, 0.6);
+            return;
+        }
 
-    assert_eq!(player.velocity, Vector2::new(-4.242641, 4.242641));
+        self.set_state(State::Move);
+        self.adrenaline.timer.stop();
+
+        let held_input = self.get_movement_input(Input::godot_singleton());
+        let delta = settings::game_delta(owner, owner.get_process_delta_time() as f32);
+        self.resume_movement(held_input, delta);
+
+        if Input::godot_singleton().is_action_pressed(self.action("attack"))
+            && self.stamina.current > 0.0
+        {
+            self.start_spin(owner);
+        }
+    }
+
+    // the attack stopped the player, who moves on the held direction right
+    // away instead of having to press it again
+    fn resume_movement(&mut self, held_input: Vector2, delta: f32) {
+        if held_input == Vector2::zero() {
+            return;
+        }
+
+        self.velocity = held_input
+            * MAX_SPEED
+            * self.speed_multiplier_for(&State::Move)
+            * self.attack_exit_speed;
+        self.move_on_input(held_input, delta);
+    }
+
+    fn start_spin(&mut self, owner: &KinematicBody2D) {
+        let sword_hitbox_node =
+            unsafe { owner.get_typed_node::<Area2D, _>("HitboxPivot/SwordHitbox") };
+
+        let _ = sword_hitbox_node
+            .cast_instance::<SwordHitbox>()
+            .unwrap()
+            .map_mut(|sword_hitbox, _| {
+                self.spin.previous_hit_mode = sword_hitbox.set_hit_mode(HitMode::Continuous)
+            });
+
+        self.set_sword_enabled(owner, true);
+        self.set_state(State::Spin);
+    }
+
+    fn end_spin(&mut self, owner: &KinematicBody2D) {
+        let sword_hitbox_node =
+            unsafe { owner.get_typed_node::<Area2D, _>("HitboxPivot/SwordHitbox") };
+        let previous_hit_mode = self.spin.previous_hit_mode;
+
+        let _ = sword_hitbox_node
+            .cast_instance::<SwordHitbox>()
+            .unwrap()
+            .map_mut(|sword_hitbox, _| sword_hitbox.set_hit_mode(previous_hit_mode));
+
+        self.set_sword_enabled(owner, false);
+        self.set_state(State::Move);
+    }
+
+    // the attack animations toggle the sword themselves, the spin has none
+    fn set_sword_enabled(&self, owner: &KinematicBody2D, enabled: bool) {
+        let shape = unsafe {
+            owner.get_typed_node::<CollisionShape2D, _>("HitboxPivot/SwordHitbox/CollisionShape2D")
+        };
+
+        shape.set_deferred("disabled", !enabled);
+    }
+
+    #[export]
+    fn roll_animation_finished(&mut self, _owner: &KinematicBody2D) {
+        self.finish_roll();
+    }
+
+    fn finish_roll(&mut self) {
+        self.velocity = self.velocity * 0.8;
+
+        self.set_state(if self.dash_attack.buffered {
+            State::Attack
+        } else {
+            State::Move
+        });
+        self.dash_attack.buffered = false;
+
+        // rolls are faster than walking, and leaving one slides on until it
+        // slows down to walking
+        if let State::Move = self.state {
+            self.slide_speed = Some(self.velocity.length());
+        }
+    }
+
+    /// Called by the attack animations where the sword stops hitting, from
+    /// when the attack can be rolled out of.
+    #[export]
+    fn attack_recovery_started(&mut self, _owner: &KinematicBody2D) {
+        self.attack_recovering = true;
+    }
+
+    // only the recovery can be cut short, so the swing always lands
+    fn can_roll_cancel(&self) -> bool {
+        self.attack_roll_cancel && matches!(self.state, State::Attack) && self.attack_recovering
+    }
+
+    // what finishing the attack would have done, for rolling out of it
+    fn cancel_attack(&mut self) {
+        self.attack_cooldown.start();
+        self.adrenaline.timer.stop();
+        self.follow_up.buffered = false;
+    }
+
+    // every state change goes through here so the log sees it
+    fn set_state(&mut self, state: State) {
+        if std::mem::discriminant(&state) != std::mem::discriminant(&self.state) {
+            self.state_log.record(&state);
+
+            if matches!(state, State::Attack) {
+                self.attack_recovering = false;
+            }
+
+            self.slide_speed = None;
+
+            if self.holds_still(&state) {
+                self.velocity = Vector2::zero();
+            }
+        }
+
+        self.state = state;
+    }
+
+    /// The latest state changes with the time they came at, oldest first,
+    /// kept only with a `state_log_size`.
+    pub fn transition_history(&self) -> impl Iterator<Item = (f32, &'static str)> + '_ {
+        self.state_log.transitions.iter().copied()
+    }
+}
+
+/// Resolves an input action for a player, the first player uses the plain
+/// action and the others use it prefixed, like `p2_attack`.
+pub fn action_name(player_index: i64, action: &str) -> String {
+    if player_index <= 0 {
+        action.to_string()
+    } else {
+        format!("p{}_{}", player_index + 1, action)
+    }
+}
+
+/// Returns the sword pivot rotation after a frame of spinning, kept within a
+/// full turn.
+fn spin_rotation_step(rotation: f32, speed: f32, delta: f32) -> f32 {
+    (rotation + speed * delta).rem_euclid(TAU)
+}
+
+fn classify_dodge(roll_elapsed: f32) -> Dodge {
+    if roll_elapsed <= PERFECT_DODGE_WINDOW {
+        Dodge::Perfect
+    } else {
+        Dodge::Normal
+    }
+}
+
+fn input_vector_from_strengths(
+    right_strength: f32,
+    left_strength: f32,
+    down_strength: f32,
+    up_strength: f32,
+) -> Vector2 {
+    let mut input_vector = Vector2::zero();
+
+    input_vector.x = right_strength - left_strength;
+    input_vector.y = down_strength - up_strength;
+
+    input_vector.try_normalize().unwrap_or(input_vector)
+}
+
+/// Returns the closest grapple point in range that the aim is pointing at.
+fn find_grapple_point(
+    position: Vector2,
+    aim: Vector2,
+    grapple_points: &[Vector2],
+    grapple_range: f32,
+) -> Option<Vector2> {
+    grapple_points
+        .iter()
+        .copied()
+        .filter(|&point| position.distance_to(point) <= grapple_range)
+        .filter(|&point| {
+            (point - position)
+                .try_normalize()
+                .map_or(false, |direction| direction.dot(aim) >= GRAPPLE_AIM_FACING)
+        })
+        .min_by(|a, b| {
+            position
+                .distance_to(*a)
+                .partial_cmp(&position.distance_to(*b))
+                .unwrap()
+        })
+}
+
+/// Returns the velocity pulling towards the target, or `None` once this
+/// frame's pull would reach it.
+fn grapple_pull(position: Vector2, target: Vector2, speed: f32, delta: f32) -> Option<Vector2> {
+    let offset = target - position;
+
+    if offset.length() <= speed * delta {
+        return None;
+    }
+
+    offset.try_normalize().map(|direction| direction * speed)
+}
+
+fn apply_gravity(vertical_velocity: f32, gravity: f32, delta: f32) -> f32 {
+    vertical_velocity + gravity * delta
+}
+
+fn jump_velocity(jump_speed: f32) -> f32 {
+    -jump_speed
+}
+
+// caps the fall speed while pressed against a wall
+fn wall_slide(vertical_velocity: f32, wall_slide_speed: f32) -> f32 {
+    vertical_velocity.min(wall_slide_speed)
+}
+
+/// Returns the velocity of a jump off a wall, up and away from it.
+fn wall_jump_velocity(wall_normal: Vector2, jump_speed: f32, push: f32) -> Vector2 {
+    Vector2::new(wall_normal.x.signum() * push, -jump_speed)
+}
+
+/// Returns the knockback left after the resistance, which is kept between
+/// none and full.
+fn resisted_knockback(knockback: Vector2, resistance: f32) -> Vector2 {
+    knockback * (1.0 - resistance.max(0.0).min(1.0) * MAX_KNOCKBACK_REDUCTION)
+}
+
+fn should_auto_attack(
+    position: Vector2,
+    facing: Vector2,
+    enemy_positions: &[Vector2],
+    attack_cooldown: f32,
+) -> bool {
+    if attack_cooldown > 0.0 {
+        return false;
+    }
+
+    enemy_positions.iter().any(|&enemy_position| {
+        position.distance_to(enemy_position) <= AUTO_ATTACK_RANGE
+            && facing.dot(position.direction_to(enemy_position)) >= AUTO_ATTACK_FACING
+    })
+}
+
+#[test]
+fn test_auto_attack_enemy_in_range_and_facing() {
+    let enemies = [Vector2::new(16.0, 0.0)];
+
+    assert!(should_auto_attack(
+        Vector2::zero(),
+        Vector2::right(),
+        &enemies,
+        0.0
+    ));
+}
+
+#[test]
+fn test_auto_attack_enemy_out_of_range() {
+    let enemies = [Vector2::new(AUTO_ATTACK_RANGE + 1.0, 0.0)];
+
+    assert!(!should_auto_attack(
+        Vector2::zero(),
+        Vector2::right(),
+        &enemies,
+        0.0
+    ));
+}
+
+#[test]
+fn test_auto_attack_enemy_behind() {
+    let enemies = [Vector2::new(-16.0, 0.0)];
+
+    assert!(!should_auto_attack(
+        Vector2::zero(),
+        Vector2::right(),
+        &enemies,
+        0.0
+    ));
+}
+
+#[test]
+fn test_auto_attack_on_cooldown() {
+    let enemies = [Vector2::new(16.0, 0.0)];
+
+    assert!(!should_auto_attack(
+        Vector2::zero(),
+        Vector2::right(),
+        &enemies,
+        0.2
+    ));
+}
+
+#[test]
+fn test_parry_window_opens_and_closes() {
+    let mut player = Player::default();
+
+    assert!(!player.is_parrying());
+
+    player.parry_window.start(PARRY_WINDOW);
+
+    assert!(player.is_parrying());
+}
+
+#[test]
+fn test_lifesteal_disabled() {
+    let mut player = Player::default();
+
+    assert_eq!(player.lifesteal_heal(4), 0);
+}
+
+#[test]
+fn test_lifesteal_heal_from_damage() {
+    let mut player = Player {
+        lifesteal: 0.5,
+        ..Default::default()
+    };
+
+    assert_eq!(player.lifesteal_heal(4), 2);
+}
+
+#[test]
+fn test_lifesteal_builds_up_over_small_hits() {
+    let mut player = Player {
+        lifesteal: 0.25,
+        ..Default::default()
+    };
+
+    let heals: Vec<i32> = (0..4).map(|_| player.lifesteal_heal(1)).collect();
+
+    assert_eq!(heals, vec![0, 0, 0, 1]);
+}
+
+#[test]
+fn test_thorns_disabled() {
+    let player = Player::default();
+
+    assert_eq!(player.thorns_damage(3), 0);
+}
+
+#[test]
+fn test_thorns_retaliation_damage() {
+    let player = Player {
+        thorns: 0.5,
+        ..Default::default()
+    };
+
+    assert_eq!(player.thorns_damage(2), 1);
+}
+
+#[test]
+fn test_block_reflects_part_of_blocked_damage() {
+    let block = Block {
+        damage_reduction: 0.5,
+        reflect: 0.5,
+        is_blocking: true,
+    };
+
+    assert_eq!(block.block_hit(4), (2, 1));
+}
+
+#[test]
+fn test_full_block_without_reflect() {
+    let block = Block::default();
+
+    assert_eq!(block.block_hit(3), (0, 0));
+}
+
+/// Runs the inputs, each with the frame's delta, through a fresh player's
+/// movement one frame at a time, returning the velocity it ends with.
+#[cfg(test)]
+fn simulate_movement(inputs: &[(Vector2, f32)]) -> Vector2 {
+    let mut player = Player::default();
+
+    for (input_vector, delta) in inputs {
+        player.move_on_input(*input_vector, *delta);
+    }
+
+    player.velocity
+}
+
+#[cfg(test)]
+fn frames(input_vector: Vector2, count: usize) -> Vec<(Vector2, f32)> {
+    // a delta exact in binary, so each frame moves the speed by 31.25
+    vec![(input_vector, 0.0625); count]
+}
+
+#[test]
+fn test_move_nothing() {
+    let mut player = Player::default();
+
+    player.r#move(0.0, 0.0, 0.0, 0.0, 0.6);
+
+    assert_eq!(player.velocity, Vector2::zero());
+}
+
+#[test]
+fn test_move_right() {
+    let mut player = Player::default();
+
+    player.r#move(1.0, 0.0, 0.0, 0.0, 0.6);
+
+    assert_eq!(player.velocity, Vector2::new(1.0 * MAX_SPEED, 0.0));
+}
+
+#[test]
+fn test_move_left() {
+    let mut player = Player::default();
+
+    player.r#move(0.0, 1.0, 0.0, 0.0, 0.6);
+
+    assert_eq!(player.velocity, Vector2::new(-1.0 * MAX_SPEED, 0.0));
+}
+
+#[test]
+fn test_move_down() {
+    let mut player = Player::default();
+
+    player.r#move(0.0, 0.0, 1.0, 0.0, 0.6);
+
+    assert_eq!(player.velocity, Vector2::new(0.0, 1.0 * MAX_SPEED));
+}
+
+#[test]
+fn test_move_up() {
+    let mut player = Player::default();
+
+    player.r#move(0.0, 0.0, 0.0, 1.0, 0.6);
+
+    assert_eq!(player.velocity, Vector2::new(0.0, -1.0 * MAX_SPEED));
+}
+
+#[test]
+fn test_default_speed_multipliers() {
+    let player = Player::default();
+
+    assert_eq!(player.speed_multiplier_for(&State::Move), 1.0);
+    assert_eq!(player.speed_multiplier_for(&State::Attack), 1.0);
+    assert_eq!(player.speed_multiplier_for(&State::Roll), 1.0);
+}
+
+#[test]
+fn test_move_speed_multiplier_applied() {
+    let mut player = Player::default();
+    player.speed_multipliers.moving = 0.5;
+
+    player.r#move(1.0, 0.0, 0.0, 0.0, 0.6);
+
+    assert_eq!(player.velocity, Vector2::new(0.5 * MAX_SPEED, 0.0));
+}
+
+#[test]
+fn test_roll_speed_multiplier_applied() {
+    let mut player = Player {
+        last_direction: Some(Vector2::right()),
+        ..Default::default()
+    };
+    player.speed_multipliers.rolling = 1.5;
+
+    player.roll();
+
+    assert_eq!(player.velocity, Vector2::new(1.5 * ROLL_SPEED, 0.0));
+}
+
+#[test]
+fn test_early_roll_attack_is_dash_attack() {
+    let dash_attack = DashAttack::default();
+
+    assert_eq!(dash_attack.classify(0.0), RollAttack::Dash);
+    assert_eq!(dash_attack.classify(0.25), RollAttack::Dash);
+}
+
+#[test]
+fn test_late_roll_attack_is_buffered() {
+    let dash_attack = DashAttack::default();
+
+    assert_eq!(dash_attack.classify(0.3), RollAttack::Buffered);
+    assert_eq!(dash_attack.classify(0.5), RollAttack::Buffered);
+}
+
+#[test]
+fn test_zero_dash_attack_window_buffers_every_attack() {
+    let dash_attack = DashAttack {
+        window: 0.0,
+        ..DashAttack::default()
+    };
+
+    assert_eq!(dash_attack.classify(0.05), RollAttack::Buffered);
+}
+
+#[test]
+fn test_taunt_emote_boosts_taunt() {
+    let mut player = Player {
+        taunt: 8.0,
+        ..Default::default()
+    };
+
+    assert_eq!(player.taunt_weight(), 8.0);
+
+    player.taunt_emote.fade.start();
+
+    assert_eq!(player.taunt_weight(), 72.0);
+}
+
+#[test]
+fn test_taunt_emote_boost_decays() {
+    let mut player = Player::default();
+
+    player.taunt_emote.fade.start();
+    player.taunt_emote.fade.tick(1.5);
+    assert_eq!(player.taunt_weight(), 32.0);
+
+    player.taunt_emote.fade.tick(1.5);
+    assert_eq!(player.taunt_weight(), 0.0);
+}
+
+#[test]
+fn test_momentum_builds_with_same_direction_rolls() {
+    let mut momentum = Momentum::default();
+
+    momentum.start_roll(Vector2::right());
+    assert_eq!(momentum.multiplier(), 1.0);
+
+    momentum.start_roll(Vector2::right());
+    assert_eq!(momentum.multiplier(), 1.1);
+
+    momentum.start_roll(Vector2::right());
+    assert_eq!(momentum.multiplier(), 1.15);
+}
+
+#[test]
+fn test_momentum_is_capped() {
+    let mut momentum = Momentum {
+        max_bonus: 0.12,
+        ..Momentum::default()
+    };
+
+    for _ in 0..10 {
+        momentum.start_roll(Vector2::right());
+    }
+
+    assert_eq!(momentum.multiplier(), 1.12);
+}
+
+#[test]
+fn test_momentum_resets_on_direction_change() {
+    let mut momentum = Momentum::default();
+
+    momentum.start_roll(Vector2::right());
+    momentum.start_roll(Vector2::right());
+    momentum.start_roll(Vector2::down());
+
+    assert_eq!(momentum.multiplier(), 1.0);
+}
+
+#[ignore]
+#[test]
+fn test_move_diagonals() {
+    let mut player = Player::default();
+
+    player.r#move(0.0, 1.0, 1.0, 0.0, 0.6);
+    player.r#move(0.0, 1.0, 1.0, 0.0, 0.6);
+    player.r#move(1.0, 0.0, 0.0, 1.0, 0.6);
+
+    assert_eq!(player.velocity, Vector2::new(-4.242641, 4.242641));
+}
+
+#[test]
+fn test_ease_in_starts_slower_than_linear() {
+    let mut linear = Player::default();
+    let mut ease_in = Player {
+        acceleration_mode: AccelerationMode::EaseIn,
+        ..Player::default()
+    };
+
+    linear.r#move(1.0, 0.0, 0.0, 0.0, 0.016);
+    ease_in.r#move(1.0, 0.0, 0.0, 0.0, 0.016);
+
+    assert!(ease_in.velocity.x < linear.velocity.x);
+}
+
+#[test]
+fn test_huge_delta_keeps_movement_bounded() {
+    let settings = Settings::default();
+    let mut player = Player::default();
+    let delta = settings.clamp_delta(5.0);
+
+    player.r#move(1.0, 0.0, 0.0, 0.0, delta);
+
+    let distance = player.velocity.length() * delta;
+
+    assert!(distance <= MAX_SPEED * settings.max_delta);
+}
+
+#[test]
+fn test_find_grapple_point_in_aim() {
+    let points = [
+        Vector2::new(0.0, 40.0),
+        Vector2::new(80.0, 0.0),
+        Vector2::new(40.0, 0.0),
+    ];
+
+    assert_eq!(
+        find_grapple_point(Vector2::zero(), Vector2::right(), &points, 96.0),
+        Some(Vector2::new(40.0, 0.0))
+    );
+}
+
+#[test]
+fn test_find_grapple_point_out_of_range() {
+    let points = [Vector2::new(200.0, 0.0)];
+
+    assert_eq!(
+        find_grapple_point(Vector2::zero(), Vector2::right(), &points, 96.0),
+        None
+    );
+}
+
+#[test]
+fn test_grapple_pulls_towards_target() {
+    let velocity = grapple_pull(Vector2::zero(), Vector2::new(0.0, -100.0), 200.0, 0.1);
+
+    assert_eq!(velocity, Some(Vector2::new(0.0, -200.0)));
+}
+
+#[test]
+fn test_grapple_arrives_within_one_frame() {
+    let velocity = grapple_pull(Vector2::zero(), Vector2::new(10.0, 0.0), 200.0, 0.1);
+
+    assert_eq!(velocity, None);
+}
+
+#[test]
+fn test_wall_jump_off_left_wall() {
+    let velocity = wall_jump_velocity(Vector2::new(1.0, 0.0), 200.0, 120.0);
+
+    assert_eq!(velocity, Vector2::new(120.0, -200.0));
+}
+
+#[test]
+fn test_wall_jump_off_right_wall() {
+    let velocity = wall_jump_velocity(Vector2::new(-1.0, 0.0), 200.0, 120.0);
+
+    assert_eq!(velocity, Vector2::new(-120.0, -200.0));
+}
+
+#[test]
+fn test_wall_slide_caps_fall_speed() {
+    assert_eq!(wall_slide(300.0, 40.0), 40.0);
+    // moving up the wall is left alone
+    assert_eq!(wall_slide(-100.0, 40.0), -100.0);
+}
+
+#[test]
+fn test_gravity_integration() {
+    let velocity = (0..4).fold(0.0, |velocity, _| apply_gravity(velocity, 600.0, 0.25));
+
+    assert_eq!(velocity, 600.0);
+}
+
+#[test]
+fn test_jump_velocity_points_up() {
+    assert_eq!(jump_velocity(220.0), -220.0);
+}
+
+#[test]
+fn test_platformer_input_keeps_vertical_velocity() {
+    let mut player = Player::default();
+    player.platforming.mode = MovementMode::Platformer;
+    player.velocity = Vector2::new(0.0, jump_velocity(220.0));
+
+    player.r#move(1.0, 0.0, 0.0, 0.0, 0.016);
+
+    assert_eq!(player.velocity.y, -220.0);
+    assert!(player.velocity.x > 0.0);
+}
+
+#[test]
+fn test_first_player_uses_plain_actions() {
+    assert_eq!(action_name(0, "attack"), "attack");
+    assert_eq!(action_name(0, "ui_left"), "ui_left");
+}
+
+#[test]
+fn test_second_player_uses_prefixed_actions() {
+    assert_eq!(action_name(1, "attack"), "p2_attack");
+    assert_eq!(action_name(1, "ui_left"), "p2_ui_left");
+}
+
+#[test]
+fn test_off_screen_player_takes_no_damage() {
+    let player = Player {
+        off_screen_invulnerable: true,
+        ..Player::default()
+    };
+
+    assert!(player.damage_allowed(true));
+    assert!(!player.damage_allowed(false));
+}
+
+#[test]
+fn test_off_screen_damage_when_not_gated() {
+    let player = Player::default();
+
+    assert!(player.damage_allowed(false));
+}
+
+#[test]
+fn test_impact_drag_damps_velocity() {
+    let mut player = Player {
+        velocity: Vector2::new(80.0, 0.0),
+        ..Player::default()
+    };
+
+    player.apply_impact_drag(0.5);
+
+    assert_eq!(player.velocity, Vector2::new(40.0, 0.0));
+    assert_eq!(player.speed_multiplier_for(&State::Move), 0.5);
+}
+
+#[test]
+fn test_impact_drag_wears_off() {
+    let mut player = Player::default();
+
+    player.apply_impact_drag(0.5);
+    player.impact_drag.expiry.tick(IMPACT_DRAG_DURATION);
+
+    assert_eq!(player.speed_multiplier_for(&State::Move), 1.0);
+}
+
+#[test]
+fn test_perfect_dodge_within_window() {
+    assert_eq!(classify_dodge(0.0), Dodge::Perfect);
+    assert_eq!(classify_dodge(PERFECT_DODGE_WINDOW), Dodge::Perfect);
+}
+
+#[test]
+fn test_normal_dodge_after_window() {
+    assert_eq!(classify_dodge(0.3), Dodge::Normal);
+}
+
+#[test]
+fn test_finisher_needs_combo_threshold() {
+    let player = Player::default();
+    let mut player_stats = PlayerStats {
+        combo: 9,
+        ..PlayerStats::default()
+    };
+
+    assert!(!player.try_finisher(&mut player_stats));
+    assert_eq!(player_stats.combo, 9);
+}
+
+#[test]
+fn test_finisher_resets_combo() {
+    let player = Player::default();
+    let mut player_stats = PlayerStats {
+        combo: 12,
+        ..PlayerStats::default()
+    };
+
+    assert!(player.try_finisher(&mut player_stats));
+    assert_eq!(player_stats.combo, 0);
+}
+
+#[test]
+fn test_spin_rotation_step_advances_pivot() {
+    let rotation = spin_rotation_step(1.0, 12.0, 0.1);
+
+    assert!((rotation - 2.2).abs() < 1e-5);
+}
+
+#[test]
+fn test_spin_rotation_step_wraps_around() {
+    let rotation = spin_rotation_step(6.0, 12.0, 0.1);
+
+    assert!((rotation - (7.2 - TAU)).abs() < 1e-5);
+}
+
+#[test]
+fn test_spin_ends_when_stamina_is_empty() {
+    let spin = Spin::default();
+    let mut stamina = Stamina {
+        current: 10.0,
+        ..Stamina::default()
+    };
+
+    assert!(!stamina.spend(spin.drain * 0.1));
+    assert!(stamina.spend(spin.drain * 0.1));
+    assert_eq!(stamina.current, 0.0);
+}
+
+#[test]
+fn test_dodge_costs_stamina_when_affordable() {
+    assert_eq!(dodge_cost(30.0, 25.0, true), DodgeCost::Stamina);
+    assert_eq!(dodge_cost(25.0, 25.0, false), DodgeCost::Stamina);
+}
+
+#[test]
+fn test_emergency_dodge_costs_health_without_stamina() {
+    assert_eq!(dodge_cost(10.0, 25.0, true), DodgeCost::Health);
+    assert_eq!(dodge_cost(10.0, 25.0, false), DodgeCost::Unaffordable);
+}
+
+#[test]
+fn test_dodge_grants_adrenaline() {
+    let mut adrenaline = Adrenaline::default();
+
+    assert_eq!(adrenaline.attack_speed(), 1.0);
+
+    adrenaline.grant();
+
+    assert_eq!(adrenaline.attack_speed(), 1.5);
+}
+
+#[test]
+fn test_adrenaline_expires_after_window() {
+    let mut adrenaline = Adrenaline::default();
+    adrenaline.grant();
+
+    adrenaline.tick(0.5);
+
+    assert_eq!(adrenaline.attack_speed(), 1.5);
+
+    adrenaline.tick(0.5);
+
+    assert_eq!(adrenaline.attack_speed(), 1.0);
+}
+
+#[test]
+fn test_follow_up_chains_without_hit_confirm() {
+    let mut follow_up = FollowUp {
+        buffered: true,
+        ..FollowUp::default()
+    };
+
+    assert!(follow_up.finish_swing());
+    assert!(!follow_up.finish_swing());
+}
+
+#[test]
+fn test_follow_up_needs_a_hit_when_confirming() {
+    let mut follow_up = FollowUp {
+        require_hit_confirm: true,
+        buffered: true,
+        ..FollowUp::default()
+    };
+
+    assert!(!follow_up.finish_swing());
+
+    follow_up.buffered = true;
+    follow_up.confirmed = true;
+
+    assert!(follow_up.finish_swing());
+}
+
+#[test]
+fn test_parried_attack_staggers_player() {
+    let mut player = Player {
+        state: State::Attack,
+        parried_stagger_duration: 0.6,
+        ..Player::default()
+    };
+    player.follow_up.buffered = true;
+
+    player.start_stagger();
+
+    assert!(matches!(player.state, State::Staggered(_)));
+    assert!(!player.follow_up.buffered);
+
+    player.tick_stagger(0.3);
+
+    assert!(matches!(player.state, State::Staggered(_)));
+
+    player.tick_stagger(0.3);
+
+    assert!(matches!(player.state, State::Move));
+}
+
+#[test]
+fn test_accelerates_to_max_then_friction_stops() {
+    let speed_after = |pressed, released| {
+        let inputs = [
+            frames(Vector2::right(), pressed),
+            frames(Vector2::zero(), released),
+        ];
+
+        simulate_movement(&inputs.concat()).x
+    };
+
+    assert_eq!(speed_after(1, 0), 31.25);
+    assert_eq!(speed_after(2, 0), 62.5);
+    assert_eq!(speed_after(3, 0), MAX_SPEED);
+    assert_eq!(speed_after(10, 0), MAX_SPEED);
+    assert_eq!(speed_after(10, 1), 48.75);
+    assert_eq!(speed_after(10, 2), 17.5);
+    assert_eq!(speed_after(10, 3), 0.0);
+    assert_eq!(speed_after(10, 10), 0.0);
+}
+
+#[test]
+fn test_stop_start_picks_up_from_the_slowed_speed() {
+    let inputs = [
+        frames(Vector2::right(), 2),
+        frames(Vector2::zero(), 1),
+        frames(Vector2::right(), 1),
+    ];
+
+    assert_eq!(simulate_movement(&inputs.concat()), Vector2::new(62.5, 0.0));
+}
+
+#[test]
+fn test_reversing_slows_down_before_turning() {
+    let inputs = [frames(Vector2::right(), 10), frames(Vector2::left(), 1)];
+
+    assert_eq!(
+        simulate_movement(&inputs.concat()),
+        Vector2::new(48.75, 0.0)
+    );
+}
+
+#[test]
+fn test_diagonal_movement_caps_at_max_speed() {
+    let diagonal = Vector2::new(1.0, 1.0).normalize();
+
+    let velocity = simulate_movement(&frames(diagonal, 10));
+
+    assert!((velocity.length() - MAX_SPEED).abs() < 0.001);
+    assert!((velocity.x - velocity.y).abs() < 0.001);
+}
+
+#[cfg(test)]
+fn logged_states(player: &Player) -> Vec<&'static str> {
+    player
+        .transition_history()
+        .map(|(_, state)| state)
+        .collect()
+}
+
+#[test]
+fn test_state_log_records_changes_in_order() {
+    let mut player = Player::default();
+    player.state_log.capacity = 8;
+
+    player.set_state(State::Attack);
+    player.state_log.time = 0.5;
+    player.set_state(State::Move);
+    player.state_log.time = 0.75;
+    player.set_state(State::Roll);
+    player.dash_attack.buffered = true;
+    player.finish_roll();
+
+    assert_eq!(logged_states(&player), ["attack", "move", "roll", "attack"]);
+    assert_eq!(
+        player
+            .transition_history()
+            .map(|(time, _)| time)
+            .collect::<Vec<_>>(),
+        [0.0, 0.5, 0.75, 0.75]
+    );
+}
+
+#[test]
+fn test_state_log_skips_staying_in_a_state() {
+    let mut player = Player {
+        parried_stagger_duration: 0.5,
+        ..Player::default()
+    };
+    player.state_log.capacity = 8;
+
+    player.start_stagger();
+    player.tick_stagger(0.25);
+    player.tick_stagger(0.25);
+    player.set_state(State::Move);
+
+    assert_eq!(logged_states(&player), ["staggered", "move"]);
+}
+
+#[test]
+fn test_state_log_keeps_only_the_latest() {
+    let mut player = Player::default();
+    player.state_log.capacity = 2;
+
+    player.set_state(State::Attack);
+    player.set_state(State::Move);
+    player.set_state(State::Grapple);
+    player.release_grapple();
+
+    assert_eq!(logged_states(&player), ["grapple", "move"]);
+}
+
+#[test]
+fn test_state_log_is_off_by_default() {
+    let mut player = Player::default();
+
+    player.set_state(State::Attack);
+
+    assert_eq!(player.transition_history().count(), 0);
+}
+
+#[test]
+fn test_rolling_into_moving_is_clamped_to_max_speed() {
+    let mut player = Player::default();
+
+    player.set_state(State::Roll);
+    player.last_direction = Some(Vector2::right());
+    player.roll();
+    player.set_state(State::Move);
+    player.move_on_input(Vector2::right(), 0.016);
+
+    assert_eq!(
+        player.clamp_to_max_speed(),
+        Some(ROLL_SPEED - 500.0 * 0.016)
+    );
+    assert_eq!(player.velocity, Vector2::new(MAX_SPEED, 0.0));
+}
+
+#[test]
+fn test_sliding_out_of_a_roll_is_not_clamped() {
+    let mut player = Player::default();
+
+    player.set_state(State::Roll);
+    player.last_direction = Some(Vector2::right());
+    player.roll();
+    player.finish_roll();
+
+    assert_eq!(player.clamp_to_max_speed(), None);
+    assert_eq!(player.velocity, Vector2::new(ROLL_SPEED * 0.8, 0.0));
+
+    // once it's slowed down to walking, it's clamped again
+    player.move_on_input(Vector2::right(), 1.0);
+
+    assert_eq!(player.clamp_to_max_speed(), None);
+    assert_eq!(player.velocity, Vector2::new(MAX_SPEED, 0.0));
+
+    player.velocity = Vector2::new(ROLL_SPEED, 0.0);
+
+    assert_eq!(player.clamp_to_max_speed(), Some(ROLL_SPEED));
+}
+
+#[test]
+fn test_attacking_holds_the_player_still() {
+    for state in vec![State::Attack, State::Spin, State::Staggered(1.0)] {
+        let mut player = Player::default();
+
+        player.move_on_input(Vector2::right(), 10.0);
+        player.set_state(state);
+
+        assert_eq!(player.clamp_to_max_speed(), None);
+        assert_eq!(player.velocity, Vector2::zero());
+
+        player.velocity = Vector2::new(1.0, 0.0);
+
+        assert_eq!(player.clamp_to_max_speed(), Some(1.0));
+        assert_eq!(player.velocity, Vector2::zero());
+    }
+}
+
+#[test]
+fn test_overshooting_diagonal_is_clamped_keeping_its_direction() {
+    let mut player = Player {
+        velocity: Vector2::new(300.0, -400.0),
+        ..Player::default()
+    };
+
+    assert_eq!(player.clamp_to_max_speed(), Some(500.0));
+    assert!((player.velocity.length() - MAX_SPEED).abs() < 0.001);
+    assert!((player.velocity - Vector2::new(48.0, -64.0)).length() < 0.001);
+}
+
+#[test]
+fn test_speed_within_max_is_left_alone() {
+    let mut player = Player::default();
+
+    player.move_on_input(Vector2::right(), 10.0);
+
+    assert_eq!(player.clamp_to_max_speed(), None);
+    assert_eq!(player.velocity, Vector2::new(MAX_SPEED, 0.0));
+}
+
+#[test]
+fn test_platformer_jumps_are_not_clamped() {
+    let mut player = Player {
+        velocity: wall_jump_velocity(Vector2::new(1.0, 0.0), 200.0, 120.0),
+        ..Player::default()
+    };
+    player.platforming.mode = MovementMode::Platformer;
+
+    assert_eq!(player.clamp_to_max_speed(), None);
+}
+
+#[test]
+fn test_attacking_after_moving_left_faces_left() {
+    let mut player = Player::default();
+
+    player.move_on_input(Vector2::left(), 0.016);
+    player.set_state(State::Attack);
+
+    assert_eq!(player.facing(), Vector2::left());
+}
+
+#[test]
+fn test_attacking_after_idling_faces_the_last_movement() {
+    let mut player = Player::default();
+
+    player.move_on_input(Vector2::up(), 0.016);
+    for _ in 0..60 {
+        player.move_on_input(Vector2::zero(), 0.016);
+    }
+    player.set_state(State::Attack);
+    player.set_state(State::Move);
+
+    assert_eq!(player.velocity, Vector2::zero());
+    assert_eq!(player.facing(), Vector2::up());
+    assert_eq!(player.direction(), Vector2::up());
+}
+
+#[test]
+fn test_twin_stick_aim_faces_the_stick_until_released() {
+    let mut player = Player {
+        control_scheme: ControlScheme::TwinStick,
+        ..Player::default()
+    };
+
+    player.move_on_input(Vector2::left(), 0.016);
+    player.aim_input = Vector2::down();
+
+    assert_eq!(player.facing(), Vector2::down());
+
+    player.aim_input = Vector2::zero();
+
+    assert_eq!(player.facing(), Vector2::left());
+}
+
+#[test]
+fn test_first_idle_roll_goes_down() {
+    let mut player = Player::default();
+
+    player.roll();
+
+    assert_eq!(player.velocity, Vector2::down() * ROLL_SPEED);
+}
+
+#[test]
+fn test_idle_roll_goes_the_last_way_moved() {
+    for direction in [
+        Vector2::up(),
+        Vector2::down(),
+        Vector2::left(),
+        Vector2::right(),
+    ]
+    .iter()
+    {
+        let mut player = Player::default();
+
+        player.move_on_input(*direction, 0.016);
+        for _ in 0..10 {
+            player.move_on_input(Vector2::zero(), 0.016);
+        }
+        player.set_state(State::Roll);
+        player.roll();
+
+        assert_eq!(player.velocity, *direction * ROLL_SPEED);
+    }
+}
+
+#[test]
+fn test_roll_cancel_is_rejected_during_the_swing() {
+    let mut player = Player {
+        attack_roll_cancel: true,
+        ..Player::default()
+    };
+
+    player.set_state(State::Attack);
+
+    // the windup and the sword's active frames come before the recovery key
+    assert!(!player.can_roll_cancel());
+}
+
+#[test]
+fn test_roll_cancel_is_allowed_in_the_recovery() {
+    let mut player = Player {
+        attack_roll_cancel: true,
+        ..Player::default()
+    };
+
+    player.set_state(State::Attack);
+    player.attack_recovering = true;
+
+    assert!(player.can_roll_cancel());
+}
+
+#[test]
+fn test_next_attack_starts_outside_the_recovery() {
+    let mut player = Player {
+        attack_roll_cancel: true,
+        ..Player::default()
+    };
+
+    player.set_state(State::Attack);
+    player.attack_recovering = true;
+    player.set_state(State::Move);
+    player.set_state(State::Attack);
+
+    assert!(!player.can_roll_cancel());
+}
+
+#[test]
+fn test_roll_cancel_needs_enabling() {
+    let mut player = Player::default();
+
+    player.set_state(State::Attack);
+    player.attack_recovering = true;
+
+    assert!(!player.can_roll_cancel());
+}
+
+#[test]
+fn test_input_is_raw_without_smoothing() {
+    let mut player = Player::default();
+
+    assert_eq!(
+        player.smooth_input(Vector2::right(), 0.016),
+        Vector2::right()
+    );
+    assert_eq!(player.smooth_input(Vector2::left(), 0.016), Vector2::left());
+}
+
+#[test]
+fn test_smoothed_input_approaches_the_raw_input() {
+    let mut player = Player {
+        input_smoothing: 0.1,
+        ..Player::default()
+    };
+
+    // a quarter of the way there each frame
+    let first = player.smooth_input(Vector2::right(), 0.025);
+    let second = player.smooth_input(Vector2::right(), 0.025);
+
+    assert!((first.x - 0.25).abs() < 0.001);
+    assert!((second.x - 0.4375).abs() < 0.001);
+
+    for _ in 0..30 {
+        player.smooth_input(Vector2::right(), 0.025);
+    }
+
+    assert_eq!(
+        player.smooth_input(Vector2::right(), 0.025),
+        Vector2::right()
+    );
+}
+
+#[test]
+fn test_smoothed_input_turns_gradually() {
+    let mut player = Player {
+        input_smoothing: 0.1,
+        ..Player::default()
+    };
+
+    player.smooth_input(Vector2::right(), 1.0);
+    let turning = player.smooth_input(Vector2::down(), 0.05);
+
+    assert!((turning - Vector2::new(0.5, 0.5)).length() < 0.001);
+}
+
+#[test]
+fn test_smoothed_input_comes_to_a_stop() {
+    let mut player = Player {
+        input_smoothing: 0.1,
+        ..Player::default()
+    };
+
+    player.smooth_input(Vector2::right(), 1.0);
+
+    for _ in 0..30 {
+        player.smooth_input(Vector2::zero(), 0.025);
+    }
+
+    assert_eq!(player.smooth_input(Vector2::zero(), 0.025), Vector2::zero());
+}
+
+#[test]
+fn test_attack_ends_moving_in_the_held_direction() {
+    let mut player = Player::default();
+
+    player.move_on_input(Vector2::right(), 0.016);
+    player.set_state(State::Attack);
+    player.set_state(State::Move);
+    player.resume_movement(Vector2::left(), 0.016);
+
+    assert_eq!(player.velocity, Vector2::new(-ACCELERATION * 0.016, 0.0));
+    assert_eq!(player.facing(), Vector2::left());
+}
+
+#[test]
+fn test_attack_exit_speed_is_a_head_start() {
+    let mut player = Player {
+        attack_exit_speed: 0.5,
+        ..Player::default()
+    };
+
+    player.resume_movement(Vector2::down(), 0.016);
+
+    assert_eq!(
+        player.velocity,
+        Vector2::new(0.0, MAX_SPEED * 0.5 + ACCELERATION * 0.016)
+    );
+}
+
+#[test]
+fn test_attack_exit_never_passes_the_max_speed() {
+    let mut player = Player {
+        attack_exit_speed: 1.0,
+        ..Player::default()
+    };
+
+    player.resume_movement(Vector2::down(), 0.016);
+
+    assert_eq!(player.velocity, Vector2::new(0.0, MAX_SPEED));
+}
+
+#[test]
+fn test_attack_ends_standing_still_without_held_input() {
+    let mut player = Player {
+        attack_exit_speed: 1.0,
+        last_direction: Some(Vector2::up()),
+        ..Player::default()
+    };
+
+    player.resume_movement(Vector2::zero(), 0.016);
+
+    assert_eq!(player.velocity, Vector2::zero());
+    assert_eq!(player.facing(), Vector2::up());
+}
+
+#[test]
+fn test_no_resistance_takes_the_full_knockback() {
+    let knockback = Vector2::new(80.0, 0.0);
+
+    assert_eq!(resisted_knockback(knockback, 0.0), knockback);
+}
+
+#[test]
+fn test_resistance_reduces_knockback() {
+    let knockback = Vector2::new(0.0, -80.0);
+
+    assert!((resisted_knockback(knockback, 0.5).y - -44.0).abs() < 0.001);
+    assert!((resisted_knockback(knockback, 0.25).y - -62.0).abs() < 0.001);
+}
+
+#[test]
+fn test_full_resistance_barely_budges() {
+    let knockback = Vector2::new(80.0, 0.0);
+
+    assert!((resisted_knockback(knockback, 1.0).x - 8.0).abs() < 0.001);
+    // resistance past full doesn't pull the player in
+    assert_eq!(
+        resisted_knockback(knockback, 2.0),
+        resisted_knockback(knockback, 1.0)
+    );
+}
+
+#[test]
+fn test_knockback_adds_to_the_movement() {
+    let mut player = Player::default();
+
+    player.move_on_input(Vector2::right(), 10.0);
+    player.knock_back(Vector2::new(60.0, 0.0));
+
+    assert_eq!(player.velocity, Vector2::new(MAX_SPEED + 60.0, 0.0));
+    // the knockback slows down to walking like a slide out of a roll
+    assert_eq!(player.clamp_to_max_speed(), None);
+}
+
+#[test]
+fn test_attacking_takes_no_knockback() {
+    let mut player = Player::default();
+
+    player.set_state(State::Attack);
+    player.knock_back(Vector2::new(60.0, 0.0));
+
+    assert_eq!(player.velocity, Vector2::zero());
+}
+
+#[test]
+fn test_first_frame_after_entering_ignores_presses() {
+    let mut guard = InputGuard::default();
+
+    guard.reset();
+    guard.tick();
+
+    assert!(guard.guarding);
+
+    guard.tick();
+
+    assert!(!guard.guarding);
+}
+
+#[test]
+fn test_input_guard_covers_its_frames() {
+    let mut guard = InputGuard {
+        frames: 3,
+        ..InputGuard::default()
+    };
+
+    guard.reset();
+
+    let guarded: Vec<bool> = (0..5)
+        .map(|_| {
+            guard.tick();
+            guard.guarding
+        })
+        .collect();
+
+    assert_eq!(guarded, vec![true, true, true, false, false]);
+}
+
+#[test]
+fn test_entering_again_guards_again() {
+    let mut guard = InputGuard::default();
+
+    guard.reset();
+    guard.tick();
+    guard.tick();
+    guard.reset();
+    guard.tick();
+
+    assert!(guard.guarding);
+}
+
+#[test]
+fn test_no_guard_frames_processes_the_first_frame() {
+    let mut guard = InputGuard {
+        frames: 0,
+        ..InputGuard::default()
+    };
+
+    guard.reset();
+    guard.tick();
+
+    assert!(!guard.guarding);
 }
@@ -0,0 +1,277 @@
+use crate::entity_registry;
+use crate::tween::{ease_out, Tween};
+use gdnative::api::{Camera2D, Engine};
+use gdnative::prelude::{ClassBuilder, NativeClass, Node2D, Rect2, TRef, Vector2};
+
+// seconds the camera takes to move onto a focus
+const FOCUS_DURATION: f32 = 0.3;
+
+/// A position and zoom the camera eases onto from wherever it was.
+struct Focus {
+    position: Vector2,
+    zoom: f32,
+    // where the camera was when the focus started, taken on the first
+    // frame after it's set
+    from: Option<(Vector2, f32)>,
+    tween: Tween,
+}
+
+#[derive(NativeClass)]
+#[inherit(Camera2D)]
+#[register_with(register_properties)]
+pub struct PlayerCamera {
+    deadzone_width: f32,
+    deadzone_height: f32,
+    // path to a second node kept in view along with the player, like a boss
+    frame_target: String,
+    // padding kept between the framed targets and the screen edges
+    frame_margin: f32,
+    // overrides the framing while set, like for a dramatic kill
+    focus: Option<Focus>,
+}
+
+fn register_properties(builder: &ClassBuilder<PlayerCamera>) {
+    builder
+        .add_property::<f32>("deadzone_width")
+        .with_default(32.0)
+        .with_getter(|camera: &PlayerCamera, _| camera.deadzone_width)
+        .with_setter(|camera: &mut PlayerCamera, _, width| camera.deadzone_width = width)
+        .done();
+    builder
+        .add_property::<f32>("deadzone_height")
+        .with_default(24.0)
+        .with_getter(|camera: &PlayerCamera, _| camera.deadzone_height)
+        .with_setter(|camera: &mut PlayerCamera, _, height| camera.deadzone_height = height)
+        .done();
+    builder
+        .add_property::<String>("frame_target")
+        .with_ref_getter(|camera: &PlayerCamera, _| &camera.frame_target)
+        .with_setter(|camera: &mut PlayerCamera, _, target| camera.frame_target = target)
+        .done();
+    builder
+        .add_property::<f32>("frame_margin")
+        .with_default(32.0)
+        .with_getter(|camera: &PlayerCamera, _| camera.frame_margin)
+        .with_setter(|camera: &mut PlayerCamera, _, margin| camera.frame_margin = margin)
+        .done();
+}
+
+impl Default for PlayerCamera {
+    fn default() -> Self {
+        Self {
+            deadzone_width: 32.0,
+            deadzone_height: 24.0,
+            frame_target: String::new(),
+            frame_margin: 32.0,
+            focus: None,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl PlayerCamera {
+    fn new(_owner: &Camera2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: &Camera2D) {
+        owner.add_to_group("player_camera", false);
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Camera2D, delta: f32) {
+        if let Some(focus) = self.focus.as_mut() {
+            let (from_position, from_zoom) = *focus
+                .from
+                .get_or_insert((owner.global_position(), owner.zoom().x));
+            // in real seconds, as focusing comes with slow-mo
+            let real_delta = delta / Engine::godot_singleton().time_scale().max(0.01) as f32;
+            let progress = focus.tween.advance(real_delta);
+            let zoom = from_zoom + (focus.zoom - from_zoom) * progress;
+
+            owner.set_global_position(from_position.lerp(focus.position, progress));
+            owner.set_zoom(Vector2::new(zoom, zoom));
+
+            return;
+        }
+
+        // the player may have been freed
+        let player = match owner.get_node("../YSort/Player") {
+            Some(player) => unsafe { player.assume_safe() },
+            None => return,
+        };
+        let player: TRef<Node2D> = player.cast().unwrap();
+
+        // every local player is framed, along with the extra target
+        let mut targets: Vec<TRef<Node2D>> = entity_registry::all_players(owner)
+            .into_iter()
+            .map(|player| player.upcast::<Node2D>())
+            .collect();
+
+        if !self.frame_target.is_empty() {
+            if let Some(frame_target) = owner
+                .get_node(self.frame_target.as_str())
+                .map(|target| unsafe { target.assume_safe() })
+                .and_then(|target| target.cast::<Node2D>())
+            {
+                targets.push(frame_target);
+            }
+        }
+
+        if targets.len() > 1 {
+            let (center, zoom) = frame_targets(
+                &targets,
+                owner.get_viewport_rect().size.to_vector(),
+                self.frame_margin,
+            );
+
+            owner.set_global_position(center);
+            owner.set_zoom(Vector2::new(zoom, zoom));
+
+            return;
+        }
+
+        owner.set_zoom(Vector2::new(1.0, 1.0));
+        owner.set_global_position(follow_deadzone(
+            owner.global_position(),
+            player.global_position(),
+            Vector2::new(self.deadzone_width, self.deadzone_height) / 2.0,
+        ));
+    }
+
+    pub fn focus_on(&mut self, position: Vector2, zoom: f32) {
+        self.focus = Some(Focus {
+            position,
+            zoom,
+            from: None,
+            tween: Tween::new(FOCUS_DURATION, ease_out),
+        });
+    }
+
+    pub fn release_focus(&mut self) {
+        self.focus = None;
+    }
+}
+
+/// Returns the part of the world the camera currently shows.
+pub fn view_bounds(camera: &Camera2D) -> Rect2 {
+    camera_bounds(
+        camera.get_camera_screen_center(),
+        camera.zoom(),
+        camera.get_viewport_rect().size.to_vector(),
+    )
+}
+
+fn camera_bounds(center: Vector2, zoom: Vector2, viewport_size: Vector2) -> Rect2 {
+    let size = Vector2::new(viewport_size.x * zoom.x, viewport_size.y * zoom.y);
+
+    Rect2::new((center - size / 2.0).to_point(), size.to_size())
+}
+
+// moves the camera only as much as needed to keep the target inside the
+// deadzone box around it
+fn follow_deadzone(camera: Vector2, target: Vector2, half_extents: Vector2) -> Vector2 {
+    fn follow_axis(camera: f32, target: f32, half_extent: f32) -> f32 {
+        if target > camera + half_extent {
+            target - half_extent
+        } else if target < camera - half_extent {
+            target + half_extent
+        } else {
+            camera
+        }
+    }
+
+    Vector2::new(
+        follow_axis(camera.x, target.x, half_extents.x),
+        follow_axis(camera.y, target.y, half_extents.y),
+    )
+}
+
+/// Returns the center between the targets and the zoom that fits them all on
+/// screen. The camera never zooms in past the default zoom.
+pub fn frame_targets(
+    targets: &[TRef<Node2D>],
+    viewport_size: Vector2,
+    margin: f32,
+) -> (Vector2, f32) {
+    let positions: Vec<Vector2> = targets
+        .iter()
+        .map(|target| target.global_position())
+        .collect();
+
+    frame_positions(&positions, viewport_size, margin)
+}
+
+fn frame_positions(positions: &[Vector2], viewport_size: Vector2, margin: f32) -> (Vector2, f32) {
+    if positions.is_empty() {
+        return (Vector2::zero(), 1.0);
+    }
+
+    let min = positions
+        .iter()
+        .fold(positions[0], |min, position| min.min(*position));
+    let max = positions
+        .iter()
+        .fold(positions[0], |max, position| max.max(*position));
+
+    let center = (min + max) / 2.0;
+    let extents = max - min + Vector2::new(margin, margin) * 2.0;
+
+    // a zoom above one shows more of the world in godot
+    let zoom = (extents.x / viewport_size.x)
+        .max(extents.y / viewport_size.y)
+        .max(1.0);
+
+    (center, zoom)
+}
+
+#[test]
+fn test_frame_center_between_targets() {
+    let positions = [Vector2::new(-40.0, 10.0), Vector2::new(60.0, 30.0)];
+
+    let (center, zoom) = frame_positions(&positions, Vector2::new(320.0, 180.0), 16.0);
+
+    assert_eq!(center, Vector2::new(10.0, 20.0));
+    assert_eq!(zoom, 1.0);
+}
+
+#[test]
+fn test_frame_zooms_out_to_fit() {
+    let positions = [Vector2::new(-304.0, 0.0), Vector2::new(304.0, 0.0)];
+
+    let (center, zoom) = frame_positions(&positions, Vector2::new(320.0, 180.0), 16.0);
+
+    assert_eq!(center, Vector2::zero());
+    assert_eq!(zoom, 2.0);
+}
+
+#[test]
+fn test_camera_bounds_scale_with_zoom() {
+    let bounds = camera_bounds(
+        Vector2::new(100.0, 50.0),
+        Vector2::new(2.0, 2.0),
+        Vector2::new(320.0, 180.0),
+    );
+
+    assert_eq!(bounds.origin.to_vector(), Vector2::new(-220.0, -130.0));
+    assert_eq!(bounds.size.to_vector(), Vector2::new(640.0, 360.0));
+}
+
+#[test]
+fn test_target_inside_deadzone_does_not_move() {
+    let camera = Vector2::new(10.0, 10.0);
+
+    let moved = follow_deadzone(camera, Vector2::new(14.0, 6.0), Vector2::new(8.0, 8.0));
+
+    assert_eq!(moved, camera);
+}
+
+#[test]
+fn test_target_outside_deadzone_follows_edge() {
+    let camera = Vector2::new(0.0, 0.0);
+
+    let moved = follow_deadzone(camera, Vector2::new(20.0, -12.0), Vector2::new(8.0, 4.0));
+
+    assert_eq!(moved, Vector2::new(12.0, -8.0));
+}
@@ -0,0 +1,669 @@
+use crate::rng::Rng;
+use crate::settings;
+use crate::timer::{GameTimer, TimerMode};
+use crate::weapon::Weapon;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node, TRef, VariantArray};
+
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct PlayerStats {
+    pub(crate) max_health: i32,
+    pub(crate) health: i32,
+    // sword hits landed in a row
+    pub(crate) combo: i32,
+    pub(crate) coins: i32,
+    // a lethal hit taken at or above this much health leaves the player at
+    // one health instead, once per room, zero disables it
+    pub(crate) last_stand_threshold: i32,
+    pub(crate) last_stand: bool,
+    // overheal above max health, absorbing damage before health does
+    pub(crate) shield: f32,
+    pub(crate) max_shield: f32,
+    // shield lost per second
+    pub(crate) shield_decay: f32,
+    // from the permanent upgrades
+    pub(crate) bonus_damage: i32,
+    pub(crate) bonus_stamina: f32,
+    pub(crate) bonus_knockback_resistance: f32,
+    // distance coins fly to the player from, before buffs
+    pub(crate) magnet_radius: f32,
+    magnet_buff: MagnetBuff,
+    // health regenerated per second out of combat, zero disables it
+    pub(crate) regen_rate: f32,
+    // seconds without taking damage before regen starts
+    pub(crate) regen_delay: f32,
+    regen_delay_timer: GameTimer,
+    // fires for every point of health regenerated
+    regen_timer: GameTimer,
+    // kept up to date by the `CombatState` autoload, regen waits for it
+    in_combat: bool,
+    // runs started over with `start_new_game_plus`, each making enemies tougher
+    pub(crate) ng_plus_level: i32,
+    // what chests roll their loot from, kept in the save so reloading the
+    // run can't reroll them, and rolled again when a new run starts
+    pub(crate) run_seed: i64,
+    // the owned weapons new game plus carried over, which swords start with
+    // instead of the usual ones
+    pub(crate) carried_weapons: Vec<Weapon>,
+    // extra enemy max health and contact damage per new game plus level, as
+    // a fraction of them
+    pub(crate) ng_plus_health_scale: f32,
+    pub(crate) ng_plus_damage_scale: f32,
+}
+
+// a while of pulling in coins from much further away
+#[derive(Default)]
+struct MagnetBuff {
+    bonus_radius: f32,
+    timer: GameTimer,
+}
+
+fn register_properties(builder: &ClassBuilder<PlayerStats>) {
+    builder
+        .add_property::<i32>("max_health")
+        .with_default(4)
+        .with_getter(|stats: &PlayerStats, _| stats.max_health)
+        .with_setter(|stats: &mut PlayerStats, _, max_health| stats.max_health = max_health)
+        .done();
+    builder
+        .add_property::<i32>("combo")
+        .with_getter(|stats: &PlayerStats, _| stats.combo)
+        .with_setter(|stats: &mut PlayerStats, _, combo| stats.combo = combo)
+        .done();
+    builder
+        .add_property::<i32>("coins")
+        .with_getter(|stats: &PlayerStats, _| stats.coins)
+        .with_setter(|stats: &mut PlayerStats, _, coins| stats.coins = coins)
+        .done();
+    builder
+        .add_property::<i32>("last_stand_threshold")
+        .with_default(2)
+        .with_getter(|stats: &PlayerStats, _| stats.last_stand_threshold)
+        .with_setter(|stats: &mut PlayerStats, _, threshold| stats.last_stand_threshold = threshold)
+        .done();
+    builder
+        .add_property::<f32>("shield")
+        .with_getter(|stats: &PlayerStats, _| stats.shield)
+        .with_setter(|stats: &mut PlayerStats, _, shield| stats.add_shield(shield - stats.shield))
+        .done();
+    // healing past max health turns into shield, up to this much
+    builder
+        .add_property::<f32>("max_shield")
+        .with_default(4.0)
+        .with_getter(|stats: &PlayerStats, _| stats.max_shield)
+        .with_setter(|stats: &mut PlayerStats, _, max_shield| stats.max_shield = max_shield)
+        .done();
+    builder
+        .add_property::<f32>("shield_decay")
+        .with_default(0.5)
+        .with_getter(|stats: &PlayerStats, _| stats.shield_decay)
+        .with_setter(|stats: &mut PlayerStats, _, decay| stats.shield_decay = decay)
+        .done();
+    builder
+        .add_property::<f32>("magnet_radius")
+        .with_default(24.0)
+        .with_getter(|stats: &PlayerStats, _| stats.magnet_radius)
+        .with_setter(|stats: &mut PlayerStats, _, radius| stats.magnet_radius = radius)
+        .done();
+    builder
+        .add_property::<f32>("regen_rate")
+        .with_getter(|stats: &PlayerStats, _| stats.regen_rate)
+        .with_setter(|stats: &mut PlayerStats, _, rate| stats.regen_rate = rate)
+        .done();
+    builder
+        .add_property::<f32>("regen_delay")
+        .with_default(5.0)
+        .with_getter(|stats: &PlayerStats, _| stats.regen_delay)
+        .with_setter(|stats: &mut PlayerStats, _, delay| stats.regen_delay = delay)
+        .done();
+    builder
+        .add_property::<i32>("ng_plus_level")
+        .with_getter(|stats: &PlayerStats, _| stats.ng_plus_level)
+        .with_setter(|stats: &mut PlayerStats, _, level| stats.ng_plus_level = level)
+        .done();
+    builder
+        .add_property::<f32>("ng_plus_health_scale")
+        .with_default(0.5)
+        .with_getter(|stats: &PlayerStats, _| stats.ng_plus_health_scale)
+        .with_setter(|stats: &mut PlayerStats, _, scale| stats.ng_plus_health_scale = scale)
+        .done();
+    builder
+        .add_property::<f32>("ng_plus_damage_scale")
+        .with_default(0.25)
+        .with_getter(|stats: &PlayerStats, _| stats.ng_plus_damage_scale)
+        .with_setter(|stats: &mut PlayerStats, _, scale| stats.ng_plus_damage_scale = scale)
+        .done();
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        let max_health = 4;
+        Self {
+            max_health,
+            health: max_health,
+            combo: 0,
+            coins: 0,
+            last_stand_threshold: 2,
+            last_stand: true,
+            shield: 0.0,
+            max_shield: 4.0,
+            shield_decay: 0.5,
+            bonus_damage: 0,
+            bonus_stamina: 0.0,
+            bonus_knockback_resistance: 0.0,
+            magnet_radius: 24.0,
+            magnet_buff: MagnetBuff::default(),
+            regen_rate: 0.0,
+            regen_delay: 5.0,
+            regen_delay_timer: GameTimer::new(TimerMode::OneShot),
+            regen_timer: GameTimer::new(TimerMode::Repeating),
+            in_combat: false,
+            ng_plus_level: 0,
+            run_seed: 0,
+            carried_weapons: Vec::new(),
+            ng_plus_health_scale: 0.5,
+            ng_plus_damage_scale: 0.25,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl PlayerStats {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: TRef<Node>) {
+        self.health = self.max_health;
+
+        if let Some(combat_state) = owner.get_node("/root/CombatState") {
+            let _ = unsafe { combat_state.assume_safe() }.connect(
+                "in_combat_changed",
+                owner,
+                "_on_in_combat_changed",
+                VariantArray::new_shared(),
+                0,
+            );
+        }
+    }
+
+    #[export]
+    fn _on_in_combat_changed(&mut self, _owner: &Node, in_combat: bool) {
+        self.in_combat = in_combat;
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f32) {
+        let delta = settings::game_delta(owner, delta);
+
+        self.decay_shield(delta);
+        self.tick_magnet_buff(delta);
+        self.tick_regen(delta);
+    }
+
+    pub fn set_health(&mut self, health: i32) {
+        self.health = health.clamp(0, self.max_health);
+    }
+
+    /// Heals the player, turning anything past max health into shield.
+    pub fn heal(&mut self, amount: i32) {
+        let overheal = self.health + amount - self.max_health;
+
+        self.set_health(self.health + amount);
+
+        if overheal > 0 {
+            self.add_shield(overheal as f32);
+        }
+    }
+
+    pub fn add_shield(&mut self, amount: f32) {
+        self.shield = (self.shield + amount).clamp(0.0, self.max_shield);
+    }
+
+    fn decay_shield(&mut self, delta: f32) {
+        self.shield = (self.shield - self.shield_decay * delta).max(0.0);
+    }
+
+    // only whole points of shield absorb damage, returning what is left
+    fn absorb_with_shield(&mut self, damage: i32) -> i32 {
+        let absorbed = (self.shield.floor() as i32).min(damage).max(0);
+
+        self.shield -= absorbed as f32;
+
+        damage - absorbed
+    }
+
+    pub fn receive_damage(&mut self, damage: i32) {
+        // any hit counts as being in combat, even one the shield took
+        self.regen_timer.stop();
+        self.regen_delay_timer.start(self.regen_delay);
+
+        let damage = self.absorb_with_shield(damage);
+
+        if damage <= 0 {
+            return;
+        }
+
+        let is_lethal = self.health - damage <= 0;
+
+        if is_lethal
+            && self.last_stand
+            && self.last_stand_threshold > 0
+            && self.health >= self.last_stand_threshold
+        {
+            self.last_stand = false;
+            self.set_health(1);
+
+            return;
+        }
+
+        self.set_health(self.health - damage);
+    }
+
+    /// Widens the coin magnet for a while. A new buff replaces the old one.
+    pub fn apply_magnet_buff(&mut self, bonus_radius: f32, duration: f32) {
+        self.magnet_buff.bonus_radius = bonus_radius;
+        self.magnet_buff.timer.start(duration);
+    }
+
+    fn tick_magnet_buff(&mut self, delta: f32) {
+        self.magnet_buff.timer.tick(delta);
+    }
+
+    fn tick_regen(&mut self, delta: f32) {
+        if self.regen_rate <= 0.0
+            || self.in_combat
+            || self.health <= 0
+            || self.health >= self.max_health
+        {
+            self.regen_timer.stop();
+            return;
+        }
+
+        if self.regen_delay_timer.is_running() {
+            self.regen_delay_timer.tick(delta);
+            return;
+        }
+
+        if !self.regen_timer.is_running() {
+            self.regen_timer.start(1.0 / self.regen_rate);
+        }
+
+        if self.regen_timer.tick(delta) {
+            self.set_health(self.health + 1);
+        }
+    }
+
+    pub fn magnet_buff_time_left(&self) -> f32 {
+        self.magnet_buff.timer.time_left()
+    }
+
+    /// The distance coins fly to the player from, with the buff.
+    pub fn effective_magnet_radius(&self) -> f32 {
+        if self.magnet_buff.timer.is_running() {
+            self.magnet_radius + self.magnet_buff.bonus_radius
+        } else {
+            self.magnet_radius
+        }
+    }
+
+    pub fn reset_last_stand(&mut self) {
+        self.last_stand = true;
+    }
+
+    /// Starts the next new game plus level from scratch, except for what the
+    /// upgrades give, which carries over.
+    pub fn start_new_game_plus(&mut self) {
+        self.ng_plus_level += 1;
+        self.roll_run_seed();
+
+        self.health = self.max_health;
+        self.coins = 0;
+        self.combo = 0;
+        self.shield = 0.0;
+        self.last_stand = true;
+        self.magnet_buff = MagnetBuff::default();
+        self.regen_delay_timer.stop();
+        self.regen_timer.stop();
+    }
+
+    /// Rolls the seed of the next run, so it rolls its own loot, still
+    /// from the seed of the run it came from.
+    pub fn roll_run_seed(&mut self) {
+        self.run_seed = Rng::new(self.run_seed as u64).next_u64() as i64;
+    }
+
+    /// An enemy's max health at the new game plus level reached.
+    pub fn scale_enemy_health(&self, max_health: i32) -> i32 {
+        ng_plus_scaled(max_health, self.ng_plus_health_scale, self.ng_plus_level)
+    }
+
+    /// An enemy's contact damage at the new game plus level reached.
+    pub fn scale_enemy_damage(&self, damage: i32) -> i32 {
+        ng_plus_scaled(damage, self.ng_plus_damage_scale, self.ng_plus_level)
+    }
+
+    pub fn lose_combo(&mut self, amount: i32) {
+        self.combo = (self.combo - amount).max(0);
+    }
+}
+
+// grows by the fraction of the value for every level, never below the value
+fn ng_plus_scaled(value: i32, scale_per_level: f32, level: i32) -> i32 {
+    let scale = 1.0 + scale_per_level.max(0.0) * level.max(0) as f32;
+
+    (value as f32 * scale).round() as i32
+}
+
+#[test]
+fn test_last_stand_leaves_one_health() {
+    let mut stats = PlayerStats::default();
+
+    stats.receive_damage(10);
+
+    assert_eq!(stats.health, 1);
+    assert!(!stats.last_stand);
+}
+
+#[test]
+fn test_last_stand_is_used_once() {
+    let mut stats = PlayerStats::default();
+
+    stats.receive_damage(10);
+    stats.set_health(3);
+    stats.receive_damage(3);
+
+    assert_eq!(stats.health, 0);
+
+    stats.reset_last_stand();
+    stats.set_health(3);
+    stats.receive_damage(3);
+
+    assert_eq!(stats.health, 1);
+}
+
+#[test]
+fn test_no_last_stand_below_threshold() {
+    let mut stats = PlayerStats::default();
+    stats.set_health(1);
+
+    stats.receive_damage(1);
+
+    assert_eq!(stats.health, 0);
+    assert!(stats.last_stand);
+}
+
+#[test]
+fn test_lose_combo_reduces_combo() {
+    let mut stats = PlayerStats {
+        combo: 5,
+        ..PlayerStats::default()
+    };
+
+    stats.lose_combo(2);
+
+    assert_eq!(stats.combo, 3);
+}
+
+#[test]
+fn test_lose_combo_clamps_at_zero() {
+    let mut stats = PlayerStats {
+        combo: 1,
+        ..PlayerStats::default()
+    };
+
+    stats.lose_combo(3);
+
+    assert_eq!(stats.combo, 0);
+}
+
+#[test]
+fn test_set_health_clamps_to_max() {
+    let mut stats = PlayerStats::default();
+
+    stats.set_health(stats.health + 3);
+
+    assert_eq!(stats.health, stats.max_health);
+}
+
+#[test]
+fn test_set_health_clamps_to_zero() {
+    let mut stats = PlayerStats::default();
+
+    stats.set_health(-2);
+
+    assert_eq!(stats.health, 0);
+}
+
+#[test]
+fn test_shield_absorbs_damage_before_health() {
+    let mut stats = PlayerStats::default();
+    stats.add_shield(2.0);
+
+    stats.receive_damage(3);
+
+    assert_eq!(stats.shield, 0.0);
+    assert_eq!(stats.health, stats.max_health - 1);
+}
+
+#[test]
+fn test_shield_takes_all_damage_it_covers() {
+    let mut stats = PlayerStats::default();
+    stats.add_shield(3.0);
+
+    stats.receive_damage(2);
+
+    assert_eq!(stats.shield, 1.0);
+    assert_eq!(stats.health, stats.max_health);
+}
+
+#[test]
+fn test_shield_decays_over_time() {
+    let mut stats = PlayerStats::default();
+    stats.add_shield(1.0);
+
+    stats.decay_shield(1.0);
+
+    assert_eq!(stats.shield, 0.5);
+
+    stats.decay_shield(2.0);
+
+    assert_eq!(stats.shield, 0.0);
+}
+
+#[test]
+fn test_overheal_becomes_shield() {
+    let mut stats = PlayerStats::default();
+    stats.set_health(3);
+
+    stats.heal(3);
+
+    assert_eq!(stats.health, stats.max_health);
+    assert_eq!(stats.shield, 2.0);
+}
+
+#[test]
+fn test_magnet_buff_widens_radius_while_it_lasts() {
+    let mut stats = PlayerStats::default();
+
+    assert_eq!(stats.effective_magnet_radius(), 24.0);
+
+    stats.apply_magnet_buff(96.0, 8.0);
+    stats.tick_magnet_buff(7.5);
+
+    assert_eq!(stats.effective_magnet_radius(), 120.0);
+
+    stats.tick_magnet_buff(0.5);
+
+    assert_eq!(stats.effective_magnet_radius(), 24.0);
+}
+
+#[test]
+fn test_regen_starts_after_the_delay() {
+    let mut stats = PlayerStats {
+        regen_rate: 0.5,
+        regen_delay: 3.0,
+        ..PlayerStats::default()
+    };
+
+    stats.receive_damage(2);
+    stats.tick_regen(2.0);
+    stats.tick_regen(1.0);
+
+    // the delay has passed, and a point takes two seconds
+    assert_eq!(stats.health, 2);
+
+    stats.tick_regen(2.0);
+
+    assert_eq!(stats.health, 3);
+}
+
+#[test]
+fn test_regen_pauses_on_damage() {
+    let mut stats = PlayerStats {
+        regen_rate: 1.0,
+        regen_delay: 3.0,
+        ..PlayerStats::default()
+    };
+
+    stats.receive_damage(2);
+    stats.tick_regen(3.0);
+    stats.tick_regen(0.5);
+    stats.receive_damage(1);
+    stats.tick_regen(2.5);
+
+    assert_eq!(stats.health, 1);
+
+    stats.tick_regen(0.5);
+    stats.tick_regen(1.0);
+
+    assert_eq!(stats.health, 2);
+}
+
+#[test]
+fn test_regen_stops_at_max_health() {
+    let mut stats = PlayerStats {
+        regen_rate: 1.0,
+        regen_delay: 0.0,
+        health: 3,
+        ..PlayerStats::default()
+    };
+
+    for _ in 0..10 {
+        stats.tick_regen(1.0);
+    }
+
+    assert_eq!(stats.health, stats.max_health);
+}
+
+#[test]
+fn test_regen_waits_for_combat_to_end() {
+    let mut stats = PlayerStats {
+        regen_rate: 1.0,
+        regen_delay: 0.0,
+        health: 2,
+        in_combat: true,
+        ..PlayerStats::default()
+    };
+
+    stats.tick_regen(5.0);
+
+    assert_eq!(stats.health, 2);
+
+    stats.in_combat = false;
+    stats.tick_regen(1.0);
+
+    assert_eq!(stats.health, 3);
+}
+
+#[test]
+fn test_new_game_plus_resets_the_run() {
+    let mut stats = PlayerStats {
+        max_health: 6,
+        health: 2,
+        coins: 40,
+        combo: 5,
+        shield: 1.5,
+        last_stand: false,
+        ..PlayerStats::default()
+    };
+
+    stats.start_new_game_plus();
+
+    assert_eq!(stats.health, 6);
+    assert_eq!(stats.coins, 0);
+    assert_eq!(stats.combo, 0);
+    assert_eq!(stats.shield, 0.0);
+    assert!(stats.last_stand);
+}
+
+#[test]
+fn test_new_game_plus_keeps_the_upgrades() {
+    let mut stats = PlayerStats {
+        max_health: 6,
+        bonus_damage: 2,
+        bonus_stamina: 25.0,
+        ..PlayerStats::default()
+    };
+
+    stats.start_new_game_plus();
+
+    assert_eq!(stats.max_health, 6);
+    assert_eq!(stats.bonus_damage, 2);
+    assert_eq!(stats.bonus_stamina, 25.0);
+}
+
+#[test]
+fn test_new_game_plus_bumps_the_level() {
+    let mut stats = PlayerStats::default();
+
+    stats.start_new_game_plus();
+
+    assert_eq!(stats.ng_plus_level, 1);
+
+    stats.start_new_game_plus();
+
+    assert_eq!(stats.ng_plus_level, 2);
+}
+
+#[test]
+fn test_enemy_health_and_damage_scale_with_ng_plus_level() {
+    let at_level = |ng_plus_level| PlayerStats {
+        ng_plus_level,
+        ..PlayerStats::default()
+    };
+
+    assert_eq!(at_level(0).scale_enemy_health(4), 4);
+    assert_eq!(at_level(0).scale_enemy_damage(4), 4);
+    assert_eq!(at_level(1).scale_enemy_health(4), 6);
+    assert_eq!(at_level(1).scale_enemy_damage(4), 5);
+    assert_eq!(at_level(3).scale_enemy_health(4), 10);
+    assert_eq!(at_level(3).scale_enemy_damage(4), 7);
+}
+
+#[test]
+fn test_ng_plus_scaling_rounds_small_values() {
+    // a single point of damage only grows once the scale adds half of it
+    assert_eq!(ng_plus_scaled(1, 0.25, 1), 1);
+    assert_eq!(ng_plus_scaled(1, 0.25, 2), 2);
+}
+
+#[test]
+fn test_new_game_plus_rolls_its_own_loot() {
+    let mut stats = PlayerStats {
+        run_seed: 42,
+        ..PlayerStats::default()
+    };
+    let mut replayed = PlayerStats {
+        run_seed: 42,
+        ..PlayerStats::default()
+    };
+
+    stats.start_new_game_plus();
+    replayed.start_new_game_plus();
+
+    assert_ne!(stats.run_seed, 42);
+    assert_eq!(stats.run_seed, replayed.run_seed);
+}
@@ -0,0 +1,114 @@
+use crate::bat::Bat;
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use crate::player::Player;
+use crate::player_stats::PlayerStats;
+use crate::stats::Stats;
+use gdnative::prelude::{ClassBuilder, Input, KinematicBody2D, NativeClass, Node, TRef, Vector2};
+
+/// Practice mode for learning boss patterns, where pressing `practice_reset`
+/// puts the boss and the players back where they started at full health.
+/// Reached through the `Practice` autoload, and does nothing unless enabled.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+#[derive(Default)]
+pub struct Practice {
+    enabled: bool,
+}
+
+fn register_properties(builder: &ClassBuilder<Practice>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_getter(|practice: &Practice, _| practice.enabled)
+        .with_setter(|practice: &mut Practice, _, enabled| practice.enabled = enabled)
+        .done();
+}
+
+#[gdnative::methods]
+impl Practice {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, _delta: f32) {
+        if self.enabled && Input::godot_singleton().is_action_just_pressed("practice_reset") {
+            reset(owner);
+        }
+    }
+}
+
+fn reset(owner: &Node) {
+    let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+    let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+    // a boss that was already killed is gone until the room reloads
+    let bosses = entity_registry::all_enemies(owner)
+        .into_iter()
+        .filter_map(|enemy: TRef<KinematicBody2D>| enemy.cast_instance::<Bat>());
+
+    for boss in bosses {
+        let _ = boss.map_mut(|boss, boss_owner| {
+            if !boss.is_boss() {
+                return;
+            }
+
+            let stats_node = unsafe { boss_owner.get_typed_node::<Node, _>("Stats") };
+            let stats_instance = stats_node.cast_instance::<Stats>().unwrap();
+
+            let position = stats_instance
+                .map_mut(|stats, _| {
+                    player_stats_instance
+                        .map_mut(|player_stats, _| reset_encounter(boss, stats, player_stats))
+                        .unwrap()
+                })
+                .unwrap();
+
+            boss_owner.set_global_position(position);
+        });
+    }
+
+    for player in entity_registry::all_players(owner)
+        .into_iter()
+        .filter_map(|player: TRef<KinematicBody2D>| player.cast_instance::<Player>())
+    {
+        let _ = player.map_mut(|player, player_owner| player.respawn(&player_owner));
+    }
+}
+
+/// Restores the boss and the player to full health, returning where the
+/// boss goes back to.
+fn reset_encounter(
+    boss: &mut Bat,
+    boss_stats: &mut Stats,
+    player_stats: &mut PlayerStats,
+) -> Vector2 {
+    boss_stats.health = boss_stats.max_health;
+
+    player_stats.set_health(player_stats.max_health);
+    player_stats.shield = 0.0;
+
+    boss.reset()
+}
+
+#[test]
+fn test_reset_encounter_restores_health() {
+    let mut boss = Bat::default();
+    let mut boss_stats = Stats {
+        max_health: 30,
+        health: 4,
+        ..Stats::default()
+    };
+    let mut player_stats = PlayerStats {
+        health: 1,
+        shield: 2.0,
+        ..PlayerStats::default()
+    };
+
+    reset_encounter(&mut boss, &mut boss_stats, &mut player_stats);
+
+    assert_eq!(boss_stats.health, 30);
+    assert_eq!(player_stats.health, player_stats.max_health);
+    assert_eq!(player_stats.shield, 0.0);
+}
@@ -0,0 +1,233 @@
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, NativeClass, VariantArray, Vector2};
+
+const LIFETIME: f32 = 5.0;
+// the "World" physics layer, which walls are on
+pub(crate) const WORLD_LAYER: i64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Team {
+    Player,
+    Enemy,
+}
+
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct Projectile {
+    pub(crate) velocity: Vector2,
+    pub(crate) team: Team,
+    pub(crate) damage: i32,
+    // how many enemies the projectile passes through before stopping
+    pierce_count: i32,
+    // instance ids of the enemies already hit, so a pierced enemy isn't hit
+    // again while the projectile is still inside it
+    hit_targets: Vec<i64>,
+    elapsed: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<Projectile>) {
+    builder
+        .add_property::<i32>("damage")
+        .with_default(1)
+        .with_getter(|projectile: &Projectile, _| projectile.damage)
+        .with_setter(|projectile: &mut Projectile, _, damage| projectile.damage = damage)
+        .done();
+    builder
+        .add_property::<i32>("pierce_count")
+        .with_getter(|projectile: &Projectile, _| projectile.pierce_count)
+        .with_setter(|projectile: &mut Projectile, _, count| projectile.pierce_count = count)
+        .done();
+}
+
+impl Default for Projectile {
+    fn default() -> Self {
+        Self {
+            velocity: Vector2::zero(),
+            team: Team::Enemy,
+            damage: 1,
+            pierce_count: 0,
+            hit_targets: Vec::new(),
+            elapsed: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Projectile {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    pub fn setup(&mut self, velocity: Vector2, team: Team) {
+        self.velocity = velocity;
+        self.team = team;
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Area2D, delta: f32) {
+        let space_state = owner
+            .get_world_2d()
+            .and_then(|world| unsafe { world.assume_safe() }.direct_space_state())
+            .map(|space_state| unsafe { space_state.assume_safe() });
+
+        let start = owner.global_position();
+        let motion = self.velocity * delta;
+
+        // a ray over the whole movement finds the first wall in the way, so
+        // fast projectiles can't skip over thin walls
+        let wall_hit = space_state.and_then(|space_state| {
+            let result = space_state.intersect_ray(
+                start,
+                start + motion,
+                VariantArray::new_shared(),
+                WORLD_LAYER,
+                true,
+                false,
+            );
+
+            if result.is_empty() {
+                None
+            } else {
+                Some(result.get("position").to_vector2())
+            }
+        });
+
+        let (position, hit_wall) = sweep(start, motion, wall_hit);
+
+        owner.set_global_position(position);
+
+        if hit_wall {
+            owner.queue_free();
+            return;
+        }
+
+        self.elapsed += delta;
+
+        if self.elapsed >= LIFETIME {
+            owner.queue_free();
+        }
+    }
+
+    // sends the projectile back where it came from, now hurting enemies
+    pub fn reflect(&mut self) {
+        self.send_back(Team::Player);
+    }
+
+    // the enemy side of a reflect, now hurting the player
+    pub fn deflect(&mut self) {
+        self.send_back(Team::Enemy);
+    }
+
+    /// Records a hit on the target. Returns `None` when it was already hit,
+    /// otherwise whether the projectile is used up and should be freed.
+    pub fn hit_target(&mut self, target: i64) -> Option<bool> {
+        if self.hit_targets.contains(&target) {
+            return None;
+        }
+
+        self.hit_targets.push(target);
+
+        if self.pierce_count <= 0 {
+            return Some(true);
+        }
+
+        self.pierce_count -= 1;
+
+        Some(false)
+    }
+
+    fn send_back(&mut self, team: Team) {
+        self.velocity = -self.velocity;
+        self.team = team;
+    }
+}
+
+/// Moves the whole way unless a wall was hit on the way, stopping at the
+/// wall. Returns the reached position and whether it collided.
+pub fn sweep(start: Vector2, motion: Vector2, wall_hit: Option<Vector2>) -> (Vector2, bool) {
+    match wall_hit {
+        Some(wall_hit) => (wall_hit, true),
+        None => (start + motion, false),
+    }
+}
+
+#[test]
+fn test_sweep_stops_at_the_wall() {
+    let (position, collided) = sweep(
+        Vector2::zero(),
+        Vector2::new(40.0, 0.0),
+        Some(Vector2::new(25.0, 0.0)),
+    );
+
+    assert!(collided);
+    assert_eq!(position, Vector2::new(25.0, 0.0));
+}
+
+#[test]
+fn test_sweep_without_wall_moves_the_whole_way() {
+    let (position, collided) = sweep(Vector2::zero(), Vector2::new(40.0, 0.0), None);
+
+    assert!(!collided);
+    assert_eq!(position, Vector2::new(40.0, 0.0));
+}
+
+#[test]
+fn test_reflect_reverses_velocity() {
+    let mut projectile = Projectile::default();
+    projectile.setup(Vector2::new(30.0, -10.0), Team::Enemy);
+
+    projectile.reflect();
+
+    assert_eq!(projectile.velocity, Vector2::new(-30.0, 10.0));
+}
+
+#[test]
+fn test_reflect_makes_projectile_player_owned() {
+    let mut projectile = Projectile::default();
+    projectile.setup(Vector2::new(30.0, 0.0), Team::Enemy);
+
+    projectile.reflect();
+
+    assert_eq!(projectile.team, Team::Player);
+}
+
+#[test]
+fn test_deflect_makes_projectile_enemy_owned() {
+    let mut projectile = Projectile::default();
+    projectile.setup(Vector2::new(30.0, 0.0), Team::Player);
+
+    projectile.deflect();
+
+    assert_eq!(projectile.velocity, Vector2::new(-30.0, 0.0));
+    assert_eq!(projectile.team, Team::Enemy);
+}
+
+#[test]
+fn test_pierce_count_decrements_per_target() {
+    let mut projectile = Projectile {
+        pierce_count: 2,
+        ..Projectile::default()
+    };
+
+    assert_eq!(projectile.hit_target(1), Some(false));
+    assert_eq!(projectile.pierce_count, 1);
+
+    // still inside the first enemy
+    assert_eq!(projectile.hit_target(1), None);
+    assert_eq!(projectile.pierce_count, 1);
+
+    assert_eq!(projectile.hit_target(2), Some(false));
+    assert_eq!(projectile.pierce_count, 0);
+}
+
+#[test]
+fn test_projectile_freed_once_pierce_is_exhausted() {
+    let mut projectile = Projectile {
+        pierce_count: 1,
+        ..Projectile::default()
+    };
+
+    assert_eq!(projectile.hit_target(1), Some(false));
+    assert_eq!(projectile.hit_target(2), Some(true));
+}
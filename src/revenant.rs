@@ -0,0 +1,88 @@
+/// Feeds off the allies dying around it, hitting harder and flying faster
+/// for each one.
+pub struct Revenant {
+    pub enabled: bool,
+    // fractions of the base damage and speed gained per ally death
+    pub damage_per_death: f32,
+    pub speed_per_death: f32,
+    // ally deaths past this many don't make it any stronger
+    pub max_stacks: i32,
+    deaths_witnessed: i32,
+}
+
+impl Default for Revenant {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            damage_per_death: 0.25,
+            speed_per_death: 0.1,
+            max_stacks: 8,
+            deaths_witnessed: 0,
+        }
+    }
+}
+
+impl Revenant {
+    pub fn record_ally_death(&mut self) {
+        if self.enabled {
+            self.deaths_witnessed += 1;
+        }
+    }
+
+    fn stacks(&self) -> f32 {
+        self.deaths_witnessed.min(self.max_stacks).max(0) as f32
+    }
+
+    pub fn damage_multiplier(&self) -> f32 {
+        1.0 + self.damage_per_death * self.stacks()
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        1.0 + self.speed_per_death * self.stacks()
+    }
+}
+
+#[test]
+fn test_revenant_grows_with_each_ally_death() {
+    let mut revenant = Revenant {
+        enabled: true,
+        ..Revenant::default()
+    };
+
+    revenant.record_ally_death();
+
+    assert_eq!(revenant.damage_multiplier(), 1.25);
+    assert_eq!(revenant.speed_multiplier(), 1.1);
+
+    revenant.record_ally_death();
+    revenant.record_ally_death();
+
+    assert_eq!(revenant.damage_multiplier(), 1.75);
+    assert!((revenant.speed_multiplier() - 1.3).abs() < 0.001);
+}
+
+#[test]
+fn test_revenant_stops_growing_at_max_stacks() {
+    let mut revenant = Revenant {
+        enabled: true,
+        max_stacks: 2,
+        ..Revenant::default()
+    };
+
+    for _ in 0..5 {
+        revenant.record_ally_death();
+    }
+
+    assert_eq!(revenant.deaths_witnessed, 5);
+    assert_eq!(revenant.damage_multiplier(), 1.5);
+}
+
+#[test]
+fn test_only_revenants_feed_off_deaths() {
+    let mut revenant = Revenant::default();
+
+    revenant.record_ally_death();
+
+    assert_eq!(revenant.deaths_witnessed, 0);
+    assert_eq!(revenant.speed_multiplier(), 1.0);
+}
@@ -0,0 +1,69 @@
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+// small xorshift generator, so rolls can be reproduced from a seed and tested
+// without going through Godot's RandomNumberGenerator
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // a xorshift generator with a zero state only ever returns zero
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// Returns a value in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns a value in `[min, max)`.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+#[test]
+fn test_same_seed_same_sequence() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+
+    for _ in 0..10 {
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+}
+
+#[test]
+fn test_next_f32_in_range() {
+    let mut rng = Rng::new(7);
+
+    for _ in 0..1000 {
+        let value = rng.next_f32();
+        assert!(value >= 0.0 && value < 1.0);
+    }
+}
+
+#[test]
+fn test_range_f32_in_range() {
+    let mut rng = Rng::new(3);
+
+    for _ in 0..1000 {
+        let value = rng.range_f32(-4.0, 4.0);
+        assert!(value >= -4.0 && value < 4.0);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::cooldown::Cooldown;
+use crate::load_scene;
+use gdnative::api::Sprite;
+use gdnative::prelude::{Node, Node2D, TRef, Vector2, Vector2Godot};
+
+// how close to its nest a runner has to get to heal
+const NEST_RANGE: f32 = 8.0;
+
+/// Returns the direction to the nest, or `None` once the runner is there.
+pub fn nest_direction(position: Vector2, nest: Vector2) -> Option<Vector2> {
+    if position.distance_to(nest) <= NEST_RANGE {
+        return None;
+    }
+
+    (nest - position).try_normalize()
+}
+
+/// Flees to its nest when hurt, leaving a trail the player can follow
+/// there, and heals once it arrives.
+pub struct Runner {
+    pub enabled: bool,
+    // path to the nest, like a spawner, the runner's home when empty
+    pub nest: String,
+    pub heal: i32,
+    // runs for the trail interval between breadcrumbs
+    pub trail: Cooldown,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            nest: String::new(),
+            heal: 2,
+            trail: Cooldown::new(0.25),
+        }
+    }
+}
+
+impl Runner {
+    /// Starts a run to the nest, dropping the first breadcrumb right away.
+    pub fn start(&mut self) {
+        self.trail = Cooldown::new(self.trail.duration);
+    }
+
+    /// Returns where the nest is, the home position when it has none.
+    pub fn nest_position(&self, owner: &Node, home: Vector2) -> Vector2 {
+        if self.nest.is_empty() {
+            return home;
+        }
+
+        owner
+            .get_node(self.nest.as_str())
+            .map(|nest| unsafe { nest.assume_safe() })
+            .and_then(|nest| nest.cast::<Node2D>())
+            .map(|nest| nest.global_position())
+            .unwrap_or(home)
+    }
+
+    /// Returns the health after arriving at the nest.
+    pub fn arrival_health(&self, health: i32, max_health: i32) -> i32 {
+        (health + self.heal).min(max_health)
+    }
+
+    /// Returns whether to drop a breadcrumb this frame.
+    pub fn tick_trail(&mut self, delta: f32) -> bool {
+        self.trail.tick(delta);
+
+        if !self.trail.is_ready() {
+            return false;
+        }
+
+        self.trail.start();
+
+        true
+    }
+}
+
+pub fn drop_breadcrumb(owner: &Node2D) {
+    let breadcrumb_scene = load_scene("res://scenes/Breadcrumb.tscn").unwrap();
+
+    let breadcrumb_node = unsafe { breadcrumb_scene.instance(0).unwrap().assume_safe() };
+    let breadcrumb: TRef<Sprite> = breadcrumb_node.cast().unwrap();
+
+    breadcrumb.set_global_position(owner.global_position());
+
+    let runner_parent = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+    runner_parent.add_child(breadcrumb, false);
+}
+
+#[test]
+fn test_runner_heals_on_arrival() {
+    let runner = Runner {
+        heal: 2,
+        ..Runner::default()
+    };
+
+    assert_eq!(runner.arrival_health(1, 4), 3);
+    assert_eq!(runner.arrival_health(3, 4), 4);
+}
+
+#[test]
+fn test_runner_flees_toward_nest() {
+    let direction = nest_direction(Vector2::new(10.0, 0.0), Vector2::new(10.0, 40.0));
+
+    assert_eq!(direction, Some(Vector2::new(0.0, 1.0)));
+}
+
+#[test]
+fn test_runner_arrives_at_nest() {
+    let nest = Vector2::new(10.0, 40.0);
+
+    assert_eq!(nest_direction(Vector2::new(10.0, 36.0), nest), None);
+}
+
+#[test]
+fn test_first_breadcrumb_drops_right_away() {
+    let mut runner = Runner::default();
+
+    runner.start();
+
+    assert!(runner.tick_trail(0.0));
+    assert!(!runner.tick_trail(0.125));
+    assert!(runner.tick_trail(0.125));
+}
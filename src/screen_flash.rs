@@ -0,0 +1,114 @@
+use crate::cooldown::Cooldown;
+use crate::extensions::NodeExt;
+use gdnative::api::{CanvasLayer, ColorRect};
+use gdnative::prelude::{ClassBuilder, Color, NativeClass, Node};
+
+/// A full screen overlay that flashes on big hits, reached through the
+/// `ScreenFlash` autoload.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct ScreenFlash {
+    // lets players who find the flashes uncomfortable turn them off
+    enabled: bool,
+    color: Color,
+    max_alpha: f32,
+    fade: Cooldown,
+}
+
+fn register_properties(builder: &ClassBuilder<ScreenFlash>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_default(true)
+        .with_getter(|screen_flash: &ScreenFlash, _| screen_flash.enabled)
+        .with_setter(|screen_flash: &mut ScreenFlash, _, enabled| screen_flash.enabled = enabled)
+        .done();
+}
+
+impl Default for ScreenFlash {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            color: Color::rgb(1.0, 1.0, 1.0),
+            max_alpha: 0.0,
+            fade: Cooldown::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl ScreenFlash {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &CanvasLayer, delta: f32) {
+        let overlay = unsafe { owner.get_typed_node::<ColorRect, _>("Overlay") };
+        let alpha = self.update(delta);
+
+        overlay.set_frame_color(Color::rgba(self.color.r, self.color.g, self.color.b, alpha));
+    }
+
+    pub fn flash(&mut self, color: Color, duration: f32, max_alpha: f32) {
+        if !self.enabled {
+            return;
+        }
+
+        self.color = color;
+        self.max_alpha = max_alpha;
+        self.fade = Cooldown::started(duration);
+    }
+
+    /// Advances the flash, returning the overlay's alpha.
+    fn update(&mut self, delta: f32) -> f32 {
+        self.fade.tick(delta);
+
+        if self.fade.duration <= 0.0 {
+            return 0.0;
+        }
+
+        self.max_alpha * self.fade.time_left() / self.fade.duration
+    }
+}
+
+/// Flashes the screen through the `ScreenFlash` autoload.
+pub fn flash(owner: &Node, color: Color, duration: f32, max_alpha: f32) {
+    let screen_flash_node = unsafe { owner.get_typed_node::<CanvasLayer, _>("/root/ScreenFlash") };
+    let screen_flash_instance = screen_flash_node.cast_instance::<ScreenFlash>().unwrap();
+
+    let _ = screen_flash_instance
+        .map_mut(|screen_flash, _| screen_flash.flash(color, duration, max_alpha));
+}
+
+#[test]
+fn test_flash_starts_at_max_alpha() {
+    let mut screen_flash = ScreenFlash::default();
+
+    screen_flash.flash(Color::rgb(1.0, 1.0, 1.0), 0.2, 0.6);
+
+    assert_eq!(screen_flash.update(0.0), 0.6);
+    assert_eq!(screen_flash.update(0.1), 0.3);
+}
+
+#[test]
+fn test_flash_decays_to_zero() {
+    let mut screen_flash = ScreenFlash::default();
+
+    screen_flash.flash(Color::rgb(1.0, 1.0, 1.0), 0.2, 0.6);
+    screen_flash.update(0.15);
+
+    assert_eq!(screen_flash.update(0.15), 0.0);
+}
+
+#[test]
+fn test_disabled_flash_stays_clear() {
+    let mut screen_flash = ScreenFlash {
+        enabled: false,
+        ..ScreenFlash::default()
+    };
+
+    screen_flash.flash(Color::rgb(1.0, 1.0, 1.0), 0.2, 0.6);
+
+    assert_eq!(screen_flash.update(0.0), 0.0);
+}
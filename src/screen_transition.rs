@@ -0,0 +1,179 @@
+use crate::extensions::NodeExt;
+use crate::settings::Settings;
+use crate::transition_style::TransitionStyle;
+use crate::tween::ease_in_out;
+use gdnative::api::{CanvasLayer, ColorRect, ShaderMaterial};
+use gdnative::prelude::{godot_error, ClassBuilder, Color, NativeClass, Node};
+
+/// Covers the screen while the scene changes, in the style picked in the
+/// settings, then uncovers it on the new scene. Reached through the
+/// `ScreenTransition` autoload.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct ScreenTransition {
+    // seconds each of covering and uncovering takes
+    duration: f32,
+    style: TransitionStyle,
+    // `None` while not transitioning
+    phase: Option<Phase>,
+    progress: f32,
+}
+
+enum Phase {
+    // the scene changed to once the screen is covered
+    Covering(String),
+    Uncovering,
+}
+
+fn register_properties(builder: &ClassBuilder<ScreenTransition>) {
+    builder
+        .add_property::<f32>("duration")
+        .with_default(0.4)
+        .with_getter(|transition: &ScreenTransition, _| transition.duration)
+        .with_setter(|transition: &mut ScreenTransition, _, duration| {
+            transition.duration = duration
+        })
+        .done();
+}
+
+impl Default for ScreenTransition {
+    fn default() -> Self {
+        Self {
+            duration: 0.4,
+            style: TransitionStyle::default(),
+            phase: None,
+            progress: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl ScreenTransition {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &CanvasLayer, delta: f32) {
+        let covered = self.advance(delta);
+
+        if let Some(path) = covered {
+            let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+
+            if tree.change_scene(path.as_str()).is_err() {
+                godot_error!("couldn't change the scene to {}", path);
+            }
+        }
+
+        self.show_overlay(owner);
+    }
+
+    /// Starts covering the screen, changing to the scene once covered.
+    #[export]
+    fn change_scene(&mut self, owner: &CanvasLayer, path: String) {
+        let settings_node = unsafe { owner.get_typed_node::<Node, _>("/root/Settings") };
+        let settings_instance = settings_node.cast_instance::<Settings>().unwrap();
+
+        self.style = settings_instance
+            .map(|settings, _| settings.transition_style)
+            .unwrap_or_default();
+        self.start(path);
+    }
+
+    fn start(&mut self, path: String) {
+        self.phase = Some(Phase::Covering(path));
+        self.progress = 0.0;
+    }
+
+    /// Moves the transition along, returning the scene to change to once
+    /// the screen is covered.
+    fn advance(&mut self, delta: f32) -> Option<String> {
+        let step = if self.duration > 0.0 {
+            delta / self.duration
+        } else {
+            1.0
+        };
+
+        match self.phase.take() {
+            Some(Phase::Covering(path)) => {
+                self.progress = (self.progress + step).min(1.0);
+
+                if self.progress < 1.0 {
+                    self.phase = Some(Phase::Covering(path));
+                    return None;
+                }
+
+                self.phase = Some(Phase::Uncovering);
+                Some(path)
+            }
+            Some(Phase::Uncovering) => {
+                self.progress = (self.progress - step).max(0.0);
+
+                if self.progress > 0.0 {
+                    self.phase = Some(Phase::Uncovering);
+                }
+
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn show_overlay(&self, owner: &CanvasLayer) {
+        let overlay_rect = unsafe { owner.get_typed_node::<ColorRect, _>("Overlay") };
+        let overlay = self.style.overlay(ease_in_out(self.progress));
+
+        overlay_rect.set_visible(self.progress > 0.0);
+        overlay_rect.set_frame_color(Color::rgba(0.0, 0.0, 0.0, overlay.alpha));
+
+        let material = overlay_rect
+            .material()
+            .and_then(|material| unsafe { material.assume_safe() }.cast::<ShaderMaterial>());
+
+        if let Some(material) = material {
+            material.set_shader_param("iris_radius", overlay.iris_radius);
+            material.set_shader_param("slide_offset", overlay.slide_offset);
+        }
+    }
+}
+
+/// Changes the scene through the `ScreenTransition` autoload.
+pub fn change_scene(owner: &Node, path: String) {
+    let transition_node =
+        unsafe { owner.get_typed_node::<CanvasLayer, _>("/root/ScreenTransition") };
+
+    if let Some(transition) = transition_node.cast_instance::<ScreenTransition>() {
+        let _ = transition.map_mut(|transition, owner| transition.change_scene(&owner, path));
+    }
+}
+
+#[test]
+fn test_scene_changes_once_covered() {
+    let mut transition = ScreenTransition::default();
+
+    transition.start("res://scenes/World.tscn".to_string());
+
+    assert_eq!(transition.advance(0.2), None);
+    assert_eq!(
+        transition.advance(0.2),
+        Some("res://scenes/World.tscn".to_string())
+    );
+    assert_eq!(transition.progress, 1.0);
+}
+
+#[test]
+fn test_uncovers_after_changing_scene() {
+    let mut transition = ScreenTransition::default();
+
+    transition.start("res://scenes/World.tscn".to_string());
+    transition.advance(0.4);
+
+    assert_eq!(transition.advance(0.2), None);
+    assert_eq!(transition.progress, 0.5);
+
+    transition.advance(0.2);
+
+    assert_eq!(transition.progress, 0.0);
+    assert!(transition.phase.is_none());
+}
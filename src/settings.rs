@@ -0,0 +1,241 @@
+use crate::control_scheme::ControlScheme;
+use crate::extensions::NodeExt;
+use crate::transition_style::TransitionStyle;
+use gdnative::api::{AudioServer, ConfigFile};
+use gdnative::nativescript::init::property::{EnumHint, IntHint};
+use gdnative::prelude::{godot_error, ClassBuilder, Input, NativeClass, Node, Variant};
+
+const SAVE_SECTION: &str = "audio";
+const SILENT_DB: f32 = -80.0;
+
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct Settings {
+    pub(crate) control_scheme: ControlScheme,
+    // longest frame that is simulated in one step, anything longer (like
+    // dragging the window) would let bodies tunnel through walls
+    pub(crate) max_delta: f32,
+    // lets the player roll without stamina by spending a little health
+    pub(crate) emergency_dodge: bool,
+    pub(crate) transition_style: TransitionStyle,
+    // draws debug shapes like knockback arrows over bodies
+    pub(crate) debug_draw: bool,
+    // where the mute toggle is kept across sessions
+    save_path: String,
+    mute: Mute,
+}
+
+fn register_properties(builder: &ClassBuilder<Settings>) {
+    builder
+        .add_property::<i64>("control_scheme")
+        .with_hint(IntHint::Enum(EnumHint::new(
+            ControlScheme::NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )))
+        .with_getter(|settings: &Settings, _| settings.control_scheme.index())
+        .with_setter(|settings: &mut Settings, _, index| {
+            settings.control_scheme = ControlScheme::from_index(index)
+        })
+        .done();
+    builder
+        .add_property::<f32>("max_delta")
+        .with_default(0.1)
+        .with_getter(|settings: &Settings, _| settings.max_delta)
+        .with_setter(|settings: &mut Settings, _, max_delta| settings.max_delta = max_delta)
+        .done();
+    builder
+        .add_property::<bool>("emergency_dodge")
+        .with_getter(|settings: &Settings, _| settings.emergency_dodge)
+        .with_setter(|settings: &mut Settings, _, enabled| settings.emergency_dodge = enabled)
+        .done();
+    builder
+        .add_property::<i64>("transition_style")
+        .with_hint(IntHint::Enum(EnumHint::new(
+            TransitionStyle::NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )))
+        .with_getter(|settings: &Settings, _| settings.transition_style.index())
+        .with_setter(|settings: &mut Settings, _, index| {
+            settings.transition_style = TransitionStyle::from_index(index)
+        })
+        .done();
+    builder
+        .add_property::<bool>("debug_draw")
+        .with_getter(|settings: &Settings, _| settings.debug_draw)
+        .with_setter(|settings: &mut Settings, _, enabled| settings.debug_draw = enabled)
+        .done();
+    builder
+        .add_property::<String>("save_path")
+        .with_default("user://settings.cfg".to_string())
+        .with_ref_getter(|settings: &Settings, _| &settings.save_path)
+        .with_setter(|settings: &mut Settings, _, path| settings.save_path = path)
+        .done();
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            control_scheme: ControlScheme::default(),
+            max_delta: 0.1,
+            emergency_dodge: false,
+            transition_style: TransitionStyle::default(),
+            debug_draw: false,
+            save_path: "user://settings.cfg".to_string(),
+            mute: Mute::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Settings {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, _owner: &Node) {
+        let config = ConfigFile::new();
+
+        // no settings saved yet means unmuted
+        if config.load(self.save_path.as_str()).is_err() {
+            return;
+        }
+
+        if config
+            .get_value(SAVE_SECTION, "muted", Variant::new())
+            .to_bool()
+        {
+            self.toggle_mute();
+        }
+    }
+
+    #[export]
+    fn _process(&mut self, _owner: &Node, _delta: f32) {
+        if Input::godot_singleton().is_action_just_pressed("mute") {
+            self.toggle_mute();
+            self.save();
+        }
+    }
+
+    /// Mutes every audio bus, or gives them back the volumes they had.
+    fn toggle_mute(&mut self) {
+        let audio_server = AudioServer::godot_singleton();
+        let volumes: Vec<f32> = (0..audio_server.bus_count())
+            .map(|bus| audio_server.get_bus_volume_db(bus) as f32)
+            .collect();
+
+        for (bus, volume) in self.mute.toggle(&volumes).into_iter().enumerate() {
+            audio_server.set_bus_volume_db(bus as i64, volume as f64);
+        }
+    }
+
+    fn save(&self) {
+        let config = ConfigFile::new();
+
+        // kept alongside whatever else gets saved there
+        let _ = config.load(self.save_path.as_str());
+        config.set_value(SAVE_SECTION, "muted", self.mute.is_muted());
+
+        if config.save(self.save_path.as_str()).is_err() {
+            godot_error!("couldn't save the settings to {}", self.save_path);
+        }
+    }
+
+    pub fn clamp_delta(&self, delta: f32) -> f32 {
+        delta.min(self.max_delta)
+    }
+
+    /// The delta gameplay timers are ticked with, nothing while paused so
+    /// cooldowns and buffs freeze along with the rest of the game, even on
+    /// nodes that keep processing through the pause.
+    pub fn game_delta(&self, delta: f32, paused: bool) -> f32 {
+        if paused {
+            0.0
+        } else {
+            self.clamp_delta(delta)
+        }
+    }
+}
+
+/// Returns whether the `debug_draw` setting is on.
+pub fn debug_draw(owner: &Node) -> bool {
+    let settings_node = unsafe { owner.get_typed_node::<Node, _>("/root/Settings") };
+
+    settings_node
+        .cast_instance::<Settings>()
+        .and_then(|settings| settings.map(|settings, _| settings.debug_draw).ok())
+        .unwrap_or(false)
+}
+
+/// Clamps a frame delta to the `max_delta` setting, giving nothing while
+/// the tree is paused.
+pub fn game_delta(owner: &Node, delta: f32) -> f32 {
+    let settings_node = unsafe { owner.get_typed_node::<Node, _>("/root/Settings") };
+    let settings_instance = settings_node.cast_instance::<Settings>().unwrap();
+    let paused = unsafe { owner.get_tree().unwrap().assume_safe() }.is_paused();
+
+    settings_instance
+        .map(|settings, _| settings.game_delta(delta, paused))
+        .unwrap_or(delta)
+}
+
+/// Muting apart from the volumes, which are kept while muted to be given
+/// back on unmuting.
+#[derive(Default)]
+struct Mute {
+    // the volume of each bus before muting, `None` while unmuted
+    volumes: Option<Vec<f32>>,
+}
+
+impl Mute {
+    fn is_muted(&self) -> bool {
+        self.volumes.is_some()
+    }
+
+    /// Toggles the mute, returning the volume each bus goes to.
+    fn toggle(&mut self, volumes: &[f32]) -> Vec<f32> {
+        match self.volumes.take() {
+            Some(previous) => previous,
+            None => {
+                self.volumes = Some(volumes.to_vec());
+
+                vec![SILENT_DB; volumes.len()]
+            }
+        }
+    }
+}
+
+#[test]
+fn test_huge_delta_is_clamped() {
+    let settings = Settings::default();
+
+    assert_eq!(settings.clamp_delta(2.0), 0.1);
+    assert_eq!(settings.clamp_delta(0.016), 0.016);
+}
+
+#[test]
+fn test_mute_silences_every_bus() {
+    let mut mute = Mute::default();
+
+    assert_eq!(mute.toggle(&[0.0, -6.0, -12.0]), vec![SILENT_DB; 3]);
+    assert!(mute.is_muted());
+}
+
+#[test]
+fn test_unmute_restores_the_prior_volumes() {
+    let mut mute = Mute::default();
+
+    mute.toggle(&[0.0, -6.0, -12.0]);
+
+    // what the buses are at while muted doesn't matter
+    assert_eq!(
+        mute.toggle(&[SILENT_DB, SILENT_DB, SILENT_DB]),
+        vec![0.0, -6.0, -12.0]
+    );
+    assert!(!mute.is_muted());
+}
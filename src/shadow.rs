@@ -0,0 +1,132 @@
+use gdnative::prelude::{ClassBuilder, Color, NativeClass, Node2D, Vector2};
+
+/// A blob shadow drawn at the feet of the body it's a child of, shrinking
+/// and fading the higher the body is off the ground, so fliers and jumps
+/// read as being in the air.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct Shadow {
+    // the ellipse's half width and half height on the ground
+    radius: Vector2,
+    // how far above the ground the body is
+    height: f32,
+    // set by the body's `Jump`, on top of the height
+    jump_height: f32,
+    // the height the shadow is smallest and faintest at
+    max_height: f32,
+    min_scale: f32,
+    alpha: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<Shadow>) {
+    builder
+        .add_property::<Vector2>("radius")
+        .with_default(Vector2::new(7.0, 3.0))
+        .with_getter(|shadow: &Shadow, _| shadow.radius)
+        .with_setter(|shadow: &mut Shadow, owner, radius| {
+            shadow.radius = radius;
+            owner.update();
+        })
+        .done();
+    builder
+        .add_property::<f32>("height")
+        .with_getter(|shadow: &Shadow, _| shadow.height)
+        .with_setter(|shadow: &mut Shadow, owner, height| {
+            shadow.height = height;
+            owner.update();
+        })
+        .done();
+    builder
+        .add_property::<f32>("jump_height")
+        .with_getter(|shadow: &Shadow, _| shadow.jump_height)
+        .with_setter(|shadow: &mut Shadow, owner, height| {
+            shadow.jump_height = height;
+            owner.update();
+        })
+        .done();
+    builder
+        .add_property::<f32>("max_height")
+        .with_default(32.0)
+        .with_getter(|shadow: &Shadow, _| shadow.max_height)
+        .with_setter(|shadow: &mut Shadow, _, max_height| shadow.max_height = max_height)
+        .done();
+    builder
+        .add_property::<f32>("min_scale")
+        .with_default(0.6)
+        .with_getter(|shadow: &Shadow, _| shadow.min_scale)
+        .with_setter(|shadow: &mut Shadow, _, min_scale| shadow.min_scale = min_scale)
+        .done();
+    builder
+        .add_property::<f32>("alpha")
+        .with_default(0.35)
+        .with_getter(|shadow: &Shadow, _| shadow.alpha)
+        .with_setter(|shadow: &mut Shadow, _, alpha| shadow.alpha = alpha)
+        .done();
+}
+
+impl Default for Shadow {
+    fn default() -> Self {
+        Self {
+            radius: Vector2::new(7.0, 3.0),
+            height: 0.0,
+            jump_height: 0.0,
+            max_height: 32.0,
+            min_scale: 0.6,
+            alpha: 0.35,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Shadow {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _draw(&self, owner: &Node2D) {
+        let scale = shadow_scale(
+            self.height + self.jump_height,
+            self.max_height,
+            self.min_scale,
+        );
+
+        // a circle squashed into the ellipse
+        owner.draw_set_transform(Vector2::zero(), 0.0, self.radius * scale);
+        owner.draw_circle(
+            Vector2::zero(),
+            1.0,
+            Color::rgba(0.0, 0.0, 0.0, self.alpha * scale),
+        );
+    }
+}
+
+/// Returns how big the shadow is for the height, full size on the ground
+/// down to the min scale at the max height and above.
+fn shadow_scale(height: f32, max_height: f32, min_scale: f32) -> f32 {
+    if max_height <= 0.0 {
+        return 1.0;
+    }
+
+    let fraction = (height / max_height).max(0.0).min(1.0);
+
+    1.0 - (1.0 - min_scale) * fraction
+}
+
+#[test]
+fn test_shadow_full_size_on_the_ground() {
+    assert_eq!(shadow_scale(0.0, 32.0, 0.6), 1.0);
+}
+
+#[test]
+fn test_shadow_shrinks_with_height() {
+    assert_eq!(shadow_scale(16.0, 32.0, 0.5), 0.75);
+    assert_eq!(shadow_scale(32.0, 32.0, 0.5), 0.5);
+}
+
+#[test]
+fn test_shadow_scale_is_clamped() {
+    assert_eq!(shadow_scale(100.0, 32.0, 0.5), 0.5);
+    assert_eq!(shadow_scale(-8.0, 32.0, 0.5), 1.0);
+}
@@ -0,0 +1,64 @@
+use gdnative::prelude::Vector2;
+
+// whether a hit falls within an arc of the given width around the back of
+// something facing that way
+fn in_vulnerable_arc(facing: Vector2, hit_direction: Vector2, arc_degrees: f32) -> bool {
+    if arc_degrees >= 360.0 {
+        return true;
+    }
+
+    let min_facing = (arc_degrees / 2.0).to_radians().cos();
+
+    hit_direction
+        .try_normalize()
+        .map_or(false, |direction| facing.dot(direction) >= min_facing)
+}
+
+/// Armor around an enemy's front that hits clang off, so it can only be
+/// hurt from behind.
+pub struct Shell {
+    // degrees of the arc around the back that hits hurt through, a full
+    // circle leaves it open from every side
+    pub vulnerable_arc: f32,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self {
+            vulnerable_arc: 360.0,
+        }
+    }
+}
+
+impl Shell {
+    /// Returns whether a hit travelling in the direction gets past the shell.
+    pub fn is_hurt_by(&self, facing: Vector2, hit_direction: Vector2) -> bool {
+        in_vulnerable_arc(facing, hit_direction, self.vulnerable_arc)
+    }
+}
+
+#[test]
+fn test_vulnerable_arc_blocks_front_hits() {
+    let facing = Vector2::new(1.0, 0.0);
+
+    // swung left into a bat facing right, so from its front
+    assert!(!in_vulnerable_arc(facing, Vector2::new(-1.0, 0.0), 90.0));
+    assert!(!in_vulnerable_arc(facing, Vector2::new(0.0, 1.0), 90.0));
+}
+
+#[test]
+fn test_vulnerable_arc_allows_back_hits() {
+    let facing = Vector2::new(1.0, 0.0);
+
+    assert!(in_vulnerable_arc(facing, Vector2::new(1.0, 0.0), 90.0));
+    assert!(in_vulnerable_arc(facing, Vector2::new(1.0, 0.9), 90.0));
+    // a full circle is open from every side
+    assert!(in_vulnerable_arc(facing, Vector2::new(-1.0, 0.0), 360.0));
+}
+
+#[test]
+fn test_no_shell_is_hurt_from_every_side() {
+    let shell = Shell::default();
+
+    assert!(shell.is_hurt_by(Vector2::new(1.0, 0.0), Vector2::new(-1.0, 0.0)));
+}
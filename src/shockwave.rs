@@ -0,0 +1,147 @@
+use crate::entity_registry;
+use crate::explosion;
+use crate::extensions::NodeExt;
+use crate::player::Player;
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, Color, NativeClass, Node2D, Vector2};
+
+/// A ring expanding from where a heavy enemy landed. Only the thin band at
+/// its edge hurts, so a player can dodge it by being inside or outside the
+/// ring as it passes.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct Shockwave {
+    damage: i32,
+    max_radius: f32,
+    duration: f32,
+    band_width: f32,
+    elapsed: f32,
+    // instance ids of the players already hit, each is hit once per wave
+    hit: Vec<i64>,
+}
+
+fn register_properties(builder: &ClassBuilder<Shockwave>) {
+    builder
+        .add_property::<i32>("damage")
+        .with_default(1)
+        .with_getter(|shockwave: &Shockwave, _| shockwave.damage)
+        .with_setter(|shockwave: &mut Shockwave, _, damage| shockwave.damage = damage)
+        .done();
+    builder
+        .add_property::<f32>("max_radius")
+        .with_default(64.0)
+        .with_getter(|shockwave: &Shockwave, _| shockwave.max_radius)
+        .with_setter(|shockwave: &mut Shockwave, _, radius| shockwave.max_radius = radius)
+        .done();
+    builder
+        .add_property::<f32>("duration")
+        .with_default(0.6)
+        .with_getter(|shockwave: &Shockwave, _| shockwave.duration)
+        .with_setter(|shockwave: &mut Shockwave, _, duration| shockwave.duration = duration)
+        .done();
+    builder
+        .add_property::<f32>("band_width")
+        .with_default(6.0)
+        .with_getter(|shockwave: &Shockwave, _| shockwave.band_width)
+        .with_setter(|shockwave: &mut Shockwave, _, width| shockwave.band_width = width)
+        .done();
+}
+
+impl Default for Shockwave {
+    fn default() -> Self {
+        Self {
+            damage: 1,
+            max_radius: 64.0,
+            duration: 0.6,
+            band_width: 6.0,
+            elapsed: 0.0,
+            hit: Vec::new(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Shockwave {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Node2D, delta: f32) {
+        self.elapsed += delta;
+
+        if self.elapsed >= self.duration {
+            owner.queue_free();
+            return;
+        }
+
+        let players: Vec<_> = entity_registry::all_players(owner)
+            .into_iter()
+            .filter(|player| !self.hit.contains(&player.get_instance_id()))
+            .collect();
+
+        // the wave hits hurtboxes, which are direct children of the player
+        // they protect
+        let positions: Vec<Vector2> = players
+            .iter()
+            .map(|player| unsafe { player.get_typed_node::<Area2D, _>("Hurtbox") })
+            .map(|hurtbox| hurtbox.global_position())
+            .collect();
+
+        for index in explosion::targets_in_band(
+            owner.global_position(),
+            self.radius(),
+            self.band_width,
+            &positions,
+        ) {
+            let player = players[index];
+            self.hit.push(player.get_instance_id());
+
+            if let Some(player) = player.cast_instance::<Player>() {
+                let damage = self.damage;
+                let _ = player.map_mut(|player, player_owner| {
+                    player.receive_hazard_damage(&player_owner, damage)
+                });
+            }
+        }
+
+        owner.update();
+    }
+
+    #[export]
+    fn _draw(&self, owner: &Node2D) {
+        // fades out as it spreads
+        let alpha = 1.0 - self.elapsed / self.duration;
+
+        owner.draw_arc(
+            Vector2::zero(),
+            self.radius() as f64,
+            0.0,
+            std::f64::consts::PI * 2.0,
+            32,
+            Color::rgba(1.0, 0.9, 0.7, alpha),
+            self.band_width as f64,
+            false,
+        );
+    }
+
+    fn radius(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return self.max_radius;
+        }
+
+        self.max_radius * (self.elapsed / self.duration).min(1.0)
+    }
+}
+
+#[test]
+fn test_ring_expands_over_duration() {
+    let mut shockwave = Shockwave::default();
+
+    assert_eq!(shockwave.radius(), 0.0);
+
+    shockwave.elapsed = 0.3;
+
+    assert!((shockwave.radius() - 32.0).abs() < 1e-4);
+}
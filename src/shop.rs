@@ -0,0 +1,296 @@
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use crate::menu_input::MenuInput;
+use crate::player_stats::PlayerStats;
+use crate::sword_hitbox::SwordHitbox;
+use crate::weapon::Weapon;
+use gdnative::api::{Area2D, Control};
+use gdnative::prelude::{ClassBuilder, Input, Label, NativeClass, Node, TRef, Vector2};
+
+const UPGRADES: [Upgrade; 4] = [
+    Upgrade::MaxHealth,
+    Upgrade::Heal,
+    Upgrade::Damage,
+    Upgrade::CritChance,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Upgrade {
+    MaxHealth,
+    Heal,
+    Damage,
+    CritChance,
+}
+
+impl Upgrade {
+    fn label(self) -> &'static str {
+        match self {
+            Self::MaxHealth => "Max Health +1",
+            Self::Heal => "Heal 2",
+            Self::Damage => "Damage +1",
+            Self::CritChance => "Crit Chance +10%",
+        }
+    }
+
+    fn apply_to_stats(self, player_stats: &mut PlayerStats) {
+        match self {
+            Self::MaxHealth => {
+                player_stats.max_health += 1;
+                player_stats.set_health(player_stats.health + 1);
+            }
+            Self::Heal => player_stats.heal(2),
+            Self::Damage | Self::CritChance => {}
+        }
+    }
+
+    fn apply_to_weapon(self, weapon: &mut Weapon) {
+        match self {
+            Self::Damage => weapon.damage += 1,
+            Self::CritChance => weapon.crit_chance = (weapon.crit_chance + 0.1).min(1.0),
+            Self::MaxHealth | Self::Heal => {}
+        }
+    }
+}
+
+/// The shop between arena waves, where coins buy upgrades. Time stops while
+/// it's open. Spawners open it by connecting their `wave_cleared` signal to
+/// the handler below.
+#[derive(NativeClass)]
+#[inherit(Control)]
+#[register_with(register_properties)]
+pub struct Shop {
+    max_health_cost: i32,
+    heal_cost: i32,
+    damage_cost: i32,
+    crit_chance_cost: i32,
+    selected: usize,
+    menu_input: MenuInput,
+}
+
+fn register_properties(builder: &ClassBuilder<Shop>) {
+    builder
+        .add_property::<i32>("max_health_cost")
+        .with_default(10)
+        .with_getter(|shop: &Shop, _| shop.max_health_cost)
+        .with_setter(|shop: &mut Shop, _, cost| shop.max_health_cost = cost)
+        .done();
+    builder
+        .add_property::<i32>("heal_cost")
+        .with_default(5)
+        .with_getter(|shop: &Shop, _| shop.heal_cost)
+        .with_setter(|shop: &mut Shop, _, cost| shop.heal_cost = cost)
+        .done();
+    builder
+        .add_property::<i32>("damage_cost")
+        .with_default(15)
+        .with_getter(|shop: &Shop, _| shop.damage_cost)
+        .with_setter(|shop: &mut Shop, _, cost| shop.damage_cost = cost)
+        .done();
+    builder
+        .add_property::<i32>("crit_chance_cost")
+        .with_default(12)
+        .with_getter(|shop: &Shop, _| shop.crit_chance_cost)
+        .with_setter(|shop: &mut Shop, _, cost| shop.crit_chance_cost = cost)
+        .done();
+}
+
+impl Default for Shop {
+    fn default() -> Self {
+        Self {
+            max_health_cost: 10,
+            heal_cost: 5,
+            damage_cost: 15,
+            crit_chance_cost: 12,
+            selected: 0,
+            menu_input: MenuInput::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Shop {
+    fn new(_owner: &Control) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: &Control) {
+        owner.set_visible(false);
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Control, delta: f32) {
+        if !owner.is_visible() {
+            return;
+        }
+
+        let input = Input::godot_singleton();
+        let direction = Vector2::new(
+            0.0,
+            (input.get_action_strength("ui_down") - input.get_action_strength("ui_up")) as f32,
+        );
+
+        if self.menu_input.poll(direction, delta) {
+            self.select_next(direction.y.signum() as i32);
+            self.refresh(owner);
+        }
+
+        if input.is_action_just_pressed("ui_accept") {
+            self.purchase_selected(owner);
+            self.refresh(owner);
+        }
+
+        if input.is_action_just_pressed("ui_cancel") {
+            self.close(owner);
+        }
+    }
+
+    #[export]
+    fn _on_wave_cleared(&mut self, owner: &Control, _wave: i64) {
+        self.selected = 0;
+        self.refresh(owner);
+
+        owner.set_visible(true);
+        set_paused(owner, true);
+    }
+
+    fn close(&mut self, owner: &Control) {
+        owner.set_visible(false);
+        set_paused(owner, false);
+    }
+
+    fn cost(&self, upgrade: Upgrade) -> i32 {
+        match upgrade {
+            Upgrade::MaxHealth => self.max_health_cost,
+            Upgrade::Heal => self.heal_cost,
+            Upgrade::Damage => self.damage_cost,
+            Upgrade::CritChance => self.crit_chance_cost,
+        }
+    }
+
+    fn select_next(&mut self, step: i32) {
+        let count = UPGRADES.len() as i32;
+
+        self.selected = (self.selected as i32 + step).rem_euclid(count) as usize;
+    }
+
+    /// Buys the upgrade if the coins cover it, applying what it does to the
+    /// player stats. Returns the upgrade bought.
+    fn buy(&self, upgrade: Upgrade, player_stats: &mut PlayerStats) -> Option<Upgrade> {
+        let cost = self.cost(upgrade);
+
+        if player_stats.coins < cost {
+            return None;
+        }
+
+        player_stats.coins -= cost;
+        upgrade.apply_to_stats(player_stats);
+
+        Some(upgrade)
+    }
+
+    fn purchase_selected(&self, owner: &Control) {
+        let upgrade = UPGRADES[self.selected];
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let bought = player_stats_node
+            .cast_instance::<PlayerStats>()
+            .and_then(|player_stats| {
+                player_stats
+                    .map_mut(|player_stats, _| self.buy(upgrade, player_stats))
+                    .ok()
+            })
+            .flatten();
+
+        if let Some(upgrade) = bought {
+            upgrade_swords(owner, upgrade);
+        }
+    }
+
+    fn refresh(&self, owner: &Control) {
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let coins = player_stats_node
+            .cast_instance::<PlayerStats>()
+            .and_then(|player_stats| player_stats.map(|player_stats, _| player_stats.coins).ok())
+            .unwrap_or(0);
+
+        let mut lines = vec![format!("Coins: {}", coins)];
+        lines.extend(UPGRADES.iter().enumerate().map(|(index, upgrade)| {
+            let marker = if index == self.selected { ">" } else { " " };
+
+            format!("{} {} - {}", marker, upgrade.label(), self.cost(*upgrade))
+        }));
+
+        let list = unsafe { owner.get_typed_node::<Label, _>("List") };
+        list.set_text(lines.join("\n"));
+    }
+}
+
+fn set_paused(owner: &Control, paused: bool) {
+    let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+
+    tree.set_pause(paused);
+}
+
+// every local player's sword gets weapon upgrades
+fn upgrade_swords(owner: &Control, upgrade: Upgrade) {
+    for player in entity_registry::all_players(owner) {
+        let sword = player
+            .get_node("HitboxPivot/SwordHitbox")
+            .map(|sword| unsafe { sword.assume_safe() })
+            .and_then(|sword| sword.cast::<Area2D>())
+            .and_then(|sword: TRef<Area2D>| sword.cast_instance::<SwordHitbox>());
+
+        if let Some(sword) = sword {
+            let _ = sword.map_mut(|sword, _| {
+                sword.upgrade_weapons(|weapon| upgrade.apply_to_weapon(weapon))
+            });
+        }
+    }
+}
+
+#[test]
+fn test_purchase_deducts_coins() {
+    let shop = Shop::default();
+    let mut player_stats = PlayerStats {
+        coins: 12,
+        ..PlayerStats::default()
+    };
+
+    assert_eq!(
+        shop.buy(Upgrade::MaxHealth, &mut player_stats),
+        Some(Upgrade::MaxHealth)
+    );
+    assert_eq!(player_stats.coins, 2);
+    assert_eq!(player_stats.max_health, 5);
+}
+
+#[test]
+fn test_unaffordable_purchase_is_rejected() {
+    let shop = Shop::default();
+    let mut player_stats = PlayerStats {
+        coins: 4,
+        ..PlayerStats::default()
+    };
+
+    assert_eq!(shop.buy(Upgrade::Heal, &mut player_stats), None);
+    assert_eq!(player_stats.coins, 4);
+}
+
+#[test]
+fn test_weapon_upgrade_raises_damage() {
+    let mut weapon = Weapon::sword();
+
+    Upgrade::Damage.apply_to_weapon(&mut weapon);
+
+    assert_eq!(weapon.damage, 2);
+}
+
+#[test]
+fn test_selection_wraps_around() {
+    let mut shop = Shop::default();
+
+    shop.select_next(-1);
+
+    assert_eq!(shop.selected, UPGRADES.len() - 1);
+}
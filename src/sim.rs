@@ -0,0 +1,143 @@
+use crate::aggro::{self, AggroTarget};
+use crate::bat::{sight_transition, tick_search, State};
+use gdnative::prelude::Vector2;
+
+/// A headless stand-in for a bat's AI, stepping the same decisions the bat
+/// makes each physics frame with scripted player positions, so state
+/// changes can be tested without Godot. The bat stays put and leaving a
+/// search always wanders, so runs are deterministic.
+pub struct Sim {
+    pub state: State,
+    pub position: Vector2,
+    pub detection_range: f32,
+    pub search_duration: f32,
+}
+
+impl Default for Sim {
+    fn default() -> Self {
+        Self {
+            state: State::Idle,
+            position: Vector2::zero(),
+            detection_range: 64.0,
+            search_duration: 2.0,
+        }
+    }
+}
+
+impl Sim {
+    /// Steps the AI once with the player where it is this frame.
+    pub fn step(&mut self, player: Vector2, delta: f32) {
+        let targets = [AggroTarget {
+            position: player,
+            taunt: 0.0,
+        }];
+        let in_sight = aggro::pick_target(self.position, &targets, self.detection_range).is_some();
+
+        if let Some(state) = sight_transition(&self.state, in_sight, self.search_duration) {
+            self.state = state;
+        }
+
+        if let State::Search(time_left) = self.state {
+            self.state = match tick_search(time_left, delta) {
+                Some(time_left) => State::Search(time_left),
+                None => State::Wander,
+            };
+        }
+    }
+
+    /// Steps through the player positions a fixed delta apart, returning
+    /// the states the AI went through, each once per change.
+    pub fn run(&mut self, players: &[Vector2], delta: f32) -> Vec<&'static str> {
+        let mut states = vec![state_name(&self.state)];
+
+        for player in players {
+            self.step(*player, delta);
+
+            let name = state_name(&self.state);
+
+            if states.last() != Some(&name) {
+                states.push(name);
+            }
+        }
+
+        states
+    }
+}
+
+fn state_name(state: &State) -> &'static str {
+    match state {
+        State::Spawning(_) => "Spawning",
+        State::Staggered(_) => "Staggered",
+        State::FeigningDeath(_) => "FeigningDeath",
+        State::Fleeing(_) => "Fleeing",
+        State::FleeingToNest => "FleeingToNest",
+        State::CounterWindup(_) => "CounterWindup",
+        State::DeathSlide { .. } => "DeathSlide",
+        State::Idle => "Idle",
+        State::Wander => "Wander",
+        State::Chase => "Chase",
+        State::Search(_) => "Search",
+    }
+}
+
+// the player walking from out of range up to the bat, then back out
+#[cfg(test)]
+fn approach_and_retreat() -> Vec<Vector2> {
+    let approach = (0..10).map(|step| Vector2::new(100.0 - step as f32 * 8.0, 0.0));
+    let retreat = (0..60).map(|step| Vector2::new(28.0 + step as f32 * 8.0, 0.0));
+
+    approach.chain(retreat).collect()
+}
+
+#[test]
+fn test_approach_and_retreat_chases_searches_then_wanders() {
+    let mut sim = Sim::default();
+
+    let states = sim.run(&approach_and_retreat(), 0.1);
+
+    assert_eq!(states, vec!["Idle", "Chase", "Search", "Wander"]);
+}
+
+#[test]
+fn test_search_lasts_its_duration() {
+    let mut sim = Sim {
+        state: State::Chase,
+        ..Sim::default()
+    };
+    let far = Vector2::new(200.0, 0.0);
+
+    sim.step(far, 0.5);
+    sim.step(far, 0.5);
+    sim.step(far, 0.5);
+
+    assert!(matches!(sim.state, State::Search(_)));
+
+    sim.step(far, 0.5);
+
+    assert!(matches!(sim.state, State::Wander));
+}
+
+#[test]
+fn test_target_found_while_searching_is_chased() {
+    let mut sim = Sim {
+        state: State::Search(1.0),
+        ..Sim::default()
+    };
+
+    sim.step(Vector2::new(10.0, 0.0), 0.1);
+
+    assert!(matches!(sim.state, State::Chase));
+}
+
+#[test]
+fn test_no_search_goes_idle() {
+    let mut sim = Sim {
+        state: State::Chase,
+        search_duration: 0.0,
+        ..Sim::default()
+    };
+
+    let states = sim.run(&[Vector2::new(200.0, 0.0)], 0.1);
+
+    assert_eq!(states, vec!["Chase", "Idle"]);
+}
@@ -0,0 +1,128 @@
+use crate::cooldown::Cooldown;
+use crate::jump::Jump;
+use crate::load_scene;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node2D, TRef};
+
+/// Sits under a heavy enemy and makes it hop up every now and then, sending
+/// out a shockwave when it lands.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct Slammer {
+    shockwave_scene: String,
+    // counts down to the next hop while on the ground, every slam interval
+    hop_cooldown: Cooldown,
+    jump: Jump,
+}
+
+fn register_properties(builder: &ClassBuilder<Slammer>) {
+    builder
+        .add_property::<f32>("slam_interval")
+        .with_default(3.0)
+        .with_getter(|slammer: &Slammer, _| slammer.hop_cooldown.duration)
+        .with_setter(|slammer: &mut Slammer, _, interval| slammer.hop_cooldown.duration = interval)
+        .done();
+    builder
+        .add_property::<f32>("hop_duration")
+        .with_default(0.5)
+        .with_getter(|slammer: &Slammer, _| slammer.jump.duration)
+        .with_setter(|slammer: &mut Slammer, _, duration| slammer.jump.duration = duration)
+        .done();
+    builder
+        .add_property::<f32>("hop_height")
+        .with_default(16.0)
+        .with_getter(|slammer: &Slammer, _| slammer.jump.apex)
+        .with_setter(|slammer: &mut Slammer, _, height| slammer.jump.apex = height)
+        .done();
+    builder
+        .add_property::<String>("shockwave_scene")
+        .with_default("res://scenes/Shockwave.tscn".to_string())
+        .with_ref_getter(|slammer: &Slammer, _| &slammer.shockwave_scene)
+        .with_setter(|slammer: &mut Slammer, _, scene| slammer.shockwave_scene = scene)
+        .done();
+}
+
+impl Default for Slammer {
+    fn default() -> Self {
+        Self {
+            shockwave_scene: "res://scenes/Shockwave.tscn".to_string(),
+            hop_cooldown: Cooldown::new(3.0),
+            jump: Jump::new(0.5, 16.0),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Slammer {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, _owner: &Node2D) {
+        self.hop_cooldown.start();
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Node2D, delta: f32) {
+        let landed = self.update(delta);
+
+        let enemy = unsafe { owner.get_parent().unwrap().assume_safe() };
+
+        self.jump.lift(&enemy);
+
+        if landed {
+            self.slam(owner);
+        }
+    }
+
+    /// Returns whether the enemy landed this frame.
+    fn update(&mut self, delta: f32) -> bool {
+        if self.jump.is_airborne() {
+            let landed = self.jump.advance(delta);
+
+            if landed {
+                self.hop_cooldown.start();
+            }
+
+            return landed;
+        }
+
+        self.hop_cooldown.tick(delta);
+
+        if self.hop_cooldown.is_ready() {
+            self.jump.start();
+        }
+
+        false
+    }
+
+    fn slam(&self, owner: &Node2D) {
+        let shockwave_scene = match load_scene(&self.shockwave_scene) {
+            Some(scene) => scene,
+            None => return,
+        };
+
+        let shockwave_node = unsafe { shockwave_scene.instance(0).unwrap().assume_safe() };
+        let shockwave: TRef<Node2D> = shockwave_node.cast().unwrap();
+
+        shockwave.set_global_position(owner.global_position());
+
+        // the shockwave is a sibling of the enemy, so it keeps spreading if
+        // the enemy dies
+        let enemy = unsafe { owner.get_parent().unwrap().assume_safe() };
+        let enemy_parent = unsafe { enemy.get_parent().unwrap().assume_safe() };
+
+        enemy_parent.add_child(shockwave, false);
+    }
+}
+
+#[test]
+fn test_lands_after_hop() {
+    let mut slammer = Slammer::default();
+
+    assert!(!slammer.update(3.0));
+    assert!(!slammer.update(0.25));
+    assert!(slammer.update(0.25));
+    assert_eq!(slammer.jump.height(), 0.0);
+}
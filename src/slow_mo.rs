@@ -0,0 +1,153 @@
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use crate::player_camera::PlayerCamera;
+use gdnative::api::{Camera2D, Engine};
+use gdnative::prelude::{ClassBuilder, NativeClass, Node, Vector2};
+
+/// Slows the whole game down for a moment and zooms the camera in on the
+/// kill when the last enemy dies. Reached through the `SlowMo` autoload.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct SlowMo {
+    time_scale: f32,
+    // in real seconds, not slowed down ones
+    duration: f32,
+    zoom: f32,
+    time_left: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<SlowMo>) {
+    builder
+        .add_property::<f32>("time_scale")
+        .with_default(0.25)
+        .with_getter(|slow_mo: &SlowMo, _| slow_mo.time_scale)
+        .with_setter(|slow_mo: &mut SlowMo, _, time_scale| slow_mo.time_scale = time_scale)
+        .done();
+    builder
+        .add_property::<f32>("duration")
+        .with_default(0.8)
+        .with_getter(|slow_mo: &SlowMo, _| slow_mo.duration)
+        .with_setter(|slow_mo: &mut SlowMo, _, duration| slow_mo.duration = duration)
+        .done();
+    // below one zooms in
+    builder
+        .add_property::<f32>("zoom")
+        .with_default(0.6)
+        .with_getter(|slow_mo: &SlowMo, _| slow_mo.zoom)
+        .with_setter(|slow_mo: &mut SlowMo, _, zoom| slow_mo.zoom = zoom)
+        .done();
+}
+
+impl Default for SlowMo {
+    fn default() -> Self {
+        Self {
+            time_scale: 0.25,
+            duration: 0.8,
+            zoom: 0.6,
+            time_left: 0.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl SlowMo {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Node, delta: f32) {
+        if self.time_left <= 0.0 {
+            return;
+        }
+
+        // the delta is slowed down along with everything else
+        let engine = Engine::godot_singleton();
+        let real_delta = delta / engine.time_scale().max(0.01) as f32;
+
+        if self.update(real_delta) {
+            engine.set_time_scale(1.0);
+
+            with_camera(owner, |camera| camera.release_focus());
+        }
+    }
+
+    pub fn trigger(&mut self, owner: &Node, position: Vector2) {
+        self.time_left = self.duration;
+
+        Engine::godot_singleton().set_time_scale(self.time_scale as f64);
+
+        let zoom = self.zoom;
+        with_camera(owner, |camera| camera.focus_on(position, zoom));
+    }
+
+    /// Counts down in real seconds, returning whether the slow-mo ended.
+    fn update(&mut self, real_delta: f32) -> bool {
+        self.time_left -= real_delta;
+
+        self.time_left <= 0.0
+    }
+}
+
+fn with_camera(owner: &Node, f: impl FnOnce(&mut PlayerCamera)) {
+    let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+    let camera = tree
+        .get_nodes_in_group("player_camera")
+        .iter()
+        .find_map(|camera| camera.try_to_object::<Camera2D>())
+        .map(|camera| unsafe { camera.assume_safe() })
+        .and_then(|camera| camera.cast_instance::<PlayerCamera>());
+
+    if let Some(camera) = camera {
+        let _ = camera.map_mut(|camera, _| f(camera));
+    }
+}
+
+/// Returns whether a kill left no enemy alive. Enemies killed this frame
+/// stay registered until they leave the tree, so they're told apart by whether
+/// they're queued for deletion.
+pub fn is_last_kill(queued_for_deletion: &[bool]) -> bool {
+    queued_for_deletion.iter().all(|queued| *queued)
+}
+
+/// Triggers the slow-mo through the `SlowMo` autoload if the enemy killed at
+/// `position` was the last one.
+pub fn on_enemy_killed(owner: &Node, position: Vector2) {
+    let queued_for_deletion: Vec<bool> = entity_registry::all_enemies(owner)
+        .iter()
+        .map(|enemy| enemy.is_queued_for_deletion())
+        .collect();
+
+    if !is_last_kill(&queued_for_deletion) {
+        return;
+    }
+
+    let slow_mo_node = unsafe { owner.get_typed_node::<Node, _>("/root/SlowMo") };
+    let slow_mo_instance = slow_mo_node.cast_instance::<SlowMo>().unwrap();
+
+    let _ = slow_mo_instance
+        .map_mut(|slow_mo, slow_mo_owner| slow_mo.trigger(&slow_mo_owner, position));
+}
+
+#[test]
+fn test_last_kill_when_every_enemy_is_dying() {
+    assert!(is_last_kill(&[true]));
+    assert!(is_last_kill(&[true, true]));
+}
+
+#[test]
+fn test_not_last_kill_while_enemies_remain() {
+    assert!(!is_last_kill(&[true, false]));
+}
+
+#[test]
+fn test_slow_mo_ends_after_duration() {
+    let mut slow_mo = SlowMo {
+        time_left: 0.8,
+        ..SlowMo::default()
+    };
+
+    assert!(!slow_mo.update(0.5));
+    assert!(slow_mo.update(0.3));
+}
@@ -0,0 +1,187 @@
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use crate::player::Player;
+use crate::player_stats::PlayerStats;
+use crate::sword_hitbox::SwordHitbox;
+use crate::weapon::Weapon;
+use gdnative::api::{Area2D, Control};
+use gdnative::prelude::{Input, KinematicBody2D, Label, NativeClass, Node, TRef};
+
+/// Debug panel of the player's live stats, buffs and weapon, toggled with
+/// `stat_inspector`. Reached through the `StatInspector` autoload.
+#[derive(NativeClass)]
+#[inherit(Control)]
+pub struct StatInspector;
+
+#[gdnative::methods]
+impl StatInspector {
+    fn new(_owner: &Control) -> Self {
+        Self
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Control, _delta: f32) {
+        if Input::godot_singleton().is_action_just_pressed("stat_inspector") {
+            owner.set_visible(!owner.is_visible());
+        }
+
+        if !owner.is_visible() {
+            return;
+        }
+
+        let readout = unsafe { owner.get_typed_node::<Label, _>("Readout") };
+        let mut lines = Vec::new();
+        let mut buffs = Vec::new();
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+
+        if let Some(player_stats) = player_stats_node.cast_instance::<PlayerStats>() {
+            let _ = player_stats.map(|player_stats, _| {
+                lines.push(format!(
+                    "hp: {}/{} (+{:.0} shield)",
+                    player_stats.health, player_stats.max_health, player_stats.shield
+                ));
+                lines.push(format!("coins: {}", player_stats.coins));
+
+                let magnet_time_left = player_stats.magnet_buff_time_left();
+
+                if magnet_time_left > 0.0 {
+                    buffs.push(ActiveBuff {
+                        name: "magnet",
+                        time_left: Some(magnet_time_left),
+                    });
+                }
+            });
+        }
+
+        // the first player's, like the rest of the hud
+        let player = entity_registry::all_players(owner).into_iter().next();
+
+        if let Some(player) = player {
+            inspect_player(player, &mut lines, &mut buffs);
+        }
+
+        lines.push(format!("buffs: {}", format_buffs(&buffs)));
+
+        readout.set_text(lines.join("\n"));
+    }
+}
+
+fn inspect_player(
+    player: TRef<KinematicBody2D>,
+    lines: &mut Vec<String>,
+    buffs: &mut Vec<ActiveBuff>,
+) {
+    if let Some(player_instance) = player.cast_instance::<Player>() {
+        let _ = player_instance.map(|player, _| {
+            let (stamina, max_stamina) = player.stamina();
+
+            lines.push(format!("stamina: {:.0}/{:.0}", stamina, max_stamina));
+
+            let states: Vec<_> = player
+                .transition_history()
+                .map(|(_, state)| state)
+                .collect();
+
+            if !states.is_empty() {
+                lines.push(format!("states: {}", states.join(" > ")));
+            }
+        });
+    }
+
+    let sword_hitbox_node =
+        unsafe { player.get_typed_node::<Area2D, _>("HitboxPivot/SwordHitbox") };
+
+    if let Some(sword_hitbox) = sword_hitbox_node.cast_instance::<SwordHitbox>() {
+        let _ = sword_hitbox.map(|sword_hitbox, _| {
+            lines.push(format!("weapon: {}", describe_weapon(&sword_hitbox.weapon)));
+
+            if let Some(time_left) = sword_hitbox.temporary_weapon_time_left() {
+                buffs.push(ActiveBuff {
+                    name: "temporary weapon",
+                    time_left: Some(time_left),
+                });
+            }
+
+            if sword_hitbox.is_counter_ready() {
+                buffs.push(ActiveBuff {
+                    name: "counter",
+                    time_left: None,
+                });
+            }
+        });
+    }
+}
+
+struct ActiveBuff {
+    name: &'static str,
+    // `None` for buffs that last until used
+    time_left: Option<f32>,
+}
+
+fn format_buffs(buffs: &[ActiveBuff]) -> String {
+    if buffs.is_empty() {
+        return "none".to_string();
+    }
+
+    buffs
+        .iter()
+        .map(|buff| match buff.time_left {
+            Some(time_left) => format!("{} ({:.1}s)", buff.name, time_left),
+            None => buff.name.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_weapon(weapon: &Weapon) -> String {
+    let durability = weapon
+        .durability
+        .map_or("unbreakable".to_string(), |durability| {
+            format!("{} uses", durability)
+        });
+
+    format!(
+        "{} dmg, {:.0}% crit x{}, {}",
+        weapon.damage,
+        weapon.crit_chance * 100.0,
+        weapon.crit_multiplier,
+        durability
+    )
+}
+
+#[test]
+fn test_format_several_buffs() {
+    let buffs = [
+        ActiveBuff {
+            name: "magnet",
+            time_left: Some(3.3),
+        },
+        ActiveBuff {
+            name: "temporary weapon",
+            time_left: Some(9.0),
+        },
+        ActiveBuff {
+            name: "counter",
+            time_left: None,
+        },
+    ];
+
+    assert_eq!(
+        format_buffs(&buffs),
+        "magnet (3.3s), temporary weapon (9.0s), counter"
+    );
+}
+
+#[test]
+fn test_format_no_buffs() {
+    assert_eq!(format_buffs(&[]), "none");
+}
+
+#[test]
+fn test_describe_weapon() {
+    assert_eq!(
+        describe_weapon(&Weapon::sword()),
+        "1 dmg, 0% crit x2, unbreakable"
+    );
+}
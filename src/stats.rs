@@ -1,4 +1,7 @@
-use gdnative::prelude::{ClassBuilder, NativeClass, Node};
+use gdnative::prelude::{
+    ClassBuilder, ExportInfo, NativeClass, Node, PropertyUsage, Signal, SignalArgument, ToVariant,
+    Variant, VariantType,
+};
 
 #[derive(NativeClass)]
 #[inherit(Node)]
@@ -7,6 +10,9 @@ use gdnative::prelude::{ClassBuilder, NativeClass, Node};
 pub struct Stats {
     pub(crate) max_health: i32,
     pub(crate) health: i32,
+    pub(crate) execute_threshold: i32,
+    // health last sent with `health_changed`
+    reported_health: Option<i32>,
 }
 
 fn register_properties(builder: &ClassBuilder<Stats>) {
@@ -15,6 +21,34 @@ fn register_properties(builder: &ClassBuilder<Stats>) {
         .with_getter(|stats: &Stats, _| stats.max_health)
         .with_setter(|stats: &mut Stats, _, max_health| stats.max_health = max_health)
         .done();
+    // hits that leave the health at or below this are finishers, zero
+    // disables executes
+    builder
+        .add_property::<i32>("execute_threshold")
+        .with_default(0)
+        .with_getter(|stats: &Stats, _| stats.execute_threshold)
+        .with_setter(|stats: &mut Stats, _, execute_threshold| {
+            stats.execute_threshold = execute_threshold
+        })
+        .done();
+
+    builder.add_signal(Signal {
+        name: "health_changed",
+        args: &[
+            SignalArgument {
+                name: "health",
+                default: Variant::new(),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            },
+            SignalArgument {
+                name: "max_health",
+                default: Variant::new(),
+                export_info: ExportInfo::new(VariantType::I64),
+                usage: PropertyUsage::DEFAULT,
+            },
+        ],
+    });
 }
 
 #[gdnative::methods]
@@ -24,6 +58,8 @@ impl Stats {
         Self {
             max_health,
             health: max_health,
+            execute_threshold: 0,
+            reported_health: None,
         }
     }
 
@@ -32,6 +68,27 @@ impl Stats {
         self.health = self.max_health
     }
 
+    // checked every frame so changes from anywhere are sent
+    #[export]
+    fn _process(&mut self, owner: &Node, _delta: f32) {
+        if let Some(health) = self.take_health_change() {
+            owner.emit_signal(
+                "health_changed",
+                &[health.to_variant(), self.max_health.to_variant()],
+            );
+        }
+    }
+
+    fn take_health_change(&mut self) -> Option<i32> {
+        if self.reported_health == Some(self.health) {
+            return None;
+        }
+
+        self.reported_health = Some(self.health);
+
+        Some(self.health)
+    }
+
     pub fn receive_damage(&mut self, amount: i32) -> State {
         self.health -= amount;
 
@@ -39,6 +96,12 @@ impl Stats {
             return State::Dead;
         }
 
+        if self.health <= self.execute_threshold {
+            self.health = 0;
+
+            return State::Executed;
+        }
+
         return State::Alive;
     }
 }
@@ -46,4 +109,60 @@ impl Stats {
 pub enum State {
     Alive,
     Dead,
+    Executed,
+}
+
+#[test]
+fn test_execute_at_threshold() {
+    let mut stats = Stats {
+        max_health: 4,
+        health: 4,
+        execute_threshold: 1,
+        ..Stats::default()
+    };
+
+    assert!(matches!(stats.receive_damage(3), State::Executed));
+    assert_eq!(stats.health, 0);
+}
+
+#[test]
+fn test_execute_below_threshold() {
+    let mut stats = Stats {
+        max_health: 4,
+        health: 4,
+        execute_threshold: 2,
+        ..Stats::default()
+    };
+
+    assert!(matches!(stats.receive_damage(3), State::Executed));
+    assert_eq!(stats.health, 0);
+}
+
+#[test]
+fn test_no_execute_above_threshold() {
+    let mut stats = Stats {
+        max_health: 4,
+        health: 4,
+        execute_threshold: 1,
+        ..Stats::default()
+    };
+
+    assert!(matches!(stats.receive_damage(2), State::Alive));
+    assert_eq!(stats.health, 2);
+}
+
+#[test]
+fn test_health_change_is_reported_once() {
+    let mut stats = Stats {
+        max_health: 4,
+        health: 4,
+        ..Stats::default()
+    };
+
+    assert_eq!(stats.take_health_change(), Some(4));
+    assert_eq!(stats.take_health_change(), None);
+
+    stats.receive_damage(1);
+
+    assert_eq!(stats.take_health_change(), Some(3));
 }
@@ -0,0 +1,313 @@
+use crate::entity_registry;
+use crate::load_scene;
+use gdnative::nativescript::init::property::{EnumHint, IntHint};
+use gdnative::prelude::{
+    godot_error, godot_print, ClassBuilder, GodotObject, NativeClass, Node, Node2D, TRef, Vector2,
+};
+
+/// How a batch of enemies is laid out around the player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formation {
+    // rows as close to a square as they can be
+    Grid,
+    // evenly around a circle, spacing apart
+    Ring,
+    // a single row, centred on the player
+    Line,
+}
+
+impl Default for Formation {
+    fn default() -> Self {
+        Self::Grid
+    }
+}
+
+impl Formation {
+    pub const NAMES: [&'static str; 3] = ["Grid", "Ring", "Line"];
+
+    pub fn from_index(index: i64) -> Self {
+        match index {
+            1 => Self::Ring,
+            2 => Self::Line,
+            _ => Self::Grid,
+        }
+    }
+
+    pub fn index(self) -> i64 {
+        match self {
+            Self::Grid => 0,
+            Self::Ring => 1,
+            Self::Line => 2,
+        }
+    }
+
+    /// Returns the positions of `count` enemies around the centre.
+    fn positions(self, count: usize, centre: Vector2, spacing: f32) -> Vec<Vector2> {
+        match self {
+            Self::Grid => {
+                let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+                let rows = (count + columns - 1) / columns;
+                let origin =
+                    centre - Vector2::new(columns as f32 - 1.0, rows as f32 - 1.0) * spacing / 2.0;
+
+                (0..count)
+                    .map(|i| {
+                        origin + Vector2::new((i % columns) as f32, (i / columns) as f32) * spacing
+                    })
+                    .collect()
+            }
+            Self::Ring => {
+                // round enough that neighbours are spacing apart
+                let radius = spacing * count as f32 / std::f32::consts::TAU;
+
+                (0..count)
+                    .map(|i| {
+                        let angle = std::f32::consts::TAU * i as f32 / count as f32;
+
+                        centre + Vector2::new(angle.cos(), angle.sin()) * radius
+                    })
+                    .collect()
+            }
+            Self::Line => {
+                let origin = centre - Vector2::new((count as f32 - 1.0) * spacing / 2.0, 0.0);
+
+                (0..count)
+                    .map(|i| origin + Vector2::new(i as f32 * spacing, 0.0))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The enemies spawned for stress testing, up to a cap, so a typo in a
+/// batch size can't hang the game.
+struct ScenePool {
+    capacity: usize,
+    // instance ids of the spawned enemies that are still alive
+    spawned: Vec<i64>,
+}
+
+impl ScenePool {
+    fn available(&self) -> usize {
+        self.capacity.saturating_sub(self.spawned.len())
+    }
+}
+
+/// Returns where to spawn a batch, as much of it as the pool has room for.
+fn batch_positions(
+    pool: &ScenePool,
+    count: usize,
+    formation: Formation,
+    centre: Vector2,
+    spacing: f32,
+) -> Vec<Vector2> {
+    formation.positions(count.min(pool.available()), centre, spacing)
+}
+
+/// Reads a command like `spawn bat 50` into the scene and the batch size.
+fn parse_command(command: &str) -> Option<(String, usize)> {
+    let mut words = command.split_whitespace();
+
+    if words.next()? != "spawn" {
+        return None;
+    }
+
+    let name = words.next()?;
+    let count = words.next()?.parse().ok()?;
+
+    let mut chars = name.chars();
+    let scene_name: String = chars
+        .next()?
+        .to_uppercase()
+        .chain(chars.flat_map(char::to_lowercase))
+        .collect();
+
+    Some((format!("res://scenes/{}.tscn", scene_name), count))
+}
+
+/// Debug commands for load testing, like `spawn bat 50` spawning a batch of
+/// bats around the player and reporting the frame time that follows. Run
+/// through `run_command` on the `StressTest` autoload.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct StressTest {
+    formation: Formation,
+    spacing: f32,
+    pool: ScenePool,
+    // the size of the last batch, until the frame after it is measured
+    pending_report: Option<usize>,
+}
+
+fn register_properties(builder: &ClassBuilder<StressTest>) {
+    builder
+        .add_property::<i64>("formation")
+        .with_hint(IntHint::Enum(EnumHint::new(
+            Formation::NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        )))
+        .with_getter(|stress_test: &StressTest, _| stress_test.formation.index())
+        .with_setter(|stress_test: &mut StressTest, _, index| {
+            stress_test.formation = Formation::from_index(index)
+        })
+        .done();
+    builder
+        .add_property::<f32>("spacing")
+        .with_default(24.0)
+        .with_getter(|stress_test: &StressTest, _| stress_test.spacing)
+        .with_setter(|stress_test: &mut StressTest, _, spacing| stress_test.spacing = spacing)
+        .done();
+    builder
+        .add_property::<i64>("pool_size")
+        .with_default(500)
+        .with_getter(|stress_test: &StressTest, _| stress_test.pool.capacity as i64)
+        .with_setter(|stress_test: &mut StressTest, _, size| {
+            stress_test.pool.capacity = size.max(0) as usize
+        })
+        .done();
+}
+
+impl Default for StressTest {
+    fn default() -> Self {
+        Self {
+            formation: Formation::default(),
+            spacing: 24.0,
+            pool: ScenePool {
+                capacity: 500,
+                spawned: Vec::new(),
+            },
+            pending_report: None,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl StressTest {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _process(&mut self, _owner: &Node, delta: f32) {
+        if let Some(count) = self.pending_report.take() {
+            godot_print!(
+                "spawned {}, {} alive, frame took {:.2} ms",
+                count,
+                self.pool.spawned.len(),
+                delta * 1000.0
+            );
+        }
+    }
+
+    #[export]
+    fn run_command(&mut self, owner: &Node, command: String) {
+        match parse_command(&command) {
+            Some((scene, count)) => {
+                let spawned = self.batch_spawn(owner, &scene, count, self.formation);
+                self.pending_report = Some(spawned);
+            }
+            None => godot_error!("unknown command: {}", command),
+        }
+    }
+
+    /// Spawns up to `count` of the scene around the first player in the
+    /// formation, returning how many the pool had room for.
+    pub fn batch_spawn(
+        &mut self,
+        owner: &Node,
+        scene: &str,
+        count: usize,
+        formation: Formation,
+    ) -> usize {
+        let scene = match load_scene(scene) {
+            Some(scene) => scene,
+            None => {
+                godot_error!("couldn't load {}", scene);
+                return 0;
+            }
+        };
+
+        // enemies killed since the last batch free up the pool
+        self.pool
+            .spawned
+            .retain(|id| unsafe { Node::try_from_instance_id(*id) }.is_some());
+
+        let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+        let room = match tree.current_scene() {
+            Some(room) => unsafe { room.assume_safe() },
+            None => return 0,
+        };
+
+        let centre = entity_registry::all_players(owner)
+            .first()
+            .map(|player| player.global_position())
+            .unwrap_or_else(Vector2::zero);
+
+        let positions = batch_positions(&self.pool, count, formation, centre, self.spacing);
+
+        for position in &positions {
+            let enemy_node = unsafe { scene.instance(0).unwrap().assume_safe() };
+            let enemy: TRef<Node2D> = enemy_node.cast().unwrap();
+
+            enemy.set_global_position(*position);
+            self.pool.spawned.push(enemy.get_instance_id());
+
+            room.add_child(enemy, false);
+        }
+
+        positions.len()
+    }
+}
+
+#[test]
+fn test_batch_has_a_position_per_enemy() {
+    let pool = ScenePool {
+        capacity: 500,
+        spawned: Vec::new(),
+    };
+
+    for formation in &[Formation::Grid, Formation::Ring, Formation::Line] {
+        let positions = batch_positions(&pool, 50, *formation, Vector2::zero(), 24.0);
+
+        assert_eq!(positions.len(), 50);
+    }
+}
+
+#[test]
+fn test_batch_respects_the_pool() {
+    let pool = ScenePool {
+        capacity: 10,
+        spawned: vec![1, 2, 3, 4],
+    };
+
+    let positions = batch_positions(&pool, 50, Formation::Grid, Vector2::zero(), 24.0);
+
+    assert_eq!(positions.len(), 6);
+}
+
+#[test]
+fn test_grid_is_centred() {
+    let positions = Formation::Grid.positions(4, Vector2::new(100.0, 100.0), 20.0);
+
+    assert_eq!(
+        positions,
+        vec![
+            Vector2::new(90.0, 90.0),
+            Vector2::new(110.0, 90.0),
+            Vector2::new(90.0, 110.0),
+            Vector2::new(110.0, 110.0),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_spawn_command() {
+    assert_eq!(
+        parse_command("spawn bat 50"),
+        Some(("res://scenes/Bat.tscn".to_string(), 50))
+    );
+    assert_eq!(parse_command("spawn bat lots"), None);
+    assert_eq!(parse_command("kill bat 50"), None);
+}
@@ -0,0 +1,197 @@
+use crate::cooldown::Cooldown;
+use crate::load_scene;
+use crate::settings;
+use gdnative::prelude::{ClassBuilder, GodotObject, NativeClass, Node, Node2D, TRef};
+
+/// Sits under an enemy and periodically summons minions next to it, up to a
+/// cap. Minions go through their own spawn animation before they can act.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct Summoner {
+    summon_cooldown: Cooldown,
+    minion_scene: String,
+    max_minions: i64,
+    despawn_minions_on_death: bool,
+    // instance ids of the minions summoned so far that are still alive
+    minions: Vec<i64>,
+}
+
+fn register_properties(builder: &ClassBuilder<Summoner>) {
+    builder
+        .add_property::<f32>("summon_interval")
+        .with_default(4.0)
+        .with_getter(|summoner: &Summoner, _| summoner.summon_cooldown.duration)
+        .with_setter(|summoner: &mut Summoner, _, interval| {
+            summoner.summon_cooldown.duration = interval
+        })
+        .done();
+    builder
+        .add_property::<String>("minion_scene")
+        .with_default("res://scenes/Bat.tscn".to_string())
+        .with_ref_getter(|summoner: &Summoner, _| &summoner.minion_scene)
+        .with_setter(|summoner: &mut Summoner, _, scene| summoner.minion_scene = scene)
+        .done();
+    builder
+        .add_property::<i64>("max_minions")
+        .with_default(3)
+        .with_getter(|summoner: &Summoner, _| summoner.max_minions)
+        .with_setter(|summoner: &mut Summoner, _, max_minions| summoner.max_minions = max_minions)
+        .done();
+    builder
+        .add_property::<bool>("despawn_minions_on_death")
+        .with_default(true)
+        .with_getter(|summoner: &Summoner, _| summoner.despawn_minions_on_death)
+        .with_setter(|summoner: &mut Summoner, _, despawn| {
+            summoner.despawn_minions_on_death = despawn
+        })
+        .done();
+}
+
+impl Default for Summoner {
+    fn default() -> Self {
+        let mut summon_cooldown = Cooldown::new(4.0);
+        summon_cooldown.start();
+
+        Self {
+            summon_cooldown,
+            minion_scene: "res://scenes/Bat.tscn".to_string(),
+            max_minions: 3,
+            despawn_minions_on_death: true,
+            minions: Vec::new(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Summoner {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, _owner: &Node2D) {
+        self.summon_cooldown.start();
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Node2D, delta: f32) {
+        let delta = settings::game_delta(owner, delta);
+
+        // minions killed by the player are forgotten so new ones can replace
+        // them
+        self.minions
+            .retain(|id| unsafe { Node::try_from_instance_id(*id) }.is_some());
+
+        if self.update(delta) {
+            self.summon(owner);
+        }
+    }
+
+    // the summoner leaves the tree along with the enemy it sits under
+    #[export]
+    fn _exit_tree(&mut self, _owner: &Node2D) {
+        for id in self.on_death() {
+            if let Some(minion) = unsafe { Node::try_from_instance_id(id) } {
+                minion.queue_free();
+            }
+        }
+    }
+
+    /// Returns whether a minion should be summoned this frame.
+    pub fn update(&mut self, delta: f32) -> bool {
+        if self.minions.len() as i64 >= self.max_minions {
+            self.summon_cooldown.start();
+            return false;
+        }
+
+        self.summon_cooldown.tick(delta);
+
+        if !self.summon_cooldown.is_ready() {
+            return false;
+        }
+
+        self.summon_cooldown.start();
+
+        true
+    }
+
+    /// Returns the minions to despawn now that the summoner is gone.
+    pub fn on_death(&mut self) -> Vec<i64> {
+        let minions = std::mem::take(&mut self.minions);
+
+        if self.despawn_minions_on_death {
+            minions
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn summon(&mut self, owner: &Node2D) {
+        let minion_scene = match load_scene(&self.minion_scene) {
+            Some(scene) => scene,
+            None => return,
+        };
+
+        let minion_node = unsafe { minion_scene.instance(0).unwrap().assume_safe() };
+        let minion: TRef<Node2D> = minion_node.cast().unwrap();
+
+        minion.set_global_position(owner.global_position());
+
+        self.minions.push(minion.get_instance_id());
+
+        // minions are siblings of the summoning enemy, not its children, so
+        // they're not freed along with it
+        let enemy = unsafe { owner.get_parent().unwrap().assume_safe() };
+        let enemy_parent = unsafe { enemy.get_parent().unwrap().assume_safe() };
+
+        enemy_parent.add_child(minion, false);
+    }
+}
+
+#[test]
+fn test_summons_after_interval() {
+    let mut summoner = Summoner::default();
+
+    assert!(!summoner.update(3.0));
+    assert!(summoner.update(1.0));
+    assert!(!summoner.update(1.0));
+}
+
+#[test]
+fn test_stops_summoning_at_cap() {
+    let mut summoner = Summoner {
+        max_minions: 2,
+        minions: vec![1, 2],
+        ..Summoner::default()
+    };
+
+    assert!(!summoner.update(10.0));
+
+    // a minion dying frees a slot, and the interval starts over
+    summoner.minions.pop();
+
+    assert!(!summoner.update(3.0));
+    assert!(summoner.update(1.0));
+}
+
+#[test]
+fn test_despawns_minions_on_death() {
+    let mut summoner = Summoner {
+        minions: vec![1, 2],
+        ..Summoner::default()
+    };
+
+    assert_eq!(summoner.on_death(), vec![1, 2]);
+}
+
+#[test]
+fn test_keeps_minions_on_death_when_disabled() {
+    let mut summoner = Summoner {
+        despawn_minions_on_death: false,
+        minions: vec![1, 2],
+        ..Summoner::default()
+    };
+
+    assert!(summoner.on_death().is_empty());
+}
@@ -1,11 +1,173 @@
-use gdnative::api::Area2D;
-use gdnative::prelude::{NativeClass, Vector2};
+use crate::bat::Bat;
+use crate::combat::{self, Hit, HitMode, HitOutcome, HitTracker, OverlapEntry};
+use crate::cooldown::Cooldown;
+use crate::damage_number::DamageAggregator;
+use crate::extensions::NodeExt;
+use crate::player::Player;
+use crate::player_stats::PlayerStats;
+use crate::rng::Rng;
+use crate::settings;
+use crate::weapon::Weapon;
+use gdnative::api::{Area2D, CollisionShape2D};
+use gdnative::nativescript::init::property::{EnumHint, IntHint};
+use gdnative::prelude::{
+    godot_print, ClassBuilder, KinematicBody2D, NativeClass, Node, RefInstance, Shared, Vector2,
+};
 
 #[derive(NativeClass)]
 #[inherit(Area2D)]
-#[derive(Default)]
+#[register_with(register_properties)]
 pub struct SwordHitbox {
     pub(crate) knockback_vector: Vector2,
+    pub(crate) weapon: Weapon,
+    hit_tracker: HitTracker,
+    // whether the collision shape was enabled last frame
+    swinging: bool,
+    damage_aggregator: DamageAggregator,
+    // impact drag of the last hit that landed, until the wielder takes it
+    pending_impact: Option<f32>,
+    // the next hit is a guaranteed crit, granted by a perfect dodge
+    counter_ready: bool,
+    // whether a hit landed since the wielder last asked
+    landed_hit: bool,
+    temporary_weapon: Option<TemporaryWeapon>,
+    // picked from with the weapon wheel
+    owned_weapons: Vec<Weapon>,
+    seed: i64,
+    rng: Rng,
+    // prints every overlap with a hurtbox and what came of it
+    log_overlaps: bool,
+}
+
+// a weapon equipped for a while, after which the previous one comes back
+struct TemporaryWeapon {
+    previous: Weapon,
+    expiry: Cooldown,
+}
+
+fn register_properties(builder: &ClassBuilder<SwordHitbox>) {
+    builder
+        .add_property::<i32>("damage")
+        .with_default(1)
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.weapon.damage)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, damage| {
+            sword_hitbox.weapon.damage = damage
+        })
+        .done();
+    builder
+        .add_property::<f32>("crit_chance")
+        .with_default(0.0)
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.weapon.crit_chance)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, crit_chance| {
+            sword_hitbox.weapon.crit_chance = crit_chance
+        })
+        .done();
+    builder
+        .add_property::<f32>("crit_multiplier")
+        .with_default(2.0)
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.weapon.crit_multiplier)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, crit_multiplier| {
+            sword_hitbox.weapon.crit_multiplier = crit_multiplier
+        })
+        .done();
+    // a negative durability makes the weapon unbreakable
+    builder
+        .add_property::<i32>("durability")
+        .with_default(-1)
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.weapon.durability.unwrap_or(-1))
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, durability| {
+            sword_hitbox.weapon.durability = if durability < 0 {
+                None
+            } else {
+                Some(durability)
+            }
+        })
+        .done();
+    builder
+        .add_property::<f32>("impact_drag")
+        .with_default(0.0)
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.weapon.impact_drag)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, impact_drag| {
+            sword_hitbox.weapon.impact_drag = impact_drag
+        })
+        .done();
+    builder
+        .add_property::<i64>("hit_mode")
+        .with_hint(IntHint::Enum(EnumHint::new(
+            HitMode::NAMES.iter().map(|name| name.to_string()).collect(),
+        )))
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.hit_tracker.mode.index())
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, index| {
+            sword_hitbox.hit_tracker.mode = HitMode::from_index(index)
+        })
+        .done();
+    builder
+        .add_property::<f32>("hit_interval")
+        .with_default(0.25)
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.hit_tracker.interval)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, interval| {
+            sword_hitbox.hit_tracker.interval = interval
+        })
+        .done();
+    builder
+        .add_property::<bool>("log_overlaps")
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.log_overlaps)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, enabled| {
+            sword_hitbox.log_overlaps = enabled
+        })
+        .done();
+    // zero hits every enemy in the swing
+    builder
+        .add_property::<i64>("max_targets")
+        .with_getter(|sword_hitbox: &SwordHitbox, _| {
+            sword_hitbox.hit_tracker.max_targets.unwrap_or(0) as i64
+        })
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, max_targets| {
+            sword_hitbox.hit_tracker.max_targets = if max_targets > 0 {
+                Some(max_targets as usize)
+            } else {
+                None
+            }
+        })
+        .done();
+    // hits on the same enemy within this many seconds share one damage
+    // number, zero disables it
+    builder
+        .add_property::<f32>("damage_aggregate_window")
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.damage_aggregator.window)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, window| {
+            sword_hitbox.damage_aggregator.window = window
+        })
+        .done();
+    builder
+        .add_property::<i64>("seed")
+        .with_default(0)
+        .with_getter(|sword_hitbox: &SwordHitbox, _| sword_hitbox.seed)
+        .with_setter(|sword_hitbox: &mut SwordHitbox, _, seed| {
+            sword_hitbox.seed = seed;
+            sword_hitbox.rng = Rng::new(seed as u64);
+        })
+        .done();
+}
+
+impl Default for SwordHitbox {
+    fn default() -> Self {
+        Self {
+            knockback_vector: Vector2::zero(),
+            weapon: Weapon::sword(),
+            hit_tracker: HitTracker::default(),
+            swinging: false,
+            damage_aggregator: DamageAggregator::default(),
+            pending_impact: None,
+            counter_ready: false,
+            landed_hit: false,
+            temporary_weapon: None,
+            owned_weapons: vec![Weapon::sword(), Weapon::fists()],
+            seed: 0,
+            rng: Rng::default(),
+            log_overlaps: false,
+        }
+    }
 }
 
 #[gdnative::methods]
@@ -13,4 +175,415 @@ impl SwordHitbox {
     fn new(_owner: &Area2D) -> Self {
         Self::default()
     }
+
+    #[export]
+    fn _ready(&mut self, owner: &Area2D) {
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let weapons = player_stats_instance
+            .map(|player_stats, _| player_stats.carried_weapons.clone())
+            .unwrap_or_default();
+
+        self.carry_over(weapons);
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Area2D, delta: f32) {
+        let delta = settings::game_delta(owner, delta);
+
+        self.tick_temporary_weapon(delta);
+
+        // the attack animation enables the shape for each swing
+        let shape = unsafe { owner.get_typed_node::<CollisionShape2D, _>("CollisionShape2D") };
+        let swinging = !shape.is_disabled();
+
+        if swinging && !self.swinging {
+            self.hit_tracker.start_swing();
+        }
+
+        self.swinging = swinging;
+
+        // hurtboxes are direct children of whatever they protect, and only
+        // bats take sword hits
+        let bats: Vec<(i64, Vector2, RefInstance<Bat, Shared>)> = owner
+            .get_overlapping_areas()
+            .iter()
+            .filter_map(|area| area.try_to_object::<Area2D>())
+            .map(|area| unsafe { area.assume_safe() })
+            .filter_map(|area| {
+                let bat = area
+                    .get_parent()
+                    .map(|parent| unsafe { parent.assume_safe() })
+                    .and_then(|parent| parent.cast::<KinematicBody2D>())
+                    .and_then(|parent| parent.cast_instance::<Bat>())?;
+
+                Some((area.get_instance_id(), area.global_position(), bat))
+            })
+            .collect();
+        let ids: Vec<i64> = bats.iter().map(|(id, _, _)| *id).collect();
+
+        let hit_ids = self.hit_tracker.update(&ids, delta);
+        self.damage_aggregator.update(delta);
+
+        // elites may parry the swing instead of taking it
+        let parried_ids: Vec<i64> = bats
+            .iter()
+            .filter(|(id, _, _)| hit_ids.contains(id))
+            .filter(|(_, _, bat)| {
+                bat.map_mut(|bat, bat_owner| bat.try_parry(&bat_owner))
+                    .unwrap_or(false)
+            })
+            .map(|(id, _, _)| *id)
+            .collect();
+        let hit_ids: Vec<i64> = hit_ids
+            .into_iter()
+            .filter(|id| !parried_ids.contains(id))
+            .collect();
+
+        if !parried_ids.is_empty() {
+            self.stagger_wielder(owner);
+        }
+
+        let mut bonus_damage = 0;
+
+        if !hit_ids.is_empty() {
+            let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+            let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+            let hit_count = hit_ids.len() as i32;
+
+            let _ = player_stats_instance.map_mut(|player_stats, _| {
+                player_stats.combo += hit_count;
+                bonus_damage = player_stats.bonus_damage;
+            });
+        }
+
+        let mut outcomes: Vec<(i64, Option<i32>, HitOutcome)> = Vec::new();
+        let wielder = owner
+            .get_node("../..")
+            .map(|wielder| unsafe { wielder.assume_safe() }.get_instance_id());
+
+        for (id, _, bat) in bats.iter().filter(|(id, _, _)| hit_ids.contains(id)) {
+            self.landed_hit = true;
+
+            // read before rolling, which may break the weapon
+            let knockback_vector = self.knockback_vector * self.weapon.knockback;
+
+            if self.weapon.impact_drag > 0.0 {
+                self.pending_impact = Some(self.weapon.impact_drag);
+            }
+
+            let hit = self.roll_hit(bonus_damage);
+            let popup = self.damage_aggregator.add(*id, hit.damage, hit.is_crit);
+
+            let applied = bat
+                .map_mut(|bat, bat_owner| {
+                    bat.receive_sword_hit(&bat_owner, hit, knockback_vector, popup, wielder)
+                })
+                .unwrap_or(false);
+
+            let outcome = if applied {
+                HitOutcome::Applied
+            } else {
+                HitOutcome::ConsumedByIframes
+            };
+
+            outcomes.push((*id, Some(hit.damage), outcome));
+        }
+
+        if self.log_overlaps {
+            for (id, hurtbox_position, _) in &bats {
+                let entry = overlap_entry(
+                    *id,
+                    owner.global_position(),
+                    *hurtbox_position,
+                    &parried_ids,
+                    &outcomes,
+                );
+
+                godot_print!("{}", entry.to_log_line());
+            }
+        }
+    }
+
+    // the sword sits under the hitbox pivot of the player wielding it
+    fn stagger_wielder(&self, owner: &Area2D) {
+        let wielder = owner
+            .get_node("../..")
+            .map(|wielder| unsafe { wielder.assume_safe() })
+            .and_then(|wielder| wielder.cast::<KinematicBody2D>())
+            .and_then(|wielder| wielder.cast_instance::<Player>());
+
+        if let Some(wielder) = wielder {
+            let _ =
+                wielder.map_mut(|player, player_owner| player.receive_enemy_parry(&player_owner));
+        }
+    }
+
+    /// Returns the impact drag of a hit that landed since the last call.
+    pub fn take_impact(&mut self) -> Option<f32> {
+        self.pending_impact.take()
+    }
+
+    /// Switches how the sword hits, returning the mode it replaced.
+    pub fn set_hit_mode(&mut self, mode: HitMode) -> HitMode {
+        std::mem::replace(&mut self.hit_tracker.mode, mode)
+    }
+
+    /// Returns whether a hit landed since the last call.
+    pub fn take_landed_hit(&mut self) -> bool {
+        std::mem::take(&mut self.landed_hit)
+    }
+
+    pub fn equip(&mut self, weapon: Weapon) {
+        self.weapon = weapon;
+    }
+
+    pub fn owned_weapon_count(&self) -> usize {
+        self.owned_weapons.len()
+    }
+
+    /// Equips one of the owned weapons, dropping a temporary one.
+    pub fn equip_owned(&mut self, index: usize) {
+        if let Some(weapon) = self.owned_weapons.get(index) {
+            self.weapon = weapon.clone();
+            self.temporary_weapon = None;
+        }
+    }
+
+    /// Returns the owned weapons, upgrades and all, for new game plus to
+    /// carry over.
+    pub fn owned_weapons(&self) -> Vec<Weapon> {
+        self.owned_weapons.clone()
+    }
+
+    /// Starts with the weapons carried over from the run before, the first
+    /// one equipped. Without any, the usual ones are kept.
+    fn carry_over(&mut self, weapons: Vec<Weapon>) {
+        if let Some(first) = weapons.first() {
+            self.weapon = first.clone();
+            self.owned_weapons = weapons;
+        }
+    }
+
+    /// Upgrades the equipped and owned weapons, leaving out a temporary one.
+    pub fn upgrade_weapons(&mut self, upgrade: impl Fn(&mut Weapon)) {
+        match self.temporary_weapon.as_mut() {
+            Some(temporary_weapon) => upgrade(&mut temporary_weapon.previous),
+            None => upgrade(&mut self.weapon),
+        }
+
+        self.owned_weapons.iter_mut().for_each(upgrade);
+    }
+
+    pub fn equip_temporary(&mut self, weapon: Weapon, duration: f32) {
+        // picking up another one while the first lasts keeps the original
+        // weapon to revert to
+        let previous = match self.temporary_weapon.take() {
+            Some(temporary_weapon) => temporary_weapon.previous,
+            None => self.weapon.clone(),
+        };
+
+        self.weapon = weapon;
+        self.temporary_weapon = Some(TemporaryWeapon {
+            previous,
+            expiry: Cooldown::started(duration),
+        });
+    }
+
+    fn tick_temporary_weapon(&mut self, delta: f32) {
+        if let Some(temporary_weapon) = self.temporary_weapon.as_mut() {
+            temporary_weapon.expiry.tick(delta);
+
+            if temporary_weapon.expiry.is_ready() {
+                self.unequip();
+            }
+        }
+    }
+
+    /// Drops the current weapon, back to the one a temporary weapon
+    /// replaced, or to fists.
+    pub fn unequip(&mut self) {
+        self.weapon = match self.temporary_weapon.take() {
+            Some(temporary_weapon) => temporary_weapon.previous,
+            None => Weapon::fists(),
+        };
+    }
+
+    pub fn grant_counter(&mut self) {
+        self.counter_ready = true;
+    }
+
+    pub fn is_counter_ready(&self) -> bool {
+        self.counter_ready
+    }
+
+    /// Seconds until a temporary weapon reverts, or `None` without one.
+    pub fn temporary_weapon_time_left(&self) -> Option<f32> {
+        self.temporary_weapon
+            .as_ref()
+            .map(|temporary_weapon| temporary_weapon.expiry.time_left())
+    }
+
+    pub fn roll_hit(&mut self, bonus_damage: i32) -> Hit {
+        let crit_chance = if self.counter_ready {
+            1.0
+        } else {
+            self.weapon.crit_chance
+        };
+        self.counter_ready = false;
+
+        let hit = combat::roll_hit(
+            self.weapon.damage + bonus_damage,
+            crit_chance,
+            self.weapon.crit_multiplier,
+            &mut self.rng,
+        );
+
+        if self.weapon.use_weapon() {
+            self.unequip();
+        }
+
+        hit
+    }
+}
+
+/// Works out what came of an overlap this frame. A hurtbox that was neither
+/// hit nor parried was held back by the hit mode.
+fn overlap_entry(
+    id: i64,
+    hitbox_position: Vector2,
+    hurtbox_position: Vector2,
+    parried_ids: &[i64],
+    outcomes: &[(i64, Option<i32>, HitOutcome)],
+) -> OverlapEntry {
+    let (damage, outcome) = if parried_ids.contains(&id) {
+        (None, HitOutcome::Parried)
+    } else {
+        outcomes.iter().find(|(hit_id, _, _)| *hit_id == id).map_or(
+            (None, HitOutcome::ConsumedByHitMode),
+            |(_, damage, outcome)| (*damage, *outcome),
+        )
+    };
+
+    OverlapEntry {
+        hitbox_position,
+        hurtbox_position,
+        damage,
+        outcome,
+    }
+}
+
+#[test]
+fn test_broken_weapon_unequips_to_fists() {
+    let mut sword_hitbox = SwordHitbox::default();
+    sword_hitbox.equip(Weapon {
+        durability: Some(1),
+        ..Weapon::sword()
+    });
+
+    sword_hitbox.roll_hit(0);
+
+    assert_eq!(sword_hitbox.weapon, Weapon::fists());
+}
+
+#[test]
+fn test_counter_makes_next_hit_crit() {
+    let mut sword_hitbox = SwordHitbox::default();
+    sword_hitbox.equip(Weapon {
+        crit_chance: 0.0,
+        ..Weapon::sword()
+    });
+
+    sword_hitbox.grant_counter();
+
+    assert!(sword_hitbox.roll_hit(0).is_crit);
+    assert!(!sword_hitbox.roll_hit(0).is_crit);
+}
+
+#[test]
+fn test_temporary_weapon_is_equipped() {
+    let mut sword_hitbox = SwordHitbox::default();
+
+    sword_hitbox.equip_temporary(Weapon::greatsword(), 10.0);
+    sword_hitbox.tick_temporary_weapon(9.0);
+
+    assert_eq!(sword_hitbox.weapon, Weapon::greatsword());
+}
+
+#[test]
+fn test_temporary_weapon_reverts_on_expiry() {
+    let mut sword_hitbox = SwordHitbox::default();
+    sword_hitbox.equip(Weapon::fists());
+
+    sword_hitbox.equip_temporary(Weapon::greatsword(), 10.0);
+    sword_hitbox.equip_temporary(Weapon::greatsword(), 10.0);
+    sword_hitbox.tick_temporary_weapon(10.0);
+
+    assert_eq!(sword_hitbox.weapon, Weapon::fists());
+    assert!(sword_hitbox.temporary_weapon.is_none());
+}
+
+#[test]
+fn test_upgrade_skips_temporary_weapon() {
+    let mut sword_hitbox = SwordHitbox::default();
+
+    sword_hitbox.equip_temporary(Weapon::greatsword(), 5.0);
+    sword_hitbox.upgrade_weapons(|weapon| weapon.damage += 1);
+
+    assert_eq!(sword_hitbox.weapon, Weapon::greatsword());
+
+    sword_hitbox.unequip();
+
+    assert_eq!(sword_hitbox.weapon.damage, Weapon::sword().damage + 1);
+}
+
+#[test]
+fn test_equip_owned_replaces_temporary_weapon() {
+    let mut sword_hitbox = SwordHitbox::default();
+    sword_hitbox.equip_temporary(Weapon::greatsword(), 10.0);
+
+    sword_hitbox.equip_owned(1);
+
+    assert_eq!(sword_hitbox.weapon, Weapon::fists());
+    assert!(sword_hitbox.temporary_weapon.is_none());
+}
+
+#[test]
+fn test_carried_weapons_keep_their_upgrades() {
+    let mut last_run = SwordHitbox::default();
+    last_run.upgrade_weapons(|weapon| weapon.damage += 1);
+    last_run.equip_temporary(Weapon::greatsword(), 10.0);
+
+    let mut sword_hitbox = SwordHitbox::default();
+    sword_hitbox.carry_over(last_run.owned_weapons());
+
+    assert_eq!(sword_hitbox.weapon.damage, Weapon::sword().damage + 1);
+    assert_eq!(sword_hitbox.owned_weapons, last_run.owned_weapons);
+}
+
+#[test]
+fn test_nothing_carried_keeps_the_usual_weapons() {
+    let mut sword_hitbox = SwordHitbox::default();
+
+    sword_hitbox.carry_over(Vec::new());
+
+    assert_eq!(sword_hitbox.weapon, Weapon::sword());
+    assert_eq!(sword_hitbox.owned_weapon_count(), 2);
+}
+
+#[test]
+fn test_overlap_entry_outcomes() {
+    let outcomes = [
+        (1, Some(2), HitOutcome::Applied),
+        (2, Some(2), HitOutcome::ConsumedByIframes),
+    ];
+    let outcome = |id| overlap_entry(id, Vector2::zero(), Vector2::zero(), &[3], &outcomes);
+
+    assert_eq!(outcome(1).outcome, HitOutcome::Applied);
+    assert_eq!(outcome(1).damage, Some(2));
+    assert_eq!(outcome(2).outcome, HitOutcome::ConsumedByIframes);
+    assert_eq!(outcome(3).outcome, HitOutcome::Parried);
+    assert_eq!(outcome(4).outcome, HitOutcome::ConsumedByHitMode);
+    assert_eq!(outcome(4).damage, None);
 }
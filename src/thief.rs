@@ -0,0 +1,76 @@
+use crate::player_stats::PlayerStats;
+
+// a thief can't take more than the player holds
+fn steal_amount(held: i32, steal: i32) -> i32 {
+    steal.min(held).max(0)
+}
+
+/// Coins taken from the player with every hit landed on them, held until
+/// the thief dies and hands them back.
+pub struct Thief {
+    // coins taken per hit on the player, zero for enemies that don't steal
+    pub steal_amount: i32,
+    pub flee_time: f32,
+    stolen: i32,
+}
+
+impl Default for Thief {
+    fn default() -> Self {
+        Self {
+            steal_amount: 0,
+            flee_time: 2.0,
+            stolen: 0,
+        }
+    }
+}
+
+impl Thief {
+    /// Steals from the player, returning whether anything was taken.
+    pub fn steal_from(&mut self, player_stats: &mut PlayerStats) -> bool {
+        let stolen = steal_amount(player_stats.coins, self.steal_amount);
+
+        if stolen == 0 {
+            return false;
+        }
+
+        player_stats.coins -= stolen;
+        self.stolen += stolen;
+
+        true
+    }
+
+    /// Hands back everything stolen, for dropping on death.
+    pub fn take_stolen(&mut self) -> i32 {
+        std::mem::take(&mut self.stolen)
+    }
+}
+
+#[test]
+fn test_steal_never_exceeds_held_coins() {
+    assert_eq!(steal_amount(10, 3), 3);
+    assert_eq!(steal_amount(2, 3), 2);
+    assert_eq!(steal_amount(0, 3), 0);
+}
+
+#[test]
+fn test_thief_holds_stolen_coins() {
+    let mut thief = Thief {
+        steal_amount: 3,
+        ..Thief::default()
+    };
+    let mut player_stats = PlayerStats {
+        coins: 5,
+        ..PlayerStats::default()
+    };
+
+    assert!(thief.steal_from(&mut player_stats));
+    assert!(thief.steal_from(&mut player_stats));
+    assert!(!thief.steal_from(&mut player_stats));
+
+    assert_eq!(player_stats.coins, 0);
+    assert_eq!(thief.stolen, 5);
+
+    // everything stolen is handed back once
+    assert_eq!(thief.take_stolen(), 5);
+    assert_eq!(thief.take_stolen(), 0);
+}
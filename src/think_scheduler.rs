@@ -0,0 +1,115 @@
+/// Lets only so many enemies think each physics frame, taking turns so every
+/// enemy gets to think as often as the others. Enemies ask in the same
+/// order every frame, and the turns carry on from where the last frame's
+/// left off.
+#[derive(Default)]
+pub struct ThinkScheduler {
+    // enemies that may think per frame, zero lets all of them
+    pub budget: usize,
+    // where in the asking order this frame's turns start
+    cursor: usize,
+    // how many asked last frame, what the turns wrap around
+    last_asked: usize,
+    asked: usize,
+    thought: usize,
+    // the last full frame's counts, for the profiler
+    pub last_counts: (usize, usize),
+}
+
+impl ThinkScheduler {
+    /// Closes the last frame's counts, called before any enemy asks.
+    pub fn start_frame(&mut self) {
+        if self.asked > 0 && self.budget > 0 {
+            self.cursor = (self.cursor + self.budget) % self.asked;
+        }
+
+        self.last_counts = (self.thought, self.asked);
+        self.last_asked = self.asked;
+        self.asked = 0;
+        self.thought = 0;
+    }
+
+    /// Returns whether the enemy asking gets to think this frame.
+    pub fn request(&mut self) -> bool {
+        let index = self.asked;
+        self.asked += 1;
+
+        let thinks = if self.budget == 0 {
+            true
+        } else if self.last_asked == 0 {
+            index < self.budget
+        } else {
+            let turn = (index + self.last_asked - self.cursor % self.last_asked) % self.last_asked;
+
+            // enemies new this frame, past the end of the order, wait a frame
+            index < self.last_asked && turn < self.budget
+        };
+
+        if thinks {
+            self.thought += 1;
+        }
+
+        thinks
+    }
+}
+
+#[cfg(test)]
+fn run_frame(scheduler: &mut ThinkScheduler, enemies: usize) -> Vec<usize> {
+    scheduler.start_frame();
+
+    (0..enemies).filter(|_| scheduler.request()).collect()
+}
+
+#[test]
+fn test_scheduler_respects_budget() {
+    let mut scheduler = ThinkScheduler {
+        budget: 2,
+        ..ThinkScheduler::default()
+    };
+
+    for _ in 0..10 {
+        assert_eq!(run_frame(&mut scheduler, 5).len(), 2);
+    }
+
+    scheduler.start_frame();
+
+    assert_eq!(scheduler.last_counts, (2, 5));
+}
+
+#[test]
+fn test_scheduler_wraps_around_fairly() {
+    let mut scheduler = ThinkScheduler {
+        budget: 2,
+        ..ThinkScheduler::default()
+    };
+
+    assert_eq!(run_frame(&mut scheduler, 5), vec![0, 1]);
+    assert_eq!(run_frame(&mut scheduler, 5), vec![2, 3]);
+    assert_eq!(run_frame(&mut scheduler, 5), vec![0, 4]);
+    assert_eq!(run_frame(&mut scheduler, 5), vec![1, 2]);
+}
+
+#[test]
+fn test_every_enemy_gets_equal_turns() {
+    let mut scheduler = ThinkScheduler {
+        budget: 3,
+        ..ThinkScheduler::default()
+    };
+    let mut turns = [0; 7];
+
+    // a whole number of rounds through the seven
+    for _ in 0..7 {
+        for index in run_frame(&mut scheduler, 7) {
+            turns[index] += 1;
+        }
+    }
+
+    assert_eq!(turns, [3; 7]);
+}
+
+#[test]
+fn test_no_budget_lets_everyone_think() {
+    let mut scheduler = ThinkScheduler::default();
+
+    assert_eq!(run_frame(&mut scheduler, 4), vec![0, 1, 2, 3]);
+}
@@ -0,0 +1,318 @@
+use crate::extensions::NodeExt;
+use gdnative::api::{Area2D, CanvasLayer, File};
+use gdnative::prelude::{
+    godot_error, ClassBuilder, Label, NativeClass, Node, Ref, TRef, VariantArray,
+};
+use std::collections::BTreeMap;
+
+/// The time attack timer, running from entering a room until a player
+/// reaches its goal, and the best time of every level, kept in a local
+/// file. Reached through the `TimeAttack` autoload, and does nothing unless
+/// enabled.
+#[derive(NativeClass)]
+#[inherit(CanvasLayer)]
+#[register_with(register_properties)]
+pub struct TimeAttack {
+    enabled: bool,
+    leaderboard_path: String,
+    // `None` while no run is timed
+    time: Option<f32>,
+    // the last time a goal was reached with, shown until the next run
+    finished: Option<(f32, bool)>,
+    best_times: BestTimes,
+}
+
+fn register_properties(builder: &ClassBuilder<TimeAttack>) {
+    builder
+        .add_property::<bool>("enabled")
+        .with_getter(|time_attack: &TimeAttack, _| time_attack.enabled)
+        .with_setter(|time_attack: &mut TimeAttack, _, enabled| time_attack.enabled = enabled)
+        .done();
+    builder
+        .add_property::<String>("leaderboard_path")
+        .with_default("user://best_times.txt".to_string())
+        .with_ref_getter(|time_attack: &TimeAttack, _| &time_attack.leaderboard_path)
+        .with_setter(|time_attack: &mut TimeAttack, _, path| time_attack.leaderboard_path = path)
+        .done();
+}
+
+impl Default for TimeAttack {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            leaderboard_path: "user://best_times.txt".to_string(),
+            time: None,
+            finished: None,
+            best_times: BestTimes::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl TimeAttack {
+    fn new(_owner: &CanvasLayer) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, _owner: &CanvasLayer) {
+        self.best_times = BestTimes::from_text(&read_file(&self.leaderboard_path));
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &CanvasLayer, delta: f32) {
+        let timer = unsafe { owner.get_typed_node::<Label, _>("Timer") };
+
+        timer.set_visible(self.enabled);
+
+        if !self.enabled {
+            return;
+        }
+
+        if let Some(time) = self.time.as_mut() {
+            *time += delta;
+        }
+
+        let text = match (self.time, self.finished) {
+            (Some(time), _) => format_time(time),
+            (None, Some((time, true))) => format!("{} new record!", format_time(time)),
+            (None, Some((time, false))) => format_time(time),
+            (None, None) => String::new(),
+        };
+
+        timer.set_text(text);
+    }
+
+    pub fn start(&mut self) {
+        self.time = Some(0.0);
+        self.finished = None;
+    }
+
+    /// Stops the timer, returning the time the run took, or `None` when no
+    /// run was timed.
+    pub fn stop(&mut self) -> Option<f32> {
+        self.time.take()
+    }
+
+    /// Keeps the time as the level's best if it beats the old one, saving
+    /// the leaderboard. Returns whether it's a new record.
+    pub fn save_best(&mut self, level: &str, time: f32) -> bool {
+        if !self.best_times.record(level, time) {
+            return false;
+        }
+
+        write_file(&self.leaderboard_path, &self.best_times.to_text());
+
+        true
+    }
+
+    fn finish(&mut self, level: &str) {
+        if !self.enabled {
+            return;
+        }
+
+        if let Some(time) = self.stop() {
+            let is_record = self.save_best(level, time);
+
+            self.finished = Some((time, is_record));
+        }
+    }
+}
+
+/// The best time of every level, saved as a line of `level time` each.
+#[derive(Debug, Default, PartialEq)]
+struct BestTimes {
+    times: BTreeMap<String, f32>,
+}
+
+impl BestTimes {
+    /// Returns whether the time is the level's first or beats its best.
+    fn record(&mut self, level: &str, time: f32) -> bool {
+        let is_record = self.times.get(level).map_or(true, |best| time < *best);
+
+        if is_record {
+            self.times.insert(level.to_string(), time);
+        }
+
+        is_record
+    }
+
+    fn to_text(&self) -> String {
+        self.times
+            .iter()
+            .map(|(level, time)| format!("{} {}\n", level, time))
+            .collect()
+    }
+
+    // lines that don't parse are skipped, so a damaged file loses only them
+    fn from_text(text: &str) -> Self {
+        let times = text
+            .lines()
+            .filter_map(|line| {
+                let (level, time) = line.trim().rsplit_once(' ')?;
+
+                Some((level.to_string(), time.parse().ok()?))
+            })
+            .collect();
+
+        Self { times }
+    }
+}
+
+fn read_file(path: &str) -> String {
+    let file = File::new();
+
+    // no file yet means no best times
+    if file.open(path, File::READ).is_err() {
+        return String::new();
+    }
+
+    let text = file.get_as_text().to_string();
+    file.close();
+
+    text
+}
+
+fn write_file(path: &str, text: &str) {
+    let file = File::new();
+
+    if file.open(path, File::WRITE).is_err() {
+        godot_error!("couldn't save the best times to {}", path);
+        return;
+    }
+
+    file.store_string(text);
+    file.close();
+}
+
+fn format_time(seconds: f32) -> String {
+    let minutes = (seconds / 60.0).floor() as i32;
+
+    format!("{}:{:05.2}", minutes, seconds - minutes as f32 * 60.0)
+}
+
+fn with_time_attack(owner: &Node, f: impl FnOnce(&mut TimeAttack)) {
+    let time_attack_node = unsafe { owner.get_typed_node::<CanvasLayer, _>("/root/TimeAttack") };
+
+    if let Some(time_attack) = time_attack_node.cast_instance::<TimeAttack>() {
+        let _ = time_attack.map_mut(|time_attack, _| f(time_attack));
+    }
+}
+
+/// Starts timing a run through the `TimeAttack` autoload.
+pub fn start(owner: &Node) {
+    with_time_attack(owner, |time_attack| time_attack.start());
+}
+
+/// The end of a time attack run, stopping the timer once a player reaches
+/// it and keeping the time if it's the best for the level, the level being
+/// the room's scene.
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+pub struct TimeAttackGoal;
+
+#[gdnative::methods]
+impl TimeAttackGoal {
+    fn new(_owner: &Area2D) -> Self {
+        Self
+    }
+
+    #[export]
+    fn _ready(&self, owner: TRef<Area2D>) {
+        owner
+            .connect(
+                "body_entered",
+                owner,
+                "_on_body_entered",
+                VariantArray::new_shared(),
+                0,
+            )
+            .unwrap();
+    }
+
+    #[export]
+    fn _on_body_entered(&self, owner: &Area2D, body: Ref<Node>) {
+        let body = match unsafe { body.assume_safe_if_sane() } {
+            Some(body) => body,
+            None => return,
+        };
+
+        if !body.is_in_group("player") {
+            return;
+        }
+
+        let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+        let level = tree
+            .current_scene()
+            .map(|scene| unsafe { scene.assume_safe() }.filename().to_string())
+            .unwrap_or_default();
+
+        with_time_attack(owner, |time_attack| time_attack.finish(&level));
+    }
+}
+
+#[test]
+fn test_first_time_is_a_record() {
+    let mut best_times = BestTimes::default();
+
+    assert!(best_times.record("res://scenes/World.tscn", 42.5));
+}
+
+#[test]
+fn test_only_faster_times_are_records() {
+    let mut best_times = BestTimes::default();
+
+    best_times.record("world", 42.5);
+
+    assert!(!best_times.record("world", 50.0));
+    assert!(!best_times.record("world", 42.5));
+    assert!(best_times.record("world", 40.0));
+    assert_eq!(best_times.times["world"], 40.0);
+}
+
+#[test]
+fn test_best_times_are_per_level() {
+    let mut best_times = BestTimes::default();
+
+    best_times.record("world", 42.5);
+
+    assert!(best_times.record("cave", 60.0));
+    assert_eq!(best_times.times["world"], 42.5);
+}
+
+#[test]
+fn test_best_times_file_round_trip() {
+    let mut best_times = BestTimes::default();
+
+    best_times.record("res://scenes/World.tscn", 42.5);
+    best_times.record("res://scenes/Cave.tscn", 61.25);
+
+    assert_eq!(BestTimes::from_text(&best_times.to_text()), best_times);
+}
+
+#[test]
+fn test_damaged_lines_are_skipped() {
+    let best_times = BestTimes::from_text("world 42.5\nnot a time\ncave\n");
+
+    assert_eq!(best_times.times.len(), 1);
+    assert_eq!(best_times.times["world"], 42.5);
+}
+
+#[test]
+fn test_stop_returns_the_run_time() {
+    let mut time_attack = TimeAttack::default();
+
+    assert_eq!(time_attack.stop(), None);
+
+    time_attack.start();
+    time_attack.time = time_attack.time.map(|time| time + 12.5);
+
+    assert_eq!(time_attack.stop(), Some(12.5));
+    assert_eq!(time_attack.stop(), None);
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(format_time(62.5), "1:02.50");
+    assert_eq!(format_time(9.25), "0:09.25");
+}
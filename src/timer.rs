@@ -0,0 +1,137 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerMode {
+    // stops after firing
+    OneShot,
+    // starts over after firing, every interval until stopped
+    Repeating,
+}
+
+impl Default for TimerMode {
+    fn default() -> Self {
+        Self::OneShot
+    }
+}
+
+/// A timer ticked by whatever owns it rather than a Godot `Timer` node, for
+/// things like buffs and the parry window.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GameTimer {
+    pub mode: TimerMode,
+    interval: f32,
+    // `None` while stopped
+    time_left: Option<f32>,
+}
+
+impl GameTimer {
+    pub fn new(mode: TimerMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Starts the timer over with the interval, even if it's running.
+    pub fn start(&mut self, interval: f32) {
+        self.interval = interval;
+        self.time_left = Some(interval);
+    }
+
+    pub fn stop(&mut self) {
+        self.time_left = None;
+    }
+
+    /// Counts down, returning whether the timer fired. A repeating timer
+    /// fires at most once a tick, however long the tick.
+    pub fn tick(&mut self, delta: f32) -> bool {
+        let time_left = match self.time_left.as_mut() {
+            Some(time_left) => time_left,
+            None => return false,
+        };
+
+        *time_left -= delta;
+
+        if *time_left > 0.0 {
+            return false;
+        }
+
+        self.time_left = match self.mode {
+            TimerMode::Repeating if self.interval > 0.0 => {
+                Some((*time_left + self.interval).max(0.0))
+            }
+            _ => None,
+        };
+
+        true
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.time_left.is_some()
+    }
+
+    /// Seconds until the timer fires, 0 while stopped.
+    pub fn time_left(&self) -> f32 {
+        self.time_left.unwrap_or(0.0)
+    }
+}
+
+#[test]
+fn test_one_shot_fires_once() {
+    let mut timer = GameTimer::new(TimerMode::OneShot);
+
+    timer.start(1.0);
+
+    assert!(!timer.tick(0.5));
+    assert!(timer.is_running());
+    assert!(timer.tick(0.5));
+    assert!(!timer.is_running());
+    assert!(!timer.tick(1.0));
+}
+
+#[test]
+fn test_repeating_fires_every_interval() {
+    let mut timer = GameTimer::new(TimerMode::Repeating);
+
+    timer.start(0.5);
+
+    let fired: Vec<bool> = (0..6).map(|_| timer.tick(0.25)).collect();
+
+    assert_eq!(fired, vec![false, true, false, true, false, true]);
+    assert!(timer.is_running());
+}
+
+#[test]
+fn test_stopped_timer_never_fires() {
+    let mut timer = GameTimer::new(TimerMode::Repeating);
+
+    assert!(!timer.tick(10.0));
+
+    timer.start(0.5);
+    timer.stop();
+
+    assert!(!timer.tick(10.0));
+    assert_eq!(timer.time_left(), 0.0);
+}
+
+#[test]
+fn test_restarting_resets_the_time() {
+    let mut timer = GameTimer::default();
+
+    timer.start(1.0);
+    timer.tick(0.75);
+    timer.start(1.0);
+
+    assert!(!timer.tick(0.75));
+    assert_eq!(timer.time_left(), 0.25);
+}
+
+#[test]
+fn test_paused_tick_does_not_advance_the_timer() {
+    let settings = crate::settings::Settings::default();
+    let mut timer = GameTimer::new(TimerMode::OneShot);
+
+    timer.start(0.0625);
+
+    assert!(!timer.tick(settings.game_delta(0.0625, true)));
+    assert!(timer.is_running());
+    assert!(timer.tick(settings.game_delta(0.0625, false)));
+}
@@ -0,0 +1,89 @@
+use crate::trail::Trail;
+use gdnative::prelude::Vector2;
+
+/// Has a chaser follow the path its target took instead of flying straight
+/// at it, so doubling back shakes it off for a while.
+pub struct Tracker {
+    pub enabled: bool,
+    pub trail: Trail,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trail: Trail::new(16.0, 24),
+        }
+    }
+}
+
+impl Tracker {
+    /// Returns where to fly to chase the target, the next waypoint of its
+    /// trail within reach when tracking.
+    pub fn follow(&mut self, position: Vector2, target_position: Vector2, reach: f32) -> Vector2 {
+        if !self.enabled {
+            return target_position;
+        }
+
+        self.trail.record(target_position);
+        self.trail
+            .next_waypoint(position, reach)
+            .unwrap_or(target_position)
+    }
+
+    /// Forgets the trail, so the next chase starts a new one.
+    pub fn lose_track(&mut self) {
+        self.trail.clear();
+    }
+}
+
+#[test]
+fn test_tracker_follows_the_target_path() {
+    let mut tracker = Tracker {
+        enabled: true,
+        ..Tracker::default()
+    };
+
+    assert_eq!(
+        tracker.follow(Vector2::zero(), Vector2::new(20.0, 0.0), 4.0),
+        Vector2::new(20.0, 0.0)
+    );
+
+    // the target turned a corner, the tracker still goes the way it went
+    assert_eq!(
+        tracker.follow(Vector2::new(5.0, 0.0), Vector2::new(20.0, 20.0), 4.0),
+        Vector2::new(20.0, 0.0)
+    );
+    assert_eq!(
+        tracker.follow(Vector2::new(18.0, 0.0), Vector2::new(20.0, 20.0), 4.0),
+        Vector2::new(20.0, 20.0)
+    );
+}
+
+#[test]
+fn test_non_tracker_flies_straight_at_the_target() {
+    let mut tracker = Tracker::default();
+
+    tracker.follow(Vector2::zero(), Vector2::new(20.0, 0.0), 4.0);
+
+    assert_eq!(
+        tracker.follow(Vector2::new(5.0, 0.0), Vector2::new(20.0, 20.0), 4.0),
+        Vector2::new(20.0, 20.0)
+    );
+}
+
+#[test]
+fn test_lost_track_starts_a_new_trail() {
+    let mut tracker = Tracker {
+        enabled: true,
+        ..Tracker::default()
+    };
+
+    tracker.follow(Vector2::zero(), Vector2::new(20.0, 0.0), 4.0);
+    tracker.lose_track();
+
+    assert_eq!(
+        tracker.follow(Vector2::new(5.0, 0.0), Vector2::new(20.0, 20.0), 4.0),
+        Vector2::new(20.0, 20.0)
+    );
+}
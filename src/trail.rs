@@ -0,0 +1,136 @@
+use gdnative::prelude::{Vector2, Vector2Godot};
+use std::collections::VecDeque;
+
+/// The recent path of something followed, as waypoints oldest first, for
+/// a follower to fly along rather than straight at it.
+pub struct Trail {
+    // distance moved before the next waypoint is recorded
+    pub spacing: f32,
+    // waypoints kept, the oldest dropped past it so a follower left far
+    // behind skips ahead
+    pub capacity: usize,
+    waypoints: VecDeque<Vector2>,
+}
+
+impl Trail {
+    pub fn new(spacing: f32, capacity: usize) -> Self {
+        Self {
+            spacing,
+            capacity,
+            waypoints: VecDeque::new(),
+        }
+    }
+
+    /// Adds where the followed thing is now, once it's moved far enough
+    /// from the last waypoint.
+    pub fn record(&mut self, position: Vector2) {
+        let far_enough = self
+            .waypoints
+            .back()
+            .map_or(true, |last| last.distance_to(position) >= self.spacing);
+
+        if far_enough {
+            self.waypoints.push_back(position);
+        }
+
+        while self.waypoints.len() > self.capacity.max(1) {
+            self.waypoints.pop_front();
+        }
+    }
+
+    /// Returns the waypoint to head for from the position, dropping the
+    /// ones already within reach of it.
+    pub fn next_waypoint(&mut self, from: Vector2, reach: f32) -> Option<Vector2> {
+        while let Some(waypoint) = self.waypoints.front() {
+            // the newest is kept, it's as far as the trail goes
+            if from.distance_to(*waypoint) > reach || self.waypoints.len() == 1 {
+                break;
+            }
+
+            self.waypoints.pop_front();
+        }
+
+        self.waypoints.front().copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.waypoints.clear();
+    }
+}
+
+#[cfg(test)]
+fn trail(points: &[(f32, f32)]) -> Trail {
+    let mut trail = Trail::new(8.0, 16);
+
+    for (x, y) in points {
+        trail.record(Vector2::new(*x, *y));
+    }
+
+    trail
+}
+
+#[test]
+fn test_waypoints_are_spaced() {
+    let mut trail = trail(&[(0.0, 0.0), (4.0, 0.0), (8.0, 0.0), (10.0, 0.0), (16.0, 0.0)]);
+
+    assert_eq!(trail.waypoints.len(), 3);
+    assert_eq!(
+        trail.next_waypoint(Vector2::new(-20.0, 0.0), 4.0),
+        Some(Vector2::zero())
+    );
+}
+
+#[test]
+fn test_follower_advances_along_waypoints() {
+    // doubling back around a corner
+    let mut trail = trail(&[(0.0, 0.0), (16.0, 0.0), (16.0, 16.0), (0.0, 16.0)]);
+
+    assert_eq!(
+        trail.next_waypoint(Vector2::new(-10.0, 0.0), 4.0),
+        Some(Vector2::new(0.0, 0.0))
+    );
+    assert_eq!(
+        trail.next_waypoint(Vector2::new(1.0, 0.0), 4.0),
+        Some(Vector2::new(16.0, 0.0))
+    );
+    assert_eq!(
+        trail.next_waypoint(Vector2::new(16.0, 2.0), 4.0),
+        Some(Vector2::new(16.0, 16.0))
+    );
+    assert_eq!(
+        trail.next_waypoint(Vector2::new(15.0, 15.0), 4.0),
+        Some(Vector2::new(0.0, 16.0))
+    );
+}
+
+#[test]
+fn test_last_waypoint_is_kept_once_reached() {
+    let mut trail = trail(&[(0.0, 0.0), (16.0, 0.0)]);
+
+    assert_eq!(
+        trail.next_waypoint(Vector2::new(16.0, 0.0), 4.0),
+        Some(Vector2::new(16.0, 0.0))
+    );
+}
+
+#[test]
+fn test_follower_catches_up_when_the_trail_shortens() {
+    let mut trail = trail(&[(0.0, 0.0), (16.0, 0.0), (32.0, 0.0), (48.0, 0.0)]);
+
+    trail.capacity = 2;
+    trail.record(Vector2::new(64.0, 0.0));
+
+    // the oldest waypoints are gone, so it skips straight ahead
+    assert_eq!(
+        trail.next_waypoint(Vector2::new(-10.0, 0.0), 4.0),
+        Some(Vector2::new(48.0, 0.0))
+    );
+}
+
+#[test]
+fn test_empty_trail_has_no_waypoint() {
+    assert_eq!(
+        Trail::new(8.0, 16).next_waypoint(Vector2::zero(), 4.0),
+        None
+    );
+}
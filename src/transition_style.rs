@@ -0,0 +1,110 @@
+/// How the screen is covered while the scene changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionStyle {
+    // fades to black
+    Fade,
+    // a circle closing on the middle of the screen
+    Iris,
+    // black sliding in from the left
+    Slide,
+}
+
+impl Default for TransitionStyle {
+    fn default() -> Self {
+        Self::Fade
+    }
+}
+
+// half the screen's diagonal, in the overlay's UV, so a fully open iris
+// shows every corner
+const IRIS_OPEN_RADIUS: f32 = 0.75;
+
+/// The overlay's shader params for a style at some point of its transition.
+#[derive(Debug, PartialEq)]
+pub struct Overlay {
+    pub alpha: f32,
+    // the hole left in the middle of the screen
+    pub iris_radius: f32,
+    // how much of the screen's width, from the right, is left uncovered
+    pub slide_offset: f32,
+}
+
+impl TransitionStyle {
+    pub const NAMES: [&'static str; 3] = ["Fade", "Iris", "Slide"];
+
+    pub fn from_index(index: i64) -> Self {
+        match index {
+            1 => Self::Iris,
+            2 => Self::Slide,
+            _ => Self::Fade,
+        }
+    }
+
+    pub fn index(self) -> i64 {
+        match self {
+            Self::Fade => 0,
+            Self::Iris => 1,
+            Self::Slide => 2,
+        }
+    }
+
+    /// Returns the overlay with the transition's progress, from nothing
+    /// covered at 0 to all of the screen at 1.
+    pub fn overlay(self, progress: f32) -> Overlay {
+        let progress = progress.max(0.0).min(1.0);
+
+        match self {
+            Self::Fade => Overlay {
+                alpha: progress,
+                iris_radius: 0.0,
+                slide_offset: 0.0,
+            },
+            Self::Iris => Overlay {
+                alpha: 1.0,
+                iris_radius: IRIS_OPEN_RADIUS * (1.0 - progress),
+                slide_offset: 0.0,
+            },
+            Self::Slide => Overlay {
+                alpha: 1.0,
+                iris_radius: 0.0,
+                slide_offset: 1.0 - progress,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+const FULL_COVERAGE: Overlay = Overlay {
+    alpha: 1.0,
+    iris_radius: 0.0,
+    slide_offset: 0.0,
+};
+
+#[test]
+fn test_fade_reaches_full_coverage() {
+    assert_eq!(TransitionStyle::Fade.overlay(0.0).alpha, 0.0);
+    assert_eq!(TransitionStyle::Fade.overlay(0.5).alpha, 0.5);
+    assert_eq!(TransitionStyle::Fade.overlay(1.0), FULL_COVERAGE);
+}
+
+#[test]
+fn test_iris_reaches_full_coverage() {
+    assert_eq!(
+        TransitionStyle::Iris.overlay(0.0).iris_radius,
+        IRIS_OPEN_RADIUS
+    );
+    assert_eq!(TransitionStyle::Iris.overlay(0.5).iris_radius, 0.375);
+    assert_eq!(TransitionStyle::Iris.overlay(1.0), FULL_COVERAGE);
+}
+
+#[test]
+fn test_slide_reaches_full_coverage() {
+    assert_eq!(TransitionStyle::Slide.overlay(0.0).slide_offset, 1.0);
+    assert_eq!(TransitionStyle::Slide.overlay(0.75).slide_offset, 0.25);
+    assert_eq!(TransitionStyle::Slide.overlay(1.0), FULL_COVERAGE);
+}
+
+#[test]
+fn test_progress_past_the_end_stays_covered() {
+    assert_eq!(TransitionStyle::Iris.overlay(1.5), FULL_COVERAGE);
+}
@@ -0,0 +1,107 @@
+/// Each easing takes how far through something is and returns how far
+/// along its value should be, both from 0 at the start to 1 at the end, so
+/// camera moves, bars and fades all ease the same way.
+pub fn linear(progress: f32) -> f32 {
+    progress.max(0.0).min(1.0)
+}
+
+/// Starts slow and speeds up towards the end.
+pub fn ease_in(progress: f32) -> f32 {
+    let progress = linear(progress);
+
+    progress * progress
+}
+
+/// Starts fast and slows down towards the end.
+pub fn ease_out(progress: f32) -> f32 {
+    let left = 1.0 - linear(progress);
+
+    1.0 - left * left
+}
+
+/// Starts and ends slow, fastest halfway.
+pub fn ease_in_out(progress: f32) -> f32 {
+    let progress = linear(progress);
+
+    progress * progress * (3.0 - 2.0 * progress)
+}
+
+/// Progress through something taking a set time, eased.
+pub struct Tween {
+    pub duration: f32,
+    easing: fn(f32) -> f32,
+    elapsed: f32,
+}
+
+impl Tween {
+    pub fn new(duration: f32, easing: fn(f32) -> f32) -> Self {
+        Self {
+            duration,
+            easing,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Moves the tween along, returning its eased progress, 1 once it's
+    /// finished.
+    pub fn advance(&mut self, delta: f32) -> f32 {
+        self.elapsed = (self.elapsed + delta).min(self.duration.max(0.0));
+
+        self.progress()
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+
+        (self.easing)(self.elapsed / self.duration)
+    }
+}
+
+#[cfg(test)]
+const EASINGS: [fn(f32) -> f32; 4] = [linear, ease_in, ease_out, ease_in_out];
+
+#[test]
+fn test_easings_start_at_zero_and_end_at_one() {
+    for easing in &EASINGS {
+        assert_eq!(easing(0.0), 0.0);
+        assert_eq!(easing(1.0), 1.0);
+    }
+}
+
+#[test]
+fn test_easings_are_clamped() {
+    for easing in &EASINGS {
+        assert_eq!(easing(-0.5), 0.0);
+        assert_eq!(easing(1.5), 1.0);
+    }
+}
+
+#[test]
+fn test_easings_never_go_back() {
+    for easing in &EASINGS {
+        let values: Vec<f32> = (0..=20).map(|step| easing(step as f32 / 20.0)).collect();
+
+        assert!(values.windows(2).all(|pair| pair[1] >= pair[0]));
+    }
+}
+
+#[test]
+fn test_ease_in_and_out_are_on_either_side_of_linear() {
+    assert!(ease_in(0.25) < 0.25);
+    assert!(ease_out(0.25) > 0.25);
+    assert!(ease_in_out(0.25) < 0.25);
+    assert_eq!(ease_in_out(0.5), 0.5);
+    assert!(ease_in_out(0.75) > 0.75);
+}
+
+#[test]
+fn test_tween_advances_to_the_end() {
+    let mut tween = Tween::new(2.0, ease_in);
+
+    assert_eq!(tween.progress(), 0.0);
+    assert_eq!(tween.advance(1.0), 0.25);
+    assert_eq!(tween.advance(5.0), 1.0);
+    assert_eq!(tween.progress(), 1.0);
+}
@@ -0,0 +1,587 @@
+use crate::entity_registry;
+use crate::extensions::NodeExt;
+use crate::player_stats::PlayerStats;
+use crate::screen_transition;
+use crate::sword_hitbox::SwordHitbox;
+use gdnative::api::{Area2D, ConfigFile, ProjectSettings, OS};
+use gdnative::prelude::{
+    godot_error, ClassBuilder, GodotString, NativeClass, Node, StringArray, Variant,
+};
+use std::collections::HashSet;
+
+const SAVE_SECTION: &str = "upgrades";
+const SAVE_KEY: &str = "purchased";
+const NG_PLUS_KEY: &str = "ng_plus_level";
+const RUN_SEED_KEY: &str = "run_seed";
+const VERSION_SECTION: &str = "save";
+const VERSION_KEY: &str = "version";
+
+// bumped whenever what's saved changes, with a migration from the version
+// before added to `MIGRATIONS`
+const SAVE_VERSION: i64 = 3;
+
+// each upgrades a save from the version at its index plus one to the next
+const MIGRATIONS: [fn(&mut SaveFile); 2] = [
+    // new game plus came after the upgrades, so older saves never started it
+    |file| {
+        file.ng_plus_level.get_or_insert(0);
+    },
+    // chests rolling from a saved seed came last, older saves all get the
+    // same one
+    |file| {
+        file.run_seed.get_or_insert(0);
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    MaxHealth(i32),
+    Damage(i32),
+    Stamina(f32),
+    KnockbackResistance(f32),
+}
+
+impl Effect {
+    fn apply(self, player_stats: &mut PlayerStats) {
+        match self {
+            Self::MaxHealth(amount) => {
+                player_stats.max_health += amount;
+                player_stats.set_health(player_stats.health + amount);
+            }
+            Self::Damage(amount) => player_stats.bonus_damage += amount,
+            Self::Stamina(amount) => player_stats.bonus_stamina += amount,
+            Self::KnockbackResistance(amount) => player_stats.bonus_knockback_resistance += amount,
+        }
+    }
+}
+
+pub struct UpgradeNode {
+    // what the save knows the node by
+    pub id: &'static str,
+    pub effect: Effect,
+    pub cost: i32,
+    // nodes that have to be bought first
+    pub prerequisites: &'static [&'static str],
+}
+
+pub static TREE: [UpgradeNode; 7] = [
+    UpgradeNode {
+        id: "vitality",
+        effect: Effect::MaxHealth(1),
+        cost: 10,
+        prerequisites: &[],
+    },
+    UpgradeNode {
+        id: "fortitude",
+        effect: Effect::MaxHealth(1),
+        cost: 30,
+        prerequisites: &["vitality"],
+    },
+    UpgradeNode {
+        id: "sharpness",
+        effect: Effect::Damage(1),
+        cost: 15,
+        prerequisites: &[],
+    },
+    UpgradeNode {
+        id: "keen_edge",
+        effect: Effect::Damage(1),
+        cost: 40,
+        prerequisites: &["sharpness"],
+    },
+    UpgradeNode {
+        id: "endurance",
+        effect: Effect::Stamina(25.0),
+        cost: 15,
+        prerequisites: &[],
+    },
+    UpgradeNode {
+        id: "steadfast",
+        effect: Effect::KnockbackResistance(0.5),
+        cost: 25,
+        prerequisites: &["endurance"],
+    },
+    // the capstone needs both branches it joins
+    UpgradeNode {
+        id: "champion",
+        effect: Effect::MaxHealth(2),
+        cost: 80,
+        prerequisites: &["fortitude", "keen_edge"],
+    },
+];
+
+/// The permanent upgrade tree, kept across runs in the save file. Reached
+/// through the `Upgrades` autoload, which comes after `PlayerStats` so the
+/// bought upgrades are applied to fresh stats on start.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct Upgrades {
+    purchased: HashSet<String>,
+    save_path: String,
+    // set when the save is from a newer version of the game, which isn't
+    // overwritten so it isn't lost
+    save_locked: bool,
+}
+
+fn register_properties(builder: &ClassBuilder<Upgrades>) {
+    builder
+        .add_property::<String>("save_path")
+        .with_default("user://upgrades.cfg".to_string())
+        .with_ref_getter(|upgrades: &Upgrades, _| &upgrades.save_path)
+        .with_setter(|upgrades: &mut Upgrades, _, path| upgrades.save_path = path)
+        .done();
+}
+
+impl Default for Upgrades {
+    fn default() -> Self {
+        Self {
+            purchased: HashSet::new(),
+            save_path: "user://upgrades.cfg".to_string(),
+            save_locked: false,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl Upgrades {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Node) {
+        let run = self.load();
+
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        let _ = player_stats_instance.map_mut(|player_stats, _| {
+            player_stats.ng_plus_level = run.ng_plus_level;
+            player_stats.run_seed = run.run_seed;
+            self.apply_purchased(player_stats);
+
+            // a first run's seed is saved right away, so quitting before
+            // anything else is saved can't reroll it
+            self.save(Run::of(player_stats));
+        });
+    }
+
+    /// Ends the run its last player died in, saving the seed the next run
+    /// rolls its loot from.
+    pub fn end_run(&self, player_stats: &mut PlayerStats) {
+        player_stats.roll_run_seed();
+        self.save(Run::of(player_stats));
+    }
+
+    /// Starts a new game plus run from the first room, keeping the bought
+    /// upgrades and the owned weapons while the rest of the player's stats
+    /// start over.
+    #[export]
+    fn start_new_game_plus(&self, owner: &Node) {
+        let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+        let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+        // every player's weapons get the same upgrades, so the first one's
+        // stand for all of them
+        let weapons = entity_registry::all_players(owner)
+            .first()
+            .and_then(|player| {
+                let sword_hitbox_node =
+                    unsafe { player.get_typed_node::<Area2D, _>("HitboxPivot/SwordHitbox") };
+
+                sword_hitbox_node
+                    .cast_instance::<SwordHitbox>()?
+                    .map(|sword_hitbox, _| sword_hitbox.owned_weapons())
+                    .ok()
+            });
+
+        let _ = player_stats_instance.map_mut(|player_stats, _| {
+            player_stats.start_new_game_plus();
+
+            if let Some(weapons) = weapons {
+                player_stats.carried_weapons = weapons;
+            }
+
+            self.save(Run::of(player_stats));
+        });
+
+        let first_room = ProjectSettings::godot_singleton()
+            .get("application/run/main_scene")
+            .to_string();
+
+        screen_transition::change_scene(owner, first_room);
+    }
+
+    pub fn is_purchased(&self, node: &UpgradeNode) -> bool {
+        self.purchased.contains(node.id)
+    }
+
+    /// Returns whether the node is for sale, with its prerequisites bought
+    /// and enough coins to pay for it.
+    pub fn can_purchase(&self, node: &UpgradeNode, player_stats: &PlayerStats) -> bool {
+        !self.is_purchased(node)
+            && player_stats.coins >= node.cost
+            && node
+                .prerequisites
+                .iter()
+                .all(|prerequisite| self.purchased.contains(*prerequisite))
+    }
+
+    /// Buys the node, paying its cost and applying its effect, then saves.
+    /// Returns whether it was bought.
+    pub fn purchase(&mut self, node: &UpgradeNode, player_stats: &mut PlayerStats) -> bool {
+        if !self.buy(node, player_stats) {
+            return false;
+        }
+
+        self.save(Run::of(player_stats));
+
+        true
+    }
+
+    fn buy(&mut self, node: &UpgradeNode, player_stats: &mut PlayerStats) -> bool {
+        if !self.can_purchase(node, player_stats) {
+            return false;
+        }
+
+        player_stats.coins -= node.cost;
+        node.effect.apply(player_stats);
+        self.purchased.insert(node.id.to_string());
+
+        true
+    }
+
+    fn apply_purchased(&self, player_stats: &mut PlayerStats) {
+        for node in TREE.iter().filter(|node| self.is_purchased(node)) {
+            node.effect.apply(player_stats);
+        }
+    }
+
+    /// Loads the bought upgrades, returning what's kept from the last run.
+    /// A missing save just means nothing was bought yet.
+    fn load(&mut self) -> Run {
+        let config = ConfigFile::new();
+
+        if config.load(self.save_path.as_str()).is_err() {
+            return Run::fresh();
+        }
+
+        let value = |section: &str, key: &str| config.get_value(section, key, Variant::new());
+
+        let version = value(VERSION_SECTION, VERSION_KEY);
+        let ng_plus_level = value(SAVE_SECTION, NG_PLUS_KEY);
+        let run_seed = value(SAVE_SECTION, RUN_SEED_KEY);
+        let purchased = value(SAVE_SECTION, SAVE_KEY).to_string_array();
+
+        let file = SaveFile {
+            version: (!version.is_nil()).then(|| version.to_i64()),
+            purchased: purchased.read().iter().map(|id| id.to_string()).collect(),
+            ng_plus_level: (!ng_plus_level.is_nil()).then(|| ng_plus_level.to_i64() as i32),
+            run_seed: (!run_seed.is_nil()).then(|| run_seed.to_i64()),
+        };
+
+        match migrate(file) {
+            Ok(save) => {
+                self.purchased = save.purchased.into_iter().collect();
+
+                save.run
+            }
+            Err(LoadError::TooNew(version)) => {
+                godot_error!(
+                    "the save at {} is from a newer version ({}), playing without it",
+                    self.save_path,
+                    version
+                );
+                self.save_locked = true;
+
+                Run::fresh()
+            }
+            Err(LoadError::Missing(key)) => {
+                godot_error!(
+                    "the save at {} is missing its {}, playing without it",
+                    self.save_path,
+                    key
+                );
+                self.save_locked = true;
+
+                Run::fresh()
+            }
+        }
+    }
+
+    fn save(&self, run: Run) {
+        if self.save_locked {
+            return;
+        }
+
+        let config = ConfigFile::new();
+        let purchased: Vec<GodotString> = self.purchased.iter().map(GodotString::from).collect();
+
+        config.set_value(VERSION_SECTION, VERSION_KEY, SAVE_VERSION);
+        config.set_value(SAVE_SECTION, SAVE_KEY, StringArray::from_vec(purchased));
+        config.set_value(SAVE_SECTION, NG_PLUS_KEY, run.ng_plus_level);
+        config.set_value(SAVE_SECTION, RUN_SEED_KEY, run.run_seed);
+
+        if config.save(self.save_path.as_str()).is_err() {
+            godot_error!("couldn't save upgrades to {}", self.save_path);
+        }
+    }
+}
+
+/// Ends the run through the `Upgrades` autoload.
+pub fn end_run(owner: &Node) {
+    let upgrades_node = unsafe { owner.get_typed_node::<Node, _>("/root/Upgrades") };
+    let upgrades_instance = upgrades_node.cast_instance::<Upgrades>().unwrap();
+    let player_stats_node = unsafe { owner.get_typed_node::<Node, _>("/root/PlayerStats") };
+    let player_stats_instance = player_stats_node.cast_instance::<PlayerStats>().unwrap();
+
+    let _ = upgrades_instance.map(|upgrades, _| {
+        player_stats_instance.map_mut(|player_stats, _| upgrades.end_run(player_stats))
+    });
+}
+
+/// What a save file holds, as read before knowing its version, so anything
+/// added since the first version may be missing.
+#[derive(Debug, Default)]
+struct SaveFile {
+    // saves from before versioning have none, and are version 1
+    version: Option<i64>,
+    purchased: Vec<String>,
+    ng_plus_level: Option<i32>,
+    run_seed: Option<i64>,
+}
+
+/// What the player's stats keep in the save besides the upgrades, from one
+/// run to the next and for the run going on.
+#[derive(Debug, Default, PartialEq)]
+struct Run {
+    ng_plus_level: i32,
+    run_seed: i64,
+}
+
+impl Run {
+    fn of(player_stats: &PlayerStats) -> Self {
+        Self {
+            ng_plus_level: player_stats.ng_plus_level,
+            run_seed: player_stats.run_seed,
+        }
+    }
+
+    /// A first run, with nothing kept and a seed of its own.
+    fn fresh() -> Self {
+        Self {
+            run_seed: OS::godot_singleton().get_unix_time(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A save brought up to the current version.
+#[derive(Debug, PartialEq)]
+struct Save {
+    purchased: Vec<String>,
+    run: Run,
+}
+
+#[derive(Debug, PartialEq)]
+enum LoadError {
+    // from a newer version of the game, which this one can't know how to read
+    TooNew(i64),
+    // a save of the current version without something it always has, so
+    // it was damaged since
+    Missing(&'static str),
+}
+
+fn migrate(mut file: SaveFile) -> Result<Save, LoadError> {
+    let version = file.version.unwrap_or(1);
+
+    if version > SAVE_VERSION {
+        return Err(LoadError::TooNew(version));
+    }
+
+    for migration in MIGRATIONS.iter().skip((version - 1).max(0) as usize) {
+        migration(&mut file);
+    }
+
+    Ok(Save {
+        purchased: file.purchased,
+        run: Run {
+            ng_plus_level: file.ng_plus_level.ok_or(LoadError::Missing(NG_PLUS_KEY))?,
+            run_seed: file.run_seed.ok_or(LoadError::Missing(RUN_SEED_KEY))?,
+        },
+    })
+}
+
+#[cfg(test)]
+fn node(id: &str) -> &'static UpgradeNode {
+    TREE.iter().find(|node| node.id == id).unwrap()
+}
+
+#[test]
+fn test_prerequisites_gate_purchase() {
+    let mut upgrades = Upgrades::default();
+    let mut player_stats = PlayerStats {
+        coins: 100,
+        ..PlayerStats::default()
+    };
+
+    assert!(!upgrades.can_purchase(node("fortitude"), &player_stats));
+    assert!(!upgrades.buy(node("fortitude"), &mut player_stats));
+    assert_eq!(player_stats.coins, 100);
+
+    assert!(upgrades.buy(node("vitality"), &mut player_stats));
+
+    assert!(upgrades.can_purchase(node("fortitude"), &player_stats));
+}
+
+#[test]
+fn test_capstone_needs_every_prerequisite() {
+    let upgrades = Upgrades {
+        purchased: ["vitality", "fortitude", "sharpness"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect(),
+        ..Upgrades::default()
+    };
+    let player_stats = PlayerStats {
+        coins: 100,
+        ..PlayerStats::default()
+    };
+
+    assert!(!upgrades.can_purchase(node("champion"), &player_stats));
+}
+
+#[test]
+fn test_purchase_needs_coins_and_only_happens_once() {
+    let mut upgrades = Upgrades::default();
+    let mut player_stats = PlayerStats {
+        coins: 9,
+        ..PlayerStats::default()
+    };
+
+    assert!(!upgrades.can_purchase(node("vitality"), &player_stats));
+
+    player_stats.coins = 20;
+
+    assert!(upgrades.buy(node("vitality"), &mut player_stats));
+    assert!(!upgrades.can_purchase(node("vitality"), &player_stats));
+}
+
+#[test]
+fn test_purchase_applies_stat_changes() {
+    let mut upgrades = Upgrades::default();
+    let mut player_stats = PlayerStats {
+        coins: 40,
+        ..PlayerStats::default()
+    };
+
+    upgrades.buy(node("vitality"), &mut player_stats);
+    upgrades.buy(node("sharpness"), &mut player_stats);
+    upgrades.buy(node("endurance"), &mut player_stats);
+
+    assert_eq!(player_stats.coins, 0);
+    assert_eq!(player_stats.max_health, 5);
+    assert_eq!(player_stats.health, 5);
+    assert_eq!(player_stats.bonus_damage, 1);
+    assert_eq!(player_stats.bonus_stamina, 25.0);
+}
+
+#[test]
+fn test_purchased_upgrades_reapply_to_fresh_stats() {
+    let upgrades = Upgrades {
+        purchased: ["vitality", "sharpness"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect(),
+        ..Upgrades::default()
+    };
+    let mut player_stats = PlayerStats::default();
+
+    upgrades.apply_purchased(&mut player_stats);
+
+    assert_eq!(player_stats.max_health, 5);
+    assert_eq!(player_stats.bonus_damage, 1);
+}
+
+#[test]
+fn test_unversioned_save_migrates_to_current() {
+    let file = SaveFile {
+        version: None,
+        purchased: vec!["vitality".to_string()],
+        ng_plus_level: None,
+        run_seed: None,
+    };
+
+    assert_eq!(
+        migrate(file),
+        Ok(Save {
+            purchased: vec!["vitality".to_string()],
+            run: Run::default(),
+        })
+    );
+}
+
+#[test]
+fn test_current_save_loads_as_is() {
+    let file = SaveFile {
+        version: Some(SAVE_VERSION),
+        purchased: vec!["sharpness".to_string()],
+        ng_plus_level: Some(1),
+        run_seed: Some(1234),
+    };
+
+    assert_eq!(
+        migrate(file),
+        Ok(Save {
+            purchased: vec!["sharpness".to_string()],
+            run: Run {
+                ng_plus_level: 1,
+                run_seed: 1234,
+            },
+        })
+    );
+}
+
+#[test]
+fn test_save_from_newer_version_is_rejected() {
+    let file = SaveFile {
+        version: Some(SAVE_VERSION + 1),
+        ..SaveFile::default()
+    };
+
+    assert_eq!(migrate(file), Err(LoadError::TooNew(SAVE_VERSION + 1)));
+}
+
+#[test]
+fn test_save_from_before_run_seeds_migrates_to_current() {
+    let file = SaveFile {
+        version: Some(2),
+        purchased: vec!["sharpness".to_string()],
+        ng_plus_level: Some(1),
+        run_seed: None,
+    };
+
+    assert_eq!(
+        migrate(file),
+        Ok(Save {
+            purchased: vec!["sharpness".to_string()],
+            run: Run {
+                ng_plus_level: 1,
+                run_seed: 0,
+            },
+        })
+    );
+}
+
+#[test]
+fn test_save_missing_a_current_field_is_rejected() {
+    let file = SaveFile {
+        version: Some(SAVE_VERSION),
+        purchased: vec!["sharpness".to_string()],
+        ng_plus_level: Some(1),
+        run_seed: None,
+    };
+
+    assert_eq!(migrate(file), Err(LoadError::Missing(RUN_SEED_KEY)));
+}
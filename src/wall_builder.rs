@@ -0,0 +1,211 @@
+use crate::cooldown::Cooldown;
+use crate::entity_registry;
+use crate::load_scene;
+use crate::settings;
+use gdnative::api::StaticBody2D;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node2D, TRef, Vector2, Vector2Godot};
+
+/// Sits under an enemy and every now and then raises a temporary wall
+/// between it and the closest player in range, blocking the line of sight.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct WallBuilder {
+    build_cooldown: Cooldown,
+    build_range: f32,
+    wall_scene: String,
+    wall_lifetime: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<WallBuilder>) {
+    builder
+        .add_property::<f32>("build_interval")
+        .with_default(5.0)
+        .with_getter(|wall_builder: &WallBuilder, _| wall_builder.build_cooldown.duration)
+        .with_setter(|wall_builder: &mut WallBuilder, _, interval| {
+            wall_builder.build_cooldown.duration = interval
+        })
+        .done();
+    builder
+        .add_property::<f32>("build_range")
+        .with_default(96.0)
+        .with_getter(|wall_builder: &WallBuilder, _| wall_builder.build_range)
+        .with_setter(|wall_builder: &mut WallBuilder, _, range| wall_builder.build_range = range)
+        .done();
+    builder
+        .add_property::<String>("wall_scene")
+        .with_default("res://scenes/TemporaryWall.tscn".to_string())
+        .with_ref_getter(|wall_builder: &WallBuilder, _| &wall_builder.wall_scene)
+        .with_setter(|wall_builder: &mut WallBuilder, _, scene| wall_builder.wall_scene = scene)
+        .done();
+    builder
+        .add_property::<f32>("wall_lifetime")
+        .with_default(3.0)
+        .with_getter(|wall_builder: &WallBuilder, _| wall_builder.wall_lifetime)
+        .with_setter(|wall_builder: &mut WallBuilder, _, lifetime| {
+            wall_builder.wall_lifetime = lifetime
+        })
+        .done();
+}
+
+impl Default for WallBuilder {
+    fn default() -> Self {
+        let mut build_cooldown = Cooldown::new(5.0);
+        build_cooldown.start();
+
+        Self {
+            build_cooldown,
+            build_range: 96.0,
+            wall_scene: "res://scenes/TemporaryWall.tscn".to_string(),
+            wall_lifetime: 3.0,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl WallBuilder {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, _owner: &Node2D) {
+        self.build_cooldown.start();
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &Node2D, delta: f32) {
+        let delta = settings::game_delta(owner, delta);
+        let player = self.closest_player_in_range(owner);
+
+        if self.update(delta, player.is_some()) {
+            if let Some(player) = player {
+                self.build(owner, player);
+            }
+        }
+    }
+
+    /// Returns whether a wall should be built this frame.
+    pub fn update(&mut self, delta: f32, player_in_range: bool) -> bool {
+        self.build_cooldown.tick(delta);
+
+        // the cooldown keeps running without a player, so one walking into
+        // range can be walled off right away
+        if !player_in_range || !self.build_cooldown.is_ready() {
+            return false;
+        }
+
+        self.build_cooldown.start();
+
+        true
+    }
+
+    fn closest_player_in_range(&self, owner: &Node2D) -> Option<Vector2> {
+        let position = owner.global_position();
+
+        entity_registry::all_players(owner)
+            .iter()
+            .map(|player| player.global_position())
+            .filter(|player| position.distance_to(*player) <= self.build_range)
+            .min_by(|a, b| {
+                position
+                    .distance_to(*a)
+                    .partial_cmp(&position.distance_to(*b))
+                    .unwrap()
+            })
+    }
+
+    fn build(&self, owner: &Node2D, player: Vector2) {
+        let wall_scene = match load_scene(&self.wall_scene) {
+            Some(scene) => scene,
+            None => return,
+        };
+
+        let wall_node = unsafe { wall_scene.instance(0).unwrap().assume_safe() };
+        let wall: TRef<StaticBody2D> = wall_node.cast().unwrap();
+
+        let (position, rotation) = wall_placement(owner.global_position(), player);
+
+        wall.set_global_position(position);
+        wall.set_global_rotation(rotation as f64);
+
+        let _ = wall
+            .cast_instance::<TemporaryWall>()
+            .unwrap()
+            .map_mut(|temporary_wall, _| temporary_wall.time_left = self.wall_lifetime);
+
+        // walls are siblings of the building enemy, so they outlive it
+        let enemy = unsafe { owner.get_parent().unwrap().assume_safe() };
+        let enemy_parent = unsafe { enemy.get_parent().unwrap().assume_safe() };
+
+        enemy_parent.add_child(wall, false);
+    }
+}
+
+/// Returns where a wall goes to block the line between the builder and the
+/// player, halfway between them and facing along that line.
+fn wall_placement(builder: Vector2, player: Vector2) -> (Vector2, f32) {
+    let direction = player - builder;
+
+    (builder + direction / 2.0, direction.y.atan2(direction.x))
+}
+
+#[derive(NativeClass)]
+#[inherit(StaticBody2D)]
+pub struct TemporaryWall {
+    time_left: f32,
+}
+
+#[gdnative::methods]
+impl TemporaryWall {
+    fn new(_owner: &StaticBody2D) -> Self {
+        Self { time_left: 3.0 }
+    }
+
+    #[export]
+    fn _physics_process(&mut self, owner: &StaticBody2D, delta: f32) {
+        if self.update(settings::game_delta(owner, delta)) {
+            owner.queue_free();
+        }
+    }
+
+    /// Returns whether the wall crumbled this frame.
+    pub fn update(&mut self, delta: f32) -> bool {
+        self.time_left -= delta;
+
+        self.time_left <= 0.0
+    }
+}
+
+#[test]
+fn test_builds_once_cooldown_ends() {
+    let mut wall_builder = WallBuilder::default();
+
+    assert!(!wall_builder.update(4.0, true));
+    assert!(wall_builder.update(1.0, true));
+    assert!(!wall_builder.update(1.0, true));
+}
+
+#[test]
+fn test_waits_for_player_in_range() {
+    let mut wall_builder = WallBuilder::default();
+
+    assert!(!wall_builder.update(10.0, false));
+    assert!(wall_builder.update(0.0, true));
+}
+
+#[test]
+fn test_wall_crumbles_after_lifetime() {
+    let mut wall = TemporaryWall { time_left: 3.0 };
+
+    assert!(!wall.update(2.0));
+    assert!(wall.update(1.0));
+}
+
+#[test]
+fn test_wall_placed_between_builder_and_player() {
+    let (position, rotation) = wall_placement(Vector2::new(0.0, 0.0), Vector2::new(0.0, 40.0));
+
+    assert_eq!(position, Vector2::new(0.0, 20.0));
+    assert!((rotation - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+}
@@ -0,0 +1,156 @@
+use crate::cooldown::Cooldown;
+use crate::kill_zone::KillZone;
+use crate::rng::Rng;
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, NativeClass, Node2D, Rect2, Vector2};
+
+const MAX_TARGET_ATTEMPTS: usize = 8;
+
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[register_with(register_properties)]
+pub struct WanderController {
+    wander_range: f32,
+    start_position: Vector2,
+    pub(crate) target_position: Vector2,
+    wander_cooldown: Cooldown,
+    rng: Rng,
+}
+
+fn register_properties(builder: &ClassBuilder<WanderController>) {
+    builder
+        .add_property::<f32>("wander_range")
+        .with_default(32.0)
+        .with_getter(|wander: &WanderController, _| wander.wander_range)
+        .with_setter(|wander: &mut WanderController, _, range| wander.wander_range = range)
+        .done();
+}
+
+impl Default for WanderController {
+    fn default() -> Self {
+        Self {
+            wander_range: 32.0,
+            start_position: Vector2::zero(),
+            target_position: Vector2::zero(),
+            wander_cooldown: Cooldown::default(),
+            rng: Rng::default(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl WanderController {
+    fn new(_owner: &Node2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Node2D) {
+        // seeded apart so enemies don't all wander in lockstep
+        self.rng = Rng::new(owner.get_instance_id() as u64);
+
+        self.start_position = owner.global_position();
+        self.target_position = self.start_position;
+    }
+
+    #[export]
+    fn _process(&mut self, _owner: &Node2D, delta: f32) {
+        self.wander_cooldown.tick(delta);
+    }
+
+    pub fn update_target_position(&mut self, owner: &Node2D) {
+        let kill_zones = kill_zone_bounds(owner);
+
+        self.target_position = pick_target(
+            self.start_position,
+            self.wander_range,
+            &mut self.rng,
+            &kill_zones,
+        );
+    }
+
+    pub fn start_position(&self) -> Vector2 {
+        self.start_position
+    }
+
+    pub fn time_left(&self) -> f32 {
+        self.wander_cooldown.time_left()
+    }
+
+    pub fn start_wander_timer(&mut self, duration: f32) {
+        self.wander_cooldown.duration = duration;
+        self.wander_cooldown.start();
+    }
+
+    pub fn roll(&mut self) -> f32 {
+        self.rng.next_f32()
+    }
+}
+
+fn kill_zone_bounds(owner: &Node2D) -> Vec<Rect2> {
+    let tree = unsafe { owner.get_tree().unwrap().assume_safe() };
+
+    tree.get_nodes_in_group("kill_zones")
+        .iter()
+        .filter_map(|kill_zone| kill_zone.try_to_object::<Area2D>())
+        .filter_map(|kill_zone| unsafe { kill_zone.assume_safe() }.cast_instance::<KillZone>())
+        .filter_map(|kill_zone| {
+            kill_zone
+                .map(|kill_zone, owner| kill_zone.bounds(&owner))
+                .ok()
+        })
+        .collect()
+}
+
+fn is_valid_target(target: Vector2, kill_zones: &[Rect2]) -> bool {
+    !kill_zones
+        .iter()
+        .any(|kill_zone| kill_zone.contains(target.to_point()))
+}
+
+fn pick_target(start: Vector2, range: f32, rng: &mut Rng, kill_zones: &[Rect2]) -> Vector2 {
+    for _ in 0..MAX_TARGET_ATTEMPTS {
+        let target =
+            start + Vector2::new(rng.range_f32(-range, range), rng.range_f32(-range, range));
+
+        if is_valid_target(target, kill_zones) {
+            return target;
+        }
+    }
+
+    // every sample landed in a kill zone, so stay where it started
+    start
+}
+
+#[cfg(test)]
+fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect2 {
+    use gdnative::prelude::{Point2, Size2};
+
+    Rect2::new(Point2::new(x, y), Size2::new(width, height))
+}
+
+#[test]
+fn test_target_inside_pit_is_rejected() {
+    let pits = [rect(0.0, 0.0, 16.0, 16.0)];
+
+    assert!(!is_valid_target(Vector2::new(8.0, 8.0), &pits));
+}
+
+#[test]
+fn test_target_outside_pit_is_accepted() {
+    let pits = [rect(0.0, 0.0, 16.0, 16.0)];
+
+    assert!(is_valid_target(Vector2::new(24.0, 8.0), &pits));
+}
+
+#[test]
+fn test_picked_target_avoids_pits() {
+    let pits = [rect(16.0, -32.0, 16.0, 64.0)];
+    let mut rng = Rng::new(11);
+
+    for _ in 0..100 {
+        let target = pick_target(Vector2::zero(), 32.0, &mut rng, &pits);
+
+        assert!(is_valid_target(target, &pits));
+    }
+}
@@ -0,0 +1,164 @@
+use gdnative::prelude::{Label, NativeClass, Vector2};
+
+const SLIDE_DURATION: f32 = 0.3;
+const HOLD_DURATION: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Hidden,
+    // each state holds its time left
+    SlidingIn(f32),
+    Showing(f32),
+    SlidingOut(f32),
+}
+
+/// The "Wave N" and "Wave Cleared" banner. Spawners drive it by connecting
+/// their `wave_started` and `wave_cleared` signals to the handlers below.
+#[derive(NativeClass)]
+#[inherit(Label)]
+pub struct WaveBanner {
+    state: State,
+    // where the banner rests while showing
+    rest_position: Vector2,
+}
+
+impl Default for WaveBanner {
+    fn default() -> Self {
+        Self {
+            state: State::Hidden,
+            rest_position: Vector2::zero(),
+        }
+    }
+}
+
+#[gdnative::methods]
+impl WaveBanner {
+    fn new(_owner: &Label) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&mut self, owner: &Label) {
+        self.rest_position = owner.position();
+        owner.hide();
+    }
+
+    #[export]
+    fn _process(&mut self, owner: &Label, delta: f32) {
+        if self.state == State::Hidden {
+            return;
+        }
+
+        let visible_fraction = self.update(delta);
+
+        if self.state == State::Hidden {
+            owner.hide();
+            return;
+        }
+
+        // slides in from the left edge of the screen
+        let width = owner.size().x;
+        let offset = Vector2::new(
+            -(width + self.rest_position.x) * (1.0 - visible_fraction),
+            0.0,
+        );
+
+        owner.set_position(self.rest_position + offset, false);
+    }
+
+    #[export]
+    fn _on_wave_started(&mut self, owner: &Label, wave: i64) {
+        self.announce(owner, format!("Wave {}", wave));
+    }
+
+    #[export]
+    fn _on_wave_cleared(&mut self, owner: &Label, _wave: i64) {
+        self.announce(owner, "Wave Cleared".to_string());
+    }
+
+    fn announce(&mut self, owner: &Label, text: String) {
+        owner.set_text(text);
+        owner.show();
+
+        self.start();
+    }
+
+    // a new announcement replaces whatever is showing
+    fn start(&mut self) {
+        self.state = State::SlidingIn(SLIDE_DURATION);
+    }
+
+    /// Advances the banner, returning how much of it is on screen, from 0
+    /// to 1.
+    fn update(&mut self, delta: f32) -> f32 {
+        match &mut self.state {
+            State::Hidden => 0.0,
+            State::SlidingIn(time_left) => {
+                *time_left -= delta;
+
+                if *time_left <= 0.0 {
+                    self.state = State::Showing(HOLD_DURATION);
+                    1.0
+                } else {
+                    1.0 - *time_left / SLIDE_DURATION
+                }
+            }
+            State::Showing(time_left) => {
+                *time_left -= delta;
+
+                if *time_left <= 0.0 {
+                    self.state = State::SlidingOut(SLIDE_DURATION);
+                }
+
+                1.0
+            }
+            State::SlidingOut(time_left) => {
+                *time_left -= delta;
+
+                if *time_left <= 0.0 {
+                    self.state = State::Hidden;
+                    0.0
+                } else {
+                    *time_left / SLIDE_DURATION
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_banner_slides_in_holds_and_slides_out() {
+    let mut banner = WaveBanner::default();
+
+    banner.start();
+
+    banner.update(SLIDE_DURATION);
+    assert_eq!(banner.state, State::Showing(HOLD_DURATION));
+
+    banner.update(HOLD_DURATION);
+    assert_eq!(banner.state, State::SlidingOut(SLIDE_DURATION));
+
+    assert_eq!(banner.update(SLIDE_DURATION), 0.0);
+    assert_eq!(banner.state, State::Hidden);
+}
+
+#[test]
+fn test_new_announcement_restarts_banner() {
+    let mut banner = WaveBanner::default();
+
+    banner.start();
+    banner.update(SLIDE_DURATION);
+    banner.update(HOLD_DURATION);
+
+    banner.start();
+
+    assert_eq!(banner.state, State::SlidingIn(SLIDE_DURATION));
+}
+
+#[test]
+fn test_hidden_banner_stays_hidden() {
+    let mut banner = WaveBanner::default();
+
+    assert_eq!(banner.update(1.0), 0.0);
+    assert_eq!(banner.state, State::Hidden);
+}
@@ -0,0 +1,105 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weapon {
+    pub damage: i32,
+    pub crit_chance: f32,
+    pub crit_multiplier: f32,
+    // `None` means the weapon never breaks
+    pub durability: Option<i32>,
+    // scales how far hits knock enemies back
+    pub knockback: f32,
+    // fraction of the wielder's speed lost for a moment when a hit lands,
+    // zero disables it
+    pub impact_drag: f32,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self::sword()
+    }
+}
+
+impl Weapon {
+    pub fn sword() -> Self {
+        Self {
+            damage: 1,
+            crit_chance: 0.0,
+            crit_multiplier: 2.0,
+            durability: None,
+            knockback: 1.0,
+            impact_drag: 0.0,
+        }
+    }
+
+    // a powerful weapon only ever wielded for a while, from a pickup
+    pub fn greatsword() -> Self {
+        Self {
+            damage: 5,
+            crit_chance: 0.2,
+            crit_multiplier: 2.5,
+            durability: None,
+            knockback: 1.5,
+            impact_drag: 0.8,
+        }
+    }
+
+    // what a broken weapon leaves, hitting as hard as the sword but
+    // knocking enemies back half as far
+    pub fn fists() -> Self {
+        Self {
+            damage: 1,
+            crit_chance: 0.0,
+            crit_multiplier: 1.0,
+            durability: None,
+            knockback: 0.5,
+            impact_drag: 0.0,
+        }
+    }
+
+    /// Spends one use of the weapon, returning whether it broke.
+    pub fn use_weapon(&mut self) -> bool {
+        match self.durability.as_mut() {
+            Some(durability) => {
+                *durability = (*durability - 1).max(0);
+                *durability == 0
+            }
+            None => false,
+        }
+    }
+}
+
+#[test]
+fn test_use_weapon_decrements_durability() {
+    let mut weapon = Weapon {
+        durability: Some(3),
+        ..Weapon::sword()
+    };
+
+    assert!(!weapon.use_weapon());
+    assert_eq!(weapon.durability, Some(2));
+}
+
+#[test]
+fn test_use_weapon_breaks_when_depleted() {
+    let mut weapon = Weapon {
+        durability: Some(2),
+        ..Weapon::sword()
+    };
+
+    assert!(!weapon.use_weapon());
+    assert!(weapon.use_weapon());
+    assert_eq!(weapon.durability, Some(0));
+}
+
+#[test]
+fn test_unbreakable_weapon_never_breaks() {
+    let mut weapon = Weapon::fists();
+
+    for _ in 0..100 {
+        assert!(!weapon.use_weapon());
+    }
+}
+
+#[test]
+fn test_fists_knock_back_less_than_the_sword() {
+    assert!(Weapon::fists().knockback < Weapon::sword().knockback);
+}
@@ -0,0 +1,60 @@
+use crate::extensions::NodeExt;
+use crate::sword_hitbox::SwordHitbox;
+use crate::weapon::Weapon;
+use gdnative::api::Area2D;
+use gdnative::prelude::{ClassBuilder, KinematicBody2D, NativeClass, Node, Ref};
+
+/// Equips the player with a greatsword for a while, dropped by some enemies.
+#[derive(NativeClass)]
+#[inherit(Area2D)]
+#[register_with(register_properties)]
+pub struct WeaponPickup {
+    duration: f32,
+}
+
+fn register_properties(builder: &ClassBuilder<WeaponPickup>) {
+    builder
+        .add_property::<f32>("duration")
+        .with_default(10.0)
+        .with_getter(|weapon_pickup: &WeaponPickup, _| weapon_pickup.duration)
+        .with_setter(|weapon_pickup: &mut WeaponPickup, _, duration| {
+            weapon_pickup.duration = duration
+        })
+        .done();
+}
+
+impl Default for WeaponPickup {
+    fn default() -> Self {
+        Self { duration: 10.0 }
+    }
+}
+
+#[gdnative::methods]
+impl WeaponPickup {
+    fn new(_owner: &Area2D) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    #[allow(non_snake_case)]
+    fn _on_WeaponPickup_body_entered(&self, owner: &Area2D, body: Ref<Node>) {
+        let player = match unsafe { body.assume_safe_if_sane() }
+            .filter(|body| body.is_in_group("player"))
+            .and_then(|body| body.cast::<KinematicBody2D>())
+        {
+            Some(player) => player,
+            None => return,
+        };
+
+        let sword_hitbox_node =
+            unsafe { player.get_typed_node::<Area2D, _>("HitboxPivot/SwordHitbox") };
+        let sword_hitbox_instance = sword_hitbox_node.cast_instance::<SwordHitbox>().unwrap();
+
+        let duration = self.duration;
+        let _ = sword_hitbox_instance.map_mut(|sword_hitbox, _| {
+            sword_hitbox.equip_temporary(Weapon::greatsword(), duration)
+        });
+
+        owner.queue_free();
+    }
+}
@@ -0,0 +1,171 @@
+use crate::extensions::NodeExt;
+use gdnative::api::{Control, Engine};
+use gdnative::prelude::{ClassBuilder, Color, NativeClass, Node, Vector2};
+
+const SLOT_RADIUS: f32 = 8.0;
+
+/// A radial menu for switching between the owned weapons, opened while a
+/// button is held. Time slows down while it's open, and the weapon the aim
+/// points at is picked when it closes. Reached through the `WeaponWheel`
+/// autoload.
+#[derive(NativeClass)]
+#[inherit(Control)]
+#[register_with(register_properties)]
+pub struct WeaponWheel {
+    radius: f32,
+    time_scale: f32,
+    segment_count: usize,
+    selected: Option<usize>,
+}
+
+fn register_properties(builder: &ClassBuilder<WeaponWheel>) {
+    builder
+        .add_property::<f32>("radius")
+        .with_default(40.0)
+        .with_getter(|wheel: &WeaponWheel, _| wheel.radius)
+        .with_setter(|wheel: &mut WeaponWheel, _, radius| wheel.radius = radius)
+        .done();
+    builder
+        .add_property::<f32>("time_scale")
+        .with_default(0.3)
+        .with_getter(|wheel: &WeaponWheel, _| wheel.time_scale)
+        .with_setter(|wheel: &mut WeaponWheel, _, time_scale| wheel.time_scale = time_scale)
+        .done();
+}
+
+impl Default for WeaponWheel {
+    fn default() -> Self {
+        Self {
+            radius: 40.0,
+            time_scale: 0.3,
+            segment_count: 0,
+            selected: None,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl WeaponWheel {
+    fn new(_owner: &Control) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: &Control) {
+        owner.set_visible(false);
+    }
+
+    #[export]
+    fn _draw(&self, owner: &Control) {
+        let center = owner.rect_size() / 2.0;
+
+        for index in 0..self.segment_count {
+            let color = if self.selected == Some(index) {
+                Color::rgb(1.0, 0.85, 0.2)
+            } else {
+                Color::rgba(1.0, 1.0, 1.0, 0.5)
+            };
+
+            let position = center + segment_direction(index, self.segment_count) * self.radius;
+
+            owner.draw_circle(position, SLOT_RADIUS as f64, color);
+        }
+    }
+
+    pub fn open(&mut self, owner: &Control, segment_count: usize) {
+        self.segment_count = segment_count;
+        self.selected = None;
+
+        Engine::godot_singleton().set_time_scale(self.time_scale as f64);
+
+        owner.set_visible(true);
+        owner.update();
+    }
+
+    pub fn aim(&mut self, owner: &Control, direction: Vector2) {
+        // letting go of the stick keeps the last pick
+        if let Some(selected) = select_segment(direction, self.segment_count) {
+            self.selected = Some(selected);
+        }
+
+        owner.update();
+    }
+
+    /// Closes the wheel, returning the index of the picked weapon.
+    pub fn close(&mut self, owner: &Control) -> Option<usize> {
+        Engine::godot_singleton().set_time_scale(1.0);
+
+        owner.set_visible(false);
+
+        self.selected.take()
+    }
+}
+
+fn with_wheel<T>(owner: &Node, f: impl FnOnce(&mut WeaponWheel, &Control) -> T) -> Option<T> {
+    let wheel_node = unsafe { owner.get_typed_node::<Control, _>("/root/WeaponWheel/Wheel") };
+
+    wheel_node
+        .cast_instance::<WeaponWheel>()?
+        .map_mut(|wheel, wheel_owner| f(wheel, &wheel_owner))
+        .ok()
+}
+
+/// Opens the wheel of the `WeaponWheel` autoload with a segment per weapon.
+pub fn open(owner: &Node, segment_count: usize) {
+    with_wheel(owner, |wheel, wheel_owner| {
+        wheel.open(wheel_owner, segment_count)
+    });
+}
+
+pub fn aim(owner: &Node, direction: Vector2) {
+    with_wheel(owner, |wheel, wheel_owner| {
+        wheel.aim(wheel_owner, direction)
+    });
+}
+
+pub fn close(owner: &Node) -> Option<usize> {
+    with_wheel(owner, |wheel, wheel_owner| wheel.close(wheel_owner)).flatten()
+}
+
+/// Returns the segment the direction points at, the first one is centered
+/// straight up and the rest follow clockwise.
+fn select_segment(direction: Vector2, segment_count: usize) -> Option<usize> {
+    if segment_count == 0 || direction == Vector2::zero() {
+        return None;
+    }
+
+    let segment_angle = std::f32::consts::PI * 2.0 / segment_count as f32;
+    // clockwise from straight up, since y points down
+    let angle = direction.x.atan2(-direction.y);
+    let index = ((angle + segment_angle / 2.0) / segment_angle).floor() as i64;
+
+    Some(index.rem_euclid(segment_count as i64) as usize)
+}
+
+fn segment_direction(index: usize, segment_count: usize) -> Vector2 {
+    let angle = std::f32::consts::PI * 2.0 * index as f32 / segment_count as f32;
+
+    Vector2::new(angle.sin(), -angle.cos())
+}
+
+#[test]
+fn test_select_segment_with_two_weapons() {
+    assert_eq!(select_segment(Vector2::new(0.0, -1.0), 2), Some(0));
+    assert_eq!(select_segment(Vector2::new(0.0, 1.0), 2), Some(1));
+    assert_eq!(select_segment(Vector2::new(1.0, -0.2), 2), Some(0));
+}
+
+#[test]
+fn test_select_segment_clockwise() {
+    assert_eq!(select_segment(Vector2::new(1.0, 0.0), 4), Some(1));
+    assert_eq!(select_segment(Vector2::new(0.0, 1.0), 4), Some(2));
+    assert_eq!(select_segment(Vector2::new(-1.0, 0.0), 4), Some(3));
+    // just left of straight up wraps around to the first segment
+    assert_eq!(select_segment(Vector2::new(-0.1, -1.0), 4), Some(0));
+}
+
+#[test]
+fn test_select_segment_needs_a_direction() {
+    assert_eq!(select_segment(Vector2::zero(), 3), None);
+    assert_eq!(select_segment(Vector2::new(1.0, 0.0), 0), None);
+}
@@ -0,0 +1,107 @@
+use gdnative::prelude::{ClassBuilder, NativeClass, Node, Node2D, TRef};
+
+// the range of heights sorted, anything past it shares the nearest end's
+// z index
+const MIN_Y: f32 = -2048.0;
+const MAX_Y: f32 = 6144.0;
+// z index 0 is left to the ground, like the tile map and grass, so sorted
+// bodies are always drawn over it
+const MIN_Z: i64 = 1;
+// the highest z index Godot draws
+const MAX_Z: i64 = 4096;
+
+/// Returns the z index for a body standing at the height, higher the lower
+/// it is on screen, so bodies in front are drawn over those behind.
+pub fn z_index_for(y: f32) -> i64 {
+    let fraction = ((y - MIN_Y) / (MAX_Y - MIN_Y)).max(0.0).min(1.0);
+
+    MIN_Z + (fraction * (MAX_Z - MIN_Z) as f32).floor() as i64
+}
+
+/// Keeps the body it's a child of drawn in depth order with the others by
+/// setting its z index from its height, for bodies outside of a `YSort`,
+/// like enemies spawned into the room. Props that never move only set it
+/// once.
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_properties)]
+pub struct YSortZ {
+    // from the body's origin to where it touches the ground
+    foot_offset: f32,
+    moves: bool,
+}
+
+fn register_properties(builder: &ClassBuilder<YSortZ>) {
+    builder
+        .add_property::<f32>("foot_offset")
+        .with_getter(|ysort: &YSortZ, _| ysort.foot_offset)
+        .with_setter(|ysort: &mut YSortZ, _, offset| ysort.foot_offset = offset)
+        .done();
+    builder
+        .add_property::<bool>("moves")
+        .with_default(true)
+        .with_getter(|ysort: &YSortZ, _| ysort.moves)
+        .with_setter(|ysort: &mut YSortZ, _, moves| ysort.moves = moves)
+        .done();
+}
+
+impl Default for YSortZ {
+    fn default() -> Self {
+        Self {
+            foot_offset: 0.0,
+            moves: true,
+        }
+    }
+}
+
+#[gdnative::methods]
+impl YSortZ {
+    fn new(_owner: &Node) -> Self {
+        Self::default()
+    }
+
+    #[export]
+    fn _ready(&self, owner: &Node) {
+        self.sort(owner);
+    }
+
+    #[export]
+    fn _process(&self, owner: &Node, _delta: f32) {
+        if self.moves {
+            self.sort(owner);
+        }
+    }
+
+    fn sort(&self, owner: &Node) {
+        let body = owner
+            .get_parent()
+            .map(|body| unsafe { body.assume_safe() })
+            .and_then(|body| body.cast::<Node2D>());
+
+        if let Some(body) = body {
+            let body: TRef<Node2D> = body;
+
+            body.set_z_as_relative(false);
+            body.set_z_index(z_index_for(body.global_position().y + self.foot_offset));
+        }
+    }
+}
+
+#[test]
+fn test_z_index_grows_down_the_screen() {
+    let heights = [
+        -5000.0, -2048.0, -100.0, -0.5, 0.0, 0.5, 1.0, 37.0, 640.0, 6144.0, 9000.0,
+    ];
+
+    for pair in heights.windows(2) {
+        assert!(z_index_for(pair[0]) <= z_index_for(pair[1]));
+    }
+
+    assert!(z_index_for(0.0) < z_index_for(16.0));
+}
+
+#[test]
+fn test_z_index_stays_within_godot_range() {
+    assert_eq!(z_index_for(-10_000.0), MIN_Z);
+    assert_eq!(z_index_for(10_000.0), MAX_Z);
+}